@@ -15,9 +15,9 @@ contract BN254MultiplyVerifier {
 
     // Verification Key
     G1Point constant ALPHA = G1Point(0x0cbcfcc6ec8cbdc4dba224a4b8d9b6bd6fb89df34221166f580975abfb92afcc, 0x06b404c906a84af3cef06cf8eabd2fde8b324cc9ba4111f83d0865d117f4c6d7);
-    G2Point constant BETA = G2Point([0x2660cb78fd1893bc62658cd1f3d6c16a52a91cca2d468396b2a6203a7c963336, 0x1229c422b86a886cb50250b035f34d15a50e71da2ecf8b50ef8ab527392f69e4], [0x0877cb4dd51404ac6a3314c9fc223353156467d6be8705dcb12f659a32352318, 0x0533b631b811e7210d4d93a2d9b05e1e5938467526dc0b5e846b1ca66c593140]);
-    G2Point constant GAMMA = G2Point([0x16705a32b022683812eca4283fa6a3347fef7a8e72b20a4527ec4d4e286233a5, 0x05e61e66fd7c7210ce1e3300cba8e965d716858665473866792f7e7563b918b5], [0x199c8592e5ac2a71fb79322aa377918ef836648cab38828c998d399c07f0c09e, 0x26cc46ded0bd6977a18dee4eb818b781f3afddc372977e7dee5d530537850a85]);
-    G2Point constant DELTA = G2Point([0x1da4a9663bce7af967a9f03d016729ef5f589e3d0e8394468f35cd5c80505883, 0x20a6809e1c55e4892296d70b49be008dd5882dcd37b656bdf4a67f53ebedb224], [0x1235c2140d719b1a4c2c7e841e35d7e5b768703e5d660682d0e184d5d5909ba6, 0x09810d46255f01195d966f332962d6f438390a30577578fe8a2d21d295d8fb4d]);
+    G2Point constant BETA = G2Point([0x1229c422b86a886cb50250b035f34d15a50e71da2ecf8b50ef8ab527392f69e4, 0x2660cb78fd1893bc62658cd1f3d6c16a52a91cca2d468396b2a6203a7c963336], [0x0533b631b811e7210d4d93a2d9b05e1e5938467526dc0b5e846b1ca66c593140, 0x0877cb4dd51404ac6a3314c9fc223353156467d6be8705dcb12f659a32352318]);
+    G2Point constant GAMMA = G2Point([0x05e61e66fd7c7210ce1e3300cba8e965d716858665473866792f7e7563b918b5, 0x16705a32b022683812eca4283fa6a3347fef7a8e72b20a4527ec4d4e286233a5], [0x26cc46ded0bd6977a18dee4eb818b781f3afddc372977e7dee5d530537850a85, 0x199c8592e5ac2a71fb79322aa377918ef836648cab38828c998d399c07f0c09e]);
+    G2Point constant DELTA = G2Point([0x20a6809e1c55e4892296d70b49be008dd5882dcd37b656bdf4a67f53ebedb224, 0x1da4a9663bce7af967a9f03d016729ef5f589e3d0e8394468f35cd5c80505883], [0x09810d46255f01195d966f332962d6f438390a30577578fe8a2d21d295d8fb4d, 0x1235c2140d719b1a4c2c7e841e35d7e5b768703e5d660682d0e184d5d5909ba6]);
 
     // Public inputs: 1 (gamma_abc inline expansion in verifyProof)
 
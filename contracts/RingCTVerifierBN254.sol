@@ -15,9 +15,9 @@ contract RingCTVerifierBN254 {
 
     // Verification Key
     G1Point constant ALPHA = G1Point(0x16cde7672d240777d5e1e109af2a17cf9c6f65aabd05a76d82461ed92edabcfb, 0x21d9f38f3d392cb6605fe0986b27e2ce9c23dc0b8936d7af05eb213dfdaed020);
-    G2Point constant BETA = G2Point([0x2fa32830b84e4463e4dede31a16278eb742416eae4bef6b156903587cff98380, 0x135410eb08cc99678e973f836aac67573b37106e8f6b65ddc7b2976fc16ad2eb], [0x1788fe169ad482a20fd2c4a70f3c65a55fed2022df58aca286c1acdf8c34c5f9, 0x1489d87c0678fe268212b8a9f9e57aa5ee218a972b6aa066a6d9229f4fea9b99]);
-    G2Point constant GAMMA = G2Point([0x2cf2bb37e9ec0305b71e42f5cfb5a6117a30ba1086fa0600a9720def28ba4ee7, 0x2bbec1646afc4b6f9eaa3926da6b62021e69922d4f2ffcacf2896a7bb636d4a9], [0x062f3d3b4d162be8243a46c651e1fe735841bcaed0b7b2ec9d4979fa97d48ff0, 0x0d1b2b86dac682bd0e36ee8de9c63750ffeeb520c92681976e362d1b3dc0e85d]);
-    G2Point constant DELTA = G2Point([0x01135fa9c047229ca25627c2bf2194c043e03f34a8f6cc94b5ff1d61f19b9a7e, 0x23391ed97851cfb5d0bb6bc901ec00feaa36235fac6a8dba43d0ee75e12c2aa2], [0x2d270a276a2590d9e1b2b8bd70447b7880e92942981c2b0136ac58bafb389d87, 0x137ac003c9aff19bd67336083096af7eee88535985cbb6e0b40d74e8404559d4]);
+    G2Point constant BETA = G2Point([0x135410eb08cc99678e973f836aac67573b37106e8f6b65ddc7b2976fc16ad2eb, 0x2fa32830b84e4463e4dede31a16278eb742416eae4bef6b156903587cff98380], [0x1489d87c0678fe268212b8a9f9e57aa5ee218a972b6aa066a6d9229f4fea9b99, 0x1788fe169ad482a20fd2c4a70f3c65a55fed2022df58aca286c1acdf8c34c5f9]);
+    G2Point constant GAMMA = G2Point([0x2bbec1646afc4b6f9eaa3926da6b62021e69922d4f2ffcacf2896a7bb636d4a9, 0x2cf2bb37e9ec0305b71e42f5cfb5a6117a30ba1086fa0600a9720def28ba4ee7], [0x0d1b2b86dac682bd0e36ee8de9c63750ffeeb520c92681976e362d1b3dc0e85d, 0x062f3d3b4d162be8243a46c651e1fe735841bcaed0b7b2ec9d4979fa97d48ff0]);
+    G2Point constant DELTA = G2Point([0x23391ed97851cfb5d0bb6bc901ec00feaa36235fac6a8dba43d0ee75e12c2aa2, 0x01135fa9c047229ca25627c2bf2194c043e03f34a8f6cc94b5ff1d61f19b9a7e], [0x137ac003c9aff19bd67336083096af7eee88535985cbb6e0b40d74e8404559d4, 0x2d270a276a2590d9e1b2b8bd70447b7880e92942981c2b0136ac58bafb389d87]);
 
     // Public inputs: 1 (gamma_abc inline expansion in verifyProof)
 
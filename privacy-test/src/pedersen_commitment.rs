@@ -250,6 +250,28 @@ mod tests {
         assert!(!pc.verify_opening(&commitment, value + 1, &blinding));
     }
 
+    /// 已知答案向量 (与 vm-runtime::privacy::commitment 共用)
+    #[test]
+    fn test_known_answer_vectors() {
+        let pc = PedersenCommitment::new();
+        let hex32 = |text: &str| -> [u8; 32] { hex::decode(text).unwrap().try_into().unwrap() };
+        for line in include_str!("../vectors/pedersen_kat.txt").lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["H", h] => assert_eq!(pc.h.compress().to_bytes(), hex32(h)),
+                [value, blinding, commitment] => {
+                    let r = Option::<Scalar>::from(Scalar::from_canonical_bytes(hex32(blinding)))
+                        .expect("canonical blinding factor");
+                    let c = pc.commit(value.parse().unwrap(), &r);
+                    assert_eq!(c.compress().to_bytes(), hex32(commitment), "{line}");
+                }
+                _ => panic!("malformed vector line: {line}"),
+            }
+        }
+    }
+
     #[test]
     fn test_homomorphic_property() {
         let pc = PedersenCommitment::new();
@@ -0,0 +1,456 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! EVM 执行引擎：[`ExecutionEngine`] 的 EVM 实现
+//!
+//! - `execute(code, input, context)` 以 `context.contract` 为当前合约地址运行 `code`，
+//!   `context.caller` 同时作为 `msg.sender` 与 `tx.origin`
+//! - 嵌套调用的目标合约代码从 `evm:c:` 命名空间读取（见 [`crate::state`]）
+//! - 执行期间不修改底层存储；成功时导出 [`StateChange`]，由调用方通过
+//!   [`EvmExecutor::apply_state_changes`] 决定是否落盘
+//! - REVERT 保留返回数据并退还剩余 gas；异常终止消耗全部 gas
+
+use crate::interpreter::{Env, Exit, Interpreter, Message};
+use crate::state::{self, JournaledState};
+use crate::u256::U256;
+use anyhow::Result;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use vm_runtime::{
+    ContractResult, EngineType, ExecutionContext, ExecutionEngine, MemoryStorage, StateChange,
+    Storage, StorageDeposit,
+};
+
+/// 默认链 ID（本地开发链）
+pub const DEFAULT_CHAIN_ID: u64 = 1337;
+/// EIP-170 合约代码大小上限
+pub const MAX_CODE_SIZE: usize = 24_576;
+
+/// EVM 执行配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmConfig {
+    /// CHAINID 指令返回值
+    pub chain_id: u64,
+    /// 合约代码大小上限
+    pub max_code_size: usize,
+}
+
+impl Default for EvmConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: DEFAULT_CHAIN_ID,
+            max_code_size: MAX_CODE_SIZE,
+        }
+    }
+}
+
+/// EVM 字节码校验错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EvmValidationError {
+    #[error("empty bytecode")]
+    EmptyCode,
+    #[error("bytecode too large: {size} bytes (limit {limit})")]
+    CodeTooLarge { size: usize, limit: usize },
+    #[error("bytecode starting with 0xEF is reserved (EIP-3541)")]
+    ReservedPrefix,
+}
+
+/// EVM 执行引擎
+///
+/// 持有共享状态（`Arc<RwLock<S>>`），与 [`vm_runtime::WasmExecutor`] 一致满足 `Send + Sync`
+pub struct EvmExecutor<S: Storage + Send + Sync + 'static = MemoryStorage> {
+    state: Arc<RwLock<S>>,
+    config: EvmConfig,
+}
+
+impl<S: Storage + Send + Sync + 'static> EvmExecutor<S> {
+    /// 以独占存储创建执行器
+    pub fn new(storage: S) -> Self {
+        Self::with_shared_state(Arc::new(RwLock::new(storage)))
+    }
+
+    /// 以共享存储创建执行器
+    pub fn with_shared_state(state: Arc<RwLock<S>>) -> Self {
+        Self {
+            state,
+            config: EvmConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: EvmConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.config.chain_id = chain_id;
+        self
+    }
+
+    pub fn state(&self) -> Arc<RwLock<S>> {
+        self.state.clone()
+    }
+
+    pub fn config(&self) -> &EvmConfig {
+        &self.config
+    }
+
+    /// 安装合约代码（供嵌套调用 / EXTCODE* 使用）
+    pub fn install_code(&self, address: [u8; 20], code: &[u8]) -> Result<()> {
+        self.validate_code(code)?;
+        self.state.write().set(&state::code_key(&address), code)
+    }
+
+    /// 设置账户余额
+    pub fn set_balance(&self, address: [u8; 20], balance: U256) -> Result<()> {
+        let key = state::balance_key(&address);
+        match state::encode_word(&balance) {
+            Some(bytes) => self.state.write().set(&key, &bytes),
+            None => self.state.write().delete(&key),
+        }
+    }
+
+    pub fn balance(&self, address: [u8; 20]) -> Result<U256> {
+        Ok(state::decode_word(
+            self.state.read().get(&state::balance_key(&address))?,
+        ))
+    }
+
+    /// 读取合约存储槽
+    pub fn storage_at(&self, address: [u8; 20], slot: U256) -> Result<U256> {
+        Ok(state::decode_word(
+            self.state.read().get(&state::slot_key(&address, &slot))?,
+        ))
+    }
+
+    /// 将执行结果中的状态变更写入底层存储
+    pub fn apply_state_changes(&self, changes: &[StateChange]) -> Result<()> {
+        let mut storage = self.state.write();
+        for change in changes {
+            match &change.value {
+                Some(value) => storage.set(&change.key, value)?,
+                None => storage.delete(&change.key)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// 以 `context` 运行顶层调用
+    fn run_top_level(
+        &self,
+        storage: &S,
+        code: &[u8],
+        input: &[u8],
+        context: &ExecutionContext,
+    ) -> Result<ContractResult> {
+        let mut journal = JournaledState::new(storage);
+        journal.set_code(context.contract, code.to_vec());
+
+        // EIP-2929: 调用方、目标合约与预编译地址预热
+        journal.touch_account(context.caller);
+        journal.touch_account(context.contract);
+        for id in 1..=0x0a {
+            let mut address = [0u8; 20];
+            address[19] = id;
+            journal.touch_account(address);
+        }
+
+        let value = U256::from_be_slice(&context.value.to_be_bytes());
+        if journal.balance(context.caller)? < value {
+            return Ok(Self::failed(0, Vec::new()));
+        }
+
+        let env = Env {
+            chain_id: self.config.chain_id,
+            origin: context.caller,
+            block_number: context.block_number,
+            timestamp: context.timestamp,
+            block_gas_limit: context.gas_limit,
+        };
+        let mut interpreter = Interpreter::new(journal, env);
+        let outcome = interpreter.call(Message {
+            caller: context.caller,
+            address: context.contract,
+            code_address: context.contract,
+            value,
+            transfer: true,
+            input: input.to_vec(),
+            gas: context.gas_limit,
+            is_static: false,
+            depth: 0,
+        })?;
+
+        let gas_used = context.gas_limit - outcome.gas_left;
+        match outcome.exit {
+            Exit::Stop | Exit::Return => {
+                let (logs, state_changes) = interpreter.state.finalize()?;
+                Ok(ContractResult {
+                    success: true,
+                    return_data: outcome.output,
+                    gas_used,
+                    logs,
+                    state_changes,
+                    storage_deposit: StorageDeposit::default(),
+                })
+            }
+            Exit::Revert => Ok(Self::failed(gas_used, outcome.output)),
+            Exit::Halt(reason) => {
+                log::debug!("evm execution halted: {:?}", reason);
+                Ok(Self::failed(gas_used, Vec::new()))
+            }
+        }
+    }
+
+    fn failed(gas_used: u64, return_data: Vec<u8>) -> ContractResult {
+        ContractResult {
+            success: false,
+            return_data,
+            gas_used,
+            logs: Vec::new(),
+            state_changes: Vec::new(),
+            storage_deposit: StorageDeposit::default(),
+        }
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> ExecutionEngine for EvmExecutor<S> {
+    fn execute(
+        &self,
+        code: &[u8],
+        input: &[u8],
+        context: &ExecutionContext,
+    ) -> Result<ContractResult> {
+        self.validate_code(code)?;
+
+        // 整个调用期间持有读锁，保证各层调用看到同一份状态；深层嵌套由解释器自行移到大栈线程
+        let storage = self.state.read();
+        self.run_top_level(&storage, code, input, context)
+    }
+
+    fn engine_type(&self) -> EngineType {
+        EngineType::Evm
+    }
+
+    fn validate_code(&self, code: &[u8]) -> Result<()> {
+        if code.is_empty() {
+            return Err(EvmValidationError::EmptyCode.into());
+        }
+        if code.len() > self.config.max_code_size {
+            return Err(EvmValidationError::CodeTooLarge {
+                size: code.len(),
+                limit: self.config.max_code_size,
+            }
+            .into());
+        }
+        if code[0] == 0xef {
+            return Err(EvmValidationError::ReservedPrefix.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALLER: [u8; 20] = [0xaa; 20];
+    const CONTRACT: [u8; 20] = [0xcc; 20];
+
+    fn ctx(gas_limit: u64) -> ExecutionContext {
+        ExecutionContext {
+            caller: CALLER,
+            contract: CONTRACT,
+            value: 0,
+            gas_limit,
+            block_number: 7,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    fn code(hex_str: &str) -> Vec<u8> {
+        hex::decode(hex_str).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic_return() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // PUSH1 2, PUSH1 3, ADD, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+        let result = exec
+            .execute(&code("600260030160005260206000f3"), &[], &ctx(100_000))
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(U256::from_be_slice(&result.return_data), U256::from_u64(5));
+        // 3+3+3+3+3(mem)+3+3+3 = 24
+        assert_eq!(result.gas_used, 24);
+        assert!(result.state_changes.is_empty());
+    }
+
+    #[test]
+    fn test_storage_changes_and_apply() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // SSTORE(slot 1, CALLDATALOAD(0)), SLOAD(1) → RETURN
+        let bytecode = code("60003560015560015460005260206000f3");
+        let input = U256::from_u64(0x2a).to_be_bytes();
+        let result = exec.execute(&bytecode, &input, &ctx(100_000)).unwrap();
+        assert!(result.success);
+        assert_eq!(
+            U256::from_be_slice(&result.return_data),
+            U256::from_u64(0x2a)
+        );
+        assert_eq!(result.state_changes.len(), 1);
+        assert_eq!(
+            result.state_changes[0].key,
+            state::slot_key(&CONTRACT, &U256::ONE)
+        );
+        // 冷 SSTORE 新值 22100 + 热 SLOAD 100
+        assert!(result.gas_used > 22_200);
+
+        exec.apply_state_changes(&result.state_changes).unwrap();
+        assert_eq!(
+            exec.storage_at(CONTRACT, U256::ONE).unwrap(),
+            U256::from_u64(0x2a)
+        );
+
+        // 清零后产生删除
+        let result = exec.execute(&bytecode, &[0u8; 32], &ctx(100_000)).unwrap();
+        assert_eq!(result.state_changes[0].value, None);
+    }
+
+    #[test]
+    fn test_log_topics() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // MSTORE(0, 0xff), LOG2(offset 0, len 32, topic0 = 0x11, topic1 = 0x22)
+        let bytecode = code("60ff6000526022601160206000a200");
+        let result = exec.execute(&bytecode, &[], &ctx(100_000)).unwrap();
+        assert!(result.success);
+        assert_eq!(result.logs.len(), 1);
+        let log = &result.logs[0];
+        assert_eq!(log.address, CONTRACT);
+        assert_eq!(
+            log.topics,
+            vec![
+                U256::from_u64(0x11).to_be_bytes(),
+                U256::from_u64(0x22).to_be_bytes()
+            ]
+        );
+        assert_eq!(U256::from_be_slice(&log.data), U256::from_u64(0xff));
+    }
+
+    #[test]
+    fn test_revert_discards_state_and_refunds_gas() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // SSTORE(0, 1), MSTORE(0, 0xbeef), REVERT(30, 2)
+        let bytecode = code("600160005561beef6000526002601efd");
+        let result = exec.execute(&bytecode, &[], &ctx(100_000)).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.return_data, vec![0xbe, 0xef]);
+        assert!(result.state_changes.is_empty() && result.logs.is_empty());
+        assert!(result.gas_used < 100_000);
+    }
+
+    #[test]
+    fn test_out_of_gas_and_invalid_jump_consume_all_gas() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // JUMPDEST, PUSH1 0, JUMP（死循环）
+        let result = exec.execute(&code("5b600056"), &[], &ctx(10_000)).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 10_000);
+
+        // JUMP 到非 JUMPDEST
+        let result = exec.execute(&code("600356"), &[], &ctx(10_000)).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 10_000);
+    }
+
+    #[test]
+    fn test_staticcall_bn254_precompile() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // ecMul(G1 generator, 2) via STATICCALL(gas, 0x07, 0, 96, 0, 64); RETURN(0, 64)
+        let bytecode = code(concat!(
+            "6001600052", // MSTORE(0, 1)       x = 1
+            "6002602052", // MSTORE(32, 2)      y = 2
+            "6002604052", // MSTORE(64, 2)      scalar = 2
+            "60406000606060006007",
+            "5afa",       // GAS, STATICCALL
+            "50",         // POP
+            "60406000f3"  // RETURN(0, 64)
+        ));
+        let result = exec.execute(&bytecode, &[], &ctx(100_000)).unwrap();
+        assert!(result.success);
+        assert_eq!(
+            hex::encode(&result.return_data),
+            concat!(
+                "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+                "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"
+            )
+        );
+        assert!(result.gas_used > crate::precompiles::BN254_MUL_GAS);
+    }
+
+    #[test]
+    fn test_nested_call_and_value_transfer() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        let callee = [0xdd; 20];
+        // callee: SSTORE(0, CALLER), STOP
+        exec.install_code(callee, &code("3360005500")).unwrap();
+        exec.set_balance(CALLER, U256::from_u64(1_000)).unwrap();
+
+        // CALL(gas, callee, value=0, 0, 0, 0, 0); RETURN 结果
+        let mut bytecode = code("60006000600060006000");
+        bytecode.push(0x73);
+        bytecode.extend_from_slice(&callee);
+        bytecode.extend(code("5af160005260206000f3"));
+
+        let mut context = ctx(200_000);
+        context.value = 100;
+        let result = exec.execute(&bytecode, &[], &context).unwrap();
+        assert!(result.success);
+        assert_eq!(U256::from_be_slice(&result.return_data), U256::ONE);
+
+        exec.apply_state_changes(&result.state_changes).unwrap();
+        assert_eq!(
+            exec.storage_at(callee, U256::ZERO).unwrap(),
+            U256::from_address(&CONTRACT)
+        );
+        assert_eq!(exec.balance(CALLER).unwrap(), U256::from_u64(900));
+        assert_eq!(exec.balance(CONTRACT).unwrap(), U256::from_u64(100));
+
+        // 余额不足
+        context.value = 10_000;
+        assert!(!exec.execute(&bytecode, &[], &context).unwrap().success);
+    }
+
+    #[test]
+    fn test_self_call_recursion_reaches_max_depth() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        // CALL(gas, ADDRESS, 0, 0, 0, 0, 0); STOP —— 无限自调用，应止于深度上限而非耗尽宿主栈
+        let result = exec
+            .execute(
+                &code("60006000600060006000305af100"),
+                &[],
+                &ctx(1_000_000_000),
+            )
+            .unwrap();
+        assert!(result.success);
+
+        // 每层先 SSTORE(0, SLOAD(0) + 1) 再自调用：深度 0..=MAX_CALL_DEPTH 各执行一次
+        let bytecode = code("60016000540160005560006000600060006000305af100");
+        let result = exec.execute(&bytecode, &[], &ctx(1_000_000_000)).unwrap();
+        assert!(result.success);
+        exec.apply_state_changes(&result.state_changes).unwrap();
+        assert_eq!(
+            exec.storage_at(CONTRACT, U256::ZERO).unwrap(),
+            U256::from_u64(crate::interpreter::MAX_CALL_DEPTH as u64 + 1)
+        );
+    }
+
+    #[test]
+    fn test_validate_code_and_engine_type() {
+        let exec = EvmExecutor::new(MemoryStorage::new());
+        assert_eq!(exec.engine_type(), EngineType::Evm);
+        assert!(exec.validate_code(&[]).is_err());
+        assert!(exec.validate_code(&[0xef, 0x00]).is_err());
+        assert!(exec.validate_code(&vec![0u8; MAX_CODE_SIZE + 1]).is_err());
+        assert!(exec.validate_code(&[0x00]).is_ok());
+    }
+}
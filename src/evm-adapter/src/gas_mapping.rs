@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! EVM Gas 计量映射
+//!
+//! 采用 Cancun 规则（EIP-2929 冷/热访问、EIP-2200 SSTORE 计费、EIP-3860 之前的 CREATE 不支持）。
+//! EVM gas 与 SuperVM gas 按 1:1 映射：`ExecutionContext::gas_limit` 即 EVM 调用的 gas 上限，
+//! `ContractResult::gas_used` 即 EVM 实际消耗（不含 21000 交易基础成本，也不计 SSTORE 退款）。
+
+pub const ZERO: u64 = 0;
+pub const BASE: u64 = 2;
+pub const VERYLOW: u64 = 3;
+pub const LOW: u64 = 5;
+pub const MID: u64 = 8;
+pub const HIGH: u64 = 10;
+pub const JUMPDEST: u64 = 1;
+
+pub const EXP: u64 = 10;
+pub const EXP_BYTE: u64 = 50;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256_WORD: u64 = 6;
+pub const COPY_WORD: u64 = 3;
+pub const MEMORY_WORD: u64 = 3;
+pub const QUAD_COEFF_DIV: u64 = 512;
+pub const BLOCKHASH: u64 = 20;
+
+pub const LOG: u64 = 375;
+pub const LOG_TOPIC: u64 = 375;
+pub const LOG_DATA: u64 = 8;
+
+/// EIP-2929 冷/热访问
+pub const WARM_STORAGE_READ: u64 = 100;
+pub const COLD_SLOAD: u64 = 2_100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2_600;
+
+/// EIP-2200 SSTORE
+pub const SSTORE_SET: u64 = 20_000;
+pub const SSTORE_RESET: u64 = 2_900;
+pub const SSTORE_SENTRY: u64 = 2_300;
+
+/// EIP-1153 瞬态存储
+pub const TRANSIENT: u64 = 100;
+
+pub const CALL_VALUE: u64 = 9_000;
+pub const CALL_STIPEND: u64 = 2_300;
+pub const SELFDESTRUCT: u64 = 5_000;
+
+/// 32 字节字数（向上取整）
+pub fn words(bytes: u64) -> u64 {
+    bytes.div_ceil(32)
+}
+
+/// 内存总成本：`3·w + w²/512`
+pub fn memory_cost(words: u64) -> u64 {
+    MEMORY_WORD
+        .saturating_mul(words)
+        .saturating_add(words.saturating_mul(words) / QUAD_COEFF_DIV)
+}
+
+/// CALLDATACOPY / CODECOPY / RETURNDATACOPY / MCOPY 的按字复制成本
+pub fn copy_cost(len: u64) -> u64 {
+    COPY_WORD.saturating_mul(words(len))
+}
+
+pub fn keccak256_cost(len: u64) -> u64 {
+    KECCAK256.saturating_add(KECCAK256_WORD.saturating_mul(words(len)))
+}
+
+pub fn log_cost(topics: usize, len: u64) -> u64 {
+    LOG.saturating_add(LOG_TOPIC * topics as u64)
+        .saturating_add(LOG_DATA.saturating_mul(len))
+}
+
+/// EXP 成本：按指数有效字节数计费
+pub fn exp_cost(exponent_bits: usize) -> u64 {
+    EXP + EXP_BYTE * (exponent_bits as u64).div_ceil(8)
+}
+
+/// 账户访问成本（BALANCE / EXTCODE* / CALL*）
+pub fn account_access_cost(is_cold: bool) -> u64 {
+    if is_cold {
+        COLD_ACCOUNT_ACCESS
+    } else {
+        WARM_STORAGE_READ
+    }
+}
+
+pub fn sload_cost(is_cold: bool) -> u64 {
+    if is_cold {
+        COLD_SLOAD
+    } else {
+        WARM_STORAGE_READ
+    }
+}
+
+/// SSTORE 成本（EIP-2200 + EIP-2929，不计退款）
+pub fn sstore_cost(original: &[u8; 32], current: &[u8; 32], new: &[u8; 32], is_cold: bool) -> u64 {
+    let cold = if is_cold { COLD_SLOAD } else { 0 };
+    let base = if current == new {
+        WARM_STORAGE_READ
+    } else if original == current {
+        if original.iter().all(|b| *b == 0) {
+            SSTORE_SET
+        } else {
+            SSTORE_RESET
+        }
+    } else {
+        WARM_STORAGE_READ
+    };
+    base + cold
+}
+
+/// EIP-150: 子调用最多获得剩余 gas 的 63/64
+pub fn max_call_gas(gas_left: u64) -> u64 {
+    gas_left - gas_left / 64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_and_sstore_costs() {
+        assert_eq!(memory_cost(1), 3);
+        assert_eq!(memory_cost(1024), 3 * 1024 + 2048);
+        assert_eq!(copy_cost(33), 6);
+        assert_eq!(exp_cost(0), 10);
+        assert_eq!(exp_cost(9), 110);
+
+        let zero = [0u8; 32];
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut two = [0u8; 32];
+        two[31] = 2;
+        assert_eq!(
+            sstore_cost(&zero, &zero, &one, true),
+            SSTORE_SET + COLD_SLOAD
+        );
+        assert_eq!(sstore_cost(&one, &one, &two, false), SSTORE_RESET);
+        assert_eq!(sstore_cost(&zero, &one, &two, false), WARM_STORAGE_READ);
+        assert_eq!(sstore_cost(&one, &one, &one, false), WARM_STORAGE_READ);
+        assert_eq!(max_call_gas(6400), 6300);
+    }
+}
@@ -0,0 +1,833 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! EVM 字节码解释器
+//!
+//! 覆盖 Cancun 指令集（含 PUSH0 / MCOPY / TLOAD / TSTORE），限制如下:
+//! - CREATE / CREATE2 未支持（执行即异常终止）；合约通过 [`crate::EvmExecutor::install_code`] 部署
+//! - BLOCKHASH / COINBASE / PREVRANDAO / BASEFEE / GASPRICE / BLOBHASH / BLOBBASEFEE 返回 0
+//! - 调用深度上限为 [`MAX_CALL_DEPTH`]（低于以太坊的 1024）；嵌套调用按递归实现，前
+//!   [`INLINE_CALL_DEPTH`] 层在调用方线程上运行，更深的调用子树移到栈大小为 [`CALL_STACK_SIZE`]
+//!   的线程上执行
+//! - 内存上限为 [`MAX_MEMORY_BYTES`]，超出视为 gas 耗尽
+
+use crate::gas_mapping as gas;
+use crate::precompiles;
+use crate::state::{Address, JournaledState};
+use crate::u256::U256;
+use anyhow::Result;
+use sha3::{Digest, Keccak256};
+use vm_runtime::{Log, Storage};
+
+/// 最大调用深度
+pub const MAX_CALL_DEPTH: usize = 256;
+/// 在调用方线程上直接执行的嵌套深度；达到该深度的调用连同其子树移到大栈线程上执行，
+/// 浅调用（绝大多数交易）因此不派生线程
+pub const INLINE_CALL_DEPTH: usize = 4;
+/// 深层调用线程的栈大小（覆盖 MAX_CALL_DEPTH 层递归，含调试构建余量）
+pub const CALL_STACK_SIZE: usize = 64 * 1024 * 1024;
+/// 栈深度上限
+pub const STACK_LIMIT: usize = 1024;
+/// 单帧内存上限（64 MiB）
+pub const MAX_MEMORY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 异常终止原因（消耗全部 gas，撤销本帧状态）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HaltReason {
+    OutOfGas,
+    StackUnderflow,
+    StackOverflow,
+    InvalidJump,
+    InvalidOpcode(u8),
+    StaticStateChange,
+    ReturnDataOutOfBounds,
+    PrecompileFailed,
+    CreateNotSupported,
+}
+
+/// 调用帧退出方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exit {
+    Stop,
+    Return,
+    Revert,
+    Halt(HaltReason),
+}
+
+impl Exit {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Exit::Stop | Exit::Return)
+    }
+}
+
+/// 调用结果
+#[derive(Debug, Clone)]
+pub struct CallOutcome {
+    pub exit: Exit,
+    pub gas_left: u64,
+    pub output: Vec<u8>,
+}
+
+/// 区块/交易环境
+#[derive(Debug, Clone)]
+pub struct Env {
+    pub chain_id: u64,
+    pub origin: Address,
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub block_gas_limit: u64,
+}
+
+/// 调用消息
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub caller: Address,
+    /// 存储与余额上下文
+    pub address: Address,
+    /// 代码来源（DELEGATECALL / CALLCODE 时与 address 不同）
+    pub code_address: Address,
+    pub value: U256,
+    /// 是否需要从 caller 向 address 转账
+    pub transfer: bool,
+    pub input: Vec<u8>,
+    pub gas: u64,
+    pub is_static: bool,
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+}
+
+/// 单步执行的非正常控制流
+enum Fault {
+    Halt(HaltReason),
+    /// 存储访问失败等宿主错误，直接中止整个执行
+    Fatal(anyhow::Error),
+}
+
+impl From<HaltReason> for Fault {
+    fn from(reason: HaltReason) -> Self {
+        Fault::Halt(reason)
+    }
+}
+
+impl From<anyhow::Error> for Fault {
+    fn from(err: anyhow::Error) -> Self {
+        Fault::Fatal(err)
+    }
+}
+
+struct Frame<'c> {
+    code: &'c [u8],
+    jumpdests: Vec<bool>,
+    pc: usize,
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    gas_left: u64,
+    return_data: Vec<u8>,
+}
+
+impl<'c> Frame<'c> {
+    fn new(code: &'c [u8], gas: u64) -> Self {
+        Self {
+            code,
+            jumpdests: analyze_jumpdests(code),
+            pc: 0,
+            stack: Vec::with_capacity(64),
+            memory: Vec::new(),
+            gas_left: gas,
+            return_data: Vec::new(),
+        }
+    }
+
+    fn charge(&mut self, cost: u64) -> Result<(), HaltReason> {
+        if cost > self.gas_left {
+            self.gas_left = 0;
+            return Err(HaltReason::OutOfGas);
+        }
+        self.gas_left -= cost;
+        Ok(())
+    }
+
+    fn push(&mut self, value: U256) -> Result<(), HaltReason> {
+        if self.stack.len() >= STACK_LIMIT {
+            return Err(HaltReason::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    /// 弹出 N 个元素（栈顶在前）
+    fn pop_n<const N: usize>(&mut self) -> Result<[U256; N], HaltReason> {
+        if self.stack.len() < N {
+            return Err(HaltReason::StackUnderflow);
+        }
+        let mut out = [U256::ZERO; N];
+        for slot in out.iter_mut() {
+            *slot = self.stack.pop().expect("stack length checked");
+        }
+        Ok(out)
+    }
+
+    fn pop(&mut self) -> Result<U256, HaltReason> {
+        Ok(self.pop_n::<1>()?[0])
+    }
+
+    /// 扩展内存以覆盖 [offset, offset+len) 并计费，返回 usize 区间
+    fn expand(&mut self, offset: U256, len: U256) -> Result<(usize, usize), HaltReason> {
+        if len.is_zero() {
+            return Ok((0, 0));
+        }
+        let (Some(offset), Some(len)) = (offset.to_u64(), len.to_u64()) else {
+            return Err(HaltReason::OutOfGas);
+        };
+        let end = offset.checked_add(len).ok_or(HaltReason::OutOfGas)?;
+        if end > MAX_MEMORY_BYTES {
+            return Err(HaltReason::OutOfGas);
+        }
+        let new_words = gas::words(end);
+        let old_words = self.memory.len() as u64 / 32;
+        if new_words > old_words {
+            self.charge(gas::memory_cost(new_words) - gas::memory_cost(old_words))?;
+            self.memory.resize((new_words * 32) as usize, 0);
+        }
+        Ok((offset as usize, len as usize))
+    }
+
+    fn mem(&self, offset: usize, len: usize) -> &[u8] {
+        &self.memory[offset..offset + len]
+    }
+}
+
+/// 标记合法跳转目标（跳过 PUSH 立即数）
+fn analyze_jumpdests(code: &[u8]) -> Vec<bool> {
+    let mut dests = vec![false; code.len()];
+    let mut i = 0;
+    while i < code.len() {
+        let op = code[i];
+        if op == 0x5b {
+            dests[i] = true;
+        } else if (0x60..=0x7f).contains(&op) {
+            i += (op - 0x5f) as usize;
+        }
+        i += 1;
+    }
+    dests
+}
+
+/// 从 data[offset..] 复制 len 字节，越界部分补零
+fn copy_padded(dst: &mut [u8], data: &[u8], offset: U256) {
+    let start = offset.to_u64().map(|o| o as usize).unwrap_or(usize::MAX);
+    if start < data.len() {
+        let n = dst.len().min(data.len() - start);
+        dst[..n].copy_from_slice(&data[start..start + n]);
+        dst[n..].fill(0);
+    } else {
+        dst.fill(0);
+    }
+}
+
+fn bool_word(b: bool) -> U256 {
+    if b {
+        U256::ONE
+    } else {
+        U256::ZERO
+    }
+}
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// EVM 解释器（一次顶层调用对应一个实例）
+pub struct Interpreter<'a, S: Storage> {
+    pub state: JournaledState<'a, S>,
+    env: Env,
+}
+
+impl<'a, S: Storage + Sync> Interpreter<'a, S> {
+    pub fn new(state: JournaledState<'a, S>, env: Env) -> Self {
+        Self { state, env }
+    }
+
+    /// 执行调用消息：转账、预编译分派、加载代码并运行
+    ///
+    /// 失败（回滚/异常）时撤销本次调用的全部状态修改。深度为 [`INLINE_CALL_DEPTH`] 的调用
+    /// 在栈大小为 [`CALL_STACK_SIZE`] 的作用域线程上执行，其下的嵌套调用沿用该线程。
+    pub fn call(&mut self, msg: Message) -> Result<CallOutcome> {
+        if msg.depth != INLINE_CALL_DEPTH {
+            return self.call_inline(msg);
+        }
+        std::thread::scope(|scope| {
+            let handle = std::thread::Builder::new()
+                .name("evm-call".into())
+                .stack_size(CALL_STACK_SIZE)
+                .spawn_scoped(scope, || self.call_inline(msg))?;
+            handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    fn call_inline(&mut self, msg: Message) -> Result<CallOutcome> {
+        let checkpoint = self.state.checkpoint();
+        if msg.transfer && !self.state.transfer(msg.caller, msg.address, msg.value)? {
+            // 调用方已检查余额，此处仅作防御
+            return Ok(CallOutcome {
+                exit: Exit::Revert,
+                gas_left: msg.gas,
+                output: Vec::new(),
+            });
+        }
+
+        let outcome = if precompiles::is_precompile(&msg.code_address) {
+            match precompiles::run(&msg.code_address, &msg.input, msg.gas) {
+                Ok(out) => CallOutcome {
+                    exit: Exit::Return,
+                    gas_left: msg.gas - out.gas_used,
+                    output: out.output,
+                },
+                Err(_) => CallOutcome {
+                    exit: Exit::Halt(HaltReason::PrecompileFailed),
+                    gas_left: 0,
+                    output: Vec::new(),
+                },
+            }
+        } else {
+            let code = self.state.code(msg.code_address)?;
+            if code.is_empty() {
+                CallOutcome {
+                    exit: Exit::Stop,
+                    gas_left: msg.gas,
+                    output: Vec::new(),
+                }
+            } else {
+                self.run(&msg, &code)?
+            }
+        };
+
+        if !outcome.exit.is_success() {
+            self.state.revert(checkpoint);
+        }
+        Ok(outcome)
+    }
+
+    fn run(&mut self, msg: &Message, code: &[u8]) -> Result<CallOutcome> {
+        let mut frame = Frame::new(code, msg.gas);
+        loop {
+            match self.step(msg, &mut frame) {
+                Ok(None) => continue,
+                Ok(Some((exit, output))) => {
+                    return Ok(CallOutcome {
+                        exit,
+                        gas_left: frame.gas_left,
+                        output,
+                    });
+                }
+                Err(Fault::Halt(reason)) => {
+                    return Ok(CallOutcome {
+                        exit: Exit::Halt(reason),
+                        gas_left: 0,
+                        output: Vec::new(),
+                    });
+                }
+                Err(Fault::Fatal(err)) => return Err(err),
+            }
+        }
+    }
+
+    /// 执行单条指令；返回 Some 表示帧结束
+    fn step(&mut self, msg: &Message, f: &mut Frame<'_>) -> Result<Option<(Exit, Vec<u8>)>, Fault> {
+        let Some(&op) = f.code.get(f.pc) else {
+            return Ok(Some((Exit::Stop, Vec::new())));
+        };
+        f.pc += 1;
+
+        match op {
+            0x00 => return Ok(Some((Exit::Stop, Vec::new()))),
+
+            // ---------- 算术 ----------
+            0x01 => binary(f, gas::VERYLOW, |a, b| a.wrapping_add(&b))?,
+            0x02 => binary(f, gas::LOW, |a, b| a.wrapping_mul(&b))?,
+            0x03 => binary(f, gas::VERYLOW, |a, b| a.wrapping_sub(&b))?,
+            0x04 => binary(f, gas::LOW, |a, b| a.div(&b))?,
+            0x05 => binary(f, gas::LOW, |a, b| a.sdiv(&b))?,
+            0x06 => binary(f, gas::LOW, |a, b| a.rem(&b))?,
+            0x07 => binary(f, gas::LOW, |a, b| a.smod(&b))?,
+            0x08 => {
+                f.charge(gas::MID)?;
+                let [a, b, n] = f.pop_n()?;
+                f.push(a.addmod(&b, &n))?;
+            }
+            0x09 => {
+                f.charge(gas::MID)?;
+                let [a, b, n] = f.pop_n()?;
+                f.push(a.mulmod(&b, &n))?;
+            }
+            0x0a => {
+                let [base, exponent] = f.pop_n()?;
+                f.charge(gas::exp_cost(exponent.bits()))?;
+                f.push(base.wrapping_pow(&exponent))?;
+            }
+            0x0b => binary(f, gas::LOW, |b, x| x.signextend(&b))?,
+
+            // ---------- 比较与位运算 ----------
+            0x10 => binary(f, gas::VERYLOW, |a, b| bool_word(a < b))?,
+            0x11 => binary(f, gas::VERYLOW, |a, b| bool_word(a > b))?,
+            0x12 => binary(f, gas::VERYLOW, |a, b| bool_word(a.slt(&b)))?,
+            0x13 => binary(f, gas::VERYLOW, |a, b| bool_word(b.slt(&a)))?,
+            0x14 => binary(f, gas::VERYLOW, |a, b| bool_word(a == b))?,
+            0x15 => {
+                f.charge(gas::VERYLOW)?;
+                let a = f.pop()?;
+                f.push(bool_word(a.is_zero()))?;
+            }
+            0x16 => binary(f, gas::VERYLOW, |a, b| a & b)?,
+            0x17 => binary(f, gas::VERYLOW, |a, b| a | b)?,
+            0x18 => binary(f, gas::VERYLOW, |a, b| a ^ b)?,
+            0x19 => {
+                f.charge(gas::VERYLOW)?;
+                let a = f.pop()?;
+                f.push(!a)?;
+            }
+            0x1a => binary(f, gas::VERYLOW, |i, x| x.byte(&i))?,
+            0x1b => binary(f, gas::VERYLOW, |s, x| x.shl(s.as_shift()))?,
+            0x1c => binary(f, gas::VERYLOW, |s, x| x.shr(s.as_shift()))?,
+            0x1d => binary(f, gas::VERYLOW, |s, x| x.sar(s.as_shift()))?,
+
+            // ---------- KECCAK256 ----------
+            0x20 => {
+                let [offset, len] = f.pop_n()?;
+                f.charge(gas::keccak256_cost(len.low_u64()))?;
+                let (offset, len) = f.expand(offset, len)?;
+                let hash = keccak(f.mem(offset, len));
+                f.push(U256::from_be_bytes(&hash))?;
+            }
+
+            // ---------- 执行环境 ----------
+            0x30 => push_env(f, U256::from_address(&msg.address))?,
+            0x31 => {
+                let address = f.pop()?.to_address();
+                let cold = self.state.touch_account(address);
+                f.charge(gas::account_access_cost(cold))?;
+                let balance = self.state.balance(address)?;
+                f.push(balance)?;
+            }
+            0x32 => push_env(f, U256::from_address(&self.env.origin))?,
+            0x33 => push_env(f, U256::from_address(&msg.caller))?,
+            0x34 => push_env(f, msg.value)?,
+            0x35 => {
+                f.charge(gas::VERYLOW)?;
+                let offset = f.pop()?;
+                let mut word = [0u8; 32];
+                copy_padded(&mut word, &msg.input, offset);
+                f.push(U256::from_be_bytes(&word))?;
+            }
+            0x36 => push_env(f, U256::from_u64(msg.input.len() as u64))?,
+            0x37 => copy_to_memory(f, &msg.input)?,
+            0x38 => push_env(f, U256::from_u64(f.code.len() as u64))?,
+            0x39 => {
+                let code = f.code;
+                copy_to_memory(f, code)?;
+            }
+            0x3a => push_env(f, U256::ZERO)?,
+            0x3b => {
+                let address = f.pop()?.to_address();
+                let cold = self.state.touch_account(address);
+                f.charge(gas::account_access_cost(cold))?;
+                let len = self.state.code(address)?.len();
+                f.push(U256::from_u64(len as u64))?;
+            }
+            0x3c => {
+                let [address, dest, offset, len] = f.pop_n()?;
+                let address = address.to_address();
+                let cold = self.state.touch_account(address);
+                f.charge(gas::account_access_cost(cold))?;
+                f.charge(gas::copy_cost(len.low_u64()))?;
+                let (dest, len) = f.expand(dest, len)?;
+                let code = self.state.code(address)?;
+                copy_padded(&mut f.memory[dest..dest + len], &code, offset);
+            }
+            0x3d => push_env(f, U256::from_u64(f.return_data.len() as u64))?,
+            0x3e => {
+                let [dest, offset, len] = f.pop_n()?;
+                f.charge(gas::VERYLOW)?;
+                let end = offset
+                    .to_u64()
+                    .zip(len.to_u64())
+                    .and_then(|(o, l)| o.checked_add(l));
+                match end {
+                    Some(end) if end <= f.return_data.len() as u64 => {}
+                    _ => return Err(HaltReason::ReturnDataOutOfBounds.into()),
+                }
+                f.charge(gas::copy_cost(len.low_u64()))?;
+                let (dest, len) = f.expand(dest, len)?;
+                let start = offset.low_u64() as usize;
+                f.memory[dest..dest + len].copy_from_slice(&f.return_data[start..start + len]);
+            }
+            0x3f => {
+                let address = f.pop()?.to_address();
+                let cold = self.state.touch_account(address);
+                f.charge(gas::account_access_cost(cold))?;
+                let code = self.state.code(address)?;
+                let hash = if code.is_empty() && self.state.balance(address)?.is_zero() {
+                    U256::ZERO
+                } else {
+                    U256::from_be_bytes(&keccak(&code))
+                };
+                f.push(hash)?;
+            }
+
+            // ---------- 区块信息 ----------
+            0x40 => {
+                f.charge(gas::BLOCKHASH)?;
+                f.pop()?;
+                f.push(U256::ZERO)?;
+            }
+            0x41 => push_env(f, U256::ZERO)?,
+            0x42 => push_env(f, U256::from_u64(self.env.timestamp))?,
+            0x43 => push_env(f, U256::from_u64(self.env.block_number))?,
+            0x44 => push_env(f, U256::ZERO)?,
+            0x45 => push_env(f, U256::from_u64(self.env.block_gas_limit))?,
+            0x46 => push_env(f, U256::from_u64(self.env.chain_id))?,
+            0x47 => {
+                f.charge(gas::LOW)?;
+                let balance = self.state.balance(msg.address)?;
+                f.push(balance)?;
+            }
+            0x48 => push_env(f, U256::ZERO)?,
+            0x49 => {
+                f.charge(gas::VERYLOW)?;
+                f.pop()?;
+                f.push(U256::ZERO)?;
+            }
+            0x4a => push_env(f, U256::ZERO)?,
+
+            // ---------- 栈 / 内存 / 存储 / 控制流 ----------
+            0x50 => {
+                f.charge(gas::BASE)?;
+                f.pop()?;
+            }
+            0x51 => {
+                f.charge(gas::VERYLOW)?;
+                let offset = f.pop()?;
+                let (offset, _) = f.expand(offset, U256::from_u64(32))?;
+                let word: [u8; 32] = f.mem(offset, 32).try_into().expect("32 bytes");
+                f.push(U256::from_be_bytes(&word))?;
+            }
+            0x52 => {
+                f.charge(gas::VERYLOW)?;
+                let [offset, value] = f.pop_n()?;
+                let (offset, _) = f.expand(offset, U256::from_u64(32))?;
+                f.memory[offset..offset + 32].copy_from_slice(&value.to_be_bytes());
+            }
+            0x53 => {
+                f.charge(gas::VERYLOW)?;
+                let [offset, value] = f.pop_n()?;
+                let (offset, _) = f.expand(offset, U256::ONE)?;
+                f.memory[offset] = value.low_u64() as u8;
+            }
+            0x54 => {
+                let slot = f.pop()?;
+                let cold = self.state.touch_slot(msg.address, slot);
+                f.charge(gas::sload_cost(cold))?;
+                let value = self.state.sload(msg.address, slot)?;
+                f.push(value)?;
+            }
+            0x55 => {
+                if msg.is_static {
+                    return Err(HaltReason::StaticStateChange.into());
+                }
+                // EIP-2200: 剩余 gas 不超过 stipend 时禁止写入
+                if f.gas_left <= gas::SSTORE_SENTRY {
+                    return Err(HaltReason::OutOfGas.into());
+                }
+                let [slot, value] = f.pop_n()?;
+                let cold = self.state.touch_slot(msg.address, slot);
+                let original = self.state.original(msg.address, slot)?;
+                let current = self.state.sload(msg.address, slot)?;
+                f.charge(gas::sstore_cost(
+                    &original.to_be_bytes(),
+                    &current.to_be_bytes(),
+                    &value.to_be_bytes(),
+                    cold,
+                ))?;
+                self.state.sstore(msg.address, slot, value);
+            }
+            0x56 => {
+                f.charge(gas::MID)?;
+                let dest = f.pop()?;
+                jump(f, dest)?;
+            }
+            0x57 => {
+                f.charge(gas::HIGH)?;
+                let [dest, cond] = f.pop_n()?;
+                if !cond.is_zero() {
+                    jump(f, dest)?;
+                }
+            }
+            0x58 => push_env(f, U256::from_u64((f.pc - 1) as u64))?,
+            0x59 => push_env(f, U256::from_u64(f.memory.len() as u64))?,
+            0x5a => {
+                f.charge(gas::BASE)?;
+                f.push(U256::from_u64(f.gas_left))?;
+            }
+            0x5b => f.charge(gas::JUMPDEST)?,
+            0x5c => {
+                f.charge(gas::TRANSIENT)?;
+                let slot = f.pop()?;
+                f.push(self.state.tload(msg.address, slot))?;
+            }
+            0x5d => {
+                if msg.is_static {
+                    return Err(HaltReason::StaticStateChange.into());
+                }
+                f.charge(gas::TRANSIENT)?;
+                let [slot, value] = f.pop_n()?;
+                self.state.tstore(msg.address, slot, value);
+            }
+            0x5e => {
+                let [dest, src, len] = f.pop_n()?;
+                f.charge(gas::VERYLOW + gas::copy_cost(len.low_u64()))?;
+                // 源与目标区间都需要扩展内存
+                f.expand(src, len)?;
+                let (dest, len) = f.expand(dest, len)?;
+                if len > 0 {
+                    let src = src.low_u64() as usize;
+                    f.memory.copy_within(src..src + len, dest);
+                }
+            }
+            0x5f => push_env(f, U256::ZERO)?,
+
+            // ---------- PUSH / DUP / SWAP ----------
+            0x60..=0x7f => {
+                f.charge(gas::VERYLOW)?;
+                let n = (op - 0x5f) as usize;
+                let mut bytes = [0u8; 32];
+                let start = f.pc.min(f.code.len());
+                let end = (f.pc + n).min(f.code.len());
+                bytes[32 - n..32 - n + (end - start)].copy_from_slice(&f.code[start..end]);
+                f.pc += n;
+                f.push(U256::from_be_bytes(&bytes))?;
+            }
+            0x80..=0x8f => {
+                f.charge(gas::VERYLOW)?;
+                let n = (op - 0x7f) as usize;
+                if f.stack.len() < n {
+                    return Err(HaltReason::StackUnderflow.into());
+                }
+                let value = f.stack[f.stack.len() - n];
+                f.push(value)?;
+            }
+            0x90..=0x9f => {
+                f.charge(gas::VERYLOW)?;
+                let n = (op - 0x8f) as usize;
+                let len = f.stack.len();
+                if len <= n {
+                    return Err(HaltReason::StackUnderflow.into());
+                }
+                f.stack.swap(len - 1, len - 1 - n);
+            }
+
+            // ---------- LOG ----------
+            0xa0..=0xa4 => {
+                if msg.is_static {
+                    return Err(HaltReason::StaticStateChange.into());
+                }
+                let n = (op - 0xa0) as usize;
+                let [offset, len] = f.pop_n()?;
+                f.charge(gas::log_cost(n, len.low_u64()))?;
+                let mut topics = Vec::with_capacity(n);
+                for _ in 0..n {
+                    topics.push(f.pop()?.to_be_bytes());
+                }
+                let (offset, len) = f.expand(offset, len)?;
+                let data = f.mem(offset, len).to_vec();
+                self.state.log(Log {
+                    address: msg.address,
+                    topics,
+                    data,
+                });
+            }
+
+            // ---------- 调用 ----------
+            0xf0 | 0xf5 => return Err(HaltReason::CreateNotSupported.into()),
+            0xf1 => self.call_op(msg, f, CallKind::Call)?,
+            0xf2 => self.call_op(msg, f, CallKind::CallCode)?,
+            0xf4 => self.call_op(msg, f, CallKind::DelegateCall)?,
+            0xfa => self.call_op(msg, f, CallKind::StaticCall)?,
+            0xf3 | 0xfd => {
+                let [offset, len] = f.pop_n()?;
+                let (offset, len) = f.expand(offset, len)?;
+                let output = f.mem(offset, len).to_vec();
+                let exit = if op == 0xf3 {
+                    Exit::Return
+                } else {
+                    Exit::Revert
+                };
+                return Ok(Some((exit, output)));
+            }
+            0xff => {
+                if msg.is_static {
+                    return Err(HaltReason::StaticStateChange.into());
+                }
+                let beneficiary = f.pop()?.to_address();
+                let cold = self.state.touch_account(beneficiary);
+                f.charge(gas::SELFDESTRUCT + if cold { gas::COLD_ACCOUNT_ACCESS } else { 0 })?;
+                // EIP-6780: 非同一交易内创建的合约仅转出余额，不删除账户
+                let balance = self.state.balance(msg.address)?;
+                self.state.transfer(msg.address, beneficiary, balance)?;
+                return Ok(Some((Exit::Stop, Vec::new())));
+            }
+            other => return Err(HaltReason::InvalidOpcode(other).into()),
+        }
+        Ok(None)
+    }
+
+    fn call_op(&mut self, msg: &Message, f: &mut Frame<'_>, kind: CallKind) -> Result<(), Fault> {
+        let gas_arg = f.pop()?;
+        let target = f.pop()?.to_address();
+        let value = match kind {
+            CallKind::Call | CallKind::CallCode => f.pop()?,
+            CallKind::DelegateCall | CallKind::StaticCall => U256::ZERO,
+        };
+        let [in_offset, in_len, out_offset, out_len] = f.pop_n()?;
+
+        if kind == CallKind::Call && msg.is_static && !value.is_zero() {
+            return Err(HaltReason::StaticStateChange.into());
+        }
+
+        let (in_offset, in_len) = f.expand(in_offset, in_len)?;
+        let (out_offset, out_len) = f.expand(out_offset, out_len)?;
+
+        let cold = self.state.touch_account(target);
+        let mut cost = gas::account_access_cost(cold);
+        if !value.is_zero() {
+            cost += gas::CALL_VALUE;
+        }
+        f.charge(cost)?;
+
+        let mut child_gas = gas_arg
+            .to_u64()
+            .unwrap_or(u64::MAX)
+            .min(gas::max_call_gas(f.gas_left));
+        f.charge(child_gas)?;
+        if !value.is_zero() {
+            child_gas += gas::CALL_STIPEND;
+        }
+
+        f.return_data.clear();
+        let affordable = value.is_zero() || self.state.balance(msg.address)? >= value;
+        if msg.depth + 1 > MAX_CALL_DEPTH || !affordable {
+            f.gas_left += child_gas;
+            return Ok(f.push(U256::ZERO)?);
+        }
+
+        let input = f.mem(in_offset, in_len).to_vec();
+        let child = match kind {
+            CallKind::Call => Message {
+                caller: msg.address,
+                address: target,
+                code_address: target,
+                value,
+                transfer: true,
+                input,
+                gas: child_gas,
+                is_static: msg.is_static,
+                depth: msg.depth + 1,
+            },
+            CallKind::CallCode => Message {
+                caller: msg.address,
+                address: msg.address,
+                code_address: target,
+                value,
+                transfer: true,
+                input,
+                gas: child_gas,
+                is_static: msg.is_static,
+                depth: msg.depth + 1,
+            },
+            CallKind::DelegateCall => Message {
+                caller: msg.caller,
+                address: msg.address,
+                code_address: target,
+                value: msg.value,
+                transfer: false,
+                input,
+                gas: child_gas,
+                is_static: msg.is_static,
+                depth: msg.depth + 1,
+            },
+            CallKind::StaticCall => Message {
+                caller: msg.address,
+                address: target,
+                code_address: target,
+                value: U256::ZERO,
+                transfer: false,
+                input,
+                gas: child_gas,
+                is_static: true,
+                depth: msg.depth + 1,
+            },
+        };
+
+        let outcome = self.call(child)?;
+        f.gas_left += outcome.gas_left;
+        let n = out_len.min(outcome.output.len());
+        f.memory[out_offset..out_offset + n].copy_from_slice(&outcome.output[..n]);
+        f.return_data = outcome.output;
+        f.push(bool_word(outcome.exit.is_success()))?;
+        Ok(())
+    }
+}
+
+fn binary(
+    f: &mut Frame<'_>,
+    cost: u64,
+    op: impl FnOnce(U256, U256) -> U256,
+) -> Result<(), HaltReason> {
+    f.charge(cost)?;
+    let [a, b] = f.pop_n()?;
+    f.push(op(a, b))
+}
+
+fn push_env(f: &mut Frame<'_>, value: U256) -> Result<(), HaltReason> {
+    f.charge(gas::BASE)?;
+    f.push(value)
+}
+
+fn jump(f: &mut Frame<'_>, dest: U256) -> Result<(), HaltReason> {
+    match dest.to_u64() {
+        Some(d) if (d as usize) < f.jumpdests.len() && f.jumpdests[d as usize] => {
+            f.pc = d as usize;
+            Ok(())
+        }
+        _ => Err(HaltReason::InvalidJump),
+    }
+}
+
+/// CALLDATACOPY / CODECOPY: (destOffset, offset, size)
+fn copy_to_memory(f: &mut Frame<'_>, data: &[u8]) -> Result<(), HaltReason> {
+    let [dest, offset, len] = f.pop_n()?;
+    f.charge(gas::VERYLOW + gas::copy_cost(len.low_u64()))?;
+    let (dest, len) = f.expand(dest, len)?;
+    copy_padded(&mut f.memory[dest..dest + len], data, offset);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jumpdest_analysis_skips_push_data() {
+        // PUSH1 0x5b, JUMPDEST, PUSH2 0x5b 0x5b
+        let dests = analyze_jumpdests(&[0x60, 0x5b, 0x5b, 0x61, 0x5b, 0x5b]);
+        assert_eq!(dests, vec![false, false, true, false, false, false]);
+    }
+}
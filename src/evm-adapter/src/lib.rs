@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! SuperVM EVM 适配器 (L2)
+//!
+//! 在 vm-runtime 的 [`ExecutionEngine`](vm_runtime::ExecutionEngine) 接口之上提供 EVM 字节码执行，
+//! 账户存储映射到 [`Storage`](vm_runtime::Storage)，使 Solidity 合约
+//! （包括 `contracts/` 下生成的 Groth16 验证合约）可直接在 SuperVM 内运行。
+//! vm-runtime 不依赖本 crate，核心运行时保持纯净。
+
+pub mod evm_executor; // ExecutionEngine 的 EVM 实现
+pub mod gas_mapping; // Gas 计量映射
+pub mod interpreter; // 字节码解释器
+pub mod precompiles; // 预编译合约
+pub mod state; // 账户状态 ↔ Storage 映射
+pub mod u256; // 256 位 EVM 字
+
+pub use evm_executor::{
+    EvmConfig, EvmExecutor, EvmValidationError, DEFAULT_CHAIN_ID, MAX_CODE_SIZE,
+};
+pub use interpreter::{Exit, HaltReason};
+pub use u256::U256;
@@ -0,0 +1,462 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! EVM 预编译合约
+//!
+//! 已支持:
+//! - 0x01 ecrecover
+//! - 0x02 sha256
+//! - 0x04 identity
+//! - 0x05 modexp（EIP-2565 计费）
+//! - 0x06 / 0x07 / 0x08 BN254 ecAdd / ecMul / pairing（EIP-1108 计费，Groth16 验证合约依赖）
+//!
+//! 未支持（调用失败并耗尽转入的 gas）: 0x03 ripemd160、0x09 blake2f、0x0a point evaluation。
+
+use crate::u256::U256;
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G1Projective, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInt, One, PrimeField, Zero};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// 预编译地址上限（0x01..=0x0a 为保留区间）
+const MAX_PRECOMPILE: u8 = 0x0a;
+
+pub const ECRECOVER_GAS: u64 = 3_000;
+pub const SHA256_BASE: u64 = 60;
+pub const SHA256_WORD: u64 = 12;
+pub const IDENTITY_BASE: u64 = 15;
+pub const IDENTITY_WORD: u64 = 3;
+pub const MODEXP_MIN: u64 = 200;
+pub const BN254_ADD_GAS: u64 = 150;
+pub const BN254_MUL_GAS: u64 = 6_000;
+pub const BN254_PAIRING_BASE: u64 = 45_000;
+pub const BN254_PAIRING_PER_PAIR: u64 = 34_000;
+
+/// 预编译执行错误（调用方视为失败并耗尽 gas）
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PrecompileError {
+    #[error("precompile out of gas (required {required}, available {available})")]
+    OutOfGas { required: u64, available: u64 },
+    #[error("invalid precompile input: {0}")]
+    InvalidInput(&'static str),
+    #[error("precompile 0x{0:02x} is not supported")]
+    Unsupported(u8),
+}
+
+/// 预编译执行结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecompileOutput {
+    pub gas_used: u64,
+    pub output: Vec<u8>,
+}
+
+type PrecompileFn = fn(&[u8]) -> Result<Vec<u8>, PrecompileError>;
+
+/// 地址是否落在预编译区间
+pub fn is_precompile(address: &[u8; 20]) -> bool {
+    address[..19].iter().all(|b| *b == 0) && (1..=MAX_PRECOMPILE).contains(&address[19])
+}
+
+/// 执行预编译合约
+pub fn run(
+    address: &[u8; 20],
+    input: &[u8],
+    gas_limit: u64,
+) -> Result<PrecompileOutput, PrecompileError> {
+    let id = address[19];
+    let (required, f): (u64, PrecompileFn) = match id {
+        0x01 => (ECRECOVER_GAS, ecrecover),
+        0x02 => (linear_cost(SHA256_BASE, SHA256_WORD, input.len()), sha256),
+        0x04 => (
+            linear_cost(IDENTITY_BASE, IDENTITY_WORD, input.len()),
+            identity,
+        ),
+        0x05 => (modexp_cost(input), modexp),
+        0x06 => (BN254_ADD_GAS, bn254_add),
+        0x07 => (BN254_MUL_GAS, bn254_mul),
+        0x08 => (
+            BN254_PAIRING_BASE
+                .saturating_add(BN254_PAIRING_PER_PAIR.saturating_mul((input.len() / 192) as u64)),
+            bn254_pairing,
+        ),
+        other => return Err(PrecompileError::Unsupported(other)),
+    };
+    if required > gas_limit {
+        return Err(PrecompileError::OutOfGas {
+            required,
+            available: gas_limit,
+        });
+    }
+    Ok(PrecompileOutput {
+        gas_used: required,
+        output: f(input)?,
+    })
+}
+
+fn linear_cost(base: u64, per_word: u64, len: usize) -> u64 {
+    base.saturating_add(per_word.saturating_mul((len as u64).div_ceil(32)))
+}
+
+/// 读取 input[offset..offset+len]，越界部分补零
+fn padded(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    if offset < input.len() {
+        let end = input.len().min(offset.saturating_add(len));
+        out[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+    out
+}
+
+fn word(input: &[u8], offset: usize) -> [u8; 32] {
+    padded(input, offset, 32).try_into().expect("32 bytes")
+}
+
+// ============================================
+// 0x01 ecrecover
+// ============================================
+
+fn ecrecover(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let hash = word(input, 0);
+    let v = U256::from_be_bytes(&word(input, 32));
+    let r = word(input, 64);
+    let s = word(input, 96);
+
+    // 恢复失败时返回空输出（调用本身成功）
+    let recid = match v.to_u64() {
+        Some(27) => 0u8,
+        Some(28) => 1u8,
+        _ => return Ok(Vec::new()),
+    };
+    let Ok(sig) = Signature::from_scalars(r, s) else {
+        return Ok(Vec::new());
+    };
+    // 以太坊接受高 s 签名，而 k256 恢复时只接受低 s：取 n - s 并翻转 y 奇偶性，恢复出同一公钥
+    let (sig, recid) = match sig.normalize_s() {
+        Some(normalized) => (normalized, recid ^ 1),
+        None => (sig, recid),
+    };
+    let Some(recid) = RecoveryId::from_byte(recid) else {
+        return Ok(Vec::new());
+    };
+    let Ok(key) = VerifyingKey::recover_from_prehash(&hash, &sig, recid) else {
+        return Ok(Vec::new());
+    };
+    let point = key.to_encoded_point(false);
+    let digest = Keccak256::digest(&point.as_bytes()[1..]);
+    let mut out = vec![0u8; 32];
+    out[12..].copy_from_slice(&digest[12..]);
+    Ok(out)
+}
+
+// ============================================
+// 0x02 sha256 / 0x04 identity
+// ============================================
+
+fn sha256(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    Ok(Sha256::digest(input).to_vec())
+}
+
+fn identity(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    Ok(input.to_vec())
+}
+
+// ============================================
+// 0x05 modexp
+// ============================================
+
+/// 长度字段（超出 u32 范围的长度视为无法负担的 gas）
+fn length_field(input: &[u8], offset: usize) -> Option<u64> {
+    U256::from_be_bytes(&word(input, offset))
+        .to_u64()
+        .filter(|v| *v <= u32::MAX as u64)
+}
+
+/// EIP-2565 计费
+fn modexp_cost(input: &[u8]) -> u64 {
+    let (Some(base_len), Some(exp_len), Some(mod_len)) = (
+        length_field(input, 0),
+        length_field(input, 32),
+        length_field(input, 64),
+    ) else {
+        return u64::MAX;
+    };
+
+    let words = base_len.max(mod_len).div_ceil(8);
+    let complexity = words.saturating_mul(words);
+
+    let head_len = exp_len.min(32) as usize;
+    let exp_head = U256::from_be_slice(&padded(input, 96 + base_len as usize, head_len));
+    let head_bits = exp_head.bits() as u64;
+    let iterations = if exp_len <= 32 {
+        head_bits.saturating_sub(1)
+    } else {
+        (8 * (exp_len - 32)).saturating_add(head_bits.saturating_sub(1))
+    }
+    .max(1);
+
+    (complexity.saturating_mul(iterations) / 3).max(MODEXP_MIN)
+}
+
+fn modexp(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    // 计费已保证长度可被负担
+    let base_len = length_field(input, 0).unwrap_or(0) as usize;
+    let exp_len = length_field(input, 32).unwrap_or(0) as usize;
+    let mod_len = length_field(input, 64).unwrap_or(0) as usize;
+    if mod_len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let base = BigUint::from_bytes_be(&padded(input, 96, base_len));
+    let exponent = BigUint::from_bytes_be(&padded(input, 96 + base_len, exp_len));
+    let modulus = BigUint::from_bytes_be(&padded(input, 96 + base_len + exp_len, mod_len));
+
+    let mut out = vec![0u8; mod_len];
+    if modulus.is_zero() {
+        return Ok(out);
+    }
+    let result = base.modpow(&exponent, &modulus).to_bytes_be();
+    out[mod_len - result.len()..].copy_from_slice(&result);
+    Ok(out)
+}
+
+// ============================================
+// 0x06 / 0x07 / 0x08 BN254
+// ============================================
+
+fn read_fq(bytes: &[u8; 32]) -> Result<Fq, PrecompileError> {
+    Fq::from_bigint(BigInt::new(U256::from_be_bytes(bytes).0))
+        .ok_or(PrecompileError::InvalidInput("field element not in range"))
+}
+
+fn write_fq(out: &mut Vec<u8>, value: &Fq) {
+    out.extend_from_slice(&U256(value.into_bigint().0).to_be_bytes());
+}
+
+/// G1 点（64 字节，(0, 0) 表示无穷远点）
+fn read_g1(input: &[u8], offset: usize) -> Result<G1Affine, PrecompileError> {
+    let x = read_fq(&word(input, offset))?;
+    let y = read_fq(&word(input, offset + 32))?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    // BN254 G1 余因子为 1，曲线上即在子群中
+    if !point.is_on_curve() {
+        return Err(PrecompileError::InvalidInput("G1 point not on curve"));
+    }
+    Ok(point)
+}
+
+/// G2 点（128 字节，Fq2 按 (虚部, 实部) 编码）
+fn read_g2(input: &[u8], offset: usize) -> Result<G2Affine, PrecompileError> {
+    let x_im = read_fq(&word(input, offset))?;
+    let x_re = read_fq(&word(input, offset + 32))?;
+    let y_im = read_fq(&word(input, offset + 64))?;
+    let y_re = read_fq(&word(input, offset + 96))?;
+    let x = Fq2::new(x_re, x_im);
+    let y = Fq2::new(y_re, y_im);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(PrecompileError::InvalidInput("G2 point not in subgroup"));
+    }
+    Ok(point)
+}
+
+fn encode_g1(point: G1Projective) -> Vec<u8> {
+    let affine = point.into_affine();
+    let mut out = Vec::with_capacity(64);
+    match affine.xy() {
+        Some((x, y)) => {
+            write_fq(&mut out, x);
+            write_fq(&mut out, y);
+        }
+        None => out.resize(64, 0),
+    }
+    out
+}
+
+fn bn254_add(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    let a = read_g1(input, 0)?;
+    let b = read_g1(input, 64)?;
+    Ok(encode_g1(a + b))
+}
+
+fn bn254_mul(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    let p = read_g1(input, 0)?;
+    let scalar = U256::from_be_bytes(&word(input, 64));
+    Ok(encode_g1(p.mul_bigint(scalar.0)))
+}
+
+fn bn254_pairing(input: &[u8]) -> Result<Vec<u8>, PrecompileError> {
+    if !input.len().is_multiple_of(192) {
+        return Err(PrecompileError::InvalidInput(
+            "pairing input length must be a multiple of 192",
+        ));
+    }
+    let mut g1 = Vec::with_capacity(input.len() / 192);
+    let mut g2 = Vec::with_capacity(input.len() / 192);
+    for offset in (0..input.len()).step_by(192) {
+        g1.push(read_g1(input, offset)?);
+        g2.push(read_g2(input, offset + 64)?);
+    }
+    let ok = Bn254::multi_pairing(g1, g2).0.is_one();
+    Ok(U256::from_u64(ok as u64).to_be_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(id: u8) -> [u8; 20] {
+        let mut a = [0u8; 20];
+        a[19] = id;
+        a
+    }
+
+    fn g1_bytes(p: G1Affine) -> Vec<u8> {
+        encode_g1(p.into_group())
+    }
+
+    /// ecrecover 输入：hash ‖ v ‖ r ‖ s
+    fn ecrecover_input(hash: &[u8], v: u8, r: &[u8], s: &[u8]) -> Vec<u8> {
+        let mut input = hash.to_vec();
+        input.extend_from_slice(&[0u8; 31]);
+        input.push(v);
+        input.extend_from_slice(r);
+        input.extend_from_slice(s);
+        input
+    }
+
+    #[test]
+    fn test_ecrecover_low_and_high_s() {
+        use k256::ecdsa::{Signature, SigningKey};
+
+        // 私钥 1 对应的地址 0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf
+        let expected = "0000000000000000000000007e5f4552091a69125d5dfcb7b8c2659029395bdf";
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let key = SigningKey::from_bytes(&secret.into()).unwrap();
+        let hash = Keccak256::digest(b"supervm ecrecover");
+        let (sig, recid) = key.sign_prehash_recoverable(&hash).unwrap();
+        assert!(sig.normalize_s().is_none(), "k256 signs with low s");
+        let (r, s) = (sig.r().to_bytes(), sig.s().to_bytes());
+        let v = 27 + recid.to_byte();
+
+        let out = run(&addr(1), &ecrecover_input(&hash, v, &r, &s), 3_000).unwrap();
+        assert_eq!(hex::encode(&out.output), expected);
+        assert_eq!(out.gas_used, 3_000);
+
+        // 同一签名的高 s 形式 (n - s, 翻转 v) 在以太坊上恢复出同一地址
+        let high = Signature::from_scalars(r, (-*sig.s()).to_bytes()).unwrap();
+        assert!(high.normalize_s().is_some());
+        let high_v = 27 + (recid.to_byte() ^ 1);
+        let out = run(
+            &addr(1),
+            &ecrecover_input(&hash, high_v, &r, &high.s().to_bytes()),
+            3_000,
+        )
+        .unwrap();
+        assert_eq!(hex::encode(&out.output), expected);
+
+        // v 不匹配时恢复出其它地址，非法 v 返回空输出
+        let out = run(&addr(1), &ecrecover_input(&hash, v ^ 1, &r, &s), 3_000).unwrap();
+        assert_ne!(hex::encode(&out.output), expected);
+        let out = run(&addr(1), &ecrecover_input(&hash, 29, &r, &s), 3_000).unwrap();
+        assert!(out.output.is_empty());
+    }
+
+    #[test]
+    fn test_sha256_and_identity() {
+        let out = run(&addr(2), b"abc", 1_000).unwrap();
+        assert_eq!(
+            hex::encode(out.output),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(out.gas_used, 72);
+        assert_eq!(run(&addr(4), b"xyz", 1_000).unwrap().output, b"xyz");
+        assert!(matches!(
+            run(&addr(2), b"abc", 10),
+            Err(PrecompileError::OutOfGas { .. })
+        ));
+        assert_eq!(
+            run(&addr(3), b"", 1_000),
+            Err(PrecompileError::Unsupported(3))
+        );
+        assert!(is_precompile(&addr(8)) && !is_precompile(&addr(0x0b)));
+    }
+
+    #[test]
+    fn test_modexp() {
+        // 3^5 mod 7 = 5
+        let mut input = Vec::new();
+        for len in [1u64, 1, 1] {
+            input.extend_from_slice(&U256::from_u64(len).to_be_bytes());
+        }
+        input.extend_from_slice(&[3, 5, 7]);
+        let out = run(&addr(5), &input, 1_000).unwrap();
+        assert_eq!(out.output, vec![5]);
+        assert_eq!(out.gas_used, MODEXP_MIN);
+    }
+
+    #[test]
+    fn test_bn254_add_mul_consistent() {
+        let g = G1Affine::generator();
+        let mut add_input = g1_bytes(g);
+        add_input.extend(g1_bytes(g));
+        let sum = run(&addr(6), &add_input, 1_000).unwrap().output;
+
+        let mut mul_input = g1_bytes(g);
+        mul_input.extend_from_slice(&U256::from_u64(2).to_be_bytes());
+        let doubled = run(&addr(7), &mul_input, 10_000).unwrap().output;
+        assert_eq!(sum, doubled);
+
+        // 不在曲线上的点
+        let mut bad = vec![0u8; 128];
+        bad[31] = 1;
+        bad[63] = 1;
+        assert!(matches!(
+            run(&addr(6), &bad, 1_000),
+            Err(PrecompileError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_bn254_pairing_identity() {
+        // e(P, Q) · e(-P, Q) == 1
+        let p = G1Affine::generator();
+        let q = G2Affine::generator();
+        let (qx, qy) = q.xy().unwrap();
+        let mut q_bytes = Vec::new();
+        for f in [qx.c1, qx.c0, qy.c1, qy.c0] {
+            write_fq(&mut q_bytes, &f);
+        }
+
+        let mut input = g1_bytes(p);
+        input.extend(&q_bytes);
+        input.extend(g1_bytes(-p));
+        input.extend(&q_bytes);
+        let out = run(&addr(8), &input, 200_000).unwrap();
+        assert_eq!(
+            out.gas_used,
+            BN254_PAIRING_BASE + 2 * BN254_PAIRING_PER_PAIR
+        );
+        assert_eq!(U256::from_be_slice(&out.output), U256::ONE);
+
+        // e(P, Q) 单独不为 1
+        let out = run(&addr(8), &input[..192], 200_000).unwrap();
+        assert_eq!(U256::from_be_slice(&out.output), U256::ZERO);
+        // 空输入视为成立
+        assert_eq!(
+            U256::from_be_slice(&run(&addr(8), &[], 200_000).unwrap().output),
+            U256::ONE
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! EVM 账户状态到 [`Storage`] 的映射
+//!
+//! 键布局:
+//! - `evm:s:` + address(20) + slot(32) → 32 字节存储值（零值不落盘）
+//! - `evm:b:` + address(20)            → 32 字节余额（零值不落盘）
+//! - `evm:c:` + address(20)            → 合约字节码
+//!
+//! [`JournaledState`] 只读访问底层存储，执行期间的修改全部记录在内存日志中，
+//! 每个调用帧开始时建立 [`Checkpoint`]，回滚/异常时按日志撤销。
+
+use crate::u256::U256;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use vm_runtime::{Log, StateChange, Storage};
+
+pub const SLOT_PREFIX: &[u8] = b"evm:s:";
+pub const BALANCE_PREFIX: &[u8] = b"evm:b:";
+pub const CODE_PREFIX: &[u8] = b"evm:c:";
+
+pub type Address = [u8; 20];
+
+pub fn slot_key(address: &Address, slot: &U256) -> Vec<u8> {
+    let mut key = Vec::with_capacity(SLOT_PREFIX.len() + 52);
+    key.extend_from_slice(SLOT_PREFIX);
+    key.extend_from_slice(address);
+    key.extend_from_slice(&slot.to_be_bytes());
+    key
+}
+
+pub fn balance_key(address: &Address) -> Vec<u8> {
+    [BALANCE_PREFIX, address.as_slice()].concat()
+}
+
+pub fn code_key(address: &Address) -> Vec<u8> {
+    [CODE_PREFIX, address.as_slice()].concat()
+}
+
+/// 32 字节值编码：零值映射为删除
+pub(crate) fn encode_word(value: &U256) -> Option<Vec<u8>> {
+    if value.is_zero() {
+        None
+    } else {
+        Some(value.to_be_bytes().to_vec())
+    }
+}
+
+pub(crate) fn decode_word(bytes: Option<Vec<u8>>) -> U256 {
+    match bytes {
+        Some(b) if b.len() <= 32 => U256::from_be_slice(&b),
+        _ => U256::ZERO,
+    }
+}
+
+type SlotId = (Address, U256);
+
+enum JournalEntry {
+    Storage {
+        slot: SlotId,
+        prev: Option<U256>,
+    },
+    Transient {
+        slot: SlotId,
+        prev: Option<U256>,
+    },
+    Balance {
+        address: Address,
+        prev: Option<U256>,
+    },
+    WarmAccount(Address),
+    WarmSlot(SlotId),
+}
+
+/// 调用帧检查点
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint {
+    journal_len: usize,
+    logs_len: usize,
+}
+
+/// 带撤销日志的执行期状态
+pub struct JournaledState<'a, S: Storage> {
+    base: &'a S,
+    storage: HashMap<SlotId, U256>,
+    original: HashMap<SlotId, U256>,
+    balances: HashMap<Address, U256>,
+    transient: HashMap<SlotId, U256>,
+    code: HashMap<Address, Arc<Vec<u8>>>,
+    warm_accounts: HashSet<Address>,
+    warm_slots: HashSet<SlotId>,
+    logs: Vec<Log>,
+    journal: Vec<JournalEntry>,
+}
+
+impl<'a, S: Storage> JournaledState<'a, S> {
+    pub fn new(base: &'a S) -> Self {
+        Self {
+            base,
+            storage: HashMap::new(),
+            original: HashMap::new(),
+            balances: HashMap::new(),
+            transient: HashMap::new(),
+            code: HashMap::new(),
+            warm_accounts: HashSet::new(),
+            warm_slots: HashSet::new(),
+            logs: Vec::new(),
+            journal: Vec::new(),
+        }
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            journal_len: self.journal.len(),
+            logs_len: self.logs.len(),
+        }
+    }
+
+    /// 撤销检查点之后的全部修改
+    pub fn revert(&mut self, checkpoint: Checkpoint) {
+        while self.journal.len() > checkpoint.journal_len {
+            match self.journal.pop().expect("journal length checked") {
+                JournalEntry::Storage { slot, prev } => restore(&mut self.storage, slot, prev),
+                JournalEntry::Transient { slot, prev } => restore(&mut self.transient, slot, prev),
+                JournalEntry::Balance { address, prev } => {
+                    restore(&mut self.balances, address, prev)
+                }
+                JournalEntry::WarmAccount(address) => {
+                    self.warm_accounts.remove(&address);
+                }
+                JournalEntry::WarmSlot(slot) => {
+                    self.warm_slots.remove(&slot);
+                }
+            }
+        }
+        self.logs.truncate(checkpoint.logs_len);
+    }
+
+    /// 标记账户为已访问，返回此前是否为冷访问
+    pub fn touch_account(&mut self, address: Address) -> bool {
+        let cold = self.warm_accounts.insert(address);
+        if cold {
+            self.journal.push(JournalEntry::WarmAccount(address));
+        }
+        cold
+    }
+
+    /// 标记存储槽为已访问，返回此前是否为冷访问
+    pub fn touch_slot(&mut self, address: Address, slot: U256) -> bool {
+        let cold = self.warm_slots.insert((address, slot));
+        if cold {
+            self.journal.push(JournalEntry::WarmSlot((address, slot)));
+        }
+        cold
+    }
+
+    /// 交易开始时的存储值
+    pub fn original(&mut self, address: Address, slot: U256) -> Result<U256> {
+        if let Some(v) = self.original.get(&(address, slot)) {
+            return Ok(*v);
+        }
+        let v = decode_word(self.base.get(&slot_key(&address, &slot))?);
+        self.original.insert((address, slot), v);
+        Ok(v)
+    }
+
+    pub fn sload(&mut self, address: Address, slot: U256) -> Result<U256> {
+        match self.storage.get(&(address, slot)) {
+            Some(v) => Ok(*v),
+            None => self.original(address, slot),
+        }
+    }
+
+    pub fn sstore(&mut self, address: Address, slot: U256, value: U256) {
+        let prev = self.storage.insert((address, slot), value);
+        self.journal.push(JournalEntry::Storage {
+            slot: (address, slot),
+            prev,
+        });
+    }
+
+    pub fn tload(&self, address: Address, slot: U256) -> U256 {
+        self.transient
+            .get(&(address, slot))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn tstore(&mut self, address: Address, slot: U256, value: U256) {
+        let prev = self.transient.insert((address, slot), value);
+        self.journal.push(JournalEntry::Transient {
+            slot: (address, slot),
+            prev,
+        });
+    }
+
+    pub fn balance(&mut self, address: Address) -> Result<U256> {
+        if let Some(v) = self.balances.get(&address) {
+            return Ok(*v);
+        }
+        let v = decode_word(self.base.get(&balance_key(&address))?);
+        self.balances.insert(address, v);
+        Ok(v)
+    }
+
+    fn set_balance(&mut self, address: Address, value: U256) {
+        let prev = self.balances.insert(address, value);
+        self.journal.push(JournalEntry::Balance { address, prev });
+    }
+
+    /// 转账，余额不足时返回 false 且不修改状态
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) -> Result<bool> {
+        let from_balance = self.balance(from)?;
+        if from_balance < value {
+            return Ok(false);
+        }
+        if value.is_zero() || from == to {
+            return Ok(true);
+        }
+        self.set_balance(from, from_balance.wrapping_sub(&value));
+        let to_balance = self.balance(to)?;
+        self.set_balance(to, to_balance.wrapping_add(&value));
+        Ok(true)
+    }
+
+    pub fn code(&mut self, address: Address) -> Result<Arc<Vec<u8>>> {
+        if let Some(code) = self.code.get(&address) {
+            return Ok(code.clone());
+        }
+        let code = Arc::new(self.base.get(&code_key(&address))?.unwrap_or_default());
+        self.code.insert(address, code.clone());
+        Ok(code)
+    }
+
+    /// 以给定字节码覆盖账户代码（顶层调用直接传入代码时使用）
+    pub fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        self.code.insert(address, Arc::new(code));
+    }
+
+    pub fn log(&mut self, log: Log) {
+        self.logs.push(log);
+    }
+
+    /// 导出日志与相对底层存储的净状态变更（按键排序）
+    pub fn finalize(mut self) -> Result<(Vec<Log>, Vec<StateChange>)> {
+        let mut changes = BTreeMap::new();
+
+        let slots: Vec<(SlotId, U256)> = self.storage.iter().map(|(k, v)| (*k, *v)).collect();
+        for ((address, slot), value) in slots {
+            if self.original(address, slot)? != value {
+                changes.insert(slot_key(&address, &slot), encode_word(&value));
+            }
+        }
+        for (address, value) in &self.balances {
+            let key = balance_key(address);
+            if decode_word(self.base.get(&key)?) != *value {
+                changes.insert(key, encode_word(value));
+            }
+        }
+
+        let changes = changes
+            .into_iter()
+            .map(|(key, value)| StateChange { key, value })
+            .collect();
+        Ok((self.logs, changes))
+    }
+}
+
+fn restore<K: std::hash::Hash + Eq>(map: &mut HashMap<K, U256>, key: K, prev: Option<U256>) {
+    match prev {
+        Some(v) => map.insert(key, v),
+        None => map.remove(&key),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm_runtime::MemoryStorage;
+
+    #[test]
+    fn test_checkpoint_revert_and_finalize() {
+        let mut base = MemoryStorage::new();
+        let alice = [1u8; 20];
+        let bob = [2u8; 20];
+        base.set(&balance_key(&alice), &U256::from_u64(100).to_be_bytes())
+            .unwrap();
+        base.set(
+            &slot_key(&alice, &U256::ONE),
+            &U256::from_u64(7).to_be_bytes(),
+        )
+        .unwrap();
+
+        let mut state = JournaledState::new(&base);
+        assert!(state.touch_account(bob));
+        assert!(!state.touch_account(bob));
+        state.sstore(alice, U256::ONE, U256::ZERO);
+
+        let cp = state.checkpoint();
+        assert!(state.transfer(alice, bob, U256::from_u64(40)).unwrap());
+        state.sstore(alice, U256::from_u64(2), U256::from_u64(9));
+        state.revert(cp);
+        assert_eq!(state.balance(bob).unwrap(), U256::ZERO);
+        assert_eq!(state.sload(alice, U256::from_u64(2)).unwrap(), U256::ZERO);
+
+        assert!(!state.transfer(bob, alice, U256::ONE).unwrap());
+        assert!(state.transfer(alice, bob, U256::from_u64(30)).unwrap());
+
+        let (_, changes) = state.finalize().unwrap();
+        let keys: Vec<_> = changes
+            .iter()
+            .map(|c| (c.key.clone(), c.value.clone()))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (
+                    balance_key(&alice),
+                    Some(U256::from_u64(70).to_be_bytes().to_vec())
+                ),
+                (
+                    balance_key(&bob),
+                    Some(U256::from_u64(30).to_be_bytes().to_vec())
+                ),
+                (slot_key(&alice, &U256::ONE), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,464 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+//! 256 位无符号整数（EVM 字）
+//!
+//! 小端 limb 存储，所有算术为模 2^256 的环绕运算；有符号运算按二进制补码解释。
+
+use num_bigint::BigUint;
+use std::cmp::Ordering;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U256(pub [u64; 4]);
+
+impl std::fmt::Debug for U256 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x")?;
+        for limb in self.0.iter().rev() {
+            write!(f, "{:016x}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for i in (0..4).rev() {
+            match self.0[i].cmp(&other.0[i]) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    pub const fn from_u64(v: u64) -> Self {
+        U256([v, 0, 0, 0])
+    }
+
+    /// 大端字节（不超过 32 字节，左侧补零）
+    pub fn from_be_slice(bytes: &[u8]) -> Self {
+        debug_assert!(bytes.len() <= 32);
+        let mut buf = [0u8; 32];
+        buf[32 - bytes.len()..].copy_from_slice(bytes);
+        Self::from_be_bytes(&buf)
+    }
+
+    pub fn from_be_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            *limb = u64::from_be_bytes(bytes[start..start + 8].try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            let start = 32 - (i + 1) * 8;
+            out[start..start + 8].copy_from_slice(&limb.to_be_bytes());
+        }
+        out
+    }
+
+    /// 低 160 位作为地址
+    pub fn to_address(&self) -> [u8; 20] {
+        let bytes = self.to_be_bytes();
+        bytes[12..].try_into().unwrap()
+    }
+
+    pub fn from_address(addr: &[u8; 20]) -> Self {
+        Self::from_be_slice(addr)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    pub fn low_u64(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// 若值可用 u64 表示则返回
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.0[1] == 0 && self.0[2] == 0 && self.0[3] == 0 {
+            Some(self.0[0])
+        } else {
+            None
+        }
+    }
+
+    /// 有效位数
+    pub fn bits(&self) -> usize {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return i * 64 + (64 - self.0[i].leading_zeros() as usize);
+            }
+        }
+        0
+    }
+
+    fn bit(&self, n: usize) -> bool {
+        (self.0[n / 64] >> (n % 64)) & 1 == 1
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.bit(255)
+    }
+
+    pub fn wrapping_neg(&self) -> Self {
+        (!*self).wrapping_add(&U256::ONE)
+    }
+
+    pub fn overflowing_add(&self, other: &Self) -> (Self, bool) {
+        let mut out = [0u64; 4];
+        let mut carry = false;
+        for (i, o) in out.iter_mut().enumerate() {
+            let (s1, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (s2, c2) = s1.overflowing_add(carry as u64);
+            *o = s2;
+            carry = c1 || c2;
+        }
+        (U256(out), carry)
+    }
+
+    pub fn wrapping_add(&self, other: &Self) -> Self {
+        self.overflowing_add(other).0
+    }
+
+    pub fn overflowing_sub(&self, other: &Self) -> (Self, bool) {
+        let mut out = [0u64; 4];
+        let mut borrow = false;
+        for (i, o) in out.iter_mut().enumerate() {
+            let (d1, b1) = self.0[i].overflowing_sub(other.0[i]);
+            let (d2, b2) = d1.overflowing_sub(borrow as u64);
+            *o = d2;
+            borrow = b1 || b2;
+        }
+        (U256(out), borrow)
+    }
+
+    pub fn wrapping_sub(&self, other: &Self) -> Self {
+        self.overflowing_sub(other).0
+    }
+
+    pub fn wrapping_mul(&self, other: &Self) -> Self {
+        let mut out = [0u64; 4];
+        for i in 0..4 {
+            let mut carry: u128 = 0;
+            for j in 0..(4 - i) {
+                let cur = out[i + j] as u128 + (self.0[i] as u128) * (other.0[j] as u128) + carry;
+                out[i + j] = cur as u64;
+                carry = cur >> 64;
+            }
+        }
+        U256(out)
+    }
+
+    /// 无符号除法与取余；除数为 0 时商与余数均为 0（EVM 语义）
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        if divisor.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        if self < divisor {
+            return (U256::ZERO, *self);
+        }
+        if let (Some(a), Some(b)) = (self.to_u64(), divisor.to_u64()) {
+            return (U256::from_u64(a / b), U256::from_u64(a % b));
+        }
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for i in (0..self.bits()).rev() {
+            remainder = remainder.shl(1);
+            if self.bit(i) {
+                remainder.0[0] |= 1;
+            }
+            if remainder >= *divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient.0[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (quotient, remainder)
+    }
+
+    pub fn div(&self, divisor: &Self) -> Self {
+        self.div_rem(divisor).0
+    }
+
+    pub fn rem(&self, divisor: &Self) -> Self {
+        self.div_rem(divisor).1
+    }
+
+    fn abs(&self) -> Self {
+        if self.is_negative() {
+            self.wrapping_neg()
+        } else {
+            *self
+        }
+    }
+
+    /// 有符号除法（向零取整；MIN / -1 = MIN）
+    pub fn sdiv(&self, divisor: &Self) -> Self {
+        if divisor.is_zero() {
+            return U256::ZERO;
+        }
+        let q = self.abs().div(&divisor.abs());
+        if self.is_negative() != divisor.is_negative() {
+            q.wrapping_neg()
+        } else {
+            q
+        }
+    }
+
+    /// 有符号取余（结果符号与被除数一致）
+    pub fn smod(&self, divisor: &Self) -> Self {
+        if divisor.is_zero() {
+            return U256::ZERO;
+        }
+        let r = self.abs().rem(&divisor.abs());
+        if self.is_negative() {
+            r.wrapping_neg()
+        } else {
+            r
+        }
+    }
+
+    pub fn to_biguint(&self) -> BigUint {
+        BigUint::from_bytes_be(&self.to_be_bytes())
+    }
+
+    /// BigUint 取低 256 位
+    pub fn from_biguint(v: &BigUint) -> Self {
+        let bytes = v.to_bytes_be();
+        let tail = &bytes[bytes.len().saturating_sub(32)..];
+        Self::from_be_slice(tail)
+    }
+
+    pub fn addmod(&self, other: &Self, modulus: &Self) -> Self {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        let sum = self.to_biguint() + other.to_biguint();
+        Self::from_biguint(&(sum % modulus.to_biguint()))
+    }
+
+    pub fn mulmod(&self, other: &Self, modulus: &Self) -> Self {
+        if modulus.is_zero() {
+            return U256::ZERO;
+        }
+        let product = self.to_biguint() * other.to_biguint();
+        Self::from_biguint(&(product % modulus.to_biguint()))
+    }
+
+    /// 模 2^256 幂运算
+    pub fn wrapping_pow(&self, exponent: &Self) -> Self {
+        let mut result = U256::ONE;
+        let mut base = *self;
+        for i in 0..exponent.bits() {
+            if exponent.bit(i) {
+                result = result.wrapping_mul(&base);
+            }
+            base = base.wrapping_mul(&base);
+        }
+        result
+    }
+
+    /// SIGNEXTEND: 以第 b 字节（从低位起）的最高位做符号扩展
+    pub fn signextend(&self, b: &Self) -> Self {
+        match b.to_u64() {
+            Some(b) if b < 31 => {
+                let bit = (b as usize) * 8 + 7;
+                let mask = U256::ONE.shl(bit + 1).wrapping_sub(&U256::ONE);
+                if self.bit(bit) {
+                    *self | !mask
+                } else {
+                    *self & mask
+                }
+            }
+            _ => *self,
+        }
+    }
+
+    /// BYTE: 取大端第 i 个字节
+    pub fn byte(&self, i: &Self) -> Self {
+        match i.to_u64() {
+            Some(i) if i < 32 => U256::from_u64(self.to_be_bytes()[i as usize] as u64),
+            _ => U256::ZERO,
+        }
+    }
+
+    pub fn shl(&self, shift: usize) -> Self {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let limb_shift = shift / 64;
+        let bit_shift = shift % 64;
+        let mut out = [0u64; 4];
+        for i in (limb_shift..4).rev() {
+            let src = i - limb_shift;
+            out[i] = self.0[src] << bit_shift;
+            if bit_shift > 0 && src > 0 {
+                out[i] |= self.0[src - 1] >> (64 - bit_shift);
+            }
+        }
+        U256(out)
+    }
+
+    pub fn shr(&self, shift: usize) -> Self {
+        if shift >= 256 {
+            return U256::ZERO;
+        }
+        let limb_shift = shift / 64;
+        let bit_shift = shift % 64;
+        let mut out = [0u64; 4];
+        for (i, o) in out.iter_mut().enumerate().take(4 - limb_shift) {
+            let src = i + limb_shift;
+            *o = self.0[src] >> bit_shift;
+            if bit_shift > 0 && src + 1 < 4 {
+                *o |= self.0[src + 1] << (64 - bit_shift);
+            }
+        }
+        U256(out)
+    }
+
+    /// 算术右移
+    pub fn sar(&self, shift: usize) -> Self {
+        if !self.is_negative() {
+            return self.shr(shift);
+        }
+        if shift >= 256 {
+            return U256::MAX;
+        }
+        // 高位补 1
+        self.shr(shift) | !U256::MAX.shr(shift)
+    }
+
+    pub fn slt(&self, other: &Self) -> bool {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => self < other,
+        }
+    }
+
+    /// 移位参数：超过 255 统一视为 256
+    pub fn as_shift(&self) -> usize {
+        match self.to_u64() {
+            Some(s) if s < 256 => s as usize,
+            _ => 256,
+        }
+    }
+}
+
+impl BitAnd for U256 {
+    type Output = U256;
+    fn bitand(self, rhs: Self) -> Self {
+        U256([
+            self.0[0] & rhs.0[0],
+            self.0[1] & rhs.0[1],
+            self.0[2] & rhs.0[2],
+            self.0[3] & rhs.0[3],
+        ])
+    }
+}
+
+impl BitOr for U256 {
+    type Output = U256;
+    fn bitor(self, rhs: Self) -> Self {
+        U256([
+            self.0[0] | rhs.0[0],
+            self.0[1] | rhs.0[1],
+            self.0[2] | rhs.0[2],
+            self.0[3] | rhs.0[3],
+        ])
+    }
+}
+
+impl BitXor for U256 {
+    type Output = U256;
+    fn bitxor(self, rhs: Self) -> Self {
+        U256([
+            self.0[0] ^ rhs.0[0],
+            self.0[1] ^ rhs.0[1],
+            self.0[2] ^ rhs.0[2],
+            self.0[3] ^ rhs.0[3],
+        ])
+    }
+}
+
+impl Not for U256 {
+    type Output = U256;
+    fn not(self) -> Self {
+        U256([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(v: u64) -> Self {
+        U256::from_u64(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u(v: u64) -> U256 {
+        U256::from_u64(v)
+    }
+
+    #[test]
+    fn test_arith_wraps() {
+        assert_eq!(U256::MAX.wrapping_add(&U256::ONE), U256::ZERO);
+        assert_eq!(U256::ZERO.wrapping_sub(&U256::ONE), U256::MAX);
+        assert_eq!(u(7).wrapping_mul(&u(6)), u(42));
+        let big = U256::ONE.shl(200);
+        assert_eq!(big.wrapping_mul(&U256::ONE.shl(100)), U256::ZERO);
+        assert_eq!(big.wrapping_mul(&u(2)), U256::ONE.shl(201));
+    }
+
+    #[test]
+    fn test_div_and_signed_ops() {
+        assert_eq!(u(43).div_rem(&u(5)), (u(8), u(3)));
+        assert_eq!(u(1).div(&U256::ZERO), U256::ZERO);
+        let big = U256::ONE.shl(255).wrapping_add(&u(12345));
+        let (q, r) = big.div_rem(&u(1000));
+        assert_eq!(q.wrapping_mul(&u(1000)).wrapping_add(&r), big);
+
+        let minus_8 = u(8).wrapping_neg();
+        assert_eq!(minus_8.sdiv(&u(3)), u(2).wrapping_neg());
+        assert_eq!(minus_8.smod(&u(3)), u(2).wrapping_neg());
+        assert!(minus_8.slt(&u(1)));
+        assert_eq!(minus_8.sar(1), u(4).wrapping_neg());
+        assert_eq!(U256::MAX.sar(300), U256::MAX);
+    }
+
+    #[test]
+    fn test_bytes_and_bit_ops() {
+        let v = U256::from_be_slice(&[0x12, 0x34]);
+        assert_eq!(v, u(0x1234));
+        assert_eq!(v.byte(&u(31)), u(0x34));
+        assert_eq!(v.byte(&u(30)), u(0x12));
+        assert_eq!(U256::from_be_bytes(&v.to_be_bytes()), v);
+        assert_eq!(u(0xff).signextend(&u(0)), U256::MAX);
+        assert_eq!(u(0x7f).signextend(&u(0)), u(0x7f));
+        assert_eq!(u(1).shl(64).shr(64), u(1));
+        assert_eq!(u(3).wrapping_pow(&u(5)), u(243));
+        assert_eq!(u(10).addmod(&u(10), &u(8)), u(4));
+        assert_eq!(U256::MAX.mulmod(&U256::MAX, &u(12)), u(9));
+    }
+}
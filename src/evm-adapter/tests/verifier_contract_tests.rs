@@ -0,0 +1,343 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// contracts/ 下 BN254 Groth16 验证器在 EvmExecutor 中的执行测试
+//
+// 沙箱内无 solc，字节码按 `verifyProof` 的 Solidity 逻辑逐步手工汇编（同一 ABI 布局与选择器、
+// 同样的 0x06/0x07 预编译累加 vk_x、同样的 negate(A) 与 0x08 四对配对检查），
+// 验证密钥常量直接从 contracts/*.sol 解析。
+
+use ark_bn254::{Fq, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, PrimeField};
+use evm_adapter::EvmExecutor;
+use sha3::{Digest, Keccak256};
+use vm_runtime::{ExecutionContext, ExecutionEngine, MemoryStorage};
+
+type Word = [u8; 32];
+
+/// BN254 基域模数（与合约中 negate 使用的 q 一致）
+const FIELD_MODULUS: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+/// Solidity 验证器中的验证密钥
+struct VerifyingKey {
+    alpha: [Word; 2],
+    beta: [Word; 4],
+    gamma: [Word; 4],
+    delta: [Word; 4],
+    /// gamma_abc[0..=n]
+    gamma_abc: Vec<[Word; 2]>,
+}
+
+impl VerifyingKey {
+    /// 按出现顺序解析 64 位十六进制常量: ALPHA, BETA, GAMMA, DELTA, 内联的 gamma_abc
+    fn from_solidity(source: &str) -> Self {
+        let mut words = Vec::new();
+        for token in source.split(|c: char| !c.is_ascii_alphanumeric()) {
+            if let Some(digits) = token.strip_prefix("0x") {
+                if digits.len() == 64 {
+                    words.push(hex::decode(digits).unwrap().try_into().unwrap());
+                }
+            }
+        }
+        assert!(
+            words.len() >= 18 && words.len() % 2 == 0,
+            "unexpected verifier layout"
+        );
+        let w = |i: usize| -> Word { words[i] };
+        Self {
+            alpha: [w(0), w(1)],
+            beta: [w(2), w(3), w(4), w(5)],
+            gamma: [w(6), w(7), w(8), w(9)],
+            delta: [w(10), w(11), w(12), w(13)],
+            gamma_abc: words[14..].chunks(2).map(|p| [p[0], p[1]]).collect(),
+        }
+    }
+
+    fn num_inputs(&self) -> usize {
+        self.gamma_abc.len() - 1
+    }
+}
+
+/// 最小汇编器: 所有 require 失败跳转到末尾的 REVERT(0, 0)
+#[derive(Default)]
+struct Asm {
+    code: Vec<u8>,
+    revert_jumps: Vec<usize>,
+}
+
+impl Asm {
+    fn op(&mut self, op: u8) -> &mut Self {
+        self.code.push(op);
+        self
+    }
+
+    fn push(&mut self, value: &[u8]) -> &mut Self {
+        let start = value
+            .iter()
+            .position(|&b| b != 0)
+            .unwrap_or(value.len() - 1);
+        let bytes = &value[start..];
+        self.code.push(0x5f + bytes.len() as u8);
+        self.code.extend_from_slice(bytes);
+        self
+    }
+
+    fn push_u64(&mut self, value: u64) -> &mut Self {
+        self.push(&value.to_be_bytes())
+    }
+
+    fn mstore_word(&mut self, offset: u64, word: &Word) -> &mut Self {
+        self.push(word).push_u64(offset).op(0x52)
+    }
+
+    fn calldataload(&mut self, offset: u64) -> &mut Self {
+        self.push_u64(offset).op(0x35)
+    }
+
+    fn mstore_calldata(&mut self, offset: u64, calldata_offset: u64) -> &mut Self {
+        self.calldataload(calldata_offset).push_u64(offset).op(0x52)
+    }
+
+    /// 栈顶非零时回滚
+    fn revert_if(&mut self) -> &mut Self {
+        self.revert_jumps.push(self.code.len() + 1);
+        self.op(0x61).op(0).op(0).op(0x57)
+    }
+
+    /// 栈顶为零时回滚
+    fn require(&mut self) -> &mut Self {
+        self.op(0x15).revert_if()
+    }
+
+    fn staticcall(&mut self, address: u64, input: (u64, u64), output: (u64, u64)) -> &mut Self {
+        self.push_u64(output.1)
+            .push_u64(output.0)
+            .push_u64(input.1)
+            .push_u64(input.0)
+            .push_u64(address)
+            .op(0x5a)
+            .op(0xfa)
+            .require()
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let target = (self.code.len() as u16).to_be_bytes();
+        self.op(0x5b).push_u64(0).op(0x80).op(0xfd);
+        for at in &self.revert_jumps {
+            self.code[*at..*at + 2].copy_from_slice(&target);
+        }
+        self.code
+    }
+}
+
+fn signature(num_inputs: usize) -> String {
+    format!("verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[{num_inputs}])")
+}
+
+fn selector(num_inputs: usize) -> [u8; 4] {
+    Keccak256::digest(signature(num_inputs).as_bytes())[..4]
+        .try_into()
+        .unwrap()
+}
+
+/// `verifyProof` 的字节码
+fn verifier_bytecode(vk: &VerifyingKey) -> Vec<u8> {
+    let n = vk.num_inputs() as u64;
+    let q: Word = hex::decode(FIELD_MODULUS).unwrap().try_into().unwrap();
+    let mut asm = Asm::default();
+
+    // ABI: 选择器 + 静态参数 a[2], b[2][2], c[2], input[n]
+    asm.push_u64(4 + 32 * (8 + n)).op(0x36).op(0x10).revert_if();
+    asm.calldataload(0).push_u64(0xe0).op(0x1c);
+    asm.push(&selector(n as usize)).op(0x14).require();
+
+    // vkX = (0, 0) + gamma_abc[0] + Σ input[i] * gamma_abc[i + 1]，结果位于 mem[0x00..0x40]
+    asm.mstore_word(0x40, &vk.gamma_abc[0][0])
+        .mstore_word(0x60, &vk.gamma_abc[0][1])
+        .staticcall(0x06, (0x00, 0x80), (0x00, 0x40));
+    for i in 0..n {
+        let point = &vk.gamma_abc[i as usize + 1];
+        asm.mstore_word(0x40, &point[0])
+            .mstore_word(0x60, &point[1])
+            .mstore_calldata(0x80, 4 + 32 * (8 + i))
+            .staticcall(0x07, (0x40, 0x60), (0x40, 0x40))
+            .staticcall(0x06, (0x00, 0x80), (0x00, 0x40));
+    }
+
+    // 配对输入 (-A, B), (vkX, GAMMA), (C, DELTA), (ALPHA, BETA)
+    let p = 0x100;
+    asm.mstore_calldata(p, 4);
+    // negate: (0, 0) 保持不变，否则 y' = q - (y % q)
+    asm.push(&q)
+        .calldataload(36)
+        .op(0x06)
+        .push(&q)
+        .op(0x03)
+        .calldataload(4)
+        .calldataload(36)
+        .op(0x17)
+        .op(0x15)
+        .op(0x15)
+        .op(0x02)
+        .push_u64(p + 0x20)
+        .op(0x52);
+    for (k, cd) in [68, 100, 132, 164].into_iter().enumerate() {
+        asm.mstore_calldata(p + 0x40 + 0x20 * k as u64, cd);
+    }
+    asm.push_u64(0x00).op(0x51).push_u64(p + 0xc0).op(0x52);
+    asm.push_u64(0x20).op(0x51).push_u64(p + 0xe0).op(0x52);
+    for (k, word) in vk.gamma.iter().enumerate() {
+        asm.mstore_word(p + 0x100 + 0x20 * k as u64, word);
+    }
+    asm.mstore_calldata(p + 0x180, 196)
+        .mstore_calldata(p + 0x1a0, 228);
+    for (k, word) in vk.delta.iter().enumerate() {
+        asm.mstore_word(p + 0x1c0 + 0x20 * k as u64, word);
+    }
+    asm.mstore_word(p + 0x240, &vk.alpha[0])
+        .mstore_word(p + 0x260, &vk.alpha[1]);
+    for (k, word) in vk.beta.iter().enumerate() {
+        asm.mstore_word(p + 0x280 + 0x20 * k as u64, word);
+    }
+    asm.staticcall(0x08, (p, 0x300), (0x00, 0x20));
+
+    // return abi.encode(out[0] != 0)
+    asm.push_u64(0x00)
+        .op(0x51)
+        .op(0x15)
+        .op(0x15)
+        .push_u64(0x00)
+        .op(0x52)
+        .push_u64(0x20)
+        .push_u64(0x00)
+        .op(0xf3);
+    asm.finish()
+}
+
+fn fq_word(value: &Fq) -> Word {
+    value.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+fn g1_words(point: G1Affine) -> [Word; 2] {
+    let (x, y) = point.xy().unwrap();
+    [fq_word(x), fq_word(y)]
+}
+
+/// EIP-197 编码: Fq2 按 (虚部, 实部)
+fn g2_words(point: G2Affine) -> [Word; 4] {
+    let (x, y) = point.xy().unwrap();
+    [
+        fq_word(&x.c1),
+        fq_word(&x.c0),
+        fq_word(&y.c1),
+        fq_word(&y.c0),
+    ]
+}
+
+fn fr_word(value: u64) -> Word {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+fn calldata(
+    vk: &VerifyingKey,
+    a: [Word; 2],
+    b: [Word; 4],
+    c: [Word; 2],
+    inputs: &[Word],
+) -> Vec<u8> {
+    let mut data = selector(vk.num_inputs()).to_vec();
+    for word in a.iter().chain(&b).chain(&c).chain(inputs) {
+        data.extend_from_slice(word);
+    }
+    data
+}
+
+fn ctx() -> ExecutionContext {
+    ExecutionContext {
+        caller: [0xaa; 20],
+        contract: [0xcc; 20],
+        value: 0,
+        gas_limit: 1_000_000,
+        block_number: 1,
+        timestamp: 1_700_000_000,
+    }
+}
+
+/// 执行 verifyProof；Some(返回的 bool)，None 表示回滚
+fn verify(vk: &VerifyingKey, data: &[u8]) -> Option<bool> {
+    let exec = EvmExecutor::new(MemoryStorage::new());
+    let result = exec.execute(&verifier_bytecode(vk), data, &ctx()).unwrap();
+    if !result.success {
+        return None;
+    }
+    assert_eq!(result.return_data.len(), 32);
+    assert!(
+        result.gas_used > 45_000 + 4 * 34_000,
+        "pairing gas not charged"
+    );
+    Some(result.return_data[31] == 1)
+}
+
+/// 离散对数已知的验证密钥，可构造满足配对方程的证明:
+/// a·b = α·β + vk_x·γ + c·δ
+#[test]
+fn test_verifier_accepts_valid_proof() {
+    let (alpha, beta, gamma, delta) = (
+        Fr::from(3u64),
+        Fr::from(5u64),
+        Fr::from(7u64),
+        Fr::from(11u64),
+    );
+    let (abc0, abc1) = (Fr::from(13u64), Fr::from(17u64));
+    let g1 = |s: Fr| (G1Affine::generator() * s).into_affine();
+    let g2 = |s: Fr| (G2Affine::generator() * s).into_affine();
+    let vk = VerifyingKey {
+        alpha: g1_words(g1(alpha)),
+        beta: g2_words(g2(beta)),
+        gamma: g2_words(g2(gamma)),
+        delta: g2_words(g2(delta)),
+        gamma_abc: vec![g1_words(g1(abc0)), g1_words(g1(abc1))],
+    };
+
+    let input = 12u64;
+    let vk_x = abc0 + Fr::from(input) * abc1;
+    let (b, c) = (Fr::from(23u64), Fr::from(19u64));
+    let a = (alpha * beta + vk_x * gamma + c * delta) * b.inverse().unwrap();
+    let proof = (g1_words(g1(a)), g2_words(g2(b)), g1_words(g1(c)));
+
+    let data = calldata(&vk, proof.0, proof.1, proof.2, &[fr_word(input)]);
+    assert_eq!(verify(&vk, &data), Some(true));
+
+    // 公共输入不符
+    let data = calldata(&vk, proof.0, proof.1, proof.2, &[fr_word(input + 1)]);
+    assert_eq!(verify(&vk, &data), Some(false));
+}
+
+#[test]
+fn test_contracts_bn254_verifiers_execute() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../../contracts");
+    for name in ["BN254MultiplyVerifier.sol", "RingCTVerifierBN254.sol"] {
+        let source = std::fs::read_to_string(format!("{dir}/{name}")).unwrap();
+        assert!(source.contains("uint256[1] calldata input"), "{name}");
+        let vk = VerifyingKey::from_solidity(&source);
+        assert_eq!(vk.num_inputs(), 1, "{name}");
+
+        // 合约内验证密钥点（G2 按 EIP-197 排列）均通过预编译的曲线/子群检查，伪造证明得到 false 而非回滚
+        let g1 = g1_words(G1Affine::generator());
+        let g2 = g2_words(G2Affine::generator());
+        let data = calldata(&vk, g1, g2, g1, &[fr_word(12)]);
+        assert_eq!(verify(&vk, &data), Some(false), "{name}");
+
+        // 不在曲线上的 A: 配对预编译失败，require 回滚
+        let off_curve = [fr_word(1), fr_word(3)];
+        let data = calldata(&vk, off_curve, g2, g1, &[fr_word(12)]);
+        assert_eq!(verify(&vk, &data), None, "{name}");
+
+        // 选择器不符
+        let mut data = calldata(&vk, g1, g2, g1, &[fr_word(12)]);
+        data[0] ^= 0xff;
+        assert_eq!(verify(&vk, &data), None, "{name}");
+    }
+}
+
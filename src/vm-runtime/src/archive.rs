@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 归档模式：按区块高度查询历史状态
+//!
+//! 开启 [`crate::MvccStore::set_archive_mode`] 后：
+//! - 每个区块执行完毕时 [`crate::MvccStore::record_block`] 记录该区块的提交时间戳水位线
+//! - `flush_to_storage` 除最新状态外，把上次刷盘以来的全部版本写入版本化键，并持久化已记录的区块水位线
+//! - GC / 刷盘裁剪只回收已归档的版本，`prune_old_versions` 不再删除历史
+//!
+//! 持久化编码（均位于 [`crate::MVCC_META_PREFIX`] 之下，不参与加载与状态根）：
+//! - 版本: `ARCHIVE_VERSION_PREFIX ‖ key ‖ ts(u64 be)` → `0`（删除）或 `1 ‖ value`
+//! - 区块: `BLOCK_WATERMARK_PREFIX ‖ height(u64 be)` → `ts(u64 be)`
+//!
+//! [`HistoricalState`] 基于任意 [`Storage`] 回答 `get_at_block` / `scan_at_block`。
+//! 只覆盖已刷盘的区块；挂接提交日志时，崩溃前尚未刷盘的归档版本与区块水位线由
+//! [`crate::MvccStore::replay_wal`] 写回。
+
+use crate::Storage;
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+
+/// 版本化键前缀
+pub const ARCHIVE_VERSION_PREFIX: &[u8] = b"__mvcc:v:";
+/// 区块高度 → 提交时间戳水位线
+pub const BLOCK_WATERMARK_PREFIX: &[u8] = b"__mvcc:block:";
+
+/// 版本化键：`ARCHIVE_VERSION_PREFIX ‖ key ‖ ts(be)`
+pub fn version_key(key: &[u8], ts: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(ARCHIVE_VERSION_PREFIX.len() + key.len() + 8);
+    out.extend_from_slice(ARCHIVE_VERSION_PREFIX);
+    out.extend_from_slice(key);
+    out.extend_from_slice(&ts.to_be_bytes());
+    out
+}
+
+fn version_scan_prefix(prefix: &[u8]) -> Vec<u8> {
+    let mut out = ARCHIVE_VERSION_PREFIX.to_vec();
+    out.extend_from_slice(prefix);
+    out
+}
+
+/// 区块水位线键：`BLOCK_WATERMARK_PREFIX ‖ height(be)`
+pub fn block_key(height: u64) -> Vec<u8> {
+    let mut out = BLOCK_WATERMARK_PREFIX.to_vec();
+    out.extend_from_slice(&height.to_be_bytes());
+    out
+}
+
+pub(crate) fn encode_version_value(value: Option<&[u8]>) -> Vec<u8> {
+    match value {
+        Some(v) => {
+            let mut out = Vec::with_capacity(1 + v.len());
+            out.push(1);
+            out.extend_from_slice(v);
+            out
+        }
+        None => vec![0],
+    }
+}
+
+fn decode_version_value(bytes: &[u8]) -> Result<Option<Vec<u8>>> {
+    match bytes.split_first() {
+        Some((0, [])) => Ok(None),
+        Some((1, value)) => Ok(Some(value.to_vec())),
+        _ => bail!("corrupted archived version value"),
+    }
+}
+
+/// 拆分版本化键为 (用户键, ts)；键总以 8 字节 ts 结尾，因此不需要长度前缀
+fn split_version_key(stored: &[u8]) -> Option<(&[u8], u64)> {
+    let rest = stored.strip_prefix(ARCHIVE_VERSION_PREFIX)?;
+    let split = rest.len().checked_sub(8)?;
+    let (key, ts) = rest.split_at(split);
+    Some((key, u64::from_be_bytes(ts.try_into().ok()?)))
+}
+
+/// 基于归档数据的历史状态只读视图
+pub struct HistoricalState<'a> {
+    storage: &'a dyn Storage,
+}
+
+impl<'a> HistoricalState<'a> {
+    pub fn new(storage: &'a dyn Storage) -> Self {
+        Self { storage }
+    }
+
+    /// 区块 `height` 执行完毕时的提交时间戳水位线（未归档时为 None）
+    pub fn block_ts(&self, height: u64) -> Result<Option<u64>> {
+        match self.storage.get(&block_key(height))? {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow!("corrupted block watermark for height {height}"))?;
+                Ok(Some(u64::from_be_bytes(bytes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 已归档的最高区块
+    pub fn latest_block(&self) -> Result<Option<u64>> {
+        Ok(self
+            .storage
+            .scan(BLOCK_WATERMARK_PREFIX)?
+            .iter()
+            .filter_map(|(k, _)| {
+                let height = k.strip_prefix(BLOCK_WATERMARK_PREFIX)?;
+                Some(u64::from_be_bytes(height.try_into().ok()?))
+            })
+            .max())
+    }
+
+    /// 读取 ts 时刻的值（ts 之前最后一次写入为删除或从未写入时为 None）
+    pub fn get_at_ts(&self, key: &[u8], ts: u64) -> Result<Option<Vec<u8>>> {
+        let mut best: Option<(u64, Vec<u8>)> = None;
+        for (stored, value) in self.storage.scan(&version_scan_prefix(key))? {
+            // 前缀扫描同时命中以 key 为前缀的更长键，按长度精确匹配
+            let Some((k, version_ts)) = split_version_key(&stored) else {
+                continue;
+            };
+            if k == key && version_ts <= ts && best.as_ref().is_none_or(|(b, _)| version_ts > *b) {
+                best = Some((version_ts, value));
+            }
+        }
+        match best {
+            Some((_, value)) => decode_version_value(&value),
+            None => Ok(None),
+        }
+    }
+
+    /// 扫描 ts 时刻以 `prefix` 开头的全部键值（按键排序，不含已删除的键）
+    ///
+    /// 需遍历该前缀下的全部历史版本，适合浏览器 / 审计类离线查询。
+    pub fn scan_at_ts(&self, prefix: &[u8], ts: u64) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut latest: BTreeMap<Vec<u8>, (u64, Vec<u8>)> = BTreeMap::new();
+        for (stored, value) in self.storage.scan(&version_scan_prefix(prefix))? {
+            let Some((key, version_ts)) = split_version_key(&stored) else {
+                continue;
+            };
+            if version_ts > ts || !key.starts_with(prefix) {
+                continue;
+            }
+            match latest.get(key) {
+                Some((existing, _)) if *existing >= version_ts => {}
+                _ => {
+                    latest.insert(key.to_vec(), (version_ts, value));
+                }
+            }
+        }
+        let mut out = Vec::with_capacity(latest.len());
+        for (key, (_, value)) in latest {
+            if let Some(value) = decode_version_value(&value)? {
+                out.push((key, value));
+            }
+        }
+        Ok(out)
+    }
+
+    /// 读取区块 `height` 执行完毕时 `key` 的值；区块未归档时报错
+    pub fn get_at_block(&self, key: &[u8], height: u64) -> Result<Option<Vec<u8>>> {
+        let ts = self.require_block_ts(height)?;
+        self.get_at_ts(key, ts)
+    }
+
+    /// 扫描区块 `height` 执行完毕时以 `prefix` 开头的状态；区块未归档时报错
+    pub fn scan_at_block(&self, prefix: &[u8], height: u64) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let ts = self.require_block_ts(height)?;
+        self.scan_at_ts(prefix, ts)
+    }
+
+    fn require_block_ts(&self, height: u64) -> Result<u64> {
+        self.block_ts(height)?
+            .ok_or_else(|| anyhow!("block {height} is not archived"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::{CommitLog, WalConfig};
+    use crate::{GcConfig, MemoryStorage, MvccStore};
+
+    #[test]
+    fn test_version_key_roundtrip_distinguishes_prefixed_keys() {
+        let stored = version_key(b"ab", 7);
+        assert_eq!(split_version_key(&stored), Some((&b"ab"[..], 7)));
+
+        let mut storage = MemoryStorage::new();
+        storage
+            .set(&version_key(b"a", 1), &encode_version_value(Some(b"short")))
+            .unwrap();
+        storage
+            .set(&version_key(b"ab", 1), &encode_version_value(Some(b"long")))
+            .unwrap();
+        storage
+            .set(&version_key(b"a", 2), &encode_version_value(None))
+            .unwrap();
+        let view = HistoricalState::new(&storage);
+        assert_eq!(view.get_at_ts(b"a", 1).unwrap(), Some(b"short".to_vec()));
+        assert_eq!(view.get_at_ts(b"a", 2).unwrap(), None);
+        assert_eq!(view.get_at_ts(b"ab", 5).unwrap(), Some(b"long".to_vec()));
+        assert_eq!(view.get_at_ts(b"a", 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_archive_answers_queries_by_block_height() {
+        let store = MvccStore::new_with_config(GcConfig {
+            max_versions_per_key: 1,
+            ..GcConfig::default()
+        });
+        store.set_archive_mode(true);
+        let mut storage = MemoryStorage::new();
+
+        let mut t = store.begin();
+        t.write(b"acct:a".to_vec(), b"1".to_vec());
+        t.write(b"acct:b".to_vec(), b"1".to_vec());
+        t.commit().unwrap();
+        store.record_block(1).unwrap();
+
+        let mut t = store.begin();
+        t.write(b"acct:a".to_vec(), b"2".to_vec());
+        t.delete(b"acct:b".to_vec());
+        t.write(b"acct:c".to_vec(), b"1".to_vec());
+        t.commit().unwrap();
+        store.record_block(2).unwrap();
+
+        // 归档前的 GC 不得回收尚未写入归档的版本
+        store.gc().unwrap();
+        store.flush_to_storage(&mut storage, 1).unwrap();
+
+        let mut t = store.begin();
+        t.write(b"acct:a".to_vec(), b"3".to_vec());
+        t.commit().unwrap();
+        store.record_block(3).unwrap();
+        store.gc().unwrap();
+        store.flush_to_storage(&mut storage, 1).unwrap();
+
+        let view = HistoricalState::new(&storage);
+        assert_eq!(view.latest_block().unwrap(), Some(3));
+        assert_eq!(
+            view.get_at_block(b"acct:a", 1).unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            view.get_at_block(b"acct:a", 2).unwrap(),
+            Some(b"2".to_vec())
+        );
+        assert_eq!(
+            view.get_at_block(b"acct:a", 3).unwrap(),
+            Some(b"3".to_vec())
+        );
+        assert_eq!(view.get_at_block(b"acct:b", 2).unwrap(), None);
+        assert_eq!(
+            view.scan_at_block(b"acct:", 1).unwrap(),
+            vec![
+                (b"acct:a".to_vec(), b"1".to_vec()),
+                (b"acct:b".to_vec(), b"1".to_vec())
+            ]
+        );
+        assert_eq!(
+            view.scan_at_block(b"acct:", 2).unwrap(),
+            vec![
+                (b"acct:a".to_vec(), b"2".to_vec()),
+                (b"acct:c".to_vec(), b"1".to_vec())
+            ]
+        );
+        assert!(view.get_at_block(b"acct:a", 4).is_err());
+
+        // 最新状态照常刷盘，归档数据不影响重启加载
+        assert_eq!(storage.get(b"acct:a").unwrap(), Some(b"3".to_vec()));
+        let restored = MvccStore::new();
+        assert_eq!(restored.load_from_storage(&storage, None).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_wal_replay_restores_unflushed_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MvccStore::new();
+        store.set_archive_mode(true);
+        store.attach_wal(std::sync::Arc::new(
+            CommitLog::open(WalConfig::new(dir.path())).unwrap(),
+        ));
+        let mut storage = MemoryStorage::new();
+
+        let mut t = store.begin();
+        t.write(b"acct:a".to_vec(), b"1".to_vec());
+        t.commit().unwrap();
+        store.record_block(1).unwrap();
+        store.flush_to_storage(&mut storage, 1).unwrap();
+
+        // 以下提交与区块水位线未刷盘即崩溃
+        let mut t = store.begin();
+        t.write(b"acct:a".to_vec(), b"2".to_vec());
+        t.write(b"acct:b".to_vec(), b"1".to_vec());
+        t.commit().unwrap();
+        store.record_block(2).unwrap();
+        let mut t = store.begin();
+        t.delete(b"acct:b".to_vec());
+        t.commit().unwrap();
+        store.record_block(3).unwrap();
+        drop(store);
+
+        assert_eq!(MvccStore::replay_wal(dir.path(), &mut storage).unwrap(), 4);
+        let view = HistoricalState::new(&storage);
+        assert_eq!(view.latest_block().unwrap(), Some(3));
+        assert_eq!(
+            view.get_at_block(b"acct:a", 1).unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            view.get_at_block(b"acct:a", 2).unwrap(),
+            Some(b"2".to_vec())
+        );
+        assert_eq!(
+            view.get_at_block(b"acct:b", 2).unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(view.get_at_block(b"acct:b", 3).unwrap(), None);
+        assert_eq!(storage.get(b"acct:b").unwrap(), None);
+    }
+}
@@ -0,0 +1,421 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 区块与确定性出块流水线
+//!
+//! - [`BlockHeader`]: 父哈希 / 高度 / 时间戳 / 交易根 / 状态根 / 回执根，哈希为定长大端编码的 keccak256
+//! - [`BlockBuilder`]: 按给定顺序经 [`SuperVM`] 逐笔执行交易，执行完毕后封装区块
+//! - 交易根 / 回执根: 对交易哈希、回执哈希做二叉 Merkle（叶子与内部节点分别加 0x00 / 0x01 域前缀，
+//!   奇数节点直接上提）
+//! - 状态根: 封块时把执行后的 `MvccStore` 刷入 [`AuthenticatedStorage`]，取其稀疏 Merkle 树的根，
+//!   与状态证明、状态同步使用同一套承诺；保留键（`__smt:` / `__mvcc:`）不进入树
+//!
+//! 同一父区块、时间戳、交易序列与初始状态下，任何节点重放得到的三个根与区块哈希都一致。
+//! 回执只包含确定性字段（路径、是否接受、是否成功、返回值），不含延迟等本地观测量；
+//! 启用自适应路由（软配额依赖运行期统计）时路径可能因节点而异，出块时不应启用。
+
+use crate::mvcc::{AutoFlushConfig, Txn};
+use crate::state_commitment::AuthenticatedStorage;
+use crate::storage::Storage;
+use crate::supervm::{ExecutionPath, Privacy, SuperVM, Transaction as VmTransaction};
+use anyhow::{anyhow, Result};
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+
+pub type Hash = [u8; 32];
+
+/// 交易业务逻辑（在 MVCC 事务内执行）
+pub type BlockTxnOp = Arc<dyn Fn(&mut Txn) -> Result<i32> + Send + Sync>;
+
+/// 区块头
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub parent_hash: Hash,
+    pub height: u64,
+    pub timestamp: u64,
+    pub tx_root: Hash,
+    pub state_root: Hash,
+    pub receipts_root: Hash,
+}
+
+impl BlockHeader {
+    /// 区块哈希：keccak256(parent_hash ‖ height_be ‖ timestamp_be ‖ tx_root ‖ state_root ‖ receipts_root)
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.parent_hash);
+        hasher.update(self.height.to_be_bytes());
+        hasher.update(self.timestamp.to_be_bytes());
+        hasher.update(self.tx_root);
+        hasher.update(self.state_root);
+        hasher.update(self.receipts_root);
+        hasher.finalize().into()
+    }
+}
+
+/// 区块中的交易：路由描述 + 业务负载（如 WASM 调用数据），负载参与交易哈希
+#[derive(Debug, Clone)]
+pub struct BlockTransaction {
+    pub tx: VmTransaction,
+    pub payload: Vec<u8>,
+}
+
+impl BlockTransaction {
+    /// 交易哈希：keccak256(from ‖ objects_len_be ‖ objects ‖ privacy ‖ payload_len_be ‖ payload)
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.tx.from);
+        hasher.update((self.tx.objects.len() as u32).to_be_bytes());
+        for object in &self.tx.objects {
+            hasher.update(object);
+        }
+        hasher.update([match self.tx.privacy {
+            Privacy::Public => 0u8,
+            Privacy::Private => 1u8,
+        }]);
+        hasher.update((self.payload.len() as u32).to_be_bytes());
+        hasher.update(&self.payload);
+        hasher.finalize().into()
+    }
+}
+
+/// 交易回执（仅确定性字段）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockReceipt {
+    pub tx_hash: Hash,
+    pub path: ExecutionPath,
+    pub accepted: bool,
+    pub success: bool,
+    pub return_value: Option<i32>,
+}
+
+impl BlockReceipt {
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.tx_hash);
+        hasher.update([
+            match self.path {
+                ExecutionPath::FastPath => 0u8,
+                ExecutionPath::ConsensusPath => 1u8,
+                ExecutionPath::PrivatePath => 2u8,
+            },
+            self.accepted as u8,
+            self.success as u8,
+        ]);
+        match self.return_value {
+            Some(v) => {
+                hasher.update([1u8]);
+                hasher.update(v.to_be_bytes());
+            }
+            None => hasher.update([0u8]),
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// 已封装的区块
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub transactions: Vec<BlockTransaction>,
+    pub receipts: Vec<BlockReceipt>,
+}
+
+impl Block {
+    pub fn hash(&self) -> Hash {
+        self.header.hash()
+    }
+
+    /// 校验区块体与头中的交易根 / 回执根一致（状态根需重放执行才能校验）
+    pub fn verify_body(&self) -> bool {
+        self.transactions.len() == self.receipts.len()
+            && self
+                .transactions
+                .iter()
+                .zip(&self.receipts)
+                .all(|(tx, receipt)| tx.hash() == receipt.tx_hash)
+            && tx_root(&self.transactions) == self.header.tx_root
+            && receipts_root(&self.receipts) == self.header.receipts_root
+    }
+}
+
+/// 二叉 Merkle 根（空集合为全零）
+pub fn merkle_root(leaves: &[Hash]) -> Hash {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+    let mut level: Vec<Hash> = leaves
+        .iter()
+        .map(|leaf| {
+            let mut hasher = Keccak256::new();
+            hasher.update([0x00]);
+            hasher.update(leaf);
+            hasher.finalize().into()
+        })
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Keccak256::new();
+                    hasher.update([0x01]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two items"),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+pub fn tx_root(transactions: &[BlockTransaction]) -> Hash {
+    merkle_root(
+        &transactions
+            .iter()
+            .map(BlockTransaction::hash)
+            .collect::<Vec<_>>(),
+    )
+}
+
+pub fn receipts_root(receipts: &[BlockReceipt]) -> Hash {
+    merkle_root(&receipts.iter().map(BlockReceipt::hash).collect::<Vec<_>>())
+}
+
+/// 区块构建器
+pub struct BlockBuilder {
+    parent_hash: Hash,
+    height: u64,
+    timestamp: u64,
+    transactions: Vec<(BlockTransaction, BlockTxnOp)>,
+}
+
+impl BlockBuilder {
+    /// 创世区块（高度 0，父哈希全零）
+    pub fn genesis() -> Self {
+        Self {
+            parent_hash: [0u8; 32],
+            height: 0,
+            timestamp: 0,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// 以 `parent` 为父区块
+    pub fn child_of(parent: &BlockHeader) -> Self {
+        Self {
+            parent_hash: parent.hash(),
+            height: parent.height + 1,
+            timestamp: parent.timestamp,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// 设置区块时间戳（由出块者给定，不读取本地时钟）
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// 追加一笔交易（执行顺序即追加顺序）
+    pub fn push(mut self, tx: VmTransaction, payload: Vec<u8>, op: BlockTxnOp) -> Self {
+        self.transactions
+            .push((BlockTransaction { tx, payload }, op));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// 经 SuperVM 按序逐笔执行并封装区块（SuperVM 需已配置调度器）
+    ///
+    /// 执行完毕后把调度器的 `MvccStore` 刷入 `state`，区块头的状态根即 `state` 更新后的树根；
+    /// 树按刷盘批次增量更新，不回源扫描后端。
+    pub fn seal<S: Storage>(
+        self,
+        vm: &SuperVM<'_>,
+        state: &mut AuthenticatedStorage<S>,
+    ) -> Result<Block> {
+        let scheduler = vm
+            .scheduler()
+            .ok_or_else(|| anyhow!("SuperVM not configured with scheduler"))?;
+        scheduler.store().set_current_block(self.height);
+
+        let mut transactions = Vec::with_capacity(self.transactions.len());
+        let mut receipts = Vec::with_capacity(self.transactions.len());
+        for (index, (tx, op)) in self.transactions.into_iter().enumerate() {
+            let receipt = vm.execute_transaction_with(index as u64, &tx.tx, |txn| op(txn));
+            receipts.push(BlockReceipt {
+                tx_hash: tx.hash(),
+                path: receipt.path,
+                accepted: receipt.accepted,
+                success: receipt.success,
+                return_value: receipt.return_value,
+            });
+            transactions.push(tx);
+        }
+        // 区块边界的提交水位线（归档模式下用于按高度查询历史状态）
+        scheduler
+            .store()
+            .record_block(self.height)
+            .map_err(|e| anyhow!(e))?;
+        scheduler
+            .store()
+            .flush_to_storage(state, AutoFlushConfig::default().keep_recent_versions)
+            .map_err(|e| anyhow!(e))?;
+
+        let header = BlockHeader {
+            parent_hash: self.parent_hash,
+            height: self.height,
+            timestamp: self.timestamp,
+            tx_root: tx_root(&transactions),
+            state_root: state.state_root(),
+            receipts_root: receipts_root(&receipts),
+        };
+        Ok(Block {
+            header,
+            transactions,
+            receipts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel_mvcc::MvccScheduler;
+    use crate::storage::MemoryStorage;
+    use crate::OwnershipManager;
+
+    fn transfer(from: u8, amount: u8) -> (VmTransaction, Vec<u8>, BlockTxnOp) {
+        let tx = VmTransaction {
+            from: [from; 32],
+            objects: vec![],
+            privacy: Privacy::Public,
+        };
+        let op: BlockTxnOp = Arc::new(move |txn: &mut Txn| {
+            let key = format!("balance:{}", from).into_bytes();
+            let balance = txn.read(&key).map_or(0, |v| v[0]);
+            let next = balance
+                .checked_add(amount)
+                .ok_or_else(|| anyhow!("overflow"))?;
+            txn.write(key, vec![next]);
+            Ok(next as i32)
+        });
+        (tx, vec![from, amount], op)
+    }
+
+    fn build(parent: Option<&BlockHeader>, items: &[(u8, u8)]) -> Result<Block> {
+        let mut state = AuthenticatedStorage::new(MemoryStorage::new())?;
+        build_into(parent, items, &mut state)
+    }
+
+    fn build_into(
+        parent: Option<&BlockHeader>,
+        items: &[(u8, u8)],
+        state: &mut AuthenticatedStorage<MemoryStorage>,
+    ) -> Result<Block> {
+        let ownership = OwnershipManager::new();
+        let scheduler = MvccScheduler::new();
+        let vm = SuperVM::new(&ownership).with_scheduler(&scheduler);
+        let builder = match parent {
+            Some(p) => BlockBuilder::child_of(p),
+            None => BlockBuilder::genesis(),
+        };
+        items
+            .iter()
+            .fold(
+                builder.with_timestamp(1_700_000_000),
+                |b, &(from, amount)| {
+                    let (tx, payload, op) = transfer(from, amount);
+                    b.push(tx, payload, op)
+                },
+            )
+            .seal(&vm, state)
+    }
+
+    #[test]
+    fn test_block_roots_reproducible() -> Result<()> {
+        let items = [(1, 10), (2, 5), (1, 250), (1, 1)];
+        let a = build(None, &items)?;
+        let b = build(None, &items)?;
+        assert_eq!(a.header, b.header);
+        assert!(a.verify_body());
+        assert_eq!(a.header.height, 0);
+        assert_ne!(a.header.state_root, [0u8; 32]);
+
+        // 第三笔溢出失败，第四笔仍基于第一笔的余额
+        let values: Vec<_> = a
+            .receipts
+            .iter()
+            .map(|r| (r.success, r.return_value))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (true, Some(10)),
+                (true, Some(5)),
+                (false, None),
+                (true, Some(11))
+            ]
+        );
+
+        // 交易顺序不同则交易根与区块哈希不同
+        let reordered = build(None, &[(2, 5), (1, 10), (1, 250), (1, 1)])?;
+        assert_ne!(reordered.header.tx_root, a.header.tx_root);
+        assert_ne!(reordered.hash(), a.hash());
+
+        let child = build(Some(&a.header), &[(3, 1)])?;
+        assert_eq!(child.header.parent_hash, a.hash());
+        assert_eq!(child.header.height, 1);
+
+        let mut tampered = a.clone();
+        tampered.transactions[0].payload = vec![0];
+        assert!(!tampered.verify_body());
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_root_is_state_tree_root() -> Result<()> {
+        let mut state = AuthenticatedStorage::new(MemoryStorage::new())?;
+        let block = build_into(None, &[(1, 10), (2, 5)], &mut state)?;
+        assert_eq!(block.header.state_root, state.state_root());
+
+        // 与直接写入同一组用户键值得到的树根一致：刷盘水位线等保留键不是叶子
+        let mut expected = AuthenticatedStorage::new(MemoryStorage::new())?;
+        expected.set(b"balance:1", &[10])?;
+        expected.set(b"balance:2", &[5])?;
+        assert_eq!(block.header.state_root, expected.state_root());
+
+        // 状态根可直接用于出具证明
+        let proof = state.prove(b"balance:1")?;
+        assert!(crate::state_commitment::verify_proof(
+            &block.header.state_root,
+            b"balance:1",
+            Some(&[10]),
+            &proof
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merkle_root_shapes() {
+        assert_eq!(merkle_root(&[]), [0u8; 32]);
+        let leaves: Vec<Hash> = (0..5u8).map(|i| [i; 32]).collect();
+        let root = merkle_root(&leaves);
+        assert_ne!(root, merkle_root(&leaves[..4]));
+        let mut swapped = leaves.clone();
+        swapped.swap(0, 1);
+        assert_ne!(root, merkle_root(&swapped));
+    }
+}
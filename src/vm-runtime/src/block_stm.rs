@@ -0,0 +1,449 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! Block-STM 风格的 WASM 区块乐观并行执行
+//!
+//! 以区块内顺序 0..n 为准:
+//! 1. 全部交易在基础状态上并行执行（rayon），每次执行记录读到的键及观测值、前缀扫描结果与写入
+//! 2. 按区块顺序校验: 交易 i 的读观测值与“基础状态 + 已提交交易 0..i 的写入”一致即提交其写入
+//! 3. 遇到首个校验失败的交易时，对其及之后所有读集已失效的交易在当前已提交状态上并行重执行，
+//!    回到第 2 步；首个失败交易的重执行必然通过校验，每轮至少推进一笔
+//!
+//! 固定区块号 / 时间戳 / gas 上限后执行结果只取决于读到的值，因此提交结果与按序逐笔执行
+//! （[`BlockExecutor::execute_sequential`]）完全一致。trap / gas 耗尽的交易不产生写入，
+//! 但其读集同样参与校验。
+
+use crate::execution_trait::StateChange;
+use crate::parallel::{ExecutionResult, TxId};
+use crate::storage::Storage;
+use crate::txn_executor::TxnExecutor;
+use anyhow::{anyhow, Result};
+use parking_lot::{Mutex, RwLock};
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use wasmtime::Module;
+
+type WriteMap = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+type ScanRecord = (Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>);
+
+/// 区块中的一笔 WASM 交易：模块字节码 + 导出函数 `func_name() -> i32`
+#[derive(Debug, Clone)]
+pub struct WasmTransaction {
+    pub module: Vec<u8>,
+    pub func_name: String,
+}
+
+impl WasmTransaction {
+    pub fn new(module: impl Into<Vec<u8>>, func_name: impl Into<String>) -> Self {
+        Self {
+            module: module.into(),
+            func_name: func_name.into(),
+        }
+    }
+}
+
+/// 区块执行统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockStmStats {
+    /// 执行次数（含重执行）
+    pub executions: u64,
+    /// 因读集失效而重执行的次数
+    pub reexecutions: u64,
+    /// 并行执行轮数
+    pub waves: u64,
+}
+
+/// 区块执行输出
+#[derive(Debug, Clone)]
+pub struct BlockOutput {
+    /// 各交易的执行结果（按区块顺序，`tx_id` 为区块内序号）
+    pub results: Vec<ExecutionResult>,
+    /// 区块净写入（按键排序，`None` 表示删除）
+    pub state_changes: Vec<StateChange>,
+    pub stats: BlockStmStats,
+}
+
+impl BlockOutput {
+    /// 将区块写入落到存储
+    pub fn apply_to<S: Storage>(&self, storage: &mut S) -> Result<()> {
+        for change in &self.state_changes {
+            match &change.value {
+                Some(value) => storage.set(&change.key, value)?,
+                None => storage.delete(&change.key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 基础状态 + 已按序提交交易的写入
+struct Committed<S> {
+    base: Arc<S>,
+    writes: RwLock<WriteMap>,
+}
+
+impl<S: Storage> Committed<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(v) = self.writes.read().get(key) {
+            return Ok(v.clone());
+        }
+        self.base.get(key)
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> = self.base.scan(prefix)?.into_iter().collect();
+        overlay_range(&mut merged, &self.writes.read(), prefix);
+        Ok(merged.into_iter().collect())
+    }
+
+    /// 读集观测值与当前已提交状态一致
+    fn validate(&self, incarnation: &Incarnation) -> Result<bool> {
+        for (key, observed) in &incarnation.reads {
+            if self.get(key)? != *observed {
+                return Ok(false);
+            }
+        }
+        for (prefix, observed) in &incarnation.scans {
+            if self.scan(prefix)? != *observed {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn commit(&self, incarnation: &mut Incarnation) {
+        self.writes
+            .write()
+            .extend(std::mem::take(&mut incarnation.writes));
+    }
+}
+
+fn overlay_range(target: &mut BTreeMap<Vec<u8>, Vec<u8>>, writes: &WriteMap, prefix: &[u8]) {
+    for (key, value) in writes.range(prefix.to_vec()..) {
+        if !key.starts_with(prefix) {
+            break;
+        }
+        match value {
+            Some(v) => target.insert(key.clone(), v.clone()),
+            None => target.remove(key),
+        };
+    }
+}
+
+/// 单次执行的存储视图：读穿到已提交状态并记录观测值，写入缓冲在本地
+struct BlockView<S> {
+    committed: Arc<Committed<S>>,
+    writes: WriteMap,
+    reads: RefCell<WriteMap>,
+    scans: RefCell<Vec<ScanRecord>>,
+}
+
+impl<S: Storage> Storage for BlockView<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(v) = self.writes.get(key) {
+            return Ok(v.clone());
+        }
+        let value = self.committed.get(key)?;
+        self.reads
+            .borrow_mut()
+            .entry(key.to_vec())
+            .or_insert_with(|| value.clone());
+        Ok(value)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.writes.insert(key.to_vec(), None);
+        Ok(())
+    }
+
+    /// 记录已提交状态上的扫描结果（防幻读），再叠加本地写入
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let observed = self.committed.scan(prefix)?;
+        self.scans
+            .borrow_mut()
+            .push((prefix.to_vec(), observed.clone()));
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> = observed.into_iter().collect();
+        overlay_range(&mut merged, &self.writes, prefix);
+        Ok(merged.into_iter().collect())
+    }
+}
+
+/// 一次执行（incarnation）的结果与读写记录
+struct Incarnation {
+    result: ExecutionResult,
+    reads: WriteMap,
+    scans: Vec<ScanRecord>,
+    writes: WriteMap,
+}
+
+/// Block-STM 区块执行器
+///
+/// 复用 [`TxnExecutor`] 的引擎、模块缓存、计费表与调用配置；基础状态以 `Arc<S>` 只读共享，
+/// 执行不修改基础状态，由调用方通过 [`BlockOutput::apply_to`] 落盘。
+#[derive(Clone, Default)]
+pub struct BlockExecutor {
+    executor: TxnExecutor,
+    block_number: u64,
+    timestamp: u64,
+}
+
+impl BlockExecutor {
+    pub fn new(executor: TxnExecutor) -> Self {
+        Self {
+            executor,
+            block_number: 0,
+            timestamp: 0,
+        }
+    }
+
+    /// 设置区块上下文（`chain_api::block_number` / `timestamp` 可见）
+    pub fn with_block(mut self, block_number: u64, timestamp: u64) -> Self {
+        self.block_number = block_number;
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// 乐观并行执行整个区块，结果与 [`Self::execute_sequential`] 一致
+    pub fn execute<S: Storage + Send + Sync + 'static>(
+        &self,
+        base: Arc<S>,
+        txs: &[WasmTransaction],
+    ) -> Result<BlockOutput> {
+        let modules = self.compile_all(txs)?;
+        let committed = Arc::new(Committed {
+            base,
+            writes: RwLock::new(WriteMap::new()),
+        });
+        let mut stats = BlockStmStats::default();
+        let mut incarnations: Vec<Option<Incarnation>> = (0..txs.len()).map(|_| None).collect();
+        let mut results = Vec::with_capacity(txs.len());
+        let mut pending: Vec<usize> = (0..txs.len()).collect();
+        let mut next = 0;
+
+        loop {
+            if !pending.is_empty() {
+                stats.waves += 1;
+                stats.executions += pending.len() as u64;
+                let executed: Vec<(usize, Result<Incarnation>)> = pending
+                    .par_iter()
+                    .map(|&i| {
+                        (
+                            i,
+                            self.execute_one(&committed, &modules[i], &txs[i].func_name, i),
+                        )
+                    })
+                    .collect();
+                for (i, incarnation) in executed {
+                    incarnations[i] = Some(incarnation?);
+                }
+                pending.clear();
+            }
+
+            // 按区块顺序校验并提交
+            while next < txs.len() {
+                let incarnation = incarnations[next]
+                    .as_mut()
+                    .expect("executed before validation");
+                if !committed.validate(incarnation)? {
+                    break;
+                }
+                committed.commit(incarnation);
+                results.push(incarnations[next].take().expect("validated above").result);
+                next += 1;
+            }
+            if next == txs.len() {
+                break;
+            }
+
+            // 首个失败交易必须重执行，其后读集已失效的交易一并推测重执行
+            for (i, slot) in incarnations.iter().enumerate().skip(next) {
+                let incarnation = slot.as_ref().expect("uncommitted incarnation");
+                if i == next || !committed.validate(incarnation)? {
+                    pending.push(i);
+                }
+            }
+            stats.reexecutions += pending.len() as u64;
+        }
+
+        Ok(Self::output(results, &committed, stats))
+    }
+
+    /// 按区块顺序逐笔执行（参考语义）
+    pub fn execute_sequential<S: Storage + Send + Sync + 'static>(
+        &self,
+        base: Arc<S>,
+        txs: &[WasmTransaction],
+    ) -> Result<BlockOutput> {
+        let modules = self.compile_all(txs)?;
+        let committed = Arc::new(Committed {
+            base,
+            writes: RwLock::new(WriteMap::new()),
+        });
+        let mut results = Vec::with_capacity(txs.len());
+        for (i, tx) in txs.iter().enumerate() {
+            let mut incarnation = self.execute_one(&committed, &modules[i], &tx.func_name, i)?;
+            committed.commit(&mut incarnation);
+            results.push(incarnation.result);
+        }
+        let stats = BlockStmStats {
+            executions: txs.len() as u64,
+            ..BlockStmStats::default()
+        };
+        Ok(Self::output(results, &committed, stats))
+    }
+
+    fn compile_all(&self, txs: &[WasmTransaction]) -> Result<Vec<Module>> {
+        txs.iter()
+            .map(|tx| self.executor.compile(&tx.module))
+            .collect()
+    }
+
+    fn execute_one<S: Storage + 'static>(
+        &self,
+        committed: &Arc<Committed<S>>,
+        module: &Module,
+        func_name: &str,
+        index: usize,
+    ) -> Result<Incarnation> {
+        let view = Arc::new(Mutex::new(BlockView {
+            committed: committed.clone(),
+            writes: WriteMap::new(),
+            reads: RefCell::new(WriteMap::new()),
+            scans: RefCell::new(Vec::new()),
+        }));
+        let mut result = self.executor.run(
+            view.clone(),
+            module,
+            func_name,
+            self.block_number,
+            self.timestamp,
+        )?;
+        result.tx_id = index as TxId;
+
+        let view = Arc::try_unwrap(view)
+            .map_err(|_| anyhow!("block view still borrowed after execution"))?
+            .into_inner();
+        Ok(Incarnation {
+            reads: view.reads.into_inner(),
+            scans: view.scans.into_inner(),
+            // trap / gas 耗尽的交易不产生写入
+            writes: if result.success {
+                view.writes
+            } else {
+                WriteMap::new()
+            },
+            result,
+        })
+    }
+
+    fn output<S>(
+        results: Vec<ExecutionResult>,
+        committed: &Committed<S>,
+        stats: BlockStmStats,
+    ) -> BlockOutput {
+        let state_changes = committed
+            .writes
+            .read()
+            .iter()
+            .map(|(key, value)| StateChange {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        BlockOutput {
+            results,
+            state_changes,
+            stats,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    /// `incr`: storage["c"] += 1（单字节）；`copy`: storage["d"] = storage["c"]；`boom`: 写入后 trap
+    const WAT: &str = r#"
+    (module
+        (import "storage_api" "storage_get" (func $get (param i32 i32) (result i64)))
+        (import "storage_api" "storage_read_value" (func $read (param i32 i32) (result i32)))
+        (import "storage_api" "storage_set" (func $set (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "cd")
+        (func $load_c (result i32)
+            (i32.store8 (i32.const 16) (i32.const 0))
+            (if (i64.ne (call $get (i32.const 0) (i32.const 1)) (i64.const 0))
+                (then (drop (call $read (i32.const 16) (i32.const 1))))
+            )
+            (i32.load8_u (i32.const 16))
+        )
+        (func (export "incr") (result i32)
+            (i32.store8 (i32.const 16) (i32.add (call $load_c) (i32.const 1)))
+            (drop (call $set (i32.const 0) (i32.const 1) (i32.const 16) (i32.const 1)))
+            (i32.load8_u (i32.const 16))
+        )
+        (func (export "copy") (result i32)
+            (drop (call $load_c))
+            (drop (call $set (i32.const 1) (i32.const 1) (i32.const 16) (i32.const 1)))
+            (i32.load8_u (i32.const 16))
+        )
+        (func (export "boom") (result i32)
+            (drop (call $set (i32.const 0) (i32.const 1) (i32.const 0) (i32.const 1)))
+            unreachable
+        )
+    )
+    "#;
+
+    #[test]
+    fn test_conflicting_block_matches_sequential() -> Result<()> {
+        let wasm = wat::parse_str(WAT)?;
+        let txs: Vec<WasmTransaction> = ["incr", "copy", "boom", "incr", "incr", "copy"]
+            .iter()
+            .map(|f| WasmTransaction::new(wasm.clone(), *f))
+            .collect();
+        let executor = BlockExecutor::default();
+        let base = Arc::new(MemoryStorage::new());
+
+        let parallel = executor.execute(base.clone(), &txs)?;
+        let sequential = executor.execute_sequential(base, &txs)?;
+
+        let values: Vec<i32> = parallel.results.iter().map(|r| r.return_value).collect();
+        assert_eq!(values, vec![1, 1, -1, 2, 3, 3]);
+        assert!(!parallel.results[2].success);
+        assert_eq!(
+            parallel.state_changes,
+            vec![
+                StateChange {
+                    key: b"c".to_vec(),
+                    value: Some(vec![3])
+                },
+                StateChange {
+                    key: b"d".to_vec(),
+                    value: Some(vec![3])
+                },
+            ]
+        );
+        assert_eq!(parallel.state_changes, sequential.state_changes);
+        assert!(parallel.stats.reexecutions > 0);
+        assert_eq!(
+            parallel.stats.executions,
+            txs.len() as u64 + parallel.stats.reexecutions
+        );
+
+        let mut storage = MemoryStorage::new();
+        parallel.apply_to(&mut storage)?;
+        assert_eq!(storage.get(b"c")?, Some(vec![3]));
+        Ok(())
+    }
+}
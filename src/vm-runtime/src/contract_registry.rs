@@ -0,0 +1,676 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 合约注册表与编译模块缓存
+//!
+//! 部署一次、按地址调用:
+//! - 地址派生: [`AddressScheme::DeployerNonce`] = keccak256(deployer ‖ nonce_be)[12..]，
+//!   [`AddressScheme::CodeHash`] = keccak256(0xff ‖ deployer ‖ salt ‖ code_hash)[12..]（CREATE2 风格）
+//! - 存储布局（`Storage` 命名空间 `contract:`）:
+//!   - `contract:code:` + code_hash(32)  → WASM 字节码（相同代码仅存一份）
+//!   - `contract:info:` + address(20)    → code_hash(32) ‖ deployer(20)
+//!   - `contract:nonce:` + deployer(20)  → 部署计数（u64 大端）
+//!   - `contract:store:` + address(20)   → 合约私有存储命名空间（按地址调用时 `storage_api` 的键前缀）
+//! - [`ModuleCache`] 按 code_hash 缓存编译后的 `wasmtime::Module`（LRU，容量有上限），
+//!   可选将预编译产物序列化到磁盘目录，进程重启后免编译加载；加载前校验引擎指纹与校验和
+//! - 合约间调用（`contract_api::call`）的深度限制与重入策略见 [`CallConfig`]
+
+use crate::crypto::{keccak256, sha256};
+use crate::Storage;
+use anyhow::Result;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use wasmtime::{Engine, Module};
+
+/// 注册表命名空间（下列前缀的公共前缀）；直接执行（无合约地址）时 `storage_api` 不得访问
+pub const NAMESPACE: &[u8] = b"contract:";
+pub const CODE_PREFIX: &[u8] = b"contract:code:";
+pub const INFO_PREFIX: &[u8] = b"contract:info:";
+pub const NONCE_PREFIX: &[u8] = b"contract:nonce:";
+pub const STORAGE_PREFIX: &[u8] = b"contract:store:";
+
+/// 默认最大调用深度（顶层调用为第 0 层）
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 64;
+
+/// 默认内存模块缓存容量
+pub const DEFAULT_MODULE_CACHE_CAPACITY: usize = 256;
+
+/// 预编译产物文件扩展名
+const ARTIFACT_EXT: &str = "cwasm";
+/// 产物头部: magic(8) ‖ 引擎指纹(32) ‖ code_hash(32) ‖ 载荷 SHA-256(32)
+const ARTIFACT_MAGIC: &[u8; 8] = b"SVMCWASM";
+const ARTIFACT_HEADER_LEN: usize = 8 + 32 * 3;
+
+/// 合约地址派生方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressScheme {
+    /// 由部署者地址与其部署计数派生（每次部署得到新地址）
+    DeployerNonce,
+    /// 由部署者、盐值与代码哈希派生（地址可在部署前预先计算）
+    CodeHash { salt: [u8; 32] },
+}
+
+/// 重入策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReentrancyPolicy {
+    /// 禁止调用当前调用栈上已存在的合约（含自调用）
+    #[default]
+    Deny,
+    /// 允许重入，仅受调用深度限制
+    Allow,
+}
+
+/// 合约间调用配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallConfig {
+    /// 最大调用深度
+    pub max_depth: usize,
+    /// 重入策略
+    pub reentrancy: ReentrancyPolicy,
+}
+
+impl Default for CallConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_CALL_DEPTH,
+            reentrancy: ReentrancyPolicy::Deny,
+        }
+    }
+}
+
+/// `contract_api::call` 返回的状态码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum CallStatus {
+    /// 被调合约返回 0，写入保留
+    Success = 0,
+    /// 被调合约返回非 0 或 guest trap（含 gas 耗尽），写入已撤销；宿主故障不返回此状态而是向上传播
+    Reverted = 1,
+    /// 超出最大调用深度
+    DepthExceeded = 2,
+    /// 被重入策略拒绝
+    ReentrancyDenied = 3,
+    /// 目标地址未部署合约
+    NotFound = 4,
+}
+
+/// 已部署合约的元数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractInfo {
+    pub address: [u8; 20],
+    pub code_hash: [u8; 32],
+    pub deployer: [u8; 20],
+}
+
+/// 注册表错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RegistryError {
+    #[error("contract already deployed at 0x{}", hex::encode(.0))]
+    AlreadyDeployed([u8; 20]),
+    #[error("no contract deployed at 0x{}", hex::encode(.0))]
+    NotFound([u8; 20]),
+    #[error("code for hash 0x{} missing from storage", hex::encode(.0))]
+    CodeMissing([u8; 32]),
+    #[error("corrupted contract metadata at 0x{}", hex::encode(.0))]
+    CorruptedInfo([u8; 20]),
+    #[error("storage key under the reserved contract namespace")]
+    ReservedNamespace,
+}
+
+/// 代码哈希（keccak256）
+pub fn code_hash(code: &[u8]) -> [u8; 32] {
+    keccak256(code)
+}
+
+/// 按部署者与 nonce 派生地址
+pub fn derive_address(deployer: &[u8; 20], nonce: u64) -> [u8; 20] {
+    let mut preimage = Vec::with_capacity(28);
+    preimage.extend_from_slice(deployer);
+    preimage.extend_from_slice(&nonce.to_be_bytes());
+    truncate_address(keccak256(&preimage))
+}
+
+/// 按部署者、盐值与代码哈希派生地址
+pub fn derive_address_from_code_hash(
+    deployer: &[u8; 20],
+    salt: &[u8; 32],
+    code_hash: &[u8; 32],
+) -> [u8; 20] {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xff);
+    preimage.extend_from_slice(deployer);
+    preimage.extend_from_slice(salt);
+    preimage.extend_from_slice(code_hash);
+    truncate_address(keccak256(&preimage))
+}
+
+fn truncate_address(hash: [u8; 32]) -> [u8; 20] {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// 合约私有存储的键前缀
+pub fn storage_prefix(address: &[u8; 20]) -> Vec<u8> {
+    [STORAGE_PREFIX, address.as_slice()].concat()
+}
+
+fn code_key(code_hash: &[u8; 32]) -> Vec<u8> {
+    [CODE_PREFIX, code_hash.as_slice()].concat()
+}
+
+fn info_key(address: &[u8; 20]) -> Vec<u8> {
+    [INFO_PREFIX, address.as_slice()].concat()
+}
+
+fn nonce_key(deployer: &[u8; 20]) -> Vec<u8> {
+    [NONCE_PREFIX, deployer.as_slice()].concat()
+}
+
+/// 部署者当前 nonce（已部署次数）
+pub fn deployer_nonce<S: Storage>(storage: &S, deployer: &[u8; 20]) -> Result<u64> {
+    Ok(match storage.get(&nonce_key(deployer))? {
+        Some(bytes) if bytes.len() == 8 => u64::from_be_bytes(bytes.try_into().expect("8 bytes")),
+        _ => 0,
+    })
+}
+
+/// 读取合约元数据
+pub fn contract_info<S: Storage>(storage: &S, address: &[u8; 20]) -> Result<Option<ContractInfo>> {
+    let Some(bytes) = storage.get(&info_key(address))? else {
+        return Ok(None);
+    };
+    if bytes.len() != 52 {
+        return Err(RegistryError::CorruptedInfo(*address).into());
+    }
+    let mut code_hash = [0u8; 32];
+    let mut deployer = [0u8; 20];
+    code_hash.copy_from_slice(&bytes[..32]);
+    deployer.copy_from_slice(&bytes[32..]);
+    Ok(Some(ContractInfo {
+        address: *address,
+        code_hash,
+        deployer,
+    }))
+}
+
+/// 读取合约字节码
+pub fn contract_code<S: Storage>(storage: &S, address: &[u8; 20]) -> Result<Option<Vec<u8>>> {
+    let Some(info) = contract_info(storage, address)? else {
+        return Ok(None);
+    };
+    storage
+        .get(&code_key(&info.code_hash))?
+        .map(Some)
+        .ok_or_else(|| RegistryError::CodeMissing(info.code_hash).into())
+}
+
+/// 登记合约：写入代码、元数据并递增部署者 nonce，返回合约元数据
+///
+/// 代码合法性（可编译）由调用方在登记前保证。
+pub fn register<S: Storage>(
+    storage: &mut S,
+    deployer: &[u8; 20],
+    code: &[u8],
+    scheme: AddressScheme,
+) -> Result<ContractInfo> {
+    let hash = code_hash(code);
+    let nonce = deployer_nonce(storage, deployer)?;
+    let address = match scheme {
+        AddressScheme::DeployerNonce => derive_address(deployer, nonce),
+        AddressScheme::CodeHash { salt } => derive_address_from_code_hash(deployer, &salt, &hash),
+    };
+    if storage.get(&info_key(&address))?.is_some() {
+        return Err(RegistryError::AlreadyDeployed(address).into());
+    }
+
+    let code_key = code_key(&hash);
+    if storage.get(&code_key)?.is_none() {
+        storage.set(&code_key, code)?;
+    }
+    storage.set(
+        &info_key(&address),
+        &[hash.as_slice(), deployer.as_slice()].concat(),
+    )?;
+    storage.set(&nonce_key(deployer), &(nonce + 1).to_be_bytes())?;
+
+    Ok(ContractInfo {
+        address,
+        code_hash: hash,
+        deployer: *deployer,
+    })
+}
+
+/// 模块缓存统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// 内存命中
+    pub hits: u64,
+    /// 磁盘预编译产物命中
+    pub disk_hits: u64,
+    /// 实际编译次数
+    pub compiles: u64,
+    /// 因容量上限淘汰的内存模块数
+    pub evictions: u64,
+}
+
+/// 按代码哈希缓存的编译模块
+///
+/// `Module` 内部为 `Arc`，克隆开销很小；缓存本身线程安全。
+/// 内存中至多保留 `capacity` 个模块，超出时淘汰最久未使用者（磁盘产物保留）。
+pub struct ModuleCache {
+    modules: Mutex<LruModules>,
+    artifact_dir: Option<PathBuf>,
+    hits: AtomicU64,
+    disk_hits: AtomicU64,
+    compiles: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// 带访问时钟的模块表；容量通常为数百，淘汰时线性扫描最小时钟即可
+struct LruModules {
+    entries: HashMap<[u8; 32], (Module, u64)>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl LruModules {
+    fn get(&mut self, hash: &[u8; 32]) -> Option<Module> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(hash)?;
+        entry.1 = self.clock;
+        Some(entry.0.clone())
+    }
+
+    /// 插入模块，返回是否淘汰了旧模块
+    fn insert(&mut self, hash: [u8; 32], module: Module) -> bool {
+        self.clock += 1;
+        let mut evicted = false;
+        if !self.entries.contains_key(&hash) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(hash, _)| *hash);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+                evicted = true;
+            }
+        }
+        self.entries.insert(hash, (module, self.clock));
+        evicted
+    }
+}
+
+impl Default for ModuleCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_MODULE_CACHE_CAPACITY)
+    }
+}
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 指定内存中缓存的模块数上限（至少为 1）
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            modules: Mutex::new(LruModules {
+                entries: HashMap::new(),
+                capacity: capacity.max(1),
+                clock: 0,
+            }),
+            artifact_dir: None,
+            hits: AtomicU64::new(0),
+            disk_hits: AtomicU64::new(0),
+            compiles: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// 启用磁盘预编译产物缓存（目录不存在时自动创建）
+    pub fn with_artifact_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.artifact_dir = Some(dir.into());
+        self
+    }
+
+    pub fn artifact_dir(&self) -> Option<&Path> {
+        self.artifact_dir.as_deref()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.modules.lock().capacity
+    }
+
+    /// 获取已编译模块，未命中时依次尝试磁盘产物与编译
+    pub fn get_or_compile(&self, engine: &Engine, code: &[u8]) -> Result<Module> {
+        let hash = code_hash(code);
+        if let Some(module) = self.modules.lock().get(&hash) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(module);
+        }
+
+        let module = match self.load_artifact(engine, &hash) {
+            Some(module) => {
+                self.disk_hits.fetch_add(1, Ordering::Relaxed);
+                module
+            }
+            None => {
+                let module = Module::new(engine, code)?;
+                self.compiles.fetch_add(1, Ordering::Relaxed);
+                self.store_artifact(engine, &hash, &module);
+                module
+            }
+        };
+        if self.modules.lock().insert(hash, module.clone()) {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(module)
+    }
+
+    fn artifact_path(&self, hash: &[u8; 32]) -> Option<PathBuf> {
+        self.artifact_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.{}", hex::encode(hash), ARTIFACT_EXT)))
+    }
+
+    /// 读取磁盘产物；文件缺失、头部不符（引擎指纹 / 代码哈希 / 校验和）或反序列化失败时返回 None
+    fn load_artifact(&self, engine: &Engine, hash: &[u8; 32]) -> Option<Module> {
+        let path = self.artifact_path(hash)?;
+        if !path.exists() {
+            return None;
+        }
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("failed to read module artifact {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let Some(payload) = check_artifact(&bytes, &engine_fingerprint(engine), hash) else {
+            log::warn!(
+                "discarding module artifact {} (header or checksum mismatch)",
+                path.display()
+            );
+            return None;
+        };
+        // SAFETY: 载荷的 SHA-256 与头部一致，且头部记录的引擎指纹与代码哈希与当前相同，
+        // 即该文件为本缓存以兼容引擎写入的 Module::serialize 输出且未被截断或损坏。
+        // 校验和不防篡改，产物目录须仅对本进程可写。
+        match unsafe { Module::deserialize(engine, payload) } {
+            Ok(module) => Some(module),
+            Err(e) => {
+                log::warn!(
+                    "discarding incompatible module artifact {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// 写入磁盘产物（带头部）；失败仅记录日志，不影响执行
+    fn store_artifact(&self, engine: &Engine, hash: &[u8; 32], module: &Module) {
+        let Some(path) = self.artifact_path(hash) else {
+            return;
+        };
+        let result = module.serialize().and_then(|payload| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut bytes = Vec::with_capacity(ARTIFACT_HEADER_LEN + payload.len());
+            bytes.extend_from_slice(ARTIFACT_MAGIC);
+            bytes.extend_from_slice(&engine_fingerprint(engine));
+            bytes.extend_from_slice(hash);
+            bytes.extend_from_slice(&sha256(&payload));
+            bytes.extend_from_slice(&payload);
+            // 先写临时文件再改名，避免并发读取到半写入的产物
+            let tmp = path.with_extension(format!("{}.tmp", ARTIFACT_EXT));
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(&tmp, &path)?;
+            Ok(())
+        });
+        if let Err(e) = result {
+            log::warn!(
+                "failed to persist module artifact {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// 内存中缓存的模块数
+    pub fn len(&self) -> usize {
+        self.modules.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 清空内存缓存（磁盘产物保留）
+    pub fn clear(&self) {
+        self.modules.lock().entries.clear();
+    }
+
+    pub fn stats(&self) -> ModuleCacheStats {
+        ModuleCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            disk_hits: self.disk_hits.load(Ordering::Relaxed),
+            compiles: self.compiles.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 将 `Hash` 输入喂给 SHA-256（std 的 DefaultHasher 输出不保证跨版本稳定）
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("only the digest is used")
+    }
+}
+
+/// 引擎兼容性指纹: wasmtime 版本、编译器配置与目标平台
+fn engine_fingerprint(engine: &Engine) -> [u8; 32] {
+    let mut hasher = Sha256Hasher(Sha256::new());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    hasher.0.finalize().into()
+}
+
+/// 校验产物头部，返回序列化模块载荷
+fn check_artifact<'b>(
+    bytes: &'b [u8],
+    fingerprint: &[u8; 32],
+    hash: &[u8; 32],
+) -> Option<&'b [u8]> {
+    let (header, payload) = bytes.split_at_checked(ARTIFACT_HEADER_LEN)?;
+    let (magic, rest) = header.split_at(ARTIFACT_MAGIC.len());
+    let (stored_fingerprint, rest) = rest.split_at(32);
+    let (stored_hash, checksum) = rest.split_at(32);
+    let valid = magic == ARTIFACT_MAGIC
+        && stored_fingerprint == fingerprint
+        && stored_hash == hash
+        && checksum == sha256(payload);
+    valid.then_some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    const DEPLOYER: [u8; 20] = [0x11; 20];
+
+    #[test]
+    fn test_register_and_address_schemes() -> Result<()> {
+        let mut storage = MemoryStorage::new();
+        let code = b"\0asm fake code";
+
+        let a = register(&mut storage, &DEPLOYER, code, AddressScheme::DeployerNonce)?;
+        let b = register(&mut storage, &DEPLOYER, code, AddressScheme::DeployerNonce)?;
+        assert_eq!(a.address, derive_address(&DEPLOYER, 0));
+        assert_eq!(b.address, derive_address(&DEPLOYER, 1));
+        assert_eq!(deployer_nonce(&storage, &DEPLOYER)?, 2);
+        assert_eq!(
+            contract_code(&storage, &a.address)?.as_deref(),
+            Some(&code[..])
+        );
+        assert_eq!(contract_info(&storage, &b.address)?, Some(b.clone()));
+
+        // 代码哈希地址可预先计算，重复部署被拒绝
+        let salt = [7u8; 32];
+        let expected = derive_address_from_code_hash(&DEPLOYER, &salt, &code_hash(code));
+        let c = register(
+            &mut storage,
+            &DEPLOYER,
+            code,
+            AddressScheme::CodeHash { salt },
+        )?;
+        assert_eq!(c.address, expected);
+        let err = register(
+            &mut storage,
+            &DEPLOYER,
+            code,
+            AddressScheme::CodeHash { salt },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RegistryError>(),
+            Some(&RegistryError::AlreadyDeployed(expected))
+        );
+
+        // 相同代码只存一份
+        assert_eq!(storage.scan(CODE_PREFIX)?.len(), 1);
+        assert!(contract_code(&storage, &[0u8; 20])?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_cache_artifacts() -> Result<()> {
+        let engine = crate::gas::metered_engine()?;
+        let wasm = wat::parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+        let dir = tempfile::tempdir()?;
+
+        let cache = ModuleCache::new().with_artifact_dir(dir.path());
+        cache.get_or_compile(&engine, &wasm)?;
+        cache.get_or_compile(&engine, &wasm)?;
+        assert_eq!(
+            cache.stats(),
+            ModuleCacheStats {
+                hits: 1,
+                disk_hits: 0,
+                compiles: 1,
+                evictions: 0
+            }
+        );
+        assert_eq!(cache.len(), 1);
+
+        // 新缓存实例从磁盘产物加载，无需重新编译
+        let reloaded = ModuleCache::new().with_artifact_dir(dir.path());
+        let module = reloaded.get_or_compile(&engine, &wasm)?;
+        assert!(module.get_export("f").is_some());
+        assert_eq!(
+            reloaded.stats(),
+            ModuleCacheStats {
+                hits: 0,
+                disk_hits: 1,
+                compiles: 0,
+                evictions: 0
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_cache_rejects_tampered_artifacts() -> Result<()> {
+        let engine = crate::gas::metered_engine()?;
+        let wasm = wat::parse_str(r#"(module (func (export "f") (result i32) (i32.const 1)))"#)?;
+        let dir = tempfile::tempdir()?;
+        ModuleCache::new()
+            .with_artifact_dir(dir.path())
+            .get_or_compile(&engine, &wasm)?;
+        let path = dir.path().join(format!(
+            "{}.{}",
+            hex::encode(code_hash(&wasm)),
+            ARTIFACT_EXT
+        ));
+        let original = std::fs::read(&path)?;
+
+        let reload = |bytes: &[u8]| -> Result<ModuleCacheStats> {
+            std::fs::write(&path, bytes)?;
+            let cache = ModuleCache::new().with_artifact_dir(dir.path());
+            assert!(cache
+                .get_or_compile(&engine, &wasm)?
+                .get_export("f")
+                .is_some());
+            Ok(cache.stats())
+        };
+
+        // 载荷损坏 / 截断 / 引擎指纹不符 / 非本缓存写入的文件: 均重新编译
+        let mut corrupted = original.clone();
+        *corrupted.last_mut().unwrap() ^= 0x01;
+        let mut foreign_engine = original.clone();
+        foreign_engine[ARTIFACT_MAGIC.len()] ^= 0x01;
+        let truncated = &original[..original.len() / 2];
+        let headerless = &original[ARTIFACT_HEADER_LEN..];
+        for bytes in [&corrupted[..], &foreign_engine[..], truncated, headerless] {
+            let stats = reload(bytes)?;
+            assert_eq!((stats.disk_hits, stats.compiles), (0, 1));
+        }
+
+        // 重新编译后写回的产物可再次加载
+        let stats = reload(&std::fs::read(&path)?)?;
+        assert_eq!((stats.disk_hits, stats.compiles), (1, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_cache_lru_eviction() -> Result<()> {
+        let engine = crate::gas::metered_engine()?;
+        let wasm: Vec<Vec<u8>> = (0..3)
+            .map(|i| {
+                wat::parse_str(format!(
+                    "(module (func (export \"f\") (result i32) (i32.const {i})))"
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let cache = ModuleCache::with_capacity(2);
+        cache.get_or_compile(&engine, &wasm[0])?;
+        cache.get_or_compile(&engine, &wasm[1])?;
+        cache.get_or_compile(&engine, &wasm[0])?; // 0 最近使用
+        cache.get_or_compile(&engine, &wasm[2])?; // 淘汰 1
+        assert_eq!(cache.len(), 2);
+
+        cache.get_or_compile(&engine, &wasm[0])?;
+        cache.get_or_compile(&engine, &wasm[2])?;
+        assert_eq!(
+            cache.stats(),
+            ModuleCacheStats {
+                hits: 3,
+                disk_hits: 0,
+                compiles: 3,
+                evictions: 1
+            }
+        );
+        cache.get_or_compile(&engine, &wasm[1])?;
+        assert_eq!(cache.stats().compiles, 4);
+        assert_eq!(cache.stats().evictions, 2);
+        Ok(())
+    }
+}
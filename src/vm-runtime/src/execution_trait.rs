@@ -16,6 +16,7 @@
 // 版本: v0.1.0
 // 创建日期: 2025-11-05
 
+use crate::gas::StorageDeposit;
 use anyhow::Result;
 
 /// 执行引擎类型
@@ -36,7 +37,7 @@ pub struct ExecutionContext {
 }
 
 /// 日志事件
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Log {
     pub address: [u8; 20],
     pub topics: Vec<[u8; 32]>,
@@ -44,7 +45,7 @@ pub struct Log {
 }
 
 /// 状态变更
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StateChange {
     pub key: Vec<u8>,
     pub value: Option<Vec<u8>>, // None = delete
@@ -57,6 +58,8 @@ pub struct ContractResult {
     pub gas_used: u64,
     pub logs: Vec<Log>,
     pub state_changes: Vec<StateChange>,
+    /// 存储押金收支（收取部分已计入 gas_used，退款已从中抵扣；失败时状态被丢弃，押金为零）
+    pub storage_deposit: StorageDeposit,
 }
 
 /// 统一执行引擎 trait
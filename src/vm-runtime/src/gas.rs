@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! Gas 计量
+//!
+//! 基于 wasmtime fuel 实现:
+//! - 指令级计费: 引擎开启 `consume_fuel`, 每条 WASM 指令消耗 1 单位 fuel (= 1 gas)
+//! - Host 调用计费: `storage_api` / `crypto_api` / `chain_api` / `contract_api` 按 [`GasSchedule`] 额外扣费,
+//!   并按读写/哈希的字节数线性放大
+//! - Gas 耗尽: 指令级 `Trap::OutOfFuel` 与 host 扣费不足统一映射为 [`GasError::OutOfGas`]
+//! - 存储押金: `storage_set` 按条目字节数（key + value）收取押金（计入 gas），
+//!   删除或缩小条目时的应退押金记入 [`StorageDeposit::refunded`]，执行成功后按
+//!   [`StorageDeposit::settle`] 从 `gas_used` 中抵扣（执行失败时状态回滚，不退押金）
+
+use anyhow::Result;
+use wasmtime::{Config, Engine, Trap};
+
+/// 默认单次执行 gas 上限
+pub const DEFAULT_GAS_LIMIT: u64 = 10_000_000;
+
+/// Gas 计费表（host 调用成本）
+///
+/// 指令本身的成本由 wasmtime fuel 决定（每条指令 1 gas），此处仅定义 host 调用的附加成本。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// storage_get 基础成本
+    pub storage_read_base: u64,
+    /// storage_get 每字节成本（key + value）
+    pub storage_read_per_byte: u64,
+    /// storage_set 基础成本
+    pub storage_write_base: u64,
+    /// storage_set 每字节成本（key + value）
+    pub storage_write_per_byte: u64,
+    /// storage_delete 基础成本
+    pub storage_delete_base: u64,
+    /// 从宿主缓冲区复制到 guest 内存的每字节成本（storage_read_value / read_event）
+    pub memory_copy_per_byte: u64,
+    /// emit_event 基础成本
+    pub event_base: u64,
+    /// emit_event 每字节成本
+    pub event_per_byte: u64,
+    /// 链上下文查询成本（block_number / timestamp / events_len）
+    pub chain_query: u64,
+    /// 哈希基础成本（sha256 / keccak256）
+    pub hash_base: u64,
+    /// 哈希每 32 字节字成本
+    pub hash_per_word: u64,
+    /// secp256k1 验签成本
+    pub verify_secp256k1: u64,
+    /// ed25519 验签成本（另按消息字节计哈希成本）
+    pub verify_ed25519: u64,
+    /// secp256k1 公钥恢复成本
+    pub recover_secp256k1: u64,
+    /// 以太坊地址推导成本（另按公钥字节计哈希成本）
+    pub derive_eth_address: u64,
+    /// 合约间调用基础成本（另按输入字节计复制成本，被调合约消耗的 gas 从转发额度中扣除）
+    pub call_base: u64,
+    /// 存储押金：每字节（key + value）占用成本
+    pub storage_deposit_per_byte: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self {
+            storage_read_base: 200,
+            storage_read_per_byte: 1,
+            storage_write_base: 5_000,
+            storage_write_per_byte: 10,
+            storage_delete_base: 2_500,
+            memory_copy_per_byte: 1,
+            event_base: 375,
+            event_per_byte: 8,
+            chain_query: 2,
+            hash_base: 30,
+            hash_per_word: 6,
+            verify_secp256k1: 3_000,
+            verify_ed25519: 2_000,
+            recover_secp256k1: 3_000,
+            derive_eth_address: 100,
+            call_base: 700,
+            storage_deposit_per_byte: 50,
+        }
+    }
+}
+
+impl GasSchedule {
+    /// 全部 host 调用免费（仅指令计费），用于测试或基准
+    pub fn free() -> Self {
+        Self {
+            storage_read_base: 0,
+            storage_read_per_byte: 0,
+            storage_write_base: 0,
+            storage_write_per_byte: 0,
+            storage_delete_base: 0,
+            memory_copy_per_byte: 0,
+            event_base: 0,
+            event_per_byte: 0,
+            chain_query: 0,
+            hash_base: 0,
+            hash_per_word: 0,
+            verify_secp256k1: 0,
+            verify_ed25519: 0,
+            recover_secp256k1: 0,
+            derive_eth_address: 0,
+            call_base: 0,
+            storage_deposit_per_byte: 0,
+        }
+    }
+
+    /// storage_get 成本（key_len + value_len 字节）
+    pub fn storage_read_cost(&self, bytes: usize) -> u64 {
+        self.storage_read_base
+            .saturating_add(self.storage_read_per_byte.saturating_mul(bytes as u64))
+    }
+
+    /// storage_set 成本（key_len + value_len 字节）
+    pub fn storage_write_cost(&self, bytes: usize) -> u64 {
+        self.storage_write_base
+            .saturating_add(self.storage_write_per_byte.saturating_mul(bytes as u64))
+    }
+
+    /// storage_delete 成本（key_len 字节）
+    pub fn storage_delete_cost(&self, key_len: usize) -> u64 {
+        self.storage_delete_base
+            .saturating_add(self.storage_write_per_byte.saturating_mul(key_len as u64))
+    }
+
+    /// 宿主 -> guest 内存复制成本
+    pub fn memory_copy_cost(&self, bytes: usize) -> u64 {
+        self.memory_copy_per_byte.saturating_mul(bytes as u64)
+    }
+
+    /// emit_event 成本
+    pub fn event_cost(&self, bytes: usize) -> u64 {
+        self.event_base
+            .saturating_add(self.event_per_byte.saturating_mul(bytes as u64))
+    }
+
+    /// 存储条目押金（key_len + value_len 字节）
+    pub fn storage_deposit(&self, bytes: usize) -> u64 {
+        self.storage_deposit_per_byte.saturating_mul(bytes as u64)
+    }
+
+    /// 哈希成本（按 32 字节字向上取整）
+    pub fn hash_cost(&self, bytes: usize) -> u64 {
+        let words = (bytes as u64).div_ceil(32);
+        self.hash_base
+            .saturating_add(self.hash_per_word.saturating_mul(words))
+    }
+}
+
+/// 存储押金收支
+///
+/// 押金在写入时以 gas 形式扣除（计入 `gas_used`）；退款不在执行期间返还 fuel，
+/// 避免同一执行内反复写删套取 gas。
+///
+/// 执行成功结束时，运行时与执行器以 [`StorageDeposit::settle`] 将 `refunded` 抵扣
+/// `gas_used`（上限为 `gas_used`，净收费不为负），结果中的 `gas_used` 即调用方应付的净额。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageDeposit {
+    /// 新增占用收取的押金
+    pub charged: u64,
+    /// 删除 / 缩小条目应退还的押金
+    pub refunded: u64,
+}
+
+impl StorageDeposit {
+    /// 记录一次条目大小变化（旧押金 -> 新押金）
+    pub fn record(&mut self, old_deposit: u64, new_deposit: u64) {
+        if new_deposit >= old_deposit {
+            self.charged = self.charged.saturating_add(new_deposit - old_deposit);
+        } else {
+            self.refunded = self.refunded.saturating_add(old_deposit - new_deposit);
+        }
+    }
+
+    /// 合并嵌套调用的收支
+    pub fn merge(&mut self, other: &StorageDeposit) {
+        self.charged = self.charged.saturating_add(other.charged);
+        self.refunded = self.refunded.saturating_add(other.refunded);
+    }
+
+    /// 以退款抵扣本次执行的 gas，返回调用方应付的净 gas（不低于 0）
+    pub fn settle(&self, gas_used: u64) -> u64 {
+        gas_used.saturating_sub(self.refunded)
+    }
+
+    /// 净押金（正数为净占用，负数为净释放）
+    pub fn net(&self) -> i128 {
+        self.charged as i128 - self.refunded as i128
+    }
+}
+
+/// Gas 相关错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GasError {
+    #[error("out of gas (gas_limit={gas_limit})")]
+    OutOfGas { gas_limit: u64 },
+}
+
+/// 创建开启 fuel 计量的 wasmtime 引擎
+pub fn metered_engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+/// 判断错误是否由 gas 耗尽引起（指令级 OutOfFuel 或 host 扣费不足）
+pub fn is_out_of_gas(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<Trap>(), Some(Trap::OutOfFuel))
+        || err.downcast_ref::<GasError>().is_some()
+}
+
+/// 将 gas 耗尽类错误统一映射为 [`GasError::OutOfGas`]，其他错误原样返回
+pub fn map_out_of_gas(err: anyhow::Error, gas_limit: u64) -> anyhow::Error {
+    if is_out_of_gas(&err) {
+        GasError::OutOfGas { gas_limit }.into()
+    } else {
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_scales_with_bytes() {
+        let s = GasSchedule::default();
+        assert_eq!(s.storage_read_cost(0), s.storage_read_base);
+        assert!(s.storage_write_cost(100) > s.storage_write_cost(10));
+        assert_eq!(s.hash_cost(0), s.hash_base);
+        assert_eq!(s.hash_cost(1), s.hash_base + s.hash_per_word);
+        assert_eq!(s.hash_cost(33), s.hash_base + 2 * s.hash_per_word);
+        assert_eq!(GasSchedule::free().storage_write_cost(1024), 0);
+        assert_eq!(s.storage_deposit(10), 10 * s.storage_deposit_per_byte);
+    }
+
+    #[test]
+    fn test_storage_deposit_accounting() {
+        let mut d = StorageDeposit::default();
+        d.record(0, 500);
+        d.record(500, 300);
+        d.record(300, 0);
+        assert_eq!(d, StorageDeposit { charged: 500, refunded: 500 });
+        assert_eq!(d.net(), 0);
+        assert_eq!(d.settle(800), 300);
+        assert_eq!(d.settle(200), 0);
+
+        let mut total = StorageDeposit::default();
+        total.merge(&StorageDeposit { charged: 7, refunded: 0 });
+        assert_eq!(total.net(), 7);
+    }
+
+    #[test]
+    fn test_map_out_of_gas() {
+        let trap: anyhow::Error = Trap::OutOfFuel.into();
+        let mapped = map_out_of_gas(trap, 42);
+        assert_eq!(
+            mapped.downcast_ref::<GasError>(),
+            Some(&GasError::OutOfGas { gas_limit: 42 })
+        );
+
+        let other = map_out_of_gas(anyhow::anyhow!("boom"), 42);
+        assert!(!is_out_of_gas(&other));
+    }
+}
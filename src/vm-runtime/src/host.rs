@@ -6,11 +6,35 @@
 
 //! WebAssembly host functions 实现
 
+use crate::contract_registry::{self, CallConfig, ModuleCache, RegistryError};
+use crate::gas::{GasError, GasSchedule, StorageDeposit};
 use crate::Storage;
 use anyhow::{anyhow, Result};
-use std::cell::RefCell;
-use std::rc::Rc;
-use wasmtime::{Caller, Memory};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use wasmtime::{Caller, Linker, Memory, StoreLimits};
+
+/// 宿主故障（存储读写失败等），区别于 guest trap
+///
+/// 跨合约调用时不视为被调方回滚，而是作为 trap 向上传播并中止整个执行
+#[derive(Debug, thiserror::Error)]
+#[error("host failure: {0:#}")]
+pub struct HostError(pub anyhow::Error);
+
+impl HostError {
+    /// 供 `map_err` 包装存储错误
+    pub fn wrap(err: anyhow::Error) -> anyhow::Error {
+        HostError(err).into()
+    }
+
+    /// 错误链中是否含宿主故障
+    pub fn is_host_error(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<HostError>().is_some()
+    }
+}
+
+/// 存储撤销日志：(实际键, 写入前的值)，被调合约失败时按逆序恢复
+pub type StorageJournal = Arc<Mutex<Vec<(Vec<u8>, Option<Vec<u8>>)>>>;
 
 /// 从 WASM 内存读取字节切片
 pub fn read_memory<T>(mem: &Memory, caller: &Caller<'_, T>, ptr: i32, len: i32) -> Result<Vec<u8>> {
@@ -54,7 +78,7 @@ pub fn write_memory<T>(
 
 /// 存储操作的运行时状态
 pub struct HostState<S: Storage> {
-    pub storage: Rc<RefCell<S>>,
+    pub storage: Arc<Mutex<S>>,
     pub memory: Option<Memory>,
     pub last_get: Option<Vec<u8>>,
     // 存放由 guest 通过 emit_event 提交的事件（字节数组）
@@ -64,6 +88,205 @@ pub struct HostState<S: Storage> {
     pub timestamp: u64,
     // 读写集追踪 (用于并行执行)
     pub read_write_set: crate::parallel::ReadWriteSet,
+    // Gas 计费表与本次执行的 gas 上限
+    pub gas_schedule: GasSchedule,
+    pub gas_limit: u64,
+    // 合约调用上下文：调用者 / 当前合约地址 / 调用输入 / 返回数据
+    pub caller_address: [u8; 20],
+    pub contract_address: [u8; 20],
+    pub input: Vec<u8>,
+    pub return_data: Vec<u8>,
+    // 资源限制（内存页数等），由 Store::limiter 使用
+    pub limits: StoreLimits,
+    // storage_api 键前缀：按执行合约地址隔离存储（Runtime::execute_* 直接执行模块时为空）
+    // 合约间调用：调用深度 / 调用栈（不含当前合约）/ 调用配置
+    pub storage_prefix: Vec<u8>,
+    pub call_depth: usize,
+    pub call_stack: Vec<[u8; 20]>,
+    pub call_config: CallConfig,
+    // 嵌套调用共享的撤销日志与编译模块缓存
+    pub journal: StorageJournal,
+    pub modules: Arc<ModuleCache>,
+    // 最近一次 contract_api::call 的返回数据
+    pub last_call_return: Vec<u8>,
+    // 存储押金收支（含成功的嵌套调用）
+    pub storage_deposit: StorageDeposit,
+}
+
+impl<S: Storage> HostState<S> {
+    /// 创建 HostState（默认计费表与 gas 上限）
+    pub fn new(storage: Arc<Mutex<S>>, block_number: u64, timestamp: u64) -> Self {
+        Self {
+            storage,
+            memory: None,
+            last_get: None,
+            events: Vec::new(),
+            block_number,
+            timestamp,
+            read_write_set: crate::parallel::ReadWriteSet::new(),
+            gas_schedule: GasSchedule::default(),
+            gas_limit: crate::gas::DEFAULT_GAS_LIMIT,
+            caller_address: [0u8; 20],
+            contract_address: [0u8; 20],
+            input: Vec::new(),
+            return_data: Vec::new(),
+            limits: StoreLimits::default(),
+            storage_prefix: Vec::new(),
+            call_depth: 0,
+            call_stack: Vec::new(),
+            call_config: CallConfig::default(),
+            journal: Arc::new(Mutex::new(Vec::new())),
+            modules: Arc::new(ModuleCache::new()),
+            last_call_return: Vec::new(),
+            storage_deposit: StorageDeposit::default(),
+        }
+    }
+
+    /// 将 guest 传入的键映射为实际存储键（加上当前命名空间前缀）
+    ///
+    /// 直接执行（无前缀）时键为原始键，拒绝落在合约注册表命名空间（`contract:`）内的键，
+    /// 避免绕过地址隔离读写合约代码、元数据与私有存储
+    pub fn scoped_key(&self, key: Vec<u8>) -> Result<Vec<u8>> {
+        if self.storage_prefix.is_empty() {
+            if key.starts_with(contract_registry::NAMESPACE) {
+                return Err(RegistryError::ReservedNamespace.into());
+            }
+            Ok(key)
+        } else {
+            Ok([self.storage_prefix.as_slice(), key.as_slice()].concat())
+        }
+    }
+
+    /// 嵌套调用中的写入需要记录撤销日志（顶层调用的写入不回滚）
+    fn record_undo(&self, key: &[u8], prev: Option<Vec<u8>>) {
+        if self.call_depth > 0 {
+            self.journal.lock().push((key.to_vec(), prev));
+        }
+    }
+}
+
+/// 撤销日志回滚到 checkpoint（按逆序恢复写入前的值）
+fn rollback_journal<S: Storage>(
+    storage: &Arc<Mutex<S>>,
+    journal: &StorageJournal,
+    checkpoint: usize,
+) -> Result<()> {
+    let mut journal = journal.lock();
+    let mut storage = storage.lock();
+    while journal.len() > checkpoint {
+        let (key, prev) = journal.pop().expect("journal length checked");
+        match prev {
+            Some(value) => storage.set(&key, &value)?,
+            None => storage.delete(&key)?,
+        }
+    }
+    Ok(())
+}
+
+/// 已注册的 host functions（module, name），用于模块导入白名单校验
+pub const HOST_FUNCTIONS: &[(&str, &str)] = &[
+    ("storage_api", "storage_get"),
+    ("storage_api", "storage_read_value"),
+    ("storage_api", "storage_set"),
+    ("storage_api", "storage_delete"),
+    ("chain_api", "block_number"),
+    ("chain_api", "timestamp"),
+    ("chain_api", "emit_event"),
+    ("chain_api", "events_len"),
+    ("chain_api", "read_event"),
+    ("crypto_api", "sha256"),
+    ("crypto_api", "keccak256"),
+    ("crypto_api", "verify_secp256k1"),
+    ("crypto_api", "verify_ed25519"),
+    ("crypto_api", "recover_secp256k1_pubkey"),
+    ("crypto_api", "derive_eth_address"),
+    ("contract_api", "input_len"),
+    ("contract_api", "read_input"),
+    ("contract_api", "set_return"),
+    ("contract_api", "caller"),
+    ("contract_api", "address"),
+    ("contract_api", "call"),
+    ("contract_api", "return_data_len"),
+    ("contract_api", "read_return_data"),
+];
+
+/// 注册全部 host functions 到 linker（与 [`HOST_FUNCTIONS`] 保持一致）
+pub fn register_host_functions<S: Storage + 'static>(
+    linker: &mut Linker<HostState<S>>,
+) -> Result<()> {
+    // 注册存储相关函数
+    linker.func_wrap("storage_api", "storage_get", storage_api::storage_get)?;
+    linker.func_wrap(
+        "storage_api",
+        "storage_read_value",
+        storage_api::storage_read_value,
+    )?;
+    linker.func_wrap("storage_api", "storage_set", storage_api::storage_set)?;
+    linker.func_wrap("storage_api", "storage_delete", storage_api::storage_delete)?;
+    // 注册链/事件相关函数
+    linker.func_wrap("chain_api", "block_number", chain_api::block_number)?;
+    linker.func_wrap("chain_api", "timestamp", chain_api::timestamp)?;
+    linker.func_wrap("chain_api", "emit_event", chain_api::emit_event)?;
+    linker.func_wrap("chain_api", "events_len", chain_api::events_len)?;
+    linker.func_wrap("chain_api", "read_event", chain_api::read_event)?;
+    // 注册密码学相关函数
+    linker.func_wrap("crypto_api", "sha256", crypto_api::sha256)?;
+    linker.func_wrap("crypto_api", "keccak256", crypto_api::keccak256)?;
+    linker.func_wrap(
+        "crypto_api",
+        "verify_secp256k1",
+        crypto_api::verify_secp256k1,
+    )?;
+    linker.func_wrap("crypto_api", "verify_ed25519", crypto_api::verify_ed25519)?;
+    linker.func_wrap(
+        "crypto_api",
+        "recover_secp256k1_pubkey",
+        crypto_api::recover_secp256k1_pubkey,
+    )?;
+    linker.func_wrap(
+        "crypto_api",
+        "derive_eth_address",
+        crypto_api::derive_eth_address,
+    )?;
+    // 注册合约调用上下文相关函数
+    linker.func_wrap("contract_api", "input_len", contract_api::input_len)?;
+    linker.func_wrap("contract_api", "read_input", contract_api::read_input)?;
+    linker.func_wrap("contract_api", "set_return", contract_api::set_return)?;
+    linker.func_wrap("contract_api", "caller", contract_api::caller)?;
+    linker.func_wrap("contract_api", "address", contract_api::address)?;
+    linker.func_wrap("contract_api", "call", contract_api::call::<S>)?;
+    linker.func_wrap(
+        "contract_api",
+        "return_data_len",
+        contract_api::return_data_len,
+    )?;
+    linker.func_wrap(
+        "contract_api",
+        "read_return_data",
+        contract_api::read_return_data,
+    )?;
+    Ok(())
+}
+
+/// 扣除 host 调用的 gas
+///
+/// 剩余 fuel 不足时清零并返回 [`GasError::OutOfGas`]，执行随即中止
+pub fn charge_gas<S: Storage>(caller: &mut Caller<'_, HostState<S>>, cost: u64) -> Result<()> {
+    let remaining = caller.get_fuel()?;
+    if remaining < cost {
+        caller.set_fuel(0)?;
+        return Err(GasError::OutOfGas {
+            gas_limit: caller.data().gas_limit,
+        }
+        .into());
+    }
+    caller.set_fuel(remaining - cost)?;
+    Ok(())
+}
+
+/// 将 guest 传入的长度参数转换为计费字节数（负数按 0 计，由后续内存访问报错）
+fn billable(len: i32) -> usize {
+    len.max(0) as usize
 }
 
 /// 存储相关的 host functions
@@ -81,23 +304,30 @@ pub mod storage_api {
         key_ptr: i32,
         key_len: i32,
     ) -> Result<i64> {
+        // 先按 key 长度计费，读到值后再按值长度补扣
+        let cost = caller.data().gas_schedule.storage_read_cost(billable(key_len));
+        charge_gas(&mut caller, cost)?;
+
         // clone the Memory handle to avoid holding an immutable borrow on caller
         let memory = caller
             .data()
             .memory
             .ok_or_else(|| anyhow!("No memory exported"))?;
 
-        // 读取 key
+        // 读取 key（映射到当前命名空间）
         let key = read_memory(&memory, &caller, key_ptr, key_len)?;
+        let key = caller.data().scoped_key(key)?;
 
         // 追踪读操作
         caller.data_mut().read_write_set.add_read(key.clone());
 
         // 查询存储
         let storage_rc = caller.data().storage.clone();
-        let storage_ref = storage_rc.borrow();
-        match storage_ref.get(&key)? {
+        let storage_ref = storage_rc.lock();
+        match storage_ref.get(&key).map_err(HostError::wrap)? {
             Some(value) => {
+                let per_byte = caller.data().gas_schedule.storage_read_per_byte;
+                charge_gas(&mut caller, per_byte.saturating_mul(value.len() as u64))?;
                 // 缓存结果以便后续读取
                 caller.data_mut().last_get = Some(value.clone());
                 Ok(value.len() as i64)
@@ -128,6 +358,8 @@ pub mod storage_api {
             .clone()
             .ok_or_else(|| anyhow!("No cached value"))?;
         let write_len = std::cmp::min(data.len(), value_len as usize);
+        let cost = caller.data().gas_schedule.memory_copy_cost(write_len);
+        charge_gas(&mut caller, cost)?;
 
         // 写入内存
         write_memory(&memory, &mut caller, value_ptr, &data[..write_len])?;
@@ -146,6 +378,12 @@ pub mod storage_api {
         value_ptr: i32,
         value_len: i32,
     ) -> Result<i32> {
+        let cost = caller
+            .data()
+            .gas_schedule
+            .storage_write_cost(billable(key_len) + billable(value_len));
+        charge_gas(&mut caller, cost)?;
+
         // clone the Memory handle to avoid holding an immutable borrow while we later
         // mutably borrow `caller` to write into memory
         let memory = caller
@@ -153,15 +391,35 @@ pub mod storage_api {
             .memory
             .ok_or_else(|| anyhow!("No memory exported"))?;
 
-        // 读取 key 和 value
+        // 读取 key 和 value（key 映射到当前命名空间）
         let key = read_memory(&memory, &caller, key_ptr, key_len)?;
+        let key = caller.data().scoped_key(key)?;
         let value = read_memory(&memory, &caller, value_ptr, value_len)?;
 
         // 追踪写操作
         caller.data_mut().read_write_set.add_write(key.clone());
 
+        // 按条目大小变化收取押金（覆盖写只收差额，缩小时记退款）
+        let prev = caller.data().storage.lock().get(&key).map_err(HostError::wrap)?;
+        let schedule = &caller.data().gas_schedule;
+        let old_deposit = prev
+            .as_ref()
+            .map_or(0, |v| schedule.storage_deposit(key.len() + v.len()));
+        let new_deposit = schedule.storage_deposit(key.len() + value.len());
+        charge_gas(&mut caller, new_deposit.saturating_sub(old_deposit))?;
+        caller
+            .data_mut()
+            .storage_deposit
+            .record(old_deposit, new_deposit);
+
         // 写入存储
-        caller.data_mut().storage.borrow_mut().set(&key, &value)?;
+        caller.data().record_undo(&key, prev);
+        caller
+            .data_mut()
+            .storage
+            .lock()
+            .set(&key, &value)
+            .map_err(HostError::wrap)?;
         Ok(0)
     }
 
@@ -175,19 +433,36 @@ pub mod storage_api {
         key_ptr: i32,
         key_len: i32,
     ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.storage_delete_cost(billable(key_len));
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
             .ok_or_else(|| anyhow!("No memory exported"))?;
 
-        // 读取 key
+        // 读取 key（映射到当前命名空间）
         let key = read_memory(&memory, &caller, key_ptr, key_len)?;
+        let key = caller.data().scoped_key(key)?;
 
         // 追踪写操作 (删除也是写)
         caller.data_mut().read_write_set.add_write(key.clone());
 
+        // 退还被删除条目的押金
+        let prev = caller.data().storage.lock().get(&key).map_err(HostError::wrap)?;
+        if let Some(v) = &prev {
+            let old_deposit = caller.data().gas_schedule.storage_deposit(key.len() + v.len());
+            caller.data_mut().storage_deposit.record(old_deposit, 0);
+        }
+
         // 从存储中删除
-        caller.data_mut().storage.borrow_mut().delete(&key)?;
+        caller.data().record_undo(&key, prev);
+        caller
+            .data_mut()
+            .storage
+            .lock()
+            .delete(&key)
+            .map_err(HostError::wrap)?;
         Ok(0)
     }
 }
@@ -197,12 +472,16 @@ pub mod chain_api {
     use super::*;
 
     /// block_number() -> i64
-    pub fn block_number(caller: Caller<'_, HostState<impl Storage>>) -> Result<i64> {
+    pub fn block_number(mut caller: Caller<'_, HostState<impl Storage>>) -> Result<i64> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
         Ok(caller.data().block_number as i64)
     }
 
     /// timestamp() -> i64
-    pub fn timestamp(caller: Caller<'_, HostState<impl Storage>>) -> Result<i64> {
+    pub fn timestamp(mut caller: Caller<'_, HostState<impl Storage>>) -> Result<i64> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
         Ok(caller.data().timestamp as i64)
     }
 
@@ -214,6 +493,9 @@ pub mod chain_api {
         ptr: i32,
         len: i32,
     ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.event_cost(billable(len));
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -226,7 +508,9 @@ pub mod chain_api {
 
     /// events_len() -> i32
     /// 返回当前事件队列长度
-    pub fn events_len(caller: Caller<'_, HostState<impl Storage>>) -> Result<i32> {
+    pub fn events_len(mut caller: Caller<'_, HostState<impl Storage>>) -> Result<i32> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
         Ok(caller.data().events.len() as i32)
     }
 
@@ -257,6 +541,8 @@ pub mod chain_api {
             .clone();
 
         let write_len = std::cmp::min(ev.len(), len as usize);
+        let cost = caller.data().gas_schedule.memory_copy_cost(write_len);
+        charge_gas(&mut caller, cost)?;
         write_memory(&memory, &mut caller, ptr, &ev[..write_len])?;
         Ok(write_len as i32)
     }
@@ -282,6 +568,9 @@ pub mod crypto_api {
         data_len: i32,
         output_ptr: i32,
     ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.hash_cost(billable(data_len));
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -313,6 +602,9 @@ pub mod crypto_api {
         data_len: i32,
         output_ptr: i32,
     ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.hash_cost(billable(data_len));
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -340,12 +632,15 @@ pub mod crypto_api {
     /// # 返回
     /// 1 表示验证成功, 0 表示失败, 负数表示错误
     pub fn verify_secp256k1<S: Storage>(
-        caller: Caller<'_, HostState<S>>,
+        mut caller: Caller<'_, HostState<S>>,
         msg_ptr: i32,
         sig_ptr: i32,
         pubkey_ptr: i32,
         pubkey_len: i32,
     ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.verify_secp256k1;
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -374,12 +669,18 @@ pub mod crypto_api {
     /// # 返回
     /// 1 表示验证成功, 0 表示失败, 负数表示错误
     pub fn verify_ed25519<S: Storage>(
-        caller: Caller<'_, HostState<S>>,
+        mut caller: Caller<'_, HostState<S>>,
         msg_ptr: i32,
         msg_len: i32,
         sig_ptr: i32,
         pubkey_ptr: i32,
     ) -> Result<i32> {
+        let schedule = &caller.data().gas_schedule;
+        let cost = schedule
+            .verify_ed25519
+            .saturating_add(schedule.hash_cost(billable(msg_len)));
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -412,6 +713,9 @@ pub mod crypto_api {
         sig_ptr: i32,
         output_ptr: i32,
     ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.recover_secp256k1;
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -446,6 +750,12 @@ pub mod crypto_api {
         pubkey_len: i32,
         output_ptr: i32,
     ) -> Result<i32> {
+        let schedule = &caller.data().gas_schedule;
+        let cost = schedule
+            .derive_eth_address
+            .saturating_add(schedule.hash_cost(billable(pubkey_len)));
+        charge_gas(&mut caller, cost)?;
+
         let memory = caller
             .data()
             .memory
@@ -461,3 +771,242 @@ pub mod crypto_api {
         }
     }
 }
+
+/// 合约调用上下文相关的 host functions（输入 / 返回数据 / 地址）
+pub mod contract_api {
+    use super::*;
+    use crate::contract_registry::{self, CallStatus, ReentrancyPolicy};
+    use wasmtime::{Module, Store};
+
+    /// input_len() -> i32
+    /// 返回本次调用输入的字节长度
+    pub fn input_len(mut caller: Caller<'_, HostState<impl Storage>>) -> Result<i32> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
+        Ok(caller.data().input.len() as i32)
+    }
+
+    /// read_input(ptr: i32, len: i32) -> i32
+    /// 将调用输入复制到 guest 内存，返回写入的字节数
+    pub fn read_input(
+        mut caller: Caller<'_, HostState<impl Storage>>,
+        ptr: i32,
+        len: i32,
+    ) -> Result<i32> {
+        let memory = caller
+            .data()
+            .memory
+            .ok_or_else(|| anyhow!("No memory exported"))?;
+
+        let input = caller.data().input.clone();
+        let write_len = std::cmp::min(input.len(), billable(len));
+        let cost = caller.data().gas_schedule.memory_copy_cost(write_len);
+        charge_gas(&mut caller, cost)?;
+
+        write_memory(&memory, &mut caller, ptr, &input[..write_len])
+    }
+
+    /// set_return(ptr: i32, len: i32) -> i32
+    /// 设置本次调用的返回数据（多次调用以最后一次为准）
+    pub fn set_return(
+        mut caller: Caller<'_, HostState<impl Storage>>,
+        ptr: i32,
+        len: i32,
+    ) -> Result<i32> {
+        let cost = caller.data().gas_schedule.memory_copy_cost(billable(len));
+        charge_gas(&mut caller, cost)?;
+
+        let memory = caller
+            .data()
+            .memory
+            .ok_or_else(|| anyhow!("No memory exported"))?;
+
+        let data = read_memory(&memory, &caller, ptr, len)?;
+        caller.data_mut().return_data = data;
+        Ok(0)
+    }
+
+    /// caller(ptr: i32) -> i32
+    /// 将调用者地址（20 字节）写入 guest 内存
+    pub fn caller(mut caller: Caller<'_, HostState<impl Storage>>, ptr: i32) -> Result<i32> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
+
+        let memory = caller
+            .data()
+            .memory
+            .ok_or_else(|| anyhow!("No memory exported"))?;
+
+        let addr = caller.data().caller_address;
+        write_memory(&memory, &mut caller, ptr, &addr)
+    }
+
+    /// address(ptr: i32) -> i32
+    /// 将当前合约地址（20 字节）写入 guest 内存
+    pub fn address(mut caller: Caller<'_, HostState<impl Storage>>, ptr: i32) -> Result<i32> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
+
+        let memory = caller
+            .data()
+            .memory
+            .ok_or_else(|| anyhow!("No memory exported"))?;
+
+        let addr = caller.data().contract_address;
+        write_memory(&memory, &mut caller, ptr, &addr)
+    }
+
+    /// call(addr_ptr: i32, input_ptr: i32, input_len: i32, gas: i64) -> i32
+    ///
+    /// 调用已部署合约的入口函数 `call() -> i32`，返回 [`CallStatus`] 状态码:
+    /// - gas 转发: `gas <= 0` 或超出可用额度时转发剩余 gas 的 63/64，被调方实际消耗从调用方扣除
+    /// - 存储隔离: 被调方 `storage_api` 的键位于其自身命名空间（`contract:store:` + 地址）
+    /// - 原子回滚: 被调方返回非 0 或 trap（含 gas 耗尽）时撤销其全部写入（含更深层调用），
+    ///   调用方继续执行
+    /// - 宿主故障: 被调方（或更深层）遇到 [`HostError`] 时不返回 `Reverted`，撤销其写入后
+    ///   将错误作为调用方的 trap 继续向上传播
+    /// - 返回数据通过 `return_data_len` / `read_return_data` 读取（trap 时为空）
+    pub fn call<S: Storage + 'static>(
+        mut caller: Caller<'_, HostState<S>>,
+        addr_ptr: i32,
+        input_ptr: i32,
+        input_len: i32,
+        gas: i64,
+    ) -> Result<i32> {
+        let schedule = &caller.data().gas_schedule;
+        let cost = schedule
+            .call_base
+            .saturating_add(schedule.memory_copy_cost(billable(input_len)));
+        charge_gas(&mut caller, cost)?;
+
+        let memory = caller
+            .data()
+            .memory
+            .ok_or_else(|| anyhow!("No memory exported"))?;
+        let mut callee = [0u8; 20];
+        callee.copy_from_slice(&read_memory(&memory, &caller, addr_ptr, 20)?);
+        let input = read_memory(&memory, &caller, input_ptr, input_len)?;
+        caller.data_mut().last_call_return.clear();
+
+        let parent = caller.data();
+        if parent.call_depth + 1 > parent.call_config.max_depth {
+            return Ok(CallStatus::DepthExceeded as i32);
+        }
+        let on_stack =
+            parent.contract_address == callee || parent.call_stack.contains(&callee);
+        if on_stack && parent.call_config.reentrancy == ReentrancyPolicy::Deny {
+            return Ok(CallStatus::ReentrancyDenied as i32);
+        }
+        let Some(code) = contract_registry::contract_code(&*parent.storage.lock(), &callee)
+            .map_err(HostError::wrap)?
+        else {
+            return Ok(CallStatus::NotFound as i32);
+        };
+        let module = parent.modules.get_or_compile(caller.engine(), &code)?;
+
+        // EIP-150 风格：至少为调用方保留 1/64 的剩余 gas
+        let remaining = caller.get_fuel()?;
+        let available = remaining - remaining / 64;
+        let forwarded = match u64::try_from(gas) {
+            Ok(g) if g > 0 => g.min(available),
+            _ => available,
+        };
+
+        let parent = caller.data();
+        let mut state = HostState::new(parent.storage.clone(), parent.block_number, parent.timestamp);
+        state.gas_schedule = parent.gas_schedule.clone();
+        state.gas_limit = forwarded;
+        state.caller_address = parent.contract_address;
+        state.contract_address = callee;
+        state.input = input;
+        state.storage_prefix = contract_registry::storage_prefix(&callee);
+        state.call_depth = parent.call_depth + 1;
+        state.call_stack = parent.call_stack.clone();
+        state.call_stack.push(parent.contract_address);
+        state.call_config = parent.call_config;
+        state.journal = parent.journal.clone();
+        state.modules = parent.modules.clone();
+        state.limits = parent.limits.clone();
+        let checkpoint = state.journal.lock().len();
+
+        let mut store = Store::new(caller.engine(), state);
+        store.limiter(|s| &mut s.limits);
+        store.set_fuel(forwarded)?;
+        let result = instantiate_and_call(&mut store, &module);
+        let used = forwarded - store.get_fuel().unwrap_or(0);
+        caller.set_fuel(remaining - used)?;
+
+        let child = store.into_data();
+        let parent = caller.data_mut();
+        match result {
+            Ok(0) => {
+                parent.events.extend(child.events);
+                parent.storage_deposit.merge(&child.storage_deposit);
+                parent.read_write_set.read_set.extend(child.read_write_set.read_set);
+                parent.read_write_set.write_set.extend(child.read_write_set.write_set);
+                parent.last_call_return = child.return_data;
+                Ok(CallStatus::Success as i32)
+            }
+            Err(err) if HostError::is_host_error(&err) => {
+                if let Err(e) = rollback_journal(&child.storage, &child.journal, checkpoint) {
+                    log::warn!("failed to roll back callee writes after host failure: {e:#}");
+                }
+                Err(err)
+            }
+            outcome => {
+                rollback_journal(&child.storage, &child.journal, checkpoint)
+                    .map_err(HostError::wrap)?;
+                // 被撤销的写入不再构成写集合，但读取仍影响冲突检测
+                parent.read_write_set.read_set.extend(child.read_write_set.read_set);
+                if outcome.is_ok() {
+                    parent.last_call_return = child.return_data;
+                }
+                Ok(CallStatus::Reverted as i32)
+            }
+        }
+    }
+
+    /// 在独立 Store 中实例化被调合约并调用默认入口
+    fn instantiate_and_call<S: Storage + 'static>(
+        store: &mut Store<HostState<S>>,
+        module: &Module,
+    ) -> Result<i32> {
+        let mut linker = Linker::new(store.engine());
+        register_host_functions(&mut linker)?;
+        let instance = linker.instantiate(&mut *store, module)?;
+        if let Some(memory) = instance.get_memory(&mut *store, "memory") {
+            store.data_mut().memory = Some(memory);
+        }
+        let entry = instance
+            .get_typed_func::<(), i32>(&mut *store, crate::wasm_executor::DEFAULT_ENTRY_POINT)?;
+        entry.call(&mut *store, ())
+    }
+
+    /// return_data_len() -> i32
+    /// 返回最近一次 call 的返回数据长度
+    pub fn return_data_len(mut caller: Caller<'_, HostState<impl Storage>>) -> Result<i32> {
+        let cost = caller.data().gas_schedule.chain_query;
+        charge_gas(&mut caller, cost)?;
+        Ok(caller.data().last_call_return.len() as i32)
+    }
+
+    /// read_return_data(ptr: i32, len: i32) -> i32
+    /// 将最近一次 call 的返回数据复制到 guest 内存，返回写入的字节数
+    pub fn read_return_data(
+        mut caller: Caller<'_, HostState<impl Storage>>,
+        ptr: i32,
+        len: i32,
+    ) -> Result<i32> {
+        let memory = caller
+            .data()
+            .memory
+            .ok_or_else(|| anyhow!("No memory exported"))?;
+
+        let data = caller.data().last_call_return.clone();
+        let write_len = std::cmp::min(data.len(), billable(len));
+        let cost = caller.data().gas_schedule.memory_copy_cost(write_len);
+        charge_gas(&mut caller, cost)?;
+
+        write_memory(&memory, &mut caller, ptr, &data[..write_len])
+    }
+}
@@ -4,15 +4,20 @@
 // 开发者：king
 // Developer: king
 use anyhow::Result;
-use std::cell::RefCell;
-use std::rc::Rc;
+use parking_lot::Mutex;
+use std::sync::Arc;
 use wasmtime::{Engine, Instance, Linker, Module, Store};
 
+pub mod archive; // L1: 归档模式历史状态查询 (区块高度 → 提交水位线 / 版本化键)
 pub mod auto_tuner; // Phase 4.2: 自适应性能调优器 (智能参数调节)
+pub mod block; // L1: 区块 / 区块头与确定性出块 (交易根 / 状态根 / 回执根)
+pub mod block_stm; // L1: Block-STM 乐观并行区块执行 (读集校验 + 按序重执行)
 pub mod bloom_filter; // Phase 4.1: 布隆过滤器 (冲突检测优化)
+pub mod contract_registry; // L1: 合约注册表 (地址派生 / 代码存储 / 编译模块缓存)
 pub mod cross_shard_mvcc; // Phase 6: 跨分片 MVCC 扩展
 mod crypto;
 pub mod execution_trait; // L1: 统一执行引擎接口 (WASM/EVM)
+pub mod gas; // L0: Gas 计量 (wasmtime fuel + host 调用计费)
 mod host;
 pub mod metrics;
 pub mod mvcc;
@@ -21,6 +26,8 @@ pub mod ownership; // v2.0: Sui-Inspired 对象所有权模型
 pub mod parallel;
 pub mod parallel_mvcc; // v0.9.0: 新的基于 MVCC 的并行调度器
 pub mod privacy; // Phase 2.0: Privacy Layer (Ring Signatures, Stealth Addresses, etc.)
+pub mod state_commitment; // L1: 稀疏 Merkle 状态承诺 (状态根 / 存在性与不存在性证明)
+pub mod state_sync; // L1: 状态同步 (分块导出 / 逐块校验导入)
 pub mod shard_coordinator; // Phase 6: 分片协调器 (2PC)
 pub mod shard_types; // Phase 6: 跨分片事务类型定义
 #[cfg(feature = "partitioned-fastpath")]
@@ -36,18 +43,31 @@ pub mod supervm; // v2.0: 统一入口与模式路由 // Phase 4.3: 性能指标
 #[cfg(feature = "groth16-verifier")]
 pub mod zk_verifier; // Phase 6: 真实 ZK 验证器集成
 pub mod adaptive_router; // Phase 5+: 自适应路由器（动态调整 Fast/Consensus 比例）
+pub mod txn_executor; // L1: MVCC 事务化 WASM 执行 (快照读 / 缓冲写 / trap 回滚)
+pub mod wal; // L1: MVCC 提交日志 (分段 + 校验和 + 组提交 / 重放 / 截断)
+pub mod wasm_executor; // L1: ExecutionEngine 的 WASM 实现
 
+pub use archive::{HistoricalState, ARCHIVE_VERSION_PREFIX, BLOCK_WATERMARK_PREFIX};
 pub use auto_tuner::{AutoTuner, AutoTunerSummary};
+pub use block::{Block, BlockBuilder, BlockHeader, BlockReceipt, BlockTransaction};
+pub use block_stm::{BlockExecutor, BlockOutput, BlockStmStats, WasmTransaction};
 pub use bloom_filter::{BloomFilter, BloomFilterCache, BloomFilterCacheStats};
+pub use contract_registry::{
+    AddressScheme, CallConfig, CallStatus, ContractInfo, ModuleCache, ModuleCacheStats,
+    ReentrancyPolicy, RegistryError,
+};
 pub use cross_shard_mvcc::{CrossShardMvccExt, CrossShardScheduler};
 pub use execution_trait::{
     ContractResult, EngineType, ExecutionContext, ExecutionEngine, Log, StateChange,
 };
-use host::{chain_api, crypto_api, storage_api, HostState};
+pub use gas::{GasError, GasSchedule, StorageDeposit, DEFAULT_GAS_LIMIT};
+pub use host::HostError;
+use host::HostState;
 pub use metrics::{LatencyHistogram, MetricsCollector};
 pub use mvcc::{
-    AdaptiveGcStrategy, AutoFlushConfig, AutoGcConfig, AutoGcRuntime, FlushStats, GcConfig,
-    GcStats, MvccStore, Txn, Version,
+    AdaptiveGcStrategy, AutoFlushConfig, AutoGcConfig, AutoGcRuntime, ConflictKind, FlushStats,
+    GcConfig, GcStats, IsolationLevel, KeyRange, MvccStore, Txn, Version, FLUSH_WATERMARK_KEY,
+    MVCC_META_PREFIX, SERIALIZATION_FAILURE,
 };
 pub use optimized_mvcc::{
     OptimizedMvccScheduler, OptimizedSchedulerConfig, OptimizedSchedulerStats,
@@ -73,10 +93,23 @@ pub use shard_types::{
 pub use shard::proto as cross_shard_proto;
 #[cfg(feature = "rocksdb-storage")]
 pub use storage::{AdaptiveBatchConfig, AdaptiveBatchResult, RocksDBConfig, RocksDBStorage, RocksDBMetrics};
-pub use storage::{MemoryStorage, Storage};
+#[cfg(feature = "rocksdb-storage")]
+pub use storage::{ColumnFamilyConfig, ColumnFamilyOptions, ColumnStorage, RocksDBColumn, SnapshotConfig};
+pub use storage::snapshot as state_snapshot;
+pub use storage::snapshot::{SnapshotInfo, SnapshotManifest};
+pub use storage::{LockedStorage, MemoryStorage, SharedStorage, Storage, StorageHandle};
+#[cfg(feature = "async-storage")]
+pub use storage::AsyncStorage;
+pub use state_commitment::{
+    stored_state_root, verify_proof, AuthenticatedStorage, ProvableState, StateProof,
+};
+pub use state_sync::{export_state, import_state, ChunkInfo, StateSyncManifest};
 pub use supervm::{
     ExecutionPath, ExecutionReceipt, Privacy, SuperVM, Transaction as VmTransaction,
 };
+pub use txn_executor::{TxnExecutor, TxnStorage};
+pub use wal::{CommitLog, WalConfig, WalRecord};
+pub use wasm_executor::{OverlayStorage, ValidationError, WasmExecutor, WasmLimits};
 #[cfg(feature = "groth16-verifier")]
 pub use zk_verifier::{Groth16Verifier, ProofBytes, PublicInputBytes, ZkError, ZkVerifier};
 
@@ -98,7 +131,15 @@ type RuntimeTxnTuple = (
 /// VM 运行时的主要接口
 pub struct Runtime<S: Storage = MemoryStorage> {
     engine: Engine,
-    storage: Rc<RefCell<S>>,
+    storage: Arc<Mutex<S>>,
+    /// Host 调用计费表
+    gas_schedule: GasSchedule,
+    /// 默认单次执行 gas 上限
+    gas_limit: u64,
+    /// 编译模块缓存（按代码哈希，避免重复编译；嵌套调用共享）
+    modules: std::sync::Arc<ModuleCache>,
+    /// 合约间调用配置（深度限制 / 重入策略）
+    call_config: CallConfig,
     /// Phase 1.3: 集成对象所有权管理
     ownership_manager: Option<std::sync::Arc<OwnershipManager>>,
     /// Phase 1.3: 集成 MVCC 调度器
@@ -108,11 +149,15 @@ pub struct Runtime<S: Storage = MemoryStorage> {
 }
 
 impl<S: Storage + 'static> Runtime<S> {
-    /// 创建新的运行时实例，storage 将被内部 Rc 包装以便在 host 中共享
+    /// 创建新的运行时实例，storage 将被内部 Arc<Mutex> 包装以便在 host 中共享（S: Send 时 Runtime 可跨线程移动）
     pub fn new(storage: S) -> Self {
         Self {
-            engine: Engine::default(),
-            storage: Rc::new(RefCell::new(storage)),
+            engine: gas::metered_engine().expect("fuel-metered engine config is valid"),
+            storage: Arc::new(Mutex::new(storage)),
+            gas_schedule: GasSchedule::default(),
+            gas_limit: DEFAULT_GAS_LIMIT,
+            modules: std::sync::Arc::new(ModuleCache::new()),
+            call_config: CallConfig::default(),
             ownership_manager: None,
             scheduler: None,
             #[cfg(feature = "hybrid-exec")]
@@ -123,8 +168,12 @@ impl<S: Storage + 'static> Runtime<S> {
     /// Phase 1.3: 创建带路由能力的运行时
     pub fn new_with_routing(storage: S) -> Self {
         Self {
-            engine: Engine::default(),
-            storage: Rc::new(RefCell::new(storage)),
+            engine: gas::metered_engine().expect("fuel-metered engine config is valid"),
+            storage: Arc::new(Mutex::new(storage)),
+            gas_schedule: GasSchedule::default(),
+            gas_limit: DEFAULT_GAS_LIMIT,
+            modules: std::sync::Arc::new(ModuleCache::new()),
+            call_config: CallConfig::default(),
             ownership_manager: Some(std::sync::Arc::new(OwnershipManager::new())),
             scheduler: Some(std::sync::Arc::new(MvccScheduler::new())),
             #[cfg(feature = "hybrid-exec")]
@@ -132,11 +181,62 @@ impl<S: Storage + 'static> Runtime<S> {
         }
     }
 
-    /// 获取存储接口的不可变引用（内部为 Rc<RefCell>）
-    pub fn storage(&self) -> Rc<RefCell<S>> {
+    /// 设置 host 调用计费表
+    pub fn with_gas_schedule(mut self, schedule: GasSchedule) -> Self {
+        self.gas_schedule = schedule;
+        self
+    }
+
+    /// 设置默认单次执行 gas 上限
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    /// 启用磁盘预编译产物缓存（按代码哈希命名，重启后免编译加载）
+    pub fn with_artifact_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.modules = std::sync::Arc::new(ModuleCache::new().with_artifact_dir(dir));
+        self
+    }
+
+    /// 设置合约间调用配置
+    pub fn with_call_config(mut self, config: CallConfig) -> Self {
+        self.call_config = config;
+        self
+    }
+
+    /// 获取 host 调用计费表
+    pub fn gas_schedule(&self) -> &GasSchedule {
+        &self.gas_schedule
+    }
+
+    /// 获取默认单次执行 gas 上限
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    /// 获取编译模块缓存
+    pub fn module_cache(&self) -> &ModuleCache {
+        &self.modules
+    }
+
+    /// 获取事务化执行器（共享引擎、模块缓存、计费表与调用配置），可跨线程用于 MVCC 调度器批次
+    pub fn txn_executor(&self) -> TxnExecutor {
+        TxnExecutor::from_parts(
+            self.engine.clone(),
+            self.modules.clone(),
+            self.gas_schedule.clone(),
+            self.gas_limit,
+            self.call_config,
+        )
+    }
+
+    /// 获取共享的存储（内部为 Arc<Mutex>）
+    pub fn storage(&self) -> Arc<Mutex<S>> {
         self.storage.clone()
     }
         /// 初始化 Hybrid 组件（延迟初始化以避免默认构造开销）
+        #[cfg(feature = "hybrid-exec")]
         pub fn init_hybrid(&mut self) {
             if self.hybrid.is_none() {
                 self.hybrid = Some(HybridComponents::new_default());
@@ -163,41 +263,29 @@ impl<S: Storage + 'static> Runtime<S> {
 
     /// 注册 host functions 到 linker
     fn register_host_functions(&self, linker: &mut Linker<HostState<S>>) -> Result<()> {
-        // 注册存储相关函数
-        linker.func_wrap("storage_api", "storage_get", storage_api::storage_get)?;
-        linker.func_wrap(
-            "storage_api",
-            "storage_read_value",
-            storage_api::storage_read_value,
-        )?;
-        linker.func_wrap("storage_api", "storage_set", storage_api::storage_set)?;
-        linker.func_wrap("storage_api", "storage_delete", storage_api::storage_delete)?;
-        // 注册链/事件相关函数
-        linker.func_wrap("chain_api", "block_number", chain_api::block_number)?;
-        linker.func_wrap("chain_api", "timestamp", chain_api::timestamp)?;
-        linker.func_wrap("chain_api", "emit_event", chain_api::emit_event)?;
-        linker.func_wrap("chain_api", "events_len", chain_api::events_len)?;
-        linker.func_wrap("chain_api", "read_event", chain_api::read_event)?;
-        // 注册密码学相关函数
-        linker.func_wrap("crypto_api", "sha256", crypto_api::sha256)?;
-        linker.func_wrap("crypto_api", "keccak256", crypto_api::keccak256)?;
-        linker.func_wrap(
-            "crypto_api",
-            "verify_secp256k1",
-            crypto_api::verify_secp256k1,
-        )?;
-        linker.func_wrap("crypto_api", "verify_ed25519", crypto_api::verify_ed25519)?;
-        linker.func_wrap(
-            "crypto_api",
-            "recover_secp256k1_pubkey",
-            crypto_api::recover_secp256k1_pubkey,
-        )?;
-        linker.func_wrap(
-            "crypto_api",
-            "derive_eth_address",
-            crypto_api::derive_eth_address,
-        )?;
-        Ok(())
+        host::register_host_functions(linker)
+    }
+
+    /// 创建带 gas 预算的 Store（fuel = gas_limit）
+    fn new_store(
+        &self,
+        block_number: u64,
+        timestamp: u64,
+        gas_limit: u64,
+    ) -> Result<Store<HostState<S>>> {
+        let mut state = HostState::new(self.storage.clone(), block_number, timestamp);
+        state.gas_schedule = self.gas_schedule.clone();
+        state.gas_limit = gas_limit;
+        state.modules = self.modules.clone();
+        state.call_config = self.call_config;
+        let mut store = Store::new(&self.engine, state);
+        store.set_fuel(gas_limit)?;
+        Ok(store)
+    }
+
+    /// 编译模块（命中缓存时直接复用）
+    fn compile(&self, module_bytes: &[u8]) -> Result<Module> {
+        self.modules.get_or_compile(&self.engine, module_bytes)
     }
 
     /// 在给定 store 上实例化模块（会注册 host functions）
@@ -210,21 +298,10 @@ impl<S: Storage + 'static> Runtime<S> {
 
     /// 加载并调用导出函数 `add(i32, i32) -> i32`，返回结果
     pub fn execute_add(&self, module_bytes: &[u8], a: i32, b: i32) -> Result<i32> {
-        let module = Module::new(&self.engine, module_bytes)?;
-
-        // 创建 Store，并将 storage 的 Rc 克隆到 HostState 中
-        let mut store = Store::new(
-            &self.engine,
-            HostState {
-                storage: self.storage.clone(),
-                memory: None,
-                last_get: None,
-                events: Vec::new(),
-                block_number: 0,
-                timestamp: 0,
-                read_write_set: ReadWriteSet::new(),
-            },
-        );
+        let module = self.compile(module_bytes)?;
+
+        // 创建 Store，并将 storage 的 Arc 克隆到 HostState 中
+        let mut store = self.new_store(0, 0, self.gas_limit)?;
 
         let instance = self.instantiate(&mut store, &module)?;
 
@@ -234,7 +311,9 @@ impl<S: Storage + 'static> Runtime<S> {
         }
 
         let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
-        let res = add.call(&mut store, (a, b))?;
+        let res = add
+            .call(&mut store, (a, b))
+            .map_err(|e| gas::map_out_of_gas(e, self.gas_limit))?;
         Ok(res)
     }
 
@@ -244,6 +323,11 @@ impl<S: Storage + 'static> Runtime<S> {
     /// - 函数返回值
     /// - 执行过程中收集的事件列表
     /// - 区块号与时间戳（从 HostState 中获取）
+    ///
+    /// 直接执行不属于任何合约地址: `storage_api` 使用原始键，访问 `contract:` 命名空间内的键
+    /// 会 trap（[`RegistryError::ReservedNamespace`]）。
+    ///
+    /// gas 耗尽时返回 [`GasError::OutOfGas`]
     pub fn execute_with_context(
         &self,
         module_bytes: &[u8],
@@ -251,20 +335,9 @@ impl<S: Storage + 'static> Runtime<S> {
         block_number: u64,
         timestamp: u64,
     ) -> Result<(i32, Vec<Vec<u8>>, u64, u64)> {
-        let module = Module::new(&self.engine, module_bytes)?;
-
-        let mut store = Store::new(
-            &self.engine,
-            HostState {
-                storage: self.storage.clone(),
-                memory: None,
-                last_get: None,
-                events: Vec::new(),
-                block_number,
-                timestamp,
-                read_write_set: ReadWriteSet::new(),
-            },
-        );
+        let module = self.compile(module_bytes)?;
+
+        let mut store = self.new_store(block_number, timestamp, self.gas_limit)?;
 
         let instance = self.instantiate(&mut store, &module)?;
 
@@ -275,7 +348,9 @@ impl<S: Storage + 'static> Runtime<S> {
 
         // 调用指定的导出函数
         let func = instance.get_typed_func::<(), i32>(&mut store, func_name)?;
-        let result = func.call(&mut store, ())?;
+        let result = func
+            .call(&mut store, ())
+            .map_err(|e| gas::map_out_of_gas(e, self.gas_limit))?;
 
         // 提取事件与上下文
         let events = store.data().events.clone();
@@ -287,7 +362,7 @@ impl<S: Storage + 'static> Runtime<S> {
 
     /// 执行 WASM 模块并返回完整的执行结果 (包括读写集)
     ///
-    /// 用于并行执行场景
+    /// 用于并行执行场景，使用运行时默认 gas 上限
     pub fn execute_with_rw_tracking(
         &self,
         module_bytes: &[u8],
@@ -295,54 +370,73 @@ impl<S: Storage + 'static> Runtime<S> {
         block_number: u64,
         timestamp: u64,
     ) -> Result<ExecutionResult> {
-        let module = Module::new(&self.engine, module_bytes)?;
-
-        let mut store = Store::new(
-            &self.engine,
-            HostState {
-                storage: self.storage.clone(),
-                memory: None,
-                last_get: None,
-                events: Vec::new(),
-                block_number,
-                timestamp,
-                read_write_set: ReadWriteSet::new(),
-            },
-        );
+        self.execute_metered(module_bytes, func_name, block_number, timestamp, self.gas_limit)
+    }
 
-        let instance = self.instantiate(&mut store, &module)?;
+    /// 在指定 gas 上限下执行 WASM 模块，返回读写集与 gas 消耗
+    ///
+    /// 与 [`Runtime::execute_with_context`] 相同，直接执行使用原始键且不得访问 `contract:` 命名空间。
+    ///
+    /// gas 耗尽视为执行失败（`success = false`），`error` 为 [`GasError::OutOfGas`] 的描述，
+    /// 此时 `gas_used` 等于 `gas_limit`
+    pub fn execute_metered(
+        &self,
+        module_bytes: &[u8],
+        func_name: &str,
+        block_number: u64,
+        timestamp: u64,
+        gas_limit: u64,
+    ) -> Result<ExecutionResult> {
+        let module = self.compile(module_bytes)?;
+        let store = self.new_store(block_number, timestamp, gas_limit)?;
+        run_metered(&module, func_name, store)
+    }
 
-        // 获取导出的内存并保存
-        if let Some(memory) = instance.get_memory(&mut store, "memory") {
-            store.data_mut().memory = Some(memory);
-        }
+    /// 部署合约：校验可编译、写入注册表并预热模块缓存，返回合约地址
+    pub fn deploy(
+        &self,
+        deployer: [u8; 20],
+        module_bytes: &[u8],
+        scheme: AddressScheme,
+    ) -> Result<[u8; 20]> {
+        self.compile(module_bytes)?;
+        let info = contract_registry::register(
+            &mut *self.storage.lock(),
+            &deployer,
+            module_bytes,
+            scheme,
+        )?;
+        Ok(info.address)
+    }
 
-        // 调用指定的导出函数
-        let func = instance.get_typed_func::<(), i32>(&mut store, func_name)?;
-        let result = func.call(&mut store, ());
+    /// 查询已部署合约的元数据
+    pub fn contract_info(&self, address: &[u8; 20]) -> Result<Option<ContractInfo>> {
+        contract_registry::contract_info(&*self.storage.lock(), address)
+    }
 
-        // 提取所有状态
-        let events = store.data().events.clone();
-        let read_write_set = store.data().read_write_set.clone();
-
-        match result {
-            Ok(return_value) => Ok(ExecutionResult {
-                tx_id: 0, // 由调用者设置
-                return_value,
-                read_write_set,
-                events,
-                success: true,
-                error: None,
-            }),
-            Err(e) => Ok(ExecutionResult {
-                tx_id: 0,
-                return_value: -1,
-                read_write_set,
-                events,
-                success: false,
-                error: Some(e.to_string()),
-            }),
-        }
+    /// 按地址调用已部署合约的任意导出函数 `entry_point() -> i32`
+    ///
+    /// - `context.contract` 为被调合约地址，`context.caller` 通过 `contract_api::caller` 可见
+    /// - `storage_api` 的键位于合约私有命名空间（`contract:store:` + 地址）
+    /// - `input` 通过 `contract_api::input_len` / `read_input` 读取，返回数据由 `set_return` 设置
+    /// - gas 上限取 `context.gas_limit`；`context.value` 对 WASM 合约无意义，忽略
+    pub fn call_contract(
+        &self,
+        entry_point: &str,
+        input: &[u8],
+        context: &ExecutionContext,
+    ) -> Result<ExecutionResult> {
+        let code = contract_registry::contract_code(&*self.storage.lock(), &context.contract)?
+            .ok_or(RegistryError::NotFound(context.contract))?;
+        let module = self.compile(&code)?;
+
+        let mut store = self.new_store(context.block_number, context.timestamp, context.gas_limit)?;
+        let state = store.data_mut();
+        state.caller_address = context.caller;
+        state.contract_address = context.contract;
+        state.input = input.to_vec();
+        state.storage_prefix = contract_registry::storage_prefix(&context.contract);
+        run_metered(&module, entry_point, store)
     }
 
     /// Phase 1.3: 带路由的交易执行入口
@@ -392,6 +486,61 @@ impl<S: Storage + 'static> Runtime<S> {
     // init_hybrid 已在后续 hybrid-exec impl 模块提供，避免重复定义
 }
 
+/// 在已配置的 store 上实例化并调用 `func_name() -> i32`，收集读写集、事件、返回数据与 gas
+///
+/// 与存储后端无关：`Runtime` 直接写穿的存储与 [`TxnExecutor`] 的事务覆盖层共用此路径
+fn run_metered<T: Storage + 'static>(
+    module: &Module,
+    func_name: &str,
+    mut store: Store<HostState<T>>,
+) -> Result<ExecutionResult> {
+    let gas_limit = store.data().gas_limit;
+    let mut linker = Linker::new(store.engine());
+    host::register_host_functions(&mut linker)?;
+    let instance = linker.instantiate(&mut store, module)?;
+
+    // 获取导出的内存并保存
+    if let Some(memory) = instance.get_memory(&mut store, "memory") {
+        store.data_mut().memory = Some(memory);
+    }
+
+    // 调用指定的导出函数
+    let func = instance.get_typed_func::<(), i32>(&mut store, func_name)?;
+    let result = func.call(&mut store, ());
+
+    // 提取所有状态
+    let events = store.data().events.clone();
+    let read_write_set = store.data().read_write_set.clone();
+    let return_data = store.data().return_data.clone();
+    let storage_deposit = store.data().storage_deposit;
+    let gas_used = gas_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+
+    match result {
+        Ok(return_value) => Ok(ExecutionResult {
+            tx_id: 0, // 由调用者设置
+            return_value,
+            return_data,
+            read_write_set,
+            events,
+            success: true,
+            error: None,
+            gas_used: storage_deposit.settle(gas_used),
+            storage_deposit,
+        }),
+        Err(e) => Ok(ExecutionResult {
+            tx_id: 0,
+            return_value: -1,
+            return_data: Vec::new(),
+            read_write_set,
+            events,
+            success: false,
+            error: Some(gas::map_out_of_gas(e, gas_limit).to_string()),
+            gas_used,
+            storage_deposit,
+        }),
+    }
+}
+
 // ================= Phase 13: Hybrid Executor Integration =================
 #[cfg(feature = "hybrid-exec")]
 mod hybrid_integration {
@@ -673,15 +822,38 @@ mod tests {
         let rt = Runtime::new(MemoryStorage::new());
 
         // 测试存储操作通过直接操作存储接口
-        rt.storage().borrow_mut().set(b"test_key", b"test_value")?;
+        rt.storage().lock().set(b"test_key", b"test_value")?;
         assert_eq!(
-            rt.storage().borrow().get(b"test_key")?.unwrap(),
+            rt.storage().lock().get(b"test_key")?.unwrap(),
             b"test_value"
         );
 
         Ok(())
     }
 
+    #[test]
+    fn test_runtime_is_send_over_shared_storage() -> Result<()> {
+        let handle = LockedStorage::new(MemoryStorage::new()).into_handle();
+        let wat = r#"
+        (module
+          (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 100) "shared")
+          (data (i32.const 200) "written")
+          (func (export "main") (result i32)
+            (call $storage_set (i32.const 100) (i32.const 6) (i32.const 200) (i32.const 7))))
+        "#;
+        let wasm = wat::parse_str(wat)?;
+
+        // 运行时整体移入工作线程执行，写入经共享句柄对其它持有者立即可见
+        let rt = Runtime::new(Arc::clone(&handle));
+        std::thread::spawn(move || rt.execute_with_context(&wasm, "main", 1, 0).map(|r| r.0))
+            .join()
+            .unwrap()?;
+        assert_eq!(handle.get(b"shared")?, Some(b"written".to_vec()));
+        Ok(())
+    }
+
     #[test]
     fn test_host_functions() -> Result<()> {
         let rt = Runtime::new(MemoryStorage::new());
@@ -715,18 +887,7 @@ mod tests {
         "#;
 
         let wasm = wat::parse_str(wat)?;
-        let mut store = Store::new(
-            &rt.engine,
-            HostState {
-                storage: rt.storage.clone(),
-                memory: None,
-                last_get: None,
-                events: Vec::new(),
-                block_number: 0,
-                timestamp: 0,
-                read_write_set: ReadWriteSet::new(),
-            },
-        );
+        let mut store = rt.new_store(0, 0, rt.gas_limit())?;
         let module = Module::new(&rt.engine, &wasm)?;
         let instance = rt.instantiate(&mut store, &module)?;
 
@@ -739,7 +900,7 @@ mod tests {
 
         assert_eq!(result, 0); // 0 表示成功
         assert_eq!(
-            store.data().storage.borrow().get(b"test_key")?.unwrap(),
+            store.data().storage.lock().get(b"test_key")?.unwrap(),
             b"test_value"
         );
 
@@ -770,18 +931,7 @@ mod tests {
                 )
         "#;
         let wasm = wat::parse_str(wat)?;
-        let mut store = Store::new(
-            &rt.engine,
-            HostState {
-                storage: rt.storage.clone(),
-                memory: None,
-                last_get: None,
-                events: Vec::new(),
-                block_number: 0,
-                timestamp: 0,
-                read_write_set: ReadWriteSet::new(),
-            },
-        );
+        let mut store = rt.new_store(0, 0, rt.gas_limit())?;
         let module = Module::new(&rt.engine, &wasm)?;
         let instance = rt.instantiate(&mut store, &module)?;
         if let Some(memory) = instance.get_memory(&mut store, "memory") {
@@ -869,7 +1019,7 @@ mod tests {
 
         // 验证哈希结果
         let storage = rt.storage();
-        let store_ref = storage.borrow();
+        let store_ref = storage.lock();
         // 注意: 实际应该从 WASM 内存读取结果,这里只验证调用成功
         drop(store_ref);
 
@@ -1012,6 +1162,379 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_gas_infinite_loop_out_of_gas() -> Result<()> {
+        let rt = Runtime::new(MemoryStorage::new()).with_gas_limit(50_000);
+
+        let wat = r#"
+        (module
+            (func (export "spin") (result i32)
+                (loop $l
+                    br $l)
+                (i32.const 0)
+            )
+        )
+        "#;
+
+        let wasm = wat::parse_str(wat)?;
+        let err = rt
+            .execute_with_context(&wasm, "spin", 0, 0)
+            .expect_err("looping contract must run out of gas");
+        assert_eq!(
+            err.downcast_ref::<GasError>(),
+            Some(&GasError::OutOfGas { gas_limit: 50_000 })
+        );
+
+        let exec_result = rt.execute_metered(&wasm, "spin", 0, 0, 10_000)?;
+        assert!(!exec_result.success);
+        assert_eq!(exec_result.gas_used, 10_000);
+        assert_eq!(
+            exec_result.error,
+            Some(GasError::OutOfGas { gas_limit: 10_000 }.to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gas_host_call_costs() -> Result<()> {
+        let rt = Runtime::new(MemoryStorage::new());
+
+        let wat = r#"
+        (module
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (import "storage_api" "storage_get" (func $storage_get (param i32 i32) (result i64)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "key")
+            (data (i32.const 16) "value")
+
+            (func (export "noop") (result i32)
+                (i32.const 0)
+            )
+            (func (export "write") (result i32)
+                (call $storage_set (i32.const 0) (i32.const 3) (i32.const 16) (i32.const 5))
+            )
+            (func (export "read") (result i32)
+                (call $storage_get (i32.const 0) (i32.const 3))
+                drop
+                (i32.const 0)
+            )
+        )
+        "#;
+
+        let wasm = wat::parse_str(wat)?;
+        let schedule = rt.gas_schedule().clone();
+
+        let noop = rt.execute_with_rw_tracking(&wasm, "noop", 0, 0)?;
+        let write = rt.execute_with_rw_tracking(&wasm, "write", 0, 0)?;
+        let read = rt.execute_with_rw_tracking(&wasm, "read", 0, 0)?;
+        assert!(noop.success && write.success && read.success);
+
+        // host 调用成本按字节放大并计入 gas_used
+        assert!(write.gas_used >= noop.gas_used + schedule.storage_write_cost(3 + 5));
+        assert!(read.gas_used >= noop.gas_used + schedule.storage_read_cost(3 + 5));
+        assert!(write.gas_used > read.gas_used);
+
+        // gas 不足以支付写入成本时中止，且不落盘
+        let rt_low = Runtime::new(MemoryStorage::new()).with_gas_limit(1_000);
+        let failed = rt_low.execute_with_rw_tracking(&wasm, "write", 0, 0)?;
+        assert!(!failed.success);
+        assert_eq!(failed.gas_used, 1_000);
+        assert!(rt_low.storage().lock().get(b"key")?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deploy_and_call_contract() -> Result<()> {
+        let rt = Runtime::new(MemoryStorage::new());
+        let deployer = [0x42u8; 20];
+
+        // echo: 将输入原样写回返回数据；store: 将输入写入存储键 "k"
+        let wat = r#"
+        (module
+            (import "contract_api" "input_len" (func $input_len (result i32)))
+            (import "contract_api" "read_input" (func $read_input (param i32 i32) (result i32)))
+            (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "k")
+            (func (export "echo") (result i32)
+                (drop (call $read_input (i32.const 64) (call $input_len)))
+                (drop (call $set_return (i32.const 64) (call $input_len)))
+                (i32.const 0)
+            )
+            (func (export "store") (result i32)
+                (drop (call $read_input (i32.const 64) (call $input_len)))
+                (call $storage_set (i32.const 0) (i32.const 1) (i32.const 64) (call $input_len))
+            )
+        )
+        "#;
+        let wasm = wat::parse_str(wat)?;
+
+        let address = rt.deploy(deployer, &wasm, AddressScheme::DeployerNonce)?;
+        assert_eq!(address, contract_registry::derive_address(&deployer, 0));
+        let info = rt.contract_info(&address)?.expect("deployed");
+        assert_eq!(info.code_hash, contract_registry::code_hash(&wasm));
+        assert_eq!(rt.module_cache().stats().compiles, 1);
+
+        let ctx = ExecutionContext {
+            caller: [0x01; 20],
+            contract: address,
+            value: 0,
+            gas_limit: rt.gas_limit(),
+            block_number: 1,
+            timestamp: 0,
+        };
+        let echo = rt.call_contract("echo", b"hello", &ctx)?;
+        assert!(echo.success);
+        assert_eq!(echo.return_data, b"hello");
+
+        let stored = rt.call_contract("store", b"v1", &ctx)?;
+        assert!(stored.success);
+        let key = [contract_registry::storage_prefix(&address), b"k".to_vec()].concat();
+        assert_eq!(rt.storage().lock().get(&key)?, Some(b"v1".to_vec()));
+        assert!(rt.storage().lock().get(b"k")?.is_none());
+
+        // 多次调用复用已编译模块
+        assert_eq!(rt.module_cache().stats().compiles, 1);
+        assert!(rt.module_cache().stats().hits >= 2);
+
+        // 未部署地址
+        let missing = ExecutionContext { contract: [0xee; 20], ..ctx };
+        let err = rt.call_contract("echo", b"", &missing).unwrap_err();
+        assert!(err.downcast_ref::<RegistryError>().is_some());
+
+        // 非法代码部署失败且不登记
+        assert!(rt.deploy(deployer, b"not wasm", AddressScheme::DeployerNonce).is_err());
+        assert_eq!(contract_registry::deployer_nonce(&*rt.storage().lock(), &deployer)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_direct_execution_cannot_touch_contract_namespace() -> Result<()> {
+        let wat = r#"
+        (module
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (import "storage_api" "storage_get" (func $storage_get (param i32 i32) (result i64)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "contract:info:x")
+            (data (i32.const 32) "plain")
+            (func (export "write_reserved") (result i32)
+                (call $storage_set (i32.const 0) (i32.const 15) (i32.const 32) (i32.const 5))
+            )
+            (func (export "read_reserved") (result i32)
+                (i32.wrap_i64 (call $storage_get (i32.const 0) (i32.const 15)))
+            )
+            (func (export "write_plain") (result i32)
+                (call $storage_set (i32.const 32) (i32.const 5) (i32.const 32) (i32.const 5))
+            )
+        )
+        "#;
+        let wasm = wat::parse_str(wat)?;
+        let rt = Runtime::new(MemoryStorage::new());
+
+        for func in ["write_reserved", "read_reserved"] {
+            let result = rt.execute_with_rw_tracking(&wasm, func, 0, 0)?;
+            assert!(!result.success, "{func}");
+            assert!(rt.execute_with_context(&wasm, func, 0, 0).is_err(), "{func}");
+        }
+        assert!(rt.storage().lock().get(b"contract:info:x")?.is_none());
+
+        // 命名空间之外的原始键不受影响
+        assert!(rt.execute_with_rw_tracking(&wasm, "write_plain", 0, 0)?.success);
+        assert_eq!(rt.storage().lock().get(b"plain")?, Some(b"plain".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_contract_to_contract_calls() -> Result<()> {
+        // proxy: 输入 = 目标地址(20) ‖ 转发输入；返回 status(i32 LE) ‖ 被调方返回数据，并写入自身键 "p"
+        let proxy_wat = r#"
+        (module
+            (import "contract_api" "input_len" (func $input_len (result i32)))
+            (import "contract_api" "read_input" (func $read_input (param i32 i32) (result i32)))
+            (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+            (import "contract_api" "call" (func $call (param i32 i32 i32 i64) (result i32)))
+            (import "contract_api" "return_data_len" (func $rd_len (result i32)))
+            (import "contract_api" "read_return_data" (func $rd_read (param i32 i32) (result i32)))
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 1000) "px")
+            (func (export "call") (result i32)
+                (local $n i32)
+                (local.set $n (call $input_len))
+                (drop (call $read_input (i32.const 64) (local.get $n)))
+                (i32.store (i32.const 0)
+                    (call $call (i32.const 64) (i32.const 84) (i32.sub (local.get $n) (i32.const 20)) (i64.const 0)))
+                (drop (call $rd_read (i32.const 4) (call $rd_len)))
+                (drop (call $set_return (i32.const 0) (i32.add (i32.const 4) (call $rd_len))))
+                (call $storage_set (i32.const 1000) (i32.const 1) (i32.const 1001) (i32.const 1))
+            )
+        )
+        "#;
+        // store: 写入键 "n" = 输入并回显；输入首字节为 0xff 时写入后 trap
+        let store_wat = r#"
+        (module
+            (import "contract_api" "input_len" (func $input_len (result i32)))
+            (import "contract_api" "read_input" (func $read_input (param i32 i32) (result i32)))
+            (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "n")
+            (func (export "call") (result i32)
+                (drop (call $read_input (i32.const 64) (call $input_len)))
+                (drop (call $storage_set (i32.const 0) (i32.const 1) (i32.const 64) (call $input_len)))
+                (drop (call $set_return (i32.const 64) (call $input_len)))
+                (if (i32.eq (i32.load8_u (i32.const 64)) (i32.const 0xff)) (then unreachable))
+                (i32.const 0)
+            )
+        )
+        "#;
+
+        let rt = Runtime::new(MemoryStorage::new()).with_call_config(CallConfig {
+            max_depth: 2,
+            reentrancy: ReentrancyPolicy::Deny,
+        });
+        let deployer = [0x42u8; 20];
+        let proxy = rt.deploy(deployer, &wat::parse_str(proxy_wat)?, AddressScheme::DeployerNonce)?;
+        let store = rt.deploy(deployer, &wat::parse_str(store_wat)?, AddressScheme::DeployerNonce)?;
+        let ctx = ExecutionContext {
+            caller: [0x01; 20],
+            contract: proxy,
+            value: 0,
+            gas_limit: rt.gas_limit(),
+            block_number: 1,
+            timestamp: 0,
+        };
+        let key_of = |addr: &[u8; 20], k: &[u8]| [contract_registry::storage_prefix(addr), k.to_vec()].concat();
+        let status = |data: &[u8]| i32::from_le_bytes(data[..4].try_into().unwrap());
+
+        // 成功调用：被调方写入自身命名空间，返回数据透传
+        let ok = rt.call_contract("call", &[store.as_slice(), b"hi"].concat(), &ctx)?;
+        assert!(ok.success);
+        assert_eq!(status(&ok.return_data), CallStatus::Success as i32);
+        assert_eq!(&ok.return_data[4..], b"hi");
+        assert_eq!(rt.storage().lock().get(&key_of(&store, b"n"))?, Some(b"hi".to_vec()));
+        assert!(ok.gas_used > rt.gas_schedule().call_base);
+
+        // 被调方 trap：其写入被撤销，调用方继续执行并保留自身写入
+        let trapped = rt.call_contract("call", &[store.as_slice(), &[0xff, 1]].concat(), &ctx)?;
+        assert!(trapped.success);
+        assert_eq!(status(&trapped.return_data), CallStatus::Reverted as i32);
+        assert_eq!(rt.storage().lock().get(&key_of(&store, b"n"))?, Some(b"hi".to_vec()));
+        assert_eq!(rt.storage().lock().get(&key_of(&proxy, b"p"))?, Some(b"x".to_vec()));
+
+        // 未部署地址
+        let missing = rt.call_contract("call", &[0xee; 20], &ctx)?;
+        assert_eq!(status(&missing.return_data), CallStatus::NotFound as i32);
+
+        // 自调用被默认重入策略拒绝
+        let reentrant = rt.call_contract("call", &[proxy; 2].concat(), &ctx)?;
+        assert_eq!(status(&reentrant.return_data), CallStatus::ReentrancyDenied as i32);
+
+        // 允许重入时受深度限制：第 2 层的调用返回 DepthExceeded
+        let rt = rt.with_call_config(CallConfig {
+            max_depth: 2,
+            reentrancy: ReentrancyPolicy::Allow,
+        });
+        let deep = rt.call_contract("call", &[proxy; 5].concat(), &ctx)?;
+        assert!(deep.success);
+        let statuses: Vec<i32> = deep.return_data.chunks(4).map(status).collect();
+        assert_eq!(
+            statuses,
+            vec![CallStatus::Success as i32, CallStatus::Success as i32, CallStatus::DepthExceeded as i32]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_contract_call_propagates_host_errors() -> Result<()> {
+        /// 对指定前缀的写入失败的存储
+        #[derive(Default)]
+        struct FailingStorage {
+            inner: MemoryStorage,
+            fail_prefix: Option<Vec<u8>>,
+        }
+
+        impl Storage for FailingStorage {
+            fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+                self.inner.get(key)
+            }
+
+            fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+                match &self.fail_prefix {
+                    Some(prefix) if key.starts_with(prefix) => anyhow::bail!("disk unavailable"),
+                    _ => self.inner.set(key, value),
+                }
+            }
+
+            fn delete(&mut self, key: &[u8]) -> Result<()> {
+                self.inner.delete(key)
+            }
+
+            fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+                self.inner.scan(prefix)
+            }
+        }
+
+        // caller: 调用输入中的地址，写入自身键 "p" = 状态码后返回 0
+        let caller_wat = r#"
+        (module
+            (import "contract_api" "read_input" (func $read_input (param i32 i32) (result i32)))
+            (import "contract_api" "call" (func $call (param i32 i32 i32 i64) (result i32)))
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 100) "p")
+            (func (export "call") (result i32)
+                (drop (call $read_input (i32.const 0) (i32.const 20)))
+                (i32.store8 (i32.const 200) (call $call (i32.const 0) (i32.const 0) (i32.const 0) (i64.const 0)))
+                (call $storage_set (i32.const 100) (i32.const 1) (i32.const 200) (i32.const 1))
+            )
+        )
+        "#;
+        // callee: 写入键 "n"
+        let callee_wat = r#"
+        (module
+            (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "n")
+            (func (export "call") (result i32)
+                (call $storage_set (i32.const 0) (i32.const 1) (i32.const 0) (i32.const 1))
+            )
+        )
+        "#;
+
+        let rt = Runtime::new(FailingStorage::default());
+        let deployer = [0x42u8; 20];
+        let caller = rt.deploy(deployer, &wat::parse_str(caller_wat)?, AddressScheme::DeployerNonce)?;
+        let callee = rt.deploy(deployer, &wat::parse_str(callee_wat)?, AddressScheme::DeployerNonce)?;
+        let ctx = ExecutionContext {
+            caller: [0x01; 20],
+            contract: caller,
+            value: 0,
+            gas_limit: rt.gas_limit(),
+            block_number: 1,
+            timestamp: 0,
+        };
+        let key_of = |addr: &[u8; 20], k: &[u8]| [contract_registry::storage_prefix(addr), k.to_vec()].concat();
+
+        rt.storage().lock().fail_prefix = Some(contract_registry::storage_prefix(&callee));
+        let failed = rt.call_contract("call", &callee, &ctx)?;
+        // 被调方的存储故障不降级为 Reverted：调用方随之中止，不会写入状态码
+        assert!(!failed.success);
+        assert_eq!(rt.storage().lock().get(&key_of(&caller, b"p"))?, None);
+
+        rt.storage().lock().fail_prefix = None;
+        let ok = rt.call_contract("call", &callee, &ctx)?;
+        assert!(ok.success);
+        assert_eq!(
+            rt.storage().lock().get(&key_of(&caller, b"p"))?,
+            Some(vec![CallStatus::Success as u8])
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_parallel_conflict_detection() -> Result<()> {
         use crate::parallel::{ConflictDetector, ReadWriteSet};
@@ -1050,6 +1573,7 @@ mod tests {
     #[test]
     fn test_execution_trait() {
         use crate::execution_trait::*;
+        use crate::gas::StorageDeposit;
 
         // 测试 EngineType
         assert_eq!(EngineType::Wasm, EngineType::Wasm);
@@ -1074,6 +1598,7 @@ mod tests {
             gas_used: 5000,
             logs: vec![],
             state_changes: vec![],
+            storage_deposit: StorageDeposit::default(),
         };
         assert!(result.success);
         assert_eq!(result.gas_used, 5000);
@@ -109,6 +109,8 @@ impl MultiCoreConsensus {
                 let pid = *parts.iter().next().unwrap() as usize;
                 let part = &self.partitions[pid];
                 if part.ts_next.load(Ordering::Relaxed) >= part.ts_end.load(Ordering::Relaxed) {
+                    // 新批次从最近一次刷盘切点之后开始，切点之内的 ts 会被提交拒绝
+                    self.global_ts.fetch_max(self.store.min_override_ts(), Ordering::Relaxed);
                     let start = self.global_ts.fetch_add(self.batch_size, Ordering::Relaxed);
                     part.ts_next.store(start, Ordering::Relaxed);
                     part.ts_end.store(start + self.batch_size, Ordering::Relaxed);
@@ -2,6 +2,8 @@
 // Copyright (c) 2025 XujueKing <leadbrand@me.com>
 
 use crate::metrics::MetricsCollector;
+use crate::wal::{CommitLog, WalRecord};
+use crate::state_commitment::is_reserved_key;
 use dashmap::DashMap;
 use parking_lot::RwLock;
 #[cfg(feature = "smallvec-chains")]
@@ -110,6 +112,82 @@ impl Default for AutoFlushConfig {
     }
 }
 
+/// 持久化存储中 MVCC 元数据的保留键前缀（不属于用户状态）
+pub const MVCC_META_PREFIX: &[u8] = b"__mvcc:";
+/// 刷盘水位线：u64 大端，ts 不超过它的提交均已反映在持久化存储中
+pub const FLUSH_WATERMARK_KEY: &[u8] = b"__mvcc:flush_watermark";
+
+/// 事务隔离级别
+///
+/// - `Snapshot`（默认）：快照读 + 提交时写写冲突检测 + 读集合校验（"read-write conflict"）。
+///   读集合校验不对读键加锁，两个并发提交的事务可能同时通过校验，因此仍可能出现 write skew；
+///   `consensus-optimizations` 下的 `commit` 路径不校验读集合；范围读不做幻读检测
+/// - `Serializable`：在此基础上对读集合与写集合一并加锁后原子校验，并对范围读做幻读检测；
+///   读集合或扫描区间内任一键在 start_ts 之后被其他事务提交即中止，错误信息以 [`SERIALIZATION_FAILURE`] 开头
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+    #[default]
+    Snapshot,
+    Serializable,
+}
+
+/// 可串行化校验失败（读写反依赖）的中止原因前缀
+pub const SERIALIZATION_FAILURE: &str = "serialization failure";
+
+/// 提交中止原因分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// 写写冲突（任何隔离级别）
+    WriteWrite,
+    /// 读写冲突（快照隔离提交路径的读集合校验）
+    ReadWrite,
+    /// 读写反依赖（仅可串行化事务）
+    Serialization,
+}
+
+impl ConflictKind {
+    /// 从 `commit` / `commit_parallel` 返回的错误信息识别冲突类型，非冲突错误返回 None
+    pub fn of(err: &str) -> Option<Self> {
+        if err.starts_with(SERIALIZATION_FAILURE) {
+            Some(Self::Serialization)
+        } else if err.contains("write-write conflict") {
+            Some(Self::WriteWrite)
+        } else if err.contains("read-write conflict") {
+            Some(Self::ReadWrite)
+        } else {
+            None
+        }
+    }
+}
+
+/// 键区间 `[start, end)`，`end = None` 表示无上界；用于事务范围扫描与幻读检测
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRange {
+    pub start: Vec<u8>,
+    pub end: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    pub fn new(start: &[u8], end: Option<&[u8]>) -> Self {
+        Self { start: start.to_vec(), end: end.map(|e| e.to_vec()) }
+    }
+
+    /// 以 `prefix` 开头的全部键
+    pub fn prefix(prefix: &[u8]) -> Self {
+        // 前缀后继：去掉末尾的 0xff 后末字节加一；全为 0xff（或空前缀）时无上界
+        let mut end = prefix.to_vec();
+        while end.last() == Some(&0xff) {
+            end.pop();
+        }
+        let end = end.last_mut().map(|b| *b += 1).map(|_| end);
+        Self { start: prefix.to_vec(), end }
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        key >= self.start.as_slice() && self.end.as_deref().is_none_or(|end| key < end)
+    }
+}
+
 /// MVCC 存储实现（优化版 + GC + 自动 GC）：
 /// - 使用 DashMap 实现每键粒度的并发控制，减少全局锁竞争
 /// - 每个键的版本链使用 RwLock 保护，允许并发读
@@ -121,12 +199,16 @@ impl Default for AutoFlushConfig {
 type VersionChain = RwLock<SmallVec<[Version; 4]>>;
 #[cfg(not(feature = "smallvec-chains"))]
 type VersionChain = RwLock<Vec<Version>>;
+type KeyValuePairs = Vec<(Vec<u8>, Vec<u8>)>;
 
 
 pub struct MvccStore {
     /// 每个 key 的版本链（按 ts 升序存放）
     /// feature smallvec-chains: 使用 SmallVec 内联前 4 个版本，减少短链堆分配
     data: DashMap<Vec<u8>, VersionChain>,
+    /// 有序键索引：`data` 中的全部键（每键多存一份；仅回收按需加载墓碑时随 `data` 一并删除），
+    /// 范围扫描与幻读检测按区间定位，开销为 O(log N + 区间内键数)，无需遍历整个 DashMap
+    key_index: RwLock<std::collections::BTreeSet<Vec<u8>>>,
     /// 全局递增时间戳（原子操作，无锁）
     ts: AtomicU64,
     /// 活跃事务的最小 start_ts（水位线）
@@ -158,6 +240,12 @@ pub struct MvccStore {
     key_locks: DashMap<Vec<u8>, Arc<Mutex<()>>>,
     /// 提交中的计数：>0 时阻塞新事务 begin，确保新事务不会看到部分提交
     commit_in_progress: AtomicU64,
+    /// 新事务的默认隔离级别是否为可串行化
+    serializable_by_default: AtomicBool,
+    /// 谓词锁：写事务分配 commit_ts 并写入期间持有读锁；带范围读的可串行化事务在校验与写入期间
+    /// 持有写锁，保证校验时所有更小 commit_ts 的写入均已可见（幻读检测不漏检）。
+    /// 全局加锁顺序：先谓词锁，再按键排序的 `key_locks`
+    predicate_lock: RwLock<()>,
 
     // ===== 自动刷新相关字段 =====
     /// 自动刷新配置
@@ -170,12 +258,61 @@ pub struct MvccStore {
     current_block: Arc<AtomicU64>,
     /// 刷新统计信息
     flush_stats: Arc<Mutex<FlushStats>>,
+    /// 最近一次刷盘的水位线（见 [`FLUSH_WATERMARK_KEY`]）
+    flush_watermark: AtomicU64,
+    /// 最近一次刷盘所取的切点（持谓词写锁时推进，先于水位线；预分配的 commit_ts 须大于它）
+    flush_cut: AtomicU64,
+
+    // ===== 按需加载相关字段 =====
+    /// 是否启用按需加载（读路径快速判断）
+    lazy_enabled: AtomicBool,
+    /// 按需加载的回源存储与基线 ts
+    lazy_source: RwLock<Option<LazySource>>,
+    /// 已回源（或确认不存在）的键
+    lazy_loaded: DashMap<Vec<u8>, ()>,
+
+    /// 提交日志（可选）：提交先写日志再写版本链，刷盘成功后截断
+    wal: RwLock<Option<Arc<CommitLog>>>,
+    /// 归档模式：刷盘时保留全部历史版本（见 [`crate::archive`]）
+    archive_mode: AtomicBool,
+    /// 已记录但尚未刷盘的区块水位线 (height, ts)
+    pending_blocks: Mutex<Vec<(u64, u64)>>,
 
     // ===== 性能指标收集器 =====
     /// 性能指标收集器（可选）
     metrics: Option<Arc<MetricsCollector>>,
 }
 
+/// 自动刷新的目标存储
+enum FlushTarget {
+    /// 外层互斥锁包装的 `&mut` 存储
+    Locked(Arc<Mutex<dyn crate::Storage + Send>>),
+    /// 内部可变的共享存储句柄
+    Shared(crate::StorageHandle),
+}
+
+impl FlushTarget {
+    /// 执行一次刷盘；锁已中毒时为 None
+    fn flush(&self, store: &MvccStore, keep_recent_versions: usize) -> Option<Result<(usize, usize), String>> {
+        match self {
+            FlushTarget::Locked(storage) => {
+                let mut guard = storage.lock().ok()?;
+                Some(store.flush_to_storage(&mut *guard, keep_recent_versions))
+            }
+            FlushTarget::Shared(handle) => {
+                let mut view = Arc::clone(handle);
+                Some(store.flush_to_storage(&mut view, keep_recent_versions))
+            }
+        }
+    }
+}
+
+/// 按需加载的回源配置
+struct LazySource {
+    storage: Arc<Mutex<dyn crate::Storage + Send>>,
+    base_ts: u64,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Version {
     pub ts: u64,
@@ -221,12 +358,17 @@ pub struct AutoGcRuntime {
 impl MvccStore {
     /// 状态裁剪：批量清理每个 key 的历史版本，仅保留最新 keep_versions 个版本
     /// 返回清理的版本数和键数 (cleaned_versions, cleaned_keys)
+    ///
+    /// 归档模式下历史版本需保留，不做裁剪，返回 (0, 0)
     #[cfg(feature = "rocksdb-storage")]
     pub fn prune_old_versions(
         &self,
         keep_versions: usize,
         rocksdb: &crate::storage::RocksDBStorage,
     ) -> (u64, u64) {
+        if self.is_archive_mode() {
+            return (0, 0);
+        }
         let mut total_cleaned = 0u64;
         let mut keys_cleaned = 0u64;
         let mut batch = Vec::new();
@@ -268,6 +410,7 @@ impl MvccStore {
 
         let store = Arc::new(Self {
             data: DashMap::new(),
+            key_index: RwLock::new(std::collections::BTreeSet::new()),
             ts: AtomicU64::new(0),
             active_txns: Arc::new(Mutex::new(Vec::new())),
             gc_config: Arc::new(Mutex::new(config.clone())),
@@ -294,6 +437,8 @@ impl MvccStore {
             commit_lock: Arc::new(Mutex::new(())),
             key_locks: DashMap::new(),
             commit_in_progress: AtomicU64::new(0),
+            serializable_by_default: AtomicBool::new(false),
+            predicate_lock: RwLock::new(()),
 
             // 自动刷新字段初始化
             auto_flush_config: Arc::new(Mutex::new(None)),
@@ -301,6 +446,14 @@ impl MvccStore {
             auto_flush_stop: Arc::new(AtomicBool::new(false)),
             current_block: Arc::new(AtomicU64::new(0)),
             flush_stats: Arc::new(Mutex::new(FlushStats::default())),
+            flush_watermark: AtomicU64::new(0),
+            flush_cut: AtomicU64::new(0),
+            lazy_enabled: AtomicBool::new(false),
+            lazy_source: RwLock::new(None),
+            lazy_loaded: DashMap::new(),
+            wal: RwLock::new(None),
+            archive_mode: AtomicBool::new(false),
+            pending_blocks: Mutex::new(Vec::new()),
 
             // 默认启用指标收集器
             metrics: Some(Arc::new(MetricsCollector::new())),
@@ -333,6 +486,24 @@ impl MvccStore {
             committed: false,
             read_only: false,
             override_commit_ts: None,
+            isolation: self.isolation_level(),
+            range_reads: Vec::new(),
+            load_error: None,
+        }
+    }
+
+    /// 设置之后开启的事务的默认隔离级别（已开启的事务不受影响）
+    pub fn set_isolation_level(&self, level: IsolationLevel) {
+        self.serializable_by_default
+            .store(level == IsolationLevel::Serializable, Ordering::Relaxed);
+    }
+
+    /// 新事务的默认隔离级别
+    pub fn isolation_level(&self) -> IsolationLevel {
+        if self.serializable_by_default.load(Ordering::Relaxed) {
+            IsolationLevel::Serializable
+        } else {
+            IsolationLevel::Snapshot
         }
     }
 
@@ -356,6 +527,9 @@ impl MvccStore {
             committed: false,
             read_only: true,
             override_commit_ts: None,
+            isolation: self.isolation_level(),
+            range_reads: Vec::new(),
+            load_error: None,
         }
     }
 
@@ -404,16 +578,84 @@ impl MvccStore {
     }
 
     /// 只读接口：按给定 start_ts 查询可见版本（测试/调试辅助）
-    /// 使用读锁，允许多个事务并发读取
-    pub fn read_at(&self, key: &[u8], start_ts: u64) -> Option<Vec<u8>> {
-        self.data.get(key).and_then(|entry| {
+    /// 使用读锁，允许多个事务并发读取；按需加载回源失败时返回错误
+    pub fn read_at(&self, key: &[u8], start_ts: u64) -> Result<Option<Vec<u8>>, String> {
+        self.lazy_load_key(key)?;
+        Ok(self.data.get(key).and_then(|entry| {
             let versions = entry.value().read();
             versions
                 .iter()
                 .rev()
                 .find(|v| v.ts <= start_ts)
                 .and_then(|v| v.value.clone())
-        })
+        }))
+    }
+
+    /// 扫描前缀下在 start_ts 可见的键值（按键排序，不含已删除的键）
+    pub fn scan_prefix_at(&self, prefix: &[u8], start_ts: u64) -> Result<KeyValuePairs, String> {
+        self.scan_range_at(&KeyRange::prefix(prefix), start_ts)
+    }
+
+    /// 扫描区间内在 start_ts 可见的键值（按键排序，不含已删除的键）；按需加载回源失败时返回错误
+    pub fn scan_range_at(&self, range: &KeyRange, start_ts: u64) -> Result<KeyValuePairs, String> {
+        self.lazy_load_range(range)?;
+        Ok(self
+            .keys_in_range(range)
+            .into_iter()
+            .filter_map(|key| {
+                let value = self.data.get(&key).and_then(|entry| {
+                    let versions = entry.value().read();
+                    versions
+                        .iter()
+                        .rev()
+                        .find(|v| v.ts <= start_ts)
+                        .and_then(|v| v.value.clone())
+                })?;
+                Some((key, value))
+            })
+            .collect())
+    }
+
+    /// 区间内的键（按序，取自有序键索引；索引锁在返回前释放）
+    fn keys_in_range(&self, range: &KeyRange) -> Vec<Vec<u8>> {
+        use std::ops::Bound;
+        if range.end.as_ref().is_some_and(|end| *end < range.start) {
+            return Vec::new();
+        }
+        let end = range.end.as_deref().map_or(Bound::Unbounded, Bound::Excluded);
+        self.key_index
+            .read()
+            .range::<[u8], _>((Bound::Included(range.start.as_slice()), end))
+            .cloned()
+            .collect()
+    }
+
+    /// 取键的版本链，不存在时创建
+    ///
+    /// 新键在持有索引写锁时登记并插入 `data`，与回收墓碑时的删除互斥，`data` 中的键总在索引中。
+    /// 加锁顺序为索引锁 → `data` 分片锁；扫描在访问 `data` 前已释放索引读锁，不会互相等待
+    fn chain_entry(&self, key: &[u8]) -> dashmap::mapref::one::RefMut<'_, Vec<u8>, VersionChain> {
+        if let Some(entry) = self.data.get_mut(key) {
+            return entry;
+        }
+        let mut index = self.key_index.write();
+        index.insert(key.to_vec());
+        self.data.entry(key.to_vec()).or_default()
+    }
+
+    /// 回收按需加载插入、此后未被改写的不存在墓碑（键再次读取时重新回源）
+    fn reclaim_lazy_tombstones(&self, keys: Vec<Vec<u8>>, base_ts: u64) {
+        let mut index = self.key_index.write();
+        for key in keys {
+            let removed = self.data.remove_if(&key, |_, chain| {
+                let versions = chain.read();
+                versions.len() == 1 && versions[0].ts == base_ts && versions[0].value.is_none()
+            });
+            if removed.is_some() {
+                index.remove(&key);
+                self.lazy_loaded.remove(&key);
+            }
+        }
     }
 
     /// 注销活跃事务
@@ -459,11 +701,13 @@ impl MvccStore {
     /// 2. 对于有多个版本的键，根据配置清理旧版本：
     ///    - 基于版本数量：超过 max_versions_per_key 的旧版本
     ///    - 基于活跃事务：低于最小活跃事务 start_ts 的版本可被清理
+    /// 3. 归档模式下只清理已随刷盘写入归档的版本（ts 不超过刷盘水位线）
     ///
     /// 返回清理的版本总数
     pub fn gc(&self) -> Result<u64, String> {
         let config = self.gc_config.lock().unwrap().clone();
         let min_active_ts = self.get_min_active_ts();
+        let archived_upto = self.archived_upto();
 
         let mut total_cleaned = 0u64;
         let mut keys_cleaned = 0u64;
@@ -501,7 +745,10 @@ impl MvccStore {
             }
 
             // 执行清理：保留最后 keep_count 个版本
-            let to_remove = versions.len().saturating_sub(keep_count);
+            let mut to_remove = versions.len().saturating_sub(keep_count);
+            if let Some(upto) = archived_upto {
+                to_remove = to_remove.min(versions.iter().take_while(|v| v.ts <= upto).count());
+            }
             if to_remove > 0 {
                 versions.drain(0..to_remove);
                 total_cleaned += to_remove as u64;
@@ -693,11 +940,20 @@ impl MvccStore {
     /// - 定期将已提交的最新版本刷新到 RocksDB
     /// - 热数据保留策略: 保留最近 K 个版本在内存
     ///
+    /// 刷盘切点 cut 取自当前时间戳：每个键写入 ts ≤ cut 的最新版本，墓碑写为后端删除；
+    /// cut 作为水位线（[`FLUSH_WATERMARK_KEY`]）与数据同批写入，重启后存储状态恰为 cut 时刻的提交状态。
+    /// 上次刷盘前已存在的墓碑与按需加载时插入的不存在墓碑不再重复删除，后者随后从内存回收。
+    /// 预分配 commit_ts（[`Txn::with_ts`]）不大于已取切点的提交被拒绝（见 [`MvccStore::min_override_ts`]），
+    /// 否则它既不在本批次中，其日志记录也会随切点截断。
+    ///
+    /// 归档模式下同批写入上次水位线之后、切点之前的全部版本（版本化键）与 ts 不超过切点的区块水位线，
+    /// 内存裁剪只回收此前已归档的版本。
+    ///
     /// 参数:
     /// - storage: 持久化存储后端 (实现 Storage trait)
     /// - keep_recent_versions: 保留最近多少个版本在内存 (默认 3)
     ///
-    /// 返回: (刷新的键数量, 刷新的总字节数)
+    /// 返回: (刷新的键数量（含删除）, 刷新的总字节数)
     pub fn flush_to_storage(
         &self,
         storage: &mut dyn crate::Storage,
@@ -708,78 +964,163 @@ impl MvccStore {
         // 统一聚合为批，后端自行决定是否原子批量处理
         let mut batch: Vec<(Vec<u8>, Option<Vec<u8>>)> = Vec::new();
 
+        // 刷盘切点：持有谓词写锁读取时间戳，此刻没有事务处于分配 commit_ts 与写入完成之间，
+        // 因此 ts ≤ cut 的提交均已完整落在版本链中
+        let cut = {
+            let _predicate_guard = self.predicate_lock.write();
+            let cut = self.ts.load(Ordering::SeqCst);
+            self.flush_cut.fetch_max(cut, Ordering::SeqCst);
+            cut
+        };
+        let archived_upto = self.archived_upto();
+        // 不晚于上次水位线的墓碑已在上次刷盘时删除；按需加载的基线墓碑表示后端本就没有该键
+        let prev_watermark = self.flush_watermark();
+        let lazy_base_ts = self.lazy_source.read().as_ref().map(|source| source.base_ts);
+        let mut lazy_tombstones: Vec<Vec<u8>> = Vec::new();
+
         // 获取最小活跃事务时间戳（低于此时间戳的版本已被所有活跃事务可见）
         let min_active_ts = self.get_min_active_ts();
 
-        // 遍历所有键，刷新已提交版本
+        // 遍历所有键，刷新切点处的已提交版本
         for entry in self.data.iter() {
             let key = entry.key();
+            if is_reserved_key(key) {
+                continue;
+            }
             let mut versions = entry.value().write();
 
-            // 筛选出可以安全刷新的版本:
-            // 1. 必须是已提交的版本 (value.is_some())
-            // 2. 如果有活跃事务，版本时间戳必须 < min_active_ts (所有活跃事务都能看到)
-            // 3. 保留最新的 keep_recent_versions 个版本在内存
-
-            if versions.is_empty() {
+            // 切点处可见的最新版本（墓碑同样需要刷盘，否则已删除的键重启后复活）
+            let Some(latest) = versions.iter().rev().find(|v| v.ts <= cut) else {
                 continue;
+            };
+            match &latest.value {
+                Some(value) => {
+                    batch.push((key.clone(), Some(value.clone())));
+                    flushed_bytes += key.len() + value.len();
+                    flushed_keys += 1;
+                }
+                None if latest.ts <= prev_watermark || Some(latest.ts) == lazy_base_ts => {
+                    if versions.len() == 1 && Some(latest.ts) == lazy_base_ts {
+                        lazy_tombstones.push(key.clone());
+                    }
+                }
+                None => {
+                    batch.push((key.clone(), None));
+                    flushed_bytes += key.len();
+                    flushed_keys += 1;
+                }
             }
 
-            // 找到最新的已提交版本
-            let latest_committed = versions.iter().rev().find(|v| v.value.is_some());
-
-            if let Some(latest) = latest_committed {
-                // 检查是否可以安全刷新
-                let can_flush = match min_active_ts {
-                    Some(min_ts) => latest.ts < min_ts,
-                    None => true, // 没有活跃事务，可以安全刷新
-                };
-
-                if can_flush {
-                    if let Some(ref value) = latest.value {
-                        batch.push((key.clone(), Some(value.clone())));
-                        flushed_keys += 1;
-                        flushed_bytes += key.len() + value.len();
-                    }
+            if let Some(upto) = archived_upto {
+                for v in versions.iter().filter(|v| v.ts > upto && v.ts <= cut) {
+                    batch.push((
+                        crate::archive::version_key(key, v.ts),
+                        Some(crate::archive::encode_version_value(v.value.as_deref())),
+                    ));
+                }
+            }
 
-                    // 清理旧版本，保留最新的 keep_recent_versions 个
-                    if versions.len() > keep_recent_versions {
-                        let keep_from = versions.len().saturating_sub(keep_recent_versions);
-                        versions.drain(..keep_from);
-                    }
+            // 清理旧版本，保留最新的 keep_recent_versions 个
+            // 仅当链尾对所有活跃事务可见时裁剪，避免活跃快照丢失所需版本
+            let can_prune = match (versions.last(), min_active_ts) {
+                (Some(tail), Some(min_ts)) => tail.ts < min_ts,
+                _ => true,
+            };
+            if can_prune && versions.len() > keep_recent_versions {
+                let mut keep_from = versions.len().saturating_sub(keep_recent_versions);
+                if let Some(upto) = archived_upto {
+                    keep_from = keep_from.min(versions.iter().take_while(|v| v.ts <= upto).count());
                 }
+                versions.drain(..keep_from);
             }
         }
 
+        let blocks: Vec<(u64, u64)> = if archived_upto.is_some() {
+            self.pending_blocks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, ts)| *ts <= cut)
+                .copied()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for (height, ts) in &blocks {
+            batch.push((crate::archive::block_key(*height), Some(ts.to_be_bytes().to_vec())));
+        }
+
+        batch.push((FLUSH_WATERMARK_KEY.to_vec(), Some(cut.to_be_bytes().to_vec())));
+
         // 尝试批量提交；若后端不支持则逐条写入
-        if !batch.is_empty() {
-            // 克隆一份用于尝试批量写入；避免所有权被消费后无法回退
-            let batch_for_attempt = batch.clone();
-            match storage.write_batch_if_supported(batch_for_attempt) {
-                Ok(true) => { /* 已批量处理 */ }
-                Ok(false) => {
-                    // 回退：逐条写入（MemoryStorage 不会走到这里；为未来后端保留路径）
-                    for (k, v_opt) in batch.into_iter() {
-                        match v_opt {
-                            Some(v) => storage.set(&k, &v).map_err(|e| format!("fallback set failed: {}", e))?,
-                            None => storage.delete(&k).map_err(|e| format!("fallback delete failed: {}", e))?,
-                        }
+        // 克隆一份用于尝试批量写入；避免所有权被消费后无法回退
+        let batch_for_attempt = batch.clone();
+        match storage.write_batch_if_supported(batch_for_attempt) {
+            Ok(true) => { /* 已批量处理 */ }
+            Ok(false) => {
+                // 回退：逐条写入，水位线位于批次末尾，中途失败时不会超前于数据
+                for (k, v_opt) in batch.into_iter() {
+                    match v_opt {
+                        Some(v) => storage.set(&k, &v).map_err(|e| format!("fallback set failed: {}", e))?,
+                        None => storage.delete(&k).map_err(|e| format!("fallback delete failed: {}", e))?,
                     }
                 }
-                Err(e) => return Err(format!("Failed to flush batch: {}", e)),
             }
+            Err(e) => return Err(format!("Failed to flush batch: {}", e)),
+        }
+        self.flush_watermark.fetch_max(cut, Ordering::SeqCst);
+        if let Some(base_ts) = lazy_base_ts {
+            self.reclaim_lazy_tombstones(lazy_tombstones, base_ts);
+        }
+        if !blocks.is_empty() {
+            self.pending_blocks.lock().unwrap().retain(|(_, ts)| *ts > cut);
+        }
+        if let Some(wal) = self.wal() {
+            wal.truncate(cut).map_err(|e| format!("Failed to truncate wal: {}", e))?;
         }
 
         Ok((flushed_keys, flushed_bytes))
     }
 
+    /// 最近一次刷盘的水位线（未刷盘时为 0）
+    pub fn flush_watermark(&self) -> u64 {
+        self.flush_watermark.load(Ordering::SeqCst)
+    }
+
+    /// [`Txn::with_ts`] 当前可接受的最小 commit_ts（最近一次刷盘切点 + 1）
+    ///
+    /// 切点之内的提交不会再被刷盘，预分配时间戳的调度器应从该值之后分配。
+    pub fn min_override_ts(&self) -> u64 {
+        self.flush_cut.load(Ordering::SeqCst) + 1
+    }
+
+    /// 读取持久化存储中的刷盘水位线（从未刷盘时为 None）
+    pub fn stored_flush_watermark(storage: &dyn crate::Storage) -> Result<Option<u64>, String> {
+        match storage
+            .get(FLUSH_WATERMARK_KEY)
+            .map_err(|e| format!("Failed to read flush watermark: {}", e))?
+        {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| format!("corrupted flush watermark: {} bytes", bytes.len()))?;
+                Ok(Some(u64::from_be_bytes(bytes)))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// 从持久化存储加载数据到 MVCC Store
     ///
     /// 使用场景: 启动时从 RocksDB 恢复状态
     ///
     /// 参数:
     /// - storage: 持久化存储后端
-    /// - keys: 要加载的键列表 (None 表示加载所有键)
+    /// - keys: 要加载的键列表 (None 表示全量扫描加载，见 [`MvccStore::load_prefixes_from_storage`])
+    ///
+    /// 加载前先恢复时间戳计数器（见 [`MvccStore::restore_ts_from_storage`]），
+    /// 加载的版本 ts 大于已有版本与持久化水位线，之后的提交总排在恢复版本之后。
     ///
     /// 返回: 加载的键数量
     pub fn load_from_storage(
@@ -787,43 +1128,20 @@ impl MvccStore {
         storage: &dyn crate::Storage,
         keys: Option<&[Vec<u8>]>,
     ) -> Result<usize, String> {
-        let mut loaded_keys = 0;
-        let load_ts = self.next_ts();
-
         let keys_to_load: Vec<Vec<u8>> = match keys {
             Some(k) => k.to_vec(),
-            None => {
-                // 如果没有指定键，扫描存储中的所有键
-                // 这里需要 Storage trait 支持前缀扫描
-                // 暂时返回错误，需要用户提供键列表
-                return Err("Loading all keys requires Storage::scan() support".to_string());
-            }
+            None => return self.load_prefixes_from_storage(storage, &[Vec::new()]),
         };
 
+        self.restore_ts_from_storage(storage)?;
+        let load_ts = self.next_ts();
+        let mut loaded_keys = 0;
         for key in keys_to_load {
             if let Some(value) = storage
                 .get(&key)
                 .map_err(|e| format!("Failed to load key from storage: {}", e))?
             {
-                // 创建新版本
-                let version = Version {
-                    ts: load_ts,
-                    value: Some(value),
-                };
-
-                // 插入或更新版本链
-                let entry = self
-                    .data
-                    .entry(key)
-                    .or_insert_with(|| RwLock::new({
-                        #[cfg(feature = "smallvec-chains")]
-                        { SmallVec::<[Version;4]>::new() }
-                        #[cfg(not(feature = "smallvec-chains"))]
-                        { Vec::new() }
-                    }));
-                let mut versions = entry.write();
-                versions.push(version);
-
+                self.push_loaded_version(key, load_ts, value);
                 loaded_keys += 1;
             }
         }
@@ -831,6 +1149,210 @@ impl MvccStore {
         Ok(loaded_keys)
     }
 
+    /// 按前缀全量加载（冷启动恢复）
+    ///
+    /// 加载的键值全部常驻内存，内存占用与所加载的数据量成正比；需要限制内存时改用
+    /// [`MvccStore::enable_lazy_load`]。每个前缀按下一字节拆成 256 个子前缀分块扫描，
+    /// 只是把单次扫描的临时结果限制在最大的子前缀块内（键集中在同一子前缀时仍接近全量）。
+    /// 保留键（MVCC 元数据与状态树节点，见 [`crate::state_commitment::is_reserved_key`]）不加载。
+    /// 空前缀即加载全部键。
+    ///
+    /// 返回: 加载的键数量
+    pub fn load_prefixes_from_storage(
+        &self,
+        storage: &dyn crate::Storage,
+        prefixes: &[Vec<u8>],
+    ) -> Result<usize, String> {
+        self.restore_ts_from_storage(storage)?;
+        let load_ts = self.next_ts();
+        let mut loaded_keys = 0;
+        for prefix in prefixes {
+            // 前缀本身作为键
+            if let Some(value) = storage
+                .get(prefix)
+                .map_err(|e| format!("Failed to load key from storage: {}", e))?
+            {
+                if !is_reserved_key(prefix) {
+                    self.push_loaded_version(prefix.clone(), load_ts, value);
+                    loaded_keys += 1;
+                }
+            }
+            let mut chunk_prefix = prefix.clone();
+            chunk_prefix.push(0);
+            for byte in 0..=u8::MAX {
+                *chunk_prefix.last_mut().unwrap() = byte;
+                let chunk = storage
+                    .scan(&chunk_prefix)
+                    .map_err(|e| format!("Failed to scan storage: {}", e))?;
+                for (key, value) in chunk {
+                    if is_reserved_key(&key) {
+                        continue;
+                    }
+                    self.push_loaded_version(key, load_ts, value);
+                    loaded_keys += 1;
+                }
+            }
+        }
+        Ok(loaded_keys)
+    }
+
+    /// 从持久化水位线恢复时间戳计数器与内存水位线，返回水位线（从未刷盘时为 None）
+    ///
+    /// 计数器只增不减：恢复后新分配的 ts 总大于水位线
+    pub fn restore_ts_from_storage(&self, storage: &dyn crate::Storage) -> Result<Option<u64>, String> {
+        let watermark = Self::stored_flush_watermark(storage)?;
+        if let Some(w) = watermark {
+            self.ts.fetch_max(w, Ordering::SeqCst);
+            self.flush_watermark.fetch_max(w, Ordering::SeqCst);
+            self.flush_cut.fetch_max(w, Ordering::SeqCst);
+        }
+        Ok(watermark)
+    }
+
+    fn push_loaded_version(&self, key: Vec<u8>, ts: u64, value: Vec<u8>) {
+        let entry = self.chain_entry(&key);
+        entry.write().push(Version { ts, value: Some(value) });
+    }
+
+    /// 启用按需加载：内存中没有的键在首次读取（含范围扫描）时从 `storage` 读入，不做全量加载
+    ///
+    /// 读入的值作为 ts = 启用时刻的基线版本插入版本链头部（不存在的键插入墓碑，避免重复回源），
+    /// 之后开始的事务均可见；启用前同样会恢复时间戳计数器。
+    /// 回源失败时读取返回错误（[`Txn::read`] 记录该错误并使事务提交失败），该键下次读取时重试。
+    ///
+    /// 读取不存在的键插入的墓碑在其后第一次刷盘时回收（未被改写过的键），
+    /// 因此常驻的墓碑数量以两次刷盘之间读取过的不同缺失键数为界。
+    pub fn enable_lazy_load(&self, storage: Arc<Mutex<dyn crate::Storage + Send>>) -> Result<(), String> {
+        let base_ts = {
+            let guard = storage.lock().map_err(|_| "lazy load storage lock poisoned".to_string())?;
+            self.restore_ts_from_storage(&*guard)?;
+            self.next_ts()
+        };
+        *self.lazy_source.write() = Some(LazySource { storage, base_ts });
+        self.lazy_enabled.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// 按需加载单个键（未启用或已加载时为空操作）
+    fn lazy_load_key(&self, key: &[u8]) -> Result<(), String> {
+        if !self.lazy_enabled.load(Ordering::Acquire) || self.lazy_loaded.contains_key(key) {
+            return Ok(());
+        }
+        let source = self.lazy_source.read();
+        let Some(source) = source.as_ref() else { return Ok(()) };
+        let value = match source.storage.lock() {
+            Ok(storage) => storage.get(key),
+            Err(_) => Err(anyhow::anyhow!("lazy load storage lock poisoned")),
+        };
+        let value = value.map_err(|e| {
+            format!("lazy load failed on key {:?}: {}", String::from_utf8_lossy(key), e)
+        })?;
+        self.install_lazy_version(key.to_vec(), source.base_ts, value);
+        Ok(())
+    }
+
+    /// 按需加载区间内的全部键（范围扫描前调用，保证未加载的持久化键也出现在结果中）
+    fn lazy_load_range(&self, range: &KeyRange) -> Result<(), String> {
+        if !self.lazy_enabled.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let source = self.lazy_source.read();
+        let Some(source) = source.as_ref() else { return Ok(()) };
+        // 以区间上下界的公共前缀扫描后按区间过滤
+        let common = match &range.end {
+            Some(end) => range.start.iter().zip(end).take_while(|(a, b)| a == b).count(),
+            None => 0,
+        };
+        let scanned = match source.storage.lock() {
+            Ok(storage) => storage.scan(&range.start[..common]),
+            Err(_) => Err(anyhow::anyhow!("lazy load storage lock poisoned")),
+        };
+        let entries = scanned.map_err(|e| format!("lazy load scan failed: {}", e))?;
+        for (key, value) in entries {
+            if range.contains(&key)
+                && !is_reserved_key(&key)
+                && !self.lazy_loaded.contains_key(&key)
+            {
+                self.install_lazy_version(key, source.base_ts, Some(value));
+            }
+        }
+        Ok(())
+    }
+
+    fn install_lazy_version(&self, key: Vec<u8>, base_ts: u64, value: Option<Vec<u8>>) {
+        let entry = self.chain_entry(&key);
+        let mut versions = entry.write();
+        // 已有不晚于基线的版本（全量加载过或已回源）时以内存为准；否则基线版本排在所有新提交之前
+        if self.lazy_loaded.insert(key, ()).is_none() && versions.first().is_none_or(|v| v.ts > base_ts) {
+            versions.insert(0, Version { ts: base_ts, value });
+        }
+    }
+
+    // ===== 提交日志相关方法 =====
+
+    /// 挂接提交日志：之后的写事务提交前先追加日志
+    ///
+    /// 启动顺序: [`MvccStore::replay_wal`] → `load_from_storage` / `enable_lazy_load` → `attach_wal`
+    pub fn attach_wal(&self, wal: Arc<CommitLog>) {
+        *self.wal.write() = Some(wal);
+    }
+
+    /// 当前挂接的提交日志
+    pub fn wal(&self) -> Option<Arc<CommitLog>> {
+        self.wal.read().clone()
+    }
+
+    /// 把日志目录中水位线之后的提交按 commit_ts 顺序写回持久化存储，并推进水位线
+    ///
+    /// 返回重放的记录数。重放后存储即为崩溃前最后一次确认提交时的状态：
+    /// 归档模式下的提交同时写回版本化键，区块水位线标记写回区块索引。
+    /// 旧日志段在下一次刷盘时随水位线截断。
+    pub fn replay_wal(dir: impl AsRef<std::path::Path>, storage: &mut dyn crate::Storage) -> Result<usize, String> {
+        let watermark = Self::stored_flush_watermark(storage)?.unwrap_or(0);
+        let mut records: Vec<WalRecord> = CommitLog::replay(dir)
+            .map_err(|e| format!("Failed to read wal: {}", e))?
+            .into_iter()
+            .filter(|r| r.commit_ts > watermark)
+            .collect();
+        if records.is_empty() {
+            return Ok(0);
+        }
+        records.sort_by_key(|r| r.commit_ts);
+        let new_watermark = records.last().map_or(watermark, |r| r.commit_ts);
+
+        let mut latest: std::collections::BTreeMap<Vec<u8>, Option<Vec<u8>>> = std::collections::BTreeMap::new();
+        for record in &records {
+            if let Some(height) = record.block_height {
+                latest.insert(crate::archive::block_key(height), Some(record.commit_ts.to_be_bytes().to_vec()));
+                continue;
+            }
+            for (key, value) in &record.writes {
+                if record.archived {
+                    latest.insert(
+                        crate::archive::version_key(key, record.commit_ts),
+                        Some(crate::archive::encode_version_value(value.as_deref())),
+                    );
+                }
+                latest.insert(key.clone(), value.clone());
+            }
+        }
+        let mut batch: Vec<(Vec<u8>, Option<Vec<u8>>)> = latest.into_iter().collect();
+        batch.push((FLUSH_WATERMARK_KEY.to_vec(), Some(new_watermark.to_be_bytes().to_vec())));
+        if !storage
+            .write_batch_if_supported(batch.clone())
+            .map_err(|e| format!("Failed to replay wal: {}", e))?
+        {
+            for (k, v_opt) in batch {
+                match v_opt {
+                    Some(v) => storage.set(&k, &v),
+                    None => storage.delete(&k),
+                }
+                .map_err(|e| format!("Failed to replay wal: {}", e))?;
+            }
+        }
+        Ok(records.len())
+    }
+
     // ===== 自动刷新相关方法 =====
 
     /// 启动自动刷新后台线程
@@ -841,6 +1363,25 @@ impl MvccStore {
         self: &Arc<Self>,
         config: AutoFlushConfig,
         storage: Arc<Mutex<dyn crate::Storage + Send>>,
+    ) -> Result<(), String> {
+        self.spawn_auto_flush(config, FlushTarget::Locked(storage))
+    }
+
+    /// 启动自动刷新后台线程（共享存储句柄）
+    ///
+    /// 句柄内部可变，刷盘不再持有外层锁，同一存储可同时供运行时与服务读写
+    pub fn start_auto_flush_shared(
+        self: &Arc<Self>,
+        config: AutoFlushConfig,
+        storage: crate::StorageHandle,
+    ) -> Result<(), String> {
+        self.spawn_auto_flush(config, FlushTarget::Shared(storage))
+    }
+
+    fn spawn_auto_flush(
+        self: &Arc<Self>,
+        config: AutoFlushConfig,
+        storage: FlushTarget,
     ) -> Result<(), String> {
         // 检查是否已经在运行
         if self.auto_flush_running.load(Ordering::SeqCst) {
@@ -867,21 +1408,17 @@ impl MvccStore {
     fn auto_flush_thread(
         store: Arc<Self>,
         config: AutoFlushConfig,
-        storage: Arc<Mutex<dyn crate::Storage + Send>>,
+        storage: FlushTarget,
     ) {
         // 启动时刷新（如果配置了）
         if config.flush_on_start {
-            if let Ok(mut storage_guard) = storage.lock() {
-                if let Ok((keys, bytes)) =
-                    store.flush_to_storage(&mut *storage_guard, config.keep_recent_versions)
-                {
-                    let mut stats = store.flush_stats.lock().unwrap();
-                    stats.flush_count += 1;
-                    stats.keys_flushed += keys as u64;
-                    stats.bytes_flushed += bytes;
-                    stats.last_flush_ts = store.ts.load(Ordering::Relaxed);
-                    stats.last_flush_block = store.current_block.load(Ordering::Relaxed);
-                }
+            if let Some(Ok((keys, bytes))) = storage.flush(&store, config.keep_recent_versions) {
+                let mut stats = store.flush_stats.lock().unwrap();
+                stats.flush_count += 1;
+                stats.keys_flushed += keys as u64;
+                stats.bytes_flushed += bytes;
+                stats.last_flush_ts = store.ts.load(Ordering::Relaxed);
+                stats.last_flush_block = store.current_block.load(Ordering::Relaxed);
             }
         }
 
@@ -912,8 +1449,8 @@ impl MvccStore {
 
             // 执行刷新
             if should_flush {
-                if let Ok(mut storage_guard) = storage.lock() {
-                    match store.flush_to_storage(&mut *storage_guard, config.keep_recent_versions) {
+                if let Some(result) = storage.flush(&store, config.keep_recent_versions) {
+                    match result {
                         Ok((keys, bytes)) => {
                             let mut stats = store.flush_stats.lock().unwrap();
                             stats.flush_count += 1;
@@ -955,6 +1492,43 @@ impl MvccStore {
         self.current_block.store(block, Ordering::Relaxed);
     }
 
+    /// 开启 / 关闭归档模式（见 [`crate::archive`]）
+    ///
+    /// 开启前已刷盘的版本不会补写归档；应在写入任何状态之前开启。
+    pub fn set_archive_mode(&self, enabled: bool) {
+        self.archive_mode.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_archive_mode(&self) -> bool {
+        self.archive_mode.load(Ordering::SeqCst)
+    }
+
+    /// 区块 `height` 执行完毕：返回此刻的提交时间戳水位线
+    ///
+    /// 归档模式下记录待下次刷盘持久化；挂接了提交日志时同时追加水位线标记，
+    /// 崩溃后由 [`MvccStore::replay_wal`] 恢复。
+    pub fn record_block(&self, height: u64) -> Result<u64, String> {
+        // 与刷盘切点相同：持有谓词写锁时 ts 不超过水位线的提交均已写入版本链
+        let ts = {
+            let _predicate_guard = self.predicate_lock.write();
+            self.ts.load(Ordering::SeqCst)
+        };
+        if self.is_archive_mode() {
+            if let Some(wal) = self.wal() {
+                wal.append(&WalRecord::block(height, ts))
+                    .map_err(|e| format!("wal append failed: {}", e))?;
+            }
+            self.pending_blocks.lock().unwrap().push((height, ts));
+        }
+        Ok(ts)
+    }
+
+    /// 归档模式下已写入归档的版本上界（即刷盘水位线），非归档模式为 None
+    fn archived_upto(&self) -> Option<u64> {
+        self.is_archive_mode()
+            .then(|| self.flush_watermark.load(Ordering::SeqCst))
+    }
+
     /// 获取当前区块号
     pub fn get_current_block(&self) -> u64 {
         self.current_block.load(Ordering::Relaxed)
@@ -982,15 +1556,7 @@ impl MvccStore {
     /// 直接附加一个版本到指定 key 的版本链 (需在上层确保并发安全). 仅用于 2PC 原型的 commit 阶段。
     #[allow(dead_code)]
     pub(crate) fn append_version(&self, key: &[u8], commit_ts: u64, value: Option<Vec<u8>>) {
-        let entry = self
-            .data
-            .entry(key.to_vec())
-            .or_insert_with(|| RwLock::new({
-                #[cfg(feature = "smallvec-chains")]
-                { SmallVec::<[Version;4]>::new() }
-                #[cfg(not(feature = "smallvec-chains"))]
-                { Vec::new() }
-            }));
+        let entry = self.chain_entry(key);
         let mut versions = entry.value().write();
         versions.push(Version { ts: commit_ts, value });
     }
@@ -1062,21 +1628,34 @@ pub struct Txn {
     store: Arc<MvccStore>,
     pub start_ts: u64,
     writes: HashMap<Vec<u8>, Option<Vec<u8>>>,
-    /// 读集合：记录所有读取过的键，用于检测 read-write conflict
+    /// 读集合：记录所有读取过的键，可串行化事务提交时据此检测读写反依赖
     reads: std::collections::HashSet<Vec<u8>>,
     committed: bool,
     read_only: bool,
     /// 外部分配的 commit_ts (多核调度器使用); 如果为 Some 则提交时不再调用 next_ts()
     override_commit_ts: Option<u64>,
+    isolation: IsolationLevel,
+    /// 范围读集合：可串行化事务提交时检测区间内的幻读
+    range_reads: Vec<KeyRange>,
+    /// 读取时按需加载失败的错误：读取按缺失返回，提交时以此错误中止
+    load_error: Option<String>,
 }
 
 impl Txn {
-    /// 由调度器传入预分配的 commit_ts
+    /// 由调度器传入预分配的 commit_ts（须不小于 [`MvccStore::min_override_ts`]，否则提交被拒绝）
     pub fn with_ts(mut self, ts: u64) -> Self { self.override_commit_ts = Some(ts); self }
+    /// 覆盖本事务的隔离级别（默认取自 [`MvccStore::isolation_level`]）
+    pub fn with_isolation(mut self, level: IsolationLevel) -> Self { self.isolation = level; self }
+    /// 本事务的隔离级别
+    pub fn isolation(&self) -> IsolationLevel { self.isolation }
     /// 获取写集合引用 (仅用于调度/路由，不得在外部直接修改)
     pub fn writes(&self) -> &HashMap<Vec<u8>, Option<Vec<u8>>> { &self.writes }
     /// 获取读集合引用 (仅用于 2PC prepare 阶段校验，不得在外部直接修改)
     pub fn reads(&self) -> &std::collections::HashSet<Vec<u8>> { &self.reads }
+    /// 获取范围读集合（scan_prefix / scan_range 扫描过的区间）
+    pub fn range_reads(&self) -> &[KeyRange] { &self.range_reads }
+    /// 所属的 MVCC 存储（快照读覆盖层等外部执行器使用）
+    pub fn store(&self) -> &Arc<MvccStore> { &self.store }
     /// 暂存访问内部指标收集器（便于多核/2PC 原型记录延迟）。返回 None 如果未启用。
     pub fn metrics(&self) -> Option<Arc<MetricsCollector>> { self.store.get_metrics() }
     /// 获取指定 key 的最新提交版本时间戳 (tail_ts)，用于读集合校验。若 key 不存在或无版本返回 0。
@@ -1097,17 +1676,70 @@ impl Txn {
         set
     }
     /// 读取在 start_ts 及以前可见的值
+    ///
+    /// 按需加载回源失败时返回 None 并记录错误，之后的提交以该错误中止；
+    /// 需要就地处理错误时使用 [`Txn::try_read`]
     pub fn read(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.try_read(key) {
+            Ok(value) => value,
+            Err(e) => {
+                self.load_error.get_or_insert(e);
+                None
+            }
+        }
+    }
+
+    /// 读取在 start_ts 及以前可见的值，按需加载回源失败时返回错误
+    pub fn try_read(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
         // 记录到读集合（用于后续冲突检测）
         self.reads.insert(key.to_vec());
 
         // 优先返回当前事务未提交的写（写读自己）
         if let Some(v) = self.writes.get(key) {
-            return v.clone();
+            return Ok(v.clone());
         }
         self.store.read_at(key, self.start_ts)
     }
 
+    /// 前缀扫描：start_ts 快照叠加本事务缓冲写入，按键排序（不含已删除的键）
+    ///
+    /// 扫描区间计入范围读集合，可串行化事务提交时据此检测幻读
+    pub fn scan_prefix(&mut self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.scan(KeyRange::prefix(prefix))
+    }
+
+    /// 区间扫描 `[start, end)`，语义同 [`Txn::scan_prefix`]
+    pub fn scan_range(&mut self, start: &[u8], end: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        if start >= end {
+            return Vec::new();
+        }
+        self.scan(KeyRange::new(start, Some(end)))
+    }
+
+    fn scan(&mut self, range: KeyRange) -> Vec<(Vec<u8>, Vec<u8>)> {
+        // 回源失败同 [`Txn::read`]：记录错误，提交时中止
+        let base = self.store.scan_range_at(&range, self.start_ts).unwrap_or_else(|e| {
+            self.load_error.get_or_insert(e);
+            Vec::new()
+        });
+        let mut merged: std::collections::BTreeMap<Vec<u8>, Vec<u8>> = base.into_iter().collect();
+        for (key, value) in self.writes.iter().filter(|(k, _)| range.contains(k)) {
+            match value {
+                Some(v) => merged.insert(key.clone(), v.clone()),
+                None => merged.remove(key),
+            };
+        }
+        self.track_range_read(range);
+        merged.into_iter().collect()
+    }
+
+    /// 记录一次范围读（外部执行器自行完成扫描时回灌）
+    pub(crate) fn track_range_read(&mut self, range: KeyRange) {
+        if !self.range_reads.contains(&range) {
+            self.range_reads.push(range);
+        }
+    }
+
     /// 写入（缓存在本地事务中）
     ///
     /// 注意：只读事务调用此方法会 panic
@@ -1136,6 +1768,8 @@ impl Txn {
     /// 提交：
     /// - 分配 commit_ts
     /// - 对每个写入键做写写冲突检测：如果存在 ts > start_ts 的已提交版本，则冲突
+    /// - 校验读集合：读过的键存在 ts > start_ts 的已提交版本则冲突（读键不加锁，
+    ///   `consensus-optimizations` 下不校验）；可串行化事务的原子校验见 [`IsolationLevel::Serializable`]
     /// - 无冲突则将本地写入附加为新版本
     ///
     /// 优化点：
@@ -1157,6 +1791,14 @@ impl Txn {
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         }
 
+        // 读取时回源失败：读到的缺失不可信，整个事务失败
+        if let Some(err) = self.load_error.take() {
+            if let Some(ref metrics) = self.store.metrics {
+                metrics.txn_aborted.fetch_add(1, Ordering::Relaxed);
+            }
+            return Err(err);
+        }
+
         // 只读事务快速路径：直接返回 start_ts，无需任何操作
         if self.read_only {
             self.committed = true;
@@ -1176,6 +1818,10 @@ impl Txn {
             return Ok(self.start_ts);
         }
 
+        if self.isolation == IsolationLevel::Serializable {
+            return self.commit_serializable(start_time);
+        }
+
         // 细粒度提交锁方案：对写集合内的键按序加锁，避免写写冲突并允许不同键集并行提交
         // 1) 按键排序（字节序）
         // 2) 为每个键获取/创建独立互斥锁，并按序加锁，避免死锁
@@ -1217,12 +1863,13 @@ impl Txn {
 
         #[cfg(not(feature = "consensus-optimizations"))]
         {
-            // 原始策略：全部加锁持有直至提交完成
+            // 原始策略：全部加锁持有直至提交完成（先谓词锁、后每键锁，与其他提交路径同序）
+            let _predicate_guard = self.store.predicate_lock.read();
             let mut _guards = Vec::with_capacity(key_mutexes.len());
             for m in &key_mutexes { _guards.push(m.lock().unwrap()); }
 
             // 为本次提交分配提交时间戳（在持锁之后分配，保证随后的检测与写入一致）
-            let commit_ts = self.override_commit_ts.unwrap_or_else(|| self.store.next_ts());
+            let commit_ts = self.allocate_commit_ts()?;
 
             // ===== 阶段0/1 冲突检测（保持原实现） =====
             for key in &self.reads {
@@ -1243,18 +1890,10 @@ impl Txn {
                     }
                 }
             }
+            self.log_commit(commit_ts)?;
             // 阶段2：写入
             for key in &sorted_keys {
-                let entry = self
-                    .store
-                    .data
-                    .entry(key.clone())
-                    .or_insert_with(|| RwLock::new({
-                        #[cfg(feature = "smallvec-chains")]
-                        { SmallVec::<[Version;4]>::new() }
-                        #[cfg(not(feature = "smallvec-chains"))]
-                        { Vec::new() }
-                    }));
+                let entry = self.store.chain_entry(key);
                 let mut versions = entry.value().write();
                 let value = self.writes.get(key).unwrap().clone();
                 versions.push(Version { ts: commit_ts, value });
@@ -1267,39 +1906,33 @@ impl Txn {
 
         #[cfg(feature = "consensus-optimizations")]
         {
-            // 改进策略:逐键加锁 -> 冲突再检 -> 写入 -> 立即释放锁
-            // 减少锁持有时间,降低并发提交争用。
-            let commit_ts = self.override_commit_ts.unwrap_or_else(|| self.store.next_ts());
-
-            for (idx, key_mutex) in key_mutexes.iter().enumerate() {
-                let _guard = key_mutex.lock().unwrap();
-                let key = &sorted_keys[idx];
-
-                // 单次写锁内完成尾部冲突检查与写入，避免一次额外的读锁
-                let entry = self
-                    .store
-                    .data
-                    .entry(key.clone())
-                    .or_insert_with(|| RwLock::new({
-                        #[cfg(feature = "smallvec-chains")]
-                        { SmallVec::<[Version;4]>::new() }
-                        #[cfg(not(feature = "smallvec-chains"))]
-                        { Vec::new() }
-                    }));
-                let mut versions = entry.value().write();
-                if versions.last().is_some_and(|v| v.ts > self.start_ts) {
-                    if let Some(ref metrics) = self.store.metrics { metrics.txn_aborted.fetch_add(1, Ordering::Relaxed); }
-                    return Err(format!("late write-write conflict on key {:?}", String::from_utf8_lossy(key)));
+            // 改进策略:全部加锁 -> 冲突再检 -> 落日志 -> 逐键写入并立即释放该键锁
+            // 缩短写入阶段的锁持有时间；冲突检测在任何写入之前完成，不会留下部分写入。
+            let _predicate_guard = self.store.predicate_lock.read();
+            let mut guards: Vec<Option<_>> = key_mutexes.iter().map(|m| Some(m.lock().unwrap())).collect();
+            for key in &sorted_keys {
+                if let Some(entry) = self.store.data.get(key) {
+                    if entry.value().read().last().is_some_and(|v| v.ts > self.start_ts) {
+                        if let Some(ref metrics) = self.store.metrics { metrics.txn_aborted.fetch_add(1, Ordering::Relaxed); }
+                        return Err(format!("late write-write conflict on key {:?}", String::from_utf8_lossy(key)));
+                    }
                 }
+            }
+
+            // 需要严格 Write Skew 防护的事务使用 IsolationLevel::Serializable；这里不再扫描 reads 以减少开销。
+            let commit_ts = self.allocate_commit_ts()?;
+            // 日志先于可见：追加失败时尚未写入任何版本
+            self.log_commit(commit_ts)?;
+
+            for (idx, key) in sorted_keys.iter().enumerate() {
+                let entry = self.store.chain_entry(key);
                 // 写入(仅最终值) —— 写集合内必然存在 key, 直接 unwrap 可避免额外分支
                 let value_opt = self.writes.get(key).unwrap().clone();
-                versions.push(Version { ts: commit_ts, value: value_opt });
-                // 锁在此循环迭代结束后释放
+                entry.value().write().push(Version { ts: commit_ts, value: value_opt });
+                drop(entry);
+                guards[idx].take();
             }
 
-            // 读写冲突：单独键锁策略下需在写后做最小校验 (此处简化：写阶段已保证链尾未越界，读集合冲突概率极低，可选再次扫描)
-            // 若需要严格 Write Skew 防护，可在预检阶段已覆盖；这里不再重复扫描 reads 以减少开销。
-
             self.committed = true;
             self.store.recent_tx_count.fetch_add(1, Ordering::Relaxed);
             if let Some(ref metrics) = self.store.metrics { metrics.txn_committed.fetch_add(1, Ordering::Relaxed); metrics.txn_latency.observe(start_time.elapsed()); metrics.tps_window(); }
@@ -1307,6 +1940,37 @@ impl Txn {
         }
     }
 
+    /// 分配本次提交的 commit_ts（须持谓词锁调用，与刷盘取切点互斥）
+    ///
+    /// 预分配的 ts 不大于已取的刷盘切点时拒绝：该提交赶不上本次刷盘，日志又会按切点截断。
+    fn allocate_commit_ts(&self) -> Result<u64, String> {
+        let Some(ts) = self.override_commit_ts else { return Ok(self.store.next_ts()) };
+        let min_ts = self.store.min_override_ts();
+        if ts < min_ts {
+            if let Some(ref metrics) = self.store.metrics { metrics.txn_aborted.fetch_add(1, Ordering::Relaxed); }
+            return Err(format!("commit_ts {} is not above flush cut {}", ts, min_ts - 1));
+        }
+        Ok(ts)
+    }
+
+    /// 提交日志：在写入版本链之前追加本事务写集合（未挂接日志时为空操作）
+    fn log_commit(&self, commit_ts: u64) -> Result<(), String> {
+        let Some(wal) = self.store.wal() else { return Ok(()) };
+        let mut writes: Vec<(Vec<u8>, Option<Vec<u8>>)> =
+            self.writes.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        writes.sort_by(|a, b| a.0.cmp(&b.0));
+        let record = WalRecord {
+            commit_ts,
+            writes,
+            archived: self.store.is_archive_mode(),
+            block_height: None,
+        };
+        wal.append(&record).map_err(|e| {
+            if let Some(ref metrics) = self.store.metrics { metrics.txn_aborted.fetch_add(1, Ordering::Relaxed); }
+            format!("wal append failed: {}", e)
+        })
+    }
+
     /// 放弃事务（丢弃本地写集合）
     pub fn abort(self) {
         // 记录中止
@@ -1350,6 +2014,14 @@ impl Txn {
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         }
 
+        // 读取时回源失败：读到的缺失不可信，整个事务失败
+        if let Some(err) = self.load_error.take() {
+            if let Some(ref metrics) = self.store.metrics {
+                metrics.txn_aborted.fetch_add(1, Ordering::Relaxed);
+            }
+            return Err(err);
+        }
+
         // 只读事务快速路径
         if self.read_only {
             self.committed = true;
@@ -1367,6 +2039,10 @@ impl Txn {
             return Ok(self.start_ts);
         }
 
+        if self.isolation == IsolationLevel::Serializable {
+            return self.commit_serializable(start_time);
+        }
+
         // 标记有提交进行中，阻止新 begin
         self.store.commit_in_progress.fetch_add(1, Ordering::SeqCst);
 
@@ -1385,7 +2061,8 @@ impl Txn {
                 .clone();
             lock_arcs.push(arc);
         }
-        // 然后获取所有锁的 guard，保持到提交结束
+        // 然后获取所有锁的 guard，保持到提交结束（先谓词锁、后每键锁，与其他提交路径同序）
+        let _predicate_guard = self.store.predicate_lock.read();
         let mut _key_guards: Vec<std::sync::MutexGuard<'_, ()>> =
             Vec::with_capacity(lock_arcs.len());
         for arc in &lock_arcs {
@@ -1433,17 +2110,12 @@ impl Txn {
 
         // 分配提交 ts 并写入
         let commit_ts = self.store.next_ts();
+        if let Err(e) = self.log_commit(commit_ts) {
+            self.store.commit_in_progress.fetch_sub(1, Ordering::SeqCst);
+            return Err(e);
+        }
         for key in &sorted_keys {
-            let entry = self
-                .store
-                .data
-                .entry(key.clone())
-                .or_insert_with(|| RwLock::new({
-                    #[cfg(feature = "smallvec-chains")]
-                    { SmallVec::<[Version;4]>::new() }
-                    #[cfg(not(feature = "smallvec-chains"))]
-                    { Vec::new() }
-                }));
+            let entry = self.store.chain_entry(key);
             let mut versions = entry.value().write();
             let value = self.writes.get(key).unwrap().clone();
             versions.push(Version {
@@ -1468,6 +2140,115 @@ impl Txn {
         self.store.commit_in_progress.fetch_sub(1, Ordering::SeqCst);
         Ok(commit_ts)
     }
+
+    /// 可串行化提交（乐观并发控制的后向校验）：
+    /// - 对读集合 ∪ 写集合按序加每键锁并持有至写入完成，校验与写入对其他提交原子
+    /// - 读集合中任一键存在 ts > start_ts 的已提交版本 → 读写反依赖，中止
+    /// - 写集合照常做写写冲突检测
+    /// - 范围读集合内任一键（含新插入与删除）存在 ts > start_ts 的版本 → 幻读，中止；
+    ///   此时持有谓词写锁，校验期间没有其他事务处于分配 commit_ts 到写入完成之间
+    ///
+    /// 通过校验的事务其读取在 commit_ts 前一刻仍然有效，因此等价于按 commit_ts 串行执行。
+    /// 提交期间同样设置提交屏障，避免新事务看到部分写入。
+    fn commit_serializable(mut self, start_time: Instant) -> Result<u64, String> {
+        self.store.commit_in_progress.fetch_add(1, Ordering::SeqCst);
+
+        // 全局加锁顺序：谓词锁 → 按键排序的每键锁（与其他提交路径一致，避免死锁）
+        let (_predicate_read, _predicate_write) = if self.range_reads.is_empty() {
+            (Some(self.store.predicate_lock.read()), None)
+        } else {
+            (None, Some(self.store.predicate_lock.write()))
+        };
+        let mut lock_keys: Vec<&Vec<u8>> = self.reads.iter().chain(self.writes.keys()).collect();
+        lock_keys.sort();
+        lock_keys.dedup();
+        let lock_arcs: Vec<Arc<Mutex<()>>> = lock_keys
+            .iter()
+            .map(|k| {
+                self.store
+                    .key_locks
+                    .entry((*k).clone())
+                    .or_insert_with(|| Arc::new(Mutex::new(())))
+                    .clone()
+            })
+            .collect();
+        let _key_guards: Vec<std::sync::MutexGuard<'_, ()>> =
+            lock_arcs.iter().map(|m| m.lock().unwrap()).collect();
+
+        let changed_since_start = |key: &[u8]| {
+            self.store
+                .data
+                .get(key)
+                .is_some_and(|entry| entry.value().read().last().is_some_and(|v| v.ts > self.start_ts))
+        };
+        let mut read_keys: Vec<&Vec<u8>> = self.reads.iter().collect();
+        read_keys.sort();
+        let mut write_keys: Vec<&Vec<u8>> = self.writes.keys().collect();
+        write_keys.sort();
+        let conflict = if let Some(key) = write_keys.iter().find(|k| changed_since_start(k)) {
+            Some(format!("write-write conflict on key {:?}", String::from_utf8_lossy(key)))
+        } else {
+            read_keys
+                .iter()
+                .find(|k| changed_since_start(k))
+                .map(|key| {
+                    format!(
+                        "{}: read-write antidependency on key {:?}",
+                        SERIALIZATION_FAILURE,
+                        String::from_utf8_lossy(key)
+                    )
+                })
+                .or_else(|| {
+                    self.range_reads.iter().find_map(|range| {
+                        self.store
+                            .keys_in_range(range)
+                            .into_iter()
+                            .find(|key| changed_since_start(key))
+                            .map(|key| {
+                                format!(
+                                    "{}: phantom on key {:?} in scanned range",
+                                    SERIALIZATION_FAILURE,
+                                    String::from_utf8_lossy(&key)
+                                )
+                            })
+                    })
+                })
+        };
+        if let Some(err) = conflict {
+            if let Some(ref metrics) = self.store.metrics {
+                metrics.txn_aborted.fetch_add(1, Ordering::Relaxed);
+            }
+            self.store.commit_in_progress.fetch_sub(1, Ordering::SeqCst);
+            return Err(err);
+        }
+
+        let commit_ts = match self.allocate_commit_ts() {
+            Ok(ts) => ts,
+            Err(e) => {
+                self.store.commit_in_progress.fetch_sub(1, Ordering::SeqCst);
+                return Err(e);
+            }
+        };
+        if let Err(e) = self.log_commit(commit_ts) {
+            self.store.commit_in_progress.fetch_sub(1, Ordering::SeqCst);
+            return Err(e);
+        }
+        for key in write_keys {
+            let entry = self.store.chain_entry(key);
+            let value = self.writes.get(key).unwrap().clone();
+            entry.value().write().push(Version { ts: commit_ts, value });
+        }
+
+        self.committed = true;
+        self.store.recent_tx_count.fetch_add(1, Ordering::Relaxed);
+        if let Some(ref metrics) = self.store.metrics {
+            metrics.txn_committed.fetch_add(1, Ordering::Relaxed);
+            metrics.txn_latency.observe(start_time.elapsed());
+            metrics.tps_window();
+        }
+        self.store.commit_in_progress.fetch_sub(1, Ordering::SeqCst);
+        Ok(commit_ts)
+    }
 }
 
 /// 在事务结束时自动注销活跃事务
@@ -1550,7 +2331,7 @@ mod tests {
         assert_eq!(t4.read(b"k").as_deref(), Some(b"v1".as_ref()));
 
         // 直接用读接口校验不同时间点的可见性
-        assert_eq!(store.read_at(b"k", ts0).as_deref(), Some(b"v0".as_ref()));
+        assert_eq!(store.read_at(b"k", ts0).unwrap().as_deref(), Some(b"v0".as_ref()));
     }
 
     #[test]
@@ -1573,9 +2354,9 @@ mod tests {
         let ts3 = t3.commit().unwrap();
 
         // 不同快照读取
-        assert_eq!(store.read_at(b"k", ts1).as_deref(), Some(b"v1".as_ref()));
-        assert_eq!(store.read_at(b"k", ts2).as_deref(), Some(b"v2".as_ref()));
-        assert_eq!(store.read_at(b"k", ts3), None);
+        assert_eq!(store.read_at(b"k", ts1).unwrap().as_deref(), Some(b"v1".as_ref()));
+        assert_eq!(store.read_at(b"k", ts2).unwrap().as_deref(), Some(b"v2".as_ref()));
+        assert_eq!(store.read_at(b"k", ts3).unwrap(), None);
     }
 
     #[test]
@@ -1683,6 +2464,607 @@ mod tests {
         assert_eq!(success_count + conflict_count, 4);
     }
 
+    #[test]
+    fn test_serializable_prevents_write_skew() {
+        let store = MvccStore::new();
+        let mut init = store.begin();
+        init.write(b"x".to_vec(), b"1".to_vec());
+        init.write(b"y".to_vec(), b"1".to_vec());
+        init.commit().unwrap();
+
+        // 快照隔离：先后提交时读集合校验即可拒绝 write skew，报告为读写冲突
+        let mut t1 = store.begin();
+        let mut t2 = store.begin();
+        for t in [&mut t1, &mut t2] {
+            t.read(b"x");
+            t.read(b"y");
+        }
+        t1.write(b"x".to_vec(), b"0".to_vec());
+        t2.write(b"y".to_vec(), b"0".to_vec());
+        t1.commit_parallel().unwrap();
+        let err = t2.commit_parallel().expect_err("read set validated on commit");
+        assert_eq!(ConflictKind::of(&err), Some(ConflictKind::ReadWrite));
+
+        // 可串行化：后提交者因读写反依赖中止，且与写写冲突可区分
+        let mut t1 = store.begin().with_isolation(IsolationLevel::Serializable);
+        let mut t2 = store.begin().with_isolation(IsolationLevel::Serializable);
+        for t in [&mut t1, &mut t2] {
+            t.read(b"x");
+            t.read(b"y");
+        }
+        t1.write(b"x".to_vec(), b"1".to_vec());
+        t2.write(b"y".to_vec(), b"1".to_vec());
+        t1.commit().unwrap();
+        let err = t2.commit().expect_err("write skew must abort");
+        assert_eq!(ConflictKind::of(&err), Some(ConflictKind::Serialization));
+
+        // 写写冲突在可串行化模式下仍报告为写写冲突
+        store.set_isolation_level(IsolationLevel::Serializable);
+        let mut t3 = store.begin();
+        let mut t4 = store.begin();
+        assert_eq!(t4.isolation(), IsolationLevel::Serializable);
+        t3.write(b"x".to_vec(), b"2".to_vec());
+        t4.write(b"x".to_vec(), b"3".to_vec());
+        t3.commit().unwrap();
+        let err = t4.commit_parallel().expect_err("write-write conflict");
+        assert_eq!(ConflictKind::of(&err), Some(ConflictKind::WriteWrite));
+
+        // 未读取被改动键的可串行化事务不受影响
+        let mut t5 = store.begin();
+        let mut t6 = store.begin();
+        t5.read(b"x");
+        t5.write(b"x".to_vec(), b"4".to_vec());
+        t6.read(b"y");
+        t6.write(b"y".to_vec(), b"2".to_vec());
+        t5.commit().unwrap();
+        t6.commit().unwrap();
+    }
+
+    #[test]
+    fn test_flush_persists_deletes_and_watermark() {
+        use crate::storage::{MemoryStorage, Storage};
+
+        let store = MvccStore::new();
+        let mut storage = MemoryStorage::new();
+        assert_eq!(MvccStore::stored_flush_watermark(&storage).unwrap(), None);
+
+        let mut t = store.begin();
+        t.write(b"a".to_vec(), b"1".to_vec());
+        t.write(b"b".to_vec(), b"2".to_vec());
+        t.commit().unwrap();
+        store.flush_to_storage(&mut storage, 3).unwrap();
+        let first = store.flush_watermark();
+        assert!(first > 0);
+
+        // 删除后再写入新值不影响墓碑刷盘；活跃事务不阻止切点之前的提交落盘
+        let reader = store.begin();
+        let mut t = store.begin();
+        t.delete(b"a".to_vec());
+        t.write(b"c".to_vec(), b"3".to_vec());
+        t.commit().unwrap();
+        let (keys, _) = store.flush_to_storage(&mut storage, 3).unwrap();
+        assert_eq!(keys, 3);
+        drop(reader);
+
+        assert_eq!(
+            storage.scan(b"").unwrap(),
+            vec![
+                (b"__mvcc:flush_watermark".to_vec(), store.flush_watermark().to_be_bytes().to_vec()),
+                (b"b".to_vec(), b"2".to_vec()),
+                (b"c".to_vec(), b"3".to_vec()),
+            ]
+        );
+        assert!(store.flush_watermark() > first);
+        assert_eq!(
+            MvccStore::stored_flush_watermark(&storage).unwrap(),
+            Some(store.flush_watermark())
+        );
+    }
+
+    #[test]
+    fn test_flush_skips_persisted_tombstones() {
+        use crate::storage::{MemoryStorage, Storage};
+
+        let mut backend = MemoryStorage::new();
+        backend.set(b"a", b"1").unwrap();
+        let storage: Arc<Mutex<dyn crate::Storage + Send>> = Arc::new(Mutex::new(backend));
+        let store = MvccStore::new();
+        store.enable_lazy_load(Arc::clone(&storage)).unwrap();
+
+        // 按需加载插入的不存在墓碑不产生后端删除
+        let mut t = store.begin();
+        assert_eq!(t.read(b"a"), Some(b"1".to_vec()));
+        assert_eq!(t.read(b"missing"), None);
+        drop(t);
+        let flush = || store.flush_to_storage(&mut *storage.lock().unwrap(), 3).unwrap().0;
+        assert_eq!(flush(), 1);
+        // 刷盘后回收这些墓碑，再次读取重新回源
+        assert_eq!(store.total_keys(), 1);
+        assert_eq!(store.begin().read(b"missing"), None);
+        assert_eq!(store.total_keys(), 2);
+
+        // 新删除只在第一次刷盘时写出
+        let mut t = store.begin();
+        t.delete(b"a".to_vec());
+        t.commit().unwrap();
+        assert_eq!(flush(), 1);
+        assert_eq!(flush(), 0);
+        assert_eq!(storage.lock().unwrap().get(b"a").unwrap(), None);
+    }
+
+    /// 构造一份已刷盘的持久化状态：acct:a..acct:d、cfg，其中 acct:b 已删除
+    fn flushed_storage() -> (crate::storage::MemoryStorage, u64) {
+        let store = MvccStore::new();
+        let mut storage = crate::storage::MemoryStorage::new();
+        let mut t = store.begin();
+        for k in ["acct:a", "acct:b", "acct:c", "acct:d", "cfg"] {
+            t.write(k.as_bytes().to_vec(), k.to_uppercase().into_bytes());
+        }
+        t.commit().unwrap();
+        let mut t = store.begin();
+        t.delete(b"acct:b".to_vec());
+        t.commit().unwrap();
+        for _ in 0..20 {
+            store.begin_read_only().commit().unwrap();
+        }
+        store.flush_to_storage(&mut storage, 1).unwrap();
+        (storage, store.flush_watermark())
+    }
+
+    #[test]
+    fn test_load_from_storage_full_scan_restores_ts() {
+        let (storage, watermark) = flushed_storage();
+
+        let store = MvccStore::new();
+        assert_eq!(store.load_from_storage(&storage, None).unwrap(), 4);
+        assert_eq!(store.total_keys(), 4);
+        assert_eq!(store.flush_watermark(), watermark);
+
+        let mut t = store.begin();
+        assert!(t.start_ts > watermark);
+        assert_eq!(t.read(b"acct:a"), Some(b"ACCT:A".to_vec()));
+        assert_eq!(t.read(b"acct:b"), None);
+        assert_eq!(t.read(FLUSH_WATERMARK_KEY), None);
+        t.write(b"acct:a".to_vec(), b"new".to_vec());
+        let commit_ts = t.commit().unwrap();
+        assert!(commit_ts > watermark);
+        assert_eq!(store.read_at(b"acct:a", commit_ts).unwrap(), Some(b"new".to_vec()));
+
+        // 按前缀加载只恢复前缀下的键
+        let partial = MvccStore::new();
+        assert_eq!(partial.load_prefixes_from_storage(&storage, &[b"acct:".to_vec()]).unwrap(), 3);
+        assert_eq!(partial.read_at(b"cfg", u64::MAX).unwrap(), None);
+        assert!(partial.begin().start_ts > watermark);
+    }
+
+    #[test]
+    fn test_lazy_load_on_first_read() {
+        let (storage, watermark) = flushed_storage();
+        let storage: Arc<Mutex<dyn crate::Storage + Send>> = Arc::new(Mutex::new(storage));
+
+        let store = MvccStore::new();
+        store.enable_lazy_load(Arc::clone(&storage)).unwrap();
+        assert_eq!(store.total_keys(), 0);
+
+        // 首次读取回源；之后的新提交排在基线版本之后
+        let old = store.begin();
+        let mut t = store.begin();
+        assert!(t.start_ts > watermark);
+        assert_eq!(t.read(b"acct:c"), Some(b"ACCT:C".to_vec()));
+        assert_eq!(t.read(b"missing"), None);
+        assert_eq!(store.total_keys(), 2);
+        t.write(b"acct:d".to_vec(), b"changed".to_vec());
+        t.commit().unwrap();
+
+        // 提交早于回源：基线版本仍插在新版本之前，旧快照读到持久化的值
+        let mut old = old;
+        assert_eq!(old.read(b"acct:d"), Some(b"ACCT:D".to_vec()));
+        assert_eq!(store.begin().read(b"acct:d"), Some(b"changed".to_vec()));
+
+        // 范围扫描包含尚未加载的持久化键，已删除的键不出现
+        let entries = store.begin().scan_prefix(b"acct:");
+        let keys: Vec<&[u8]> = entries.iter().map(|(k, _)| k.as_slice()).collect();
+        assert_eq!(keys, vec![&b"acct:a"[..], b"acct:c", b"acct:d"]);
+
+        // 回源只发生一次：之后对后端的修改不再可见
+        storage.lock().unwrap().set(b"acct:c", b"external").unwrap();
+        assert_eq!(store.begin().read(b"acct:c"), Some(b"ACCT:C".to_vec()));
+    }
+
+    #[test]
+    fn test_loaders_skip_state_tree_nodes() {
+        use crate::state_commitment::{AuthenticatedStorage, STATE_TREE_PREFIX};
+        use crate::storage::{MemoryStorage, Storage};
+
+        // 经状态树写入的后端同时含有用户键与 __smt: 节点
+        let mut authed = AuthenticatedStorage::new(MemoryStorage::new()).unwrap();
+        authed.set(b"acct:a", b"A").unwrap();
+        authed.set(b"acct:b", b"B").unwrap();
+        let storage = authed.into_inner();
+        assert!(!storage.scan(STATE_TREE_PREFIX).unwrap().is_empty());
+
+        let full = MvccStore::new();
+        assert_eq!(full.load_from_storage(&storage, None).unwrap(), 2);
+        let prefixed = MvccStore::new();
+        let tree_prefix = [STATE_TREE_PREFIX.to_vec()];
+        assert_eq!(prefixed.load_prefixes_from_storage(&storage, &tree_prefix).unwrap(), 0);
+
+        let lazy = MvccStore::new();
+        lazy.enable_lazy_load(Arc::new(Mutex::new(storage))).unwrap();
+        let entries = lazy.begin().scan_prefix(b"");
+        let keys: Vec<&[u8]> = entries.iter().map(|(k, _)| k.as_slice()).collect();
+        assert_eq!(keys, vec![&b"acct:a"[..], b"acct:b"]);
+        assert_eq!(lazy.total_keys(), 2);
+    }
+
+    /// 读取以 `bad` 开头的键与任何扫描均失败的后端
+    struct FailingStorage;
+
+    impl crate::Storage for FailingStorage {
+        fn get(&self, key: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+            if key.starts_with(b"bad") {
+                anyhow::bail!("disk read error");
+            }
+            Ok(None)
+        }
+        fn set(&mut self, _key: &[u8], _value: &[u8]) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn delete(&mut self, _key: &[u8]) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn scan(&self, _prefix: &[u8]) -> anyhow::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+            anyhow::bail!("disk scan error")
+        }
+    }
+
+    #[test]
+    fn test_lazy_load_errors_fail_the_txn() {
+        let store = MvccStore::new();
+        store.enable_lazy_load(Arc::new(Mutex::new(FailingStorage))).unwrap();
+        assert!(store.read_at(b"bad", u64::MAX).is_err());
+        assert!(store.scan_prefix_at(b"acct:", u64::MAX).is_err());
+
+        // try_read 就地返回错误，由调用者处理，不影响提交
+        let mut t = store.begin();
+        assert!(t.try_read(b"bad").unwrap_err().contains("disk read error"));
+        assert_eq!(t.read(b"good"), None);
+        t.write(b"x".to_vec(), b"1".to_vec());
+        t.commit().unwrap();
+
+        // read 把回源失败记为缺失，提交时整个事务失败（只读事务同样）
+        let mut t = store.begin();
+        assert_eq!(t.read(b"bad"), None);
+        t.write(b"x".to_vec(), b"2".to_vec());
+        assert!(t.commit().unwrap_err().contains("disk read error"));
+        let mut t = store.begin_read_only();
+        t.read(b"bad");
+        assert!(t.commit().is_err());
+        let mut t = store.begin();
+        assert!(t.scan_prefix(b"acct:").is_empty());
+        t.write(b"x".to_vec(), b"3".to_vec());
+        assert!(t.commit_parallel().unwrap_err().contains("disk scan error"));
+        assert_eq!(store.begin().read(b"x"), Some(b"1".to_vec()));
+    }
+
+    #[test]
+    fn test_wal_recovers_commits_since_last_flush() {
+        use crate::storage::{MemoryStorage, Storage};
+        use crate::wal::{CommitLog, WalConfig};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = MemoryStorage::new();
+        let last_ts;
+        {
+            let store = MvccStore::new();
+            store.attach_wal(Arc::new(CommitLog::open(WalConfig::new(dir.path())).unwrap()));
+            let mut t = store.begin();
+            t.write(b"a".to_vec(), b"1".to_vec());
+            t.write(b"b".to_vec(), b"1".to_vec());
+            t.commit().unwrap();
+            store.flush_to_storage(&mut storage, 3).unwrap();
+            assert!(CommitLog::replay(dir.path()).unwrap().is_empty(), "flush truncates the log");
+
+            // 刷盘之后的提交只存在于内存与日志中
+            let mut t = store.begin();
+            t.write(b"b".to_vec(), b"2".to_vec());
+            t.write(b"c".to_vec(), b"3".to_vec());
+            t.commit().unwrap();
+            let mut t = store.begin().with_isolation(IsolationLevel::Serializable);
+            t.read(b"a");
+            t.delete(b"a".to_vec());
+            t.commit().unwrap();
+            let mut t = store.begin();
+            t.write(b"d".to_vec(), b"4".to_vec());
+            last_ts = t.commit_parallel().unwrap();
+        }
+
+        // 重启：先重放日志到持久化存储，再全量加载
+        assert_eq!(MvccStore::replay_wal(dir.path(), &mut storage).unwrap(), 3);
+        assert_eq!(MvccStore::stored_flush_watermark(&storage).unwrap(), Some(last_ts));
+        let store = MvccStore::new();
+        store.load_from_storage(&storage, None).unwrap();
+        let mut t = store.begin();
+        assert!(t.start_ts > last_ts);
+        assert_eq!(t.read(b"a"), None);
+        assert_eq!(t.read(b"b"), Some(b"2".to_vec()));
+        assert_eq!(t.read(b"c"), Some(b"3".to_vec()));
+        assert_eq!(t.read(b"d"), Some(b"4".to_vec()));
+        drop(t);
+
+        // 重新挂接日志后首次刷盘截断重启前的段；重复重放为空操作
+        store.attach_wal(Arc::new(CommitLog::open(WalConfig::new(dir.path())).unwrap()));
+        assert_eq!(MvccStore::replay_wal(dir.path(), &mut storage).unwrap(), 0);
+        store.flush_to_storage(&mut storage, 3).unwrap();
+        assert!(CommitLog::replay(dir.path()).unwrap().is_empty());
+        assert_eq!(storage.get(b"b").unwrap(), Some(b"2".to_vec()));
+    }
+
+    #[test]
+    fn test_override_ts_within_flush_cut_is_rejected() {
+        use crate::storage::{MemoryStorage, Storage};
+        use crate::wal::{CommitLog, WalConfig};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = MemoryStorage::new();
+        let accepted_ts;
+        {
+            let store = MvccStore::new();
+            store.attach_wal(Arc::new(CommitLog::open(WalConfig::new(dir.path())).unwrap()));
+            // 调度器在刷盘前预分配了 ts，提交却晚于刷盘取切点
+            let mut late = store.begin();
+            late.write(b"late".to_vec(), b"1".to_vec());
+            store.flush_to_storage(&mut storage, 3).unwrap();
+            let stale_ts = store.flush_watermark();
+            assert_eq!(store.min_override_ts(), stale_ts + 1);
+            let err = late.with_ts(stale_ts).commit().unwrap_err();
+            assert!(err.contains("flush cut"), "{err}");
+
+            let mut t = store.begin();
+            t.write(b"routed".to_vec(), b"2".to_vec());
+            accepted_ts = store.min_override_ts();
+            assert_eq!(t.with_ts(accepted_ts).commit().unwrap(), accepted_ts);
+        }
+
+        // 崩溃后重放：切点之后的预分配提交仍在日志中，被拒绝的提交未留下任何记录
+        assert_eq!(MvccStore::replay_wal(dir.path(), &mut storage).unwrap(), 1);
+        assert_eq!(MvccStore::stored_flush_watermark(&storage).unwrap(), Some(accepted_ts));
+        assert_eq!(storage.get(b"routed").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(storage.get(b"late").unwrap(), None);
+    }
+
+    #[test]
+    fn test_auto_flush_to_shared_storage_handle() {
+        use crate::storage::{LockedStorage, MemoryStorage};
+
+        let handle = LockedStorage::new(MemoryStorage::new()).into_handle();
+        let store = MvccStore::new();
+        let mut t = store.begin();
+        t.write(b"k".to_vec(), b"v".to_vec());
+        t.commit().unwrap();
+
+        let config = AutoFlushConfig {
+            interval_secs: 0,
+            blocks_per_flush: 1,
+            keep_recent_versions: 1,
+            flush_on_start: false,
+        };
+        store.start_auto_flush_shared(config, Arc::clone(&handle)).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut block = 0;
+        while store.get_flush_stats().flush_count == 0 && Instant::now() < deadline {
+            block += 1;
+            store.set_current_block(block);
+            // 刷盘线程运行期间其它持有者可直接读写同一句柄
+            handle.put(b"other", b"1").unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+        store.stop_auto_flush();
+
+        assert!(store.get_flush_stats().flush_count > 0);
+        assert_eq!(handle.get(b"k").unwrap(), Some(b"v".to_vec()));
+        assert_eq!(handle.get(b"other").unwrap(), Some(b"1".to_vec()));
+    }
+
+    #[test]
+    fn test_txn_scan_merges_snapshot_and_buffered_writes() {
+        let store = MvccStore::new();
+        let mut init = store.begin();
+        for (k, v) in [("acct:a", "1"), ("acct:b", "2"), ("acct:c", "3"), ("other", "x")] {
+            init.write(k.as_bytes().to_vec(), v.as_bytes().to_vec());
+        }
+        init.commit().unwrap();
+
+        let mut txn = store.begin();
+        // 快照之后的提交不可见
+        let mut later = store.begin();
+        later.write(b"acct:d".to_vec(), b"4".to_vec());
+        later.commit().unwrap();
+
+        txn.delete(b"acct:b".to_vec());
+        txn.write(b"acct:a".to_vec(), b"10".to_vec());
+        txn.write(b"acct:bb".to_vec(), b"5".to_vec());
+        let entries = txn.scan_prefix(b"acct:");
+        let keys: Vec<&[u8]> = entries.iter().map(|(k, _)| k.as_slice()).collect();
+        assert_eq!(keys, vec![&b"acct:a"[..], b"acct:bb", b"acct:c"]);
+        assert_eq!(entries[0].1, b"10");
+
+        let range = txn.scan_range(b"acct:b", b"acct:c");
+        assert_eq!(range, vec![(b"acct:bb".to_vec(), b"5".to_vec())]);
+        assert!(txn.scan_range(b"z", b"a").is_empty());
+        assert_eq!(txn.range_reads().len(), 2);
+
+        assert_eq!(KeyRange::prefix(&[0x61, 0xff]).end, Some(vec![0x62]));
+        assert_eq!(KeyRange::prefix(&[0xff, 0xff]).end, None);
+        assert!(KeyRange::prefix(b"").contains(b"anything"));
+    }
+
+    #[test]
+    fn test_serializable_scan_detects_phantoms() {
+        let store = MvccStore::new();
+        let mut init = store.begin();
+        init.write(b"acct:a".to_vec(), b"1".to_vec());
+        init.write(b"acct:b".to_vec(), b"2".to_vec());
+        init.commit().unwrap();
+
+        let run = |level: IsolationLevel, inserted: &[u8]| {
+            let mut txn = store.begin().with_isolation(level);
+            let total: usize = txn.scan_prefix(b"acct:").len();
+            let mut other = store.begin();
+            other.write(inserted.to_vec(), b"9".to_vec());
+            other.commit().unwrap();
+            txn.write(b"count".to_vec(), total.to_string().into_bytes());
+            txn.commit()
+        };
+
+        // 区间内新增键：可串行化事务因幻读中止，快照隔离允许提交
+        let err = run(IsolationLevel::Serializable, b"acct:c").expect_err("phantom insert");
+        assert_eq!(ConflictKind::of(&err), Some(ConflictKind::Serialization));
+        assert!(err.contains("phantom"));
+        run(IsolationLevel::Snapshot, b"acct:d").unwrap();
+        // 区间外的写入不影响
+        run(IsolationLevel::Serializable, b"other").unwrap();
+
+        // 删除区间内已有键同样视为幻读
+        let mut txn = store.begin().with_isolation(IsolationLevel::Serializable);
+        txn.scan_range(b"acct:a", b"acct:b");
+        let mut other = store.begin();
+        other.delete(b"acct:a".to_vec());
+        other.commit().unwrap();
+        txn.write(b"count".to_vec(), b"0".to_vec());
+        assert_eq!(ConflictKind::of(&txn.commit().unwrap_err()), Some(ConflictKind::Serialization));
+    }
+
+    #[test]
+    fn test_mixed_commit_paths_share_lock_order() {
+        // 各提交路径（含带范围读、持谓词写锁的可串行化提交）在同一组键上并发，不得死锁
+        let store = MvccStore::new();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let store = Arc::clone(&store);
+                thread::spawn(move || {
+                    for round in 0..200 {
+                        let key = format!("k{}", (i + round) % 4).into_bytes();
+                        let mut txn = store.begin();
+                        txn.read(&key);
+                        txn.write(key, b"v".to_vec());
+                        let _ = match i % 3 {
+                            0 => txn.commit(),
+                            1 => txn.commit_parallel(),
+                            _ => {
+                                let mut txn = txn.with_isolation(IsolationLevel::Serializable);
+                                txn.scan_prefix(b"k");
+                                txn.commit()
+                            }
+                        };
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(store.scan_prefix_at(b"k", u64::MAX).unwrap().len(), 4);
+    }
+
+    /// 值班医生：至少一人在岗。每个事务读取全部人员状态，仅在他人在岗时下岗
+    fn on_call_count(txn: &mut Txn, doctors: usize) -> usize {
+        (0..doctors)
+            .filter(|i| txn.read(format!("doc{i}").as_bytes()).as_deref() == Some(b"1"))
+            .count()
+    }
+
+    #[test]
+    fn test_write_skew_stress() {
+        use std::sync::Barrier;
+
+        // 两名医生同时读取后各自下岗：快照隔离的读集合校验不加锁，每轮要么一方因读写冲突中止、
+        // 要么双方同时通过校验出现 write skew；可串行化每轮恰好一方中止
+        let rounds = 20;
+        for level in [IsolationLevel::Snapshot, IsolationLevel::Serializable] {
+            let (mut violations, mut aborts) = (0, 0);
+            for _ in 0..rounds {
+                let store = MvccStore::new();
+                store.set_isolation_level(level);
+                let mut init = store.begin();
+                init.write(b"doc0".to_vec(), b"1".to_vec());
+                init.write(b"doc1".to_vec(), b"1".to_vec());
+                init.commit().unwrap();
+
+                let barrier = Arc::new(Barrier::new(2));
+                let handles: Vec<_> = (0..2)
+                    .map(|i| {
+                        let (store, barrier) = (Arc::clone(&store), Arc::clone(&barrier));
+                        thread::spawn(move || {
+                            let mut txn = store.begin();
+                            let on_call = on_call_count(&mut txn, 2);
+                            barrier.wait();
+                            if on_call >= 2 {
+                                txn.write(format!("doc{i}").into_bytes(), b"0".to_vec());
+                            }
+                            txn.commit_parallel()
+                        })
+                    })
+                    .collect();
+                for h in handles {
+                    if let Err(e) = h.join().unwrap() {
+                        let expected = match level {
+                            IsolationLevel::Snapshot => ConflictKind::ReadWrite,
+                            IsolationLevel::Serializable => ConflictKind::Serialization,
+                        };
+                        assert_eq!(ConflictKind::of(&e), Some(expected));
+                        aborts += 1;
+                    }
+                }
+                let mut check = store.begin_read_only();
+                if on_call_count(&mut check, 2) == 0 {
+                    violations += 1;
+                }
+            }
+            match level {
+                IsolationLevel::Snapshot => assert_eq!(violations + aborts, rounds),
+                IsolationLevel::Serializable => assert_eq!((violations, aborts), (0, rounds)),
+            }
+        }
+
+        // 自由竞争：多线程反复上岗 / 下岗并重试，可串行化下任何快照都至少一人在岗
+        let doctors = 6;
+        let store = MvccStore::new();
+        store.set_isolation_level(IsolationLevel::Serializable);
+        let mut init = store.begin();
+        for i in 0..doctors {
+            init.write(format!("doc{i}").into_bytes(), b"1".to_vec());
+        }
+        init.commit().unwrap();
+        let handles: Vec<_> = (0..doctors)
+            .map(|i| {
+                let store = Arc::clone(&store);
+                thread::spawn(move || {
+                    for round in 0..100 {
+                        loop {
+                            let mut txn = store.begin();
+                            let on_call = on_call_count(&mut txn, doctors);
+                            assert!(on_call >= 1, "snapshot observed nobody on call");
+                            let key = format!("doc{i}").into_bytes();
+                            if round % 2 == 0 && on_call >= 2 {
+                                txn.write(key, b"0".to_vec());
+                            } else if round % 2 == 1 {
+                                txn.write(key, b"1".to_vec());
+                            }
+                            if txn.commit().is_ok() {
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert!(on_call_count(&mut store.begin_read_only(), doctors) >= 1);
+    }
+
     #[test]
     fn test_mvcc_read_only_transaction() {
         let store = MvccStore::new();
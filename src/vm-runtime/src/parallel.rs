@@ -10,6 +10,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use crossbeam_deque::{Injector, Stealer, Worker};
 use rayon::prelude::*;
+use crate::gas::StorageDeposit;
 use crate::mvcc::{MvccStore, Txn};
 
 /// 重试分类
@@ -128,6 +129,8 @@ pub struct ExecutionResult {
     pub tx_id: TxId,
     /// 执行返回值
     pub return_value: i32,
+    /// 返回数据（contract_api::set_return 设置）
+    pub return_data: Vec<u8>,
     /// 读写集
     pub read_write_set: ReadWriteSet,
     /// 生成的事件
@@ -136,6 +139,10 @@ pub struct ExecutionResult {
     pub success: bool,
     /// 错误信息 (如果失败)
     pub error: Option<String>,
+    /// 消耗的 gas
+    pub gas_used: u64,
+    /// 存储押金收支（收取部分已计入 gas_used，成功时退款已从 gas_used 中抵扣）
+    pub storage_deposit: StorageDeposit,
 }
 
 /// 交易依赖图
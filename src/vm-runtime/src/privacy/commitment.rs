@@ -6,15 +6,67 @@
 // Phase 2.2.3: Pedersen Commitments (Week 17-20)
 //
 // 实现 Pedersen Commitment 用于隐藏交易金额
-// C = aG + bH, 其中 a 是金额, b 是致盲因子
+// C = a*H + b*G, 其中 a 是金额, b 是致盲因子
+// - G: Ristretto 基点
+// - H: hash_to_point("Pedersen_H_basepoint") (SHA-512 → Ristretto, 无人知道 log_G(H))
+// 与 privacy-test::pedersen_commitment::PedersenCommitment 保持一致 (共享已知答案向量)
+// Commitment([u8; 32]) 为压缩后的 Ristretto 点
 
 use crate::privacy::types::*;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use once_cell::sync::Lazy;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha512};
+
+/// 生成基点 H 的域分隔标签
+pub const PEDERSEN_H_LABEL: &[u8] = b"Pedersen_H_basepoint";
+
+static PEDERSEN_H: Lazy<RistrettoPoint> = Lazy::new(|| hash_to_point(PEDERSEN_H_LABEL));
+
+/// Hash-to-Point: SHA-512(data) → Ristretto (from_uniform_bytes)
+pub fn hash_to_point(data: &[u8]) -> RistrettoPoint {
+    let hash = Sha512::digest(data);
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(&hash);
+    RistrettoPoint::from_uniform_bytes(&bytes)
+}
+
+/// 金额基点 H
+pub fn pedersen_h() -> RistrettoPoint {
+    *PEDERSEN_H
+}
+
+/// 解析 32 字节标量 (必须为规范编码, 即 < 群阶 l)
+pub fn scalar_from_bytes(bytes: &[u8; 32]) -> Result<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(*bytes))
+        .ok_or_else(|| anyhow!("non-canonical scalar encoding"))
+}
+
+impl Commitment {
+    /// 由 Ristretto 点构造 (压缩编码)
+    pub fn from_point(point: &RistrettoPoint) -> Self {
+        Commitment(point.compress().to_bytes())
+    }
+
+    /// 解压为 Ristretto 点 (非法编码返回错误)
+    pub fn to_point(&self) -> Result<RistrettoPoint> {
+        CompressedRistretto(self.0)
+            .decompress()
+            .ok_or_else(|| anyhow!("invalid commitment encoding"))
+    }
+}
 
 /// Pedersen Commitment Generator
 /// 用于生成金额承诺
 pub struct CommitmentGenerator {
-    // TODO: Phase 2.2.3 - 添加椭圆曲线点操作
+    /// 致盲因子基点 G
+    g: RistrettoPoint,
+    /// 金额基点 H
+    h: RistrettoPoint,
 }
 
 impl Default for CommitmentGenerator {
@@ -24,31 +76,51 @@ impl Default for CommitmentGenerator {
 impl CommitmentGenerator {
     /// 创建新的生成器
     pub fn new() -> Self {
-        todo!("Phase 2.2.3: Implement commitment generator")
+        Self {
+            g: RISTRETTO_BASEPOINT_POINT,
+            h: pedersen_h(),
+        }
     }
 
     /// 生成 Pedersen Commitment
     ///
     /// # 参数
     /// - `amount`: 金额 (0 到 2^64-1)
-    /// - `blinding_factor`: 致盲因子 (32 bytes random scalar)
+    /// - `blinding_factor`: 致盲因子 (32 bytes 规范编码标量)
     ///
     /// # 返回
-    /// Commitment C = amount*G + blinding*H
-    pub fn commit(&self, _amount: u64, _blinding_factor: &[u8; 32]) -> Result<Commitment> {
-        todo!("Phase 2.2.3: Implement Pedersen commitment")
+    /// Commitment C = amount*H + blinding*G
+    pub fn commit(&self, amount: u64, blinding_factor: &[u8; 32]) -> Result<Commitment> {
+        let blinding = scalar_from_bytes(blinding_factor)?;
+        Ok(Commitment::from_point(&self.commit_point(amount, &blinding)))
+    }
+
+    /// 以标量致盲因子生成承诺点
+    pub fn commit_point(&self, amount: u64, blinding: &Scalar) -> RistrettoPoint {
+        Scalar::from(amount) * self.h + blinding * self.g
+    }
+
+    /// 验证承诺打开: C == amount*H + blinding*G
+    pub fn verify_opening(
+        &self,
+        commitment: &Commitment,
+        amount: u64,
+        blinding_factor: &[u8; 32],
+    ) -> Result<bool> {
+        Ok(self.commit(amount, blinding_factor)? == *commitment)
     }
 
     /// 生成随机致盲因子
     pub fn generate_blinding_factor(&self) -> [u8; 32] {
-        todo!("Phase 2.2.3: Implement blinding factor generation")
+        Scalar::random(&mut OsRng).to_bytes()
     }
 }
 
 /// Commitment Verifier
 /// 用于验证承诺的有效性
 pub struct CommitmentVerifier {
-    // TODO: Phase 2.2.3 - 添加验证逻辑
+    /// 金额基点 H (手续费以明文金额乘 H 计入)
+    h: RistrettoPoint,
 }
 
 impl Default for CommitmentVerifier {
@@ -58,12 +130,13 @@ impl Default for CommitmentVerifier {
 impl CommitmentVerifier {
     /// 创建新的验证器
     pub fn new() -> Self {
-        todo!("Phase 2.2.3: Implement commitment verifier")
+        Self { h: pedersen_h() }
     }
 
     /// 验证承诺和的平衡性
     ///
-    /// 验证: sum(inputs) = sum(outputs) + fee*G
+    /// 验证: sum(inputs) = sum(outputs) + fee*H
+    /// (要求 sum(输入致盲因子) = sum(输出致盲因子); 金额非负需另由 range proof 保证)
     ///
     /// # 参数
     /// - `input_commitments`: 输入承诺列表
@@ -71,42 +144,122 @@ impl CommitmentVerifier {
     /// - `fee`: 交易费 (明文)
     ///
     /// # 返回
-    /// 验证是否通过
+    /// 验证是否通过; 任一承诺不是合法的 Ristretto 编码时返回错误
     pub fn verify_sum(
         &self,
-        _input_commitments: &[Commitment],
-        _output_commitments: &[Commitment],
-        _fee: u64,
+        input_commitments: &[Commitment],
+        output_commitments: &[Commitment],
+        fee: u64,
     ) -> Result<bool> {
-        todo!("Phase 2.2.3: Implement sum verification")
+        let inputs = sum_points(input_commitments)?;
+        let outputs = sum_points(output_commitments)?;
+        Ok(inputs == outputs + Scalar::from(fee) * self.h)
     }
 }
 
+fn sum_points(commitments: &[Commitment]) -> Result<RistrettoPoint> {
+    commitments
+        .iter()
+        .try_fold(RistrettoPoint::identity(), |acc, c| Ok(acc + c.to_point()?))
+}
+
 /// 承诺加法 (同态加法)
-/// C1 + C2 = (a1 + a2)G + (b1 + b2)H
-pub fn add_commitments(_c1: &Commitment, _c2: &Commitment) -> Result<Commitment> {
-    todo!("Phase 2.2.3: Implement commitment addition")
+/// C1 + C2 = (a1 + a2)H + (b1 + b2)G
+pub fn add_commitments(c1: &Commitment, c2: &Commitment) -> Result<Commitment> {
+    Ok(Commitment::from_point(&(c1.to_point()? + c2.to_point()?)))
 }
 
 /// 承诺减法
-/// C1 - C2 = (a1 - a2)G + (b1 - b2)H
-pub fn sub_commitments(_c1: &Commitment, _c2: &Commitment) -> Result<Commitment> {
-    todo!("Phase 2.2.3: Implement commitment subtraction")
+/// C1 - C2 = (a1 - a2)H + (b1 - b2)G
+pub fn sub_commitments(c1: &Commitment, c2: &Commitment) -> Result<Commitment> {
+    Ok(Commitment::from_point(&(c1.to_point()? - c2.to_point()?)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// 与 privacy-test 共享的已知答案向量
+    const KAT_VECTORS: &str = include_str!("../../../../privacy-test/vectors/pedersen_kat.txt");
+
+    fn hex32(text: &str) -> [u8; 32] {
+        hex::decode(text).unwrap().try_into().unwrap()
+    }
+
     #[test]
-    #[should_panic(expected = "not yet implemented")]
-    fn test_commitment_generator_placeholder() {
-        let _generator = CommitmentGenerator::new();
+    fn test_known_answer_vectors() {
+        let generator = CommitmentGenerator::new();
+        let mut checked = 0;
+        for line in KAT_VECTORS.lines().filter(|l| !l.starts_with('#') && !l.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["H", h] => assert_eq!(pedersen_h().compress().to_bytes(), hex32(h)),
+                [amount, blinding, commitment] => {
+                    let c = generator.commit(amount.parse().unwrap(), &hex32(blinding)).unwrap();
+                    assert_eq!(c, Commitment(hex32(commitment)), "vector {line}");
+                    checked += 1;
+                }
+                _ => panic!("malformed vector line: {line}"),
+            }
+        }
+        assert!(checked >= 4);
     }
 
     #[test]
-    #[should_panic(expected = "not yet implemented")]
-    fn test_commitment_verifier_placeholder() {
-        let _verifier = CommitmentVerifier::new();
+    fn test_commitment_opening_and_homomorphism() {
+        let generator = CommitmentGenerator::new();
+        let r1 = generator.generate_blinding_factor();
+        let r2 = generator.generate_blinding_factor();
+        let c1 = generator.commit(100, &r1).unwrap();
+        let c2 = generator.commit(250, &r2).unwrap();
+
+        assert!(generator.verify_opening(&c1, 100, &r1).unwrap());
+        assert!(!generator.verify_opening(&c1, 101, &r1).unwrap());
+        assert_ne!(c1, generator.commit(100, &r2).unwrap(), "blinding hides the amount");
+
+        let s1 = scalar_from_bytes(&r1).unwrap();
+        let s2 = scalar_from_bytes(&r2).unwrap();
+        let sum = add_commitments(&c1, &c2).unwrap();
+        assert_eq!(sum, generator.commit(350, &(s1 + s2).to_bytes()).unwrap());
+        let diff = sub_commitments(&c2, &c1).unwrap();
+        assert_eq!(diff, generator.commit(150, &(s2 - s1).to_bytes()).unwrap());
+        assert_eq!(sub_commitments(&sum, &c2).unwrap(), c1);
+    }
+
+    #[test]
+    fn test_verify_sum_balances_inputs_outputs_and_fee() {
+        let generator = CommitmentGenerator::new();
+        let verifier = CommitmentVerifier::new();
+
+        // 输入 1000 + 500, 输出 600 + 890, 手续费 10; 输出致盲因子之和等于输入之和
+        let r_in1 = Scalar::random(&mut OsRng);
+        let r_in2 = Scalar::random(&mut OsRng);
+        let r_out1 = Scalar::random(&mut OsRng);
+        let r_out2 = r_in1 + r_in2 - r_out1;
+        let inputs = [
+            generator.commit(1000, &r_in1.to_bytes()).unwrap(),
+            generator.commit(500, &r_in2.to_bytes()).unwrap(),
+        ];
+        let outputs = [
+            generator.commit(600, &r_out1.to_bytes()).unwrap(),
+            generator.commit(890, &r_out2.to_bytes()).unwrap(),
+        ];
+
+        assert!(verifier.verify_sum(&inputs, &outputs, 10).unwrap());
+        assert!(!verifier.verify_sum(&inputs, &outputs, 11).unwrap());
+        assert!(!verifier.verify_sum(&inputs, &outputs[..1], 10).unwrap());
+        assert!(verifier.verify_sum(&[], &[], 0).unwrap());
+    }
+
+    #[test]
+    fn test_rejects_invalid_encodings() {
+        let generator = CommitmentGenerator::new();
+        // 群阶 l 之上的标量不是规范编码
+        assert!(generator.commit(1, &[0xff; 32]).is_err());
+
+        let bogus = Commitment([0xff; 32]);
+        let valid = generator.commit(1, &[1; 32]).unwrap();
+        assert!(add_commitments(&valid, &bogus).is_err());
+        assert!(CommitmentVerifier::new().verify_sum(&[bogus], &[valid], 0).is_err());
     }
 }
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// SuperVM 2.0 - Key Image Registry
+// 架构师: KING XU (CHINA)
+// Phase 2.2.1: Ring Signatures - Key Image Double-Spend Protection
+//
+// 已花费 Key Image 登记表 (防止双花)
+// - 权威数据: MVCC 键 `KEY_IMAGE_PREFIX || I` (值为花费交易 TxId), 随 flush_to_storage 持久化到 Storage
+// - 前置过滤: 内存 BloomFilter, 否定结果无需查 MVCC / 存储
+// - 原子性: 登记写入隐私交易自身的 MVCC 事务, 与业务写入一同提交或回滚
+// - 并发: 两笔交易花费同一 Key Image 时写同一个键, 写写冲突保证至多一笔提交;
+//   重试后读到已提交的登记即被拒绝
+//
+// - 签名: 登记前须经 verify_transaction_inputs 校验每个输入的 CLSAG, 伪造 / 未签名的输入不会写入登记
+//
+// 过滤器只收录已提交的花费 (事务提交成功后由 confirm 插入), 中止与被拒的交易不占用过滤器容量。
+// 因而过滤器只用于跳过持久化存储查询与 is_spent 的快速否定: MVCC 快照查询总是执行,
+// 提交与 confirm 之间的窗口内仍能读到已提交的登记; 落盘的登记必然已 confirm, 重启时由 with_storage 预热
+
+use crate::bloom_filter::BloomFilter;
+use crate::mvcc::{MvccStore, Txn};
+use crate::parallel_mvcc::TxId;
+use crate::privacy::ring_signature::{transaction_signing_message, RingVerifier};
+use crate::privacy::types::*;
+use crate::storage::StorageHandle;
+use anyhow::{bail, Result};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Key Image 登记键前缀
+pub const KEY_IMAGE_PREFIX: &[u8] = b"privacy:key_image:";
+
+/// 默认预期 Key Image 数量 (决定过滤器位数组大小)
+pub const DEFAULT_EXPECTED_KEY_IMAGES: usize = 1_000_000;
+
+/// Key Image 的登记键
+pub fn key_image_key(key_image: &KeyImage) -> Vec<u8> {
+    let mut key = Vec::with_capacity(KEY_IMAGE_PREFIX.len() + 32);
+    key.extend_from_slice(KEY_IMAGE_PREFIX);
+    key.extend_from_slice(&key_image.0);
+    key
+}
+
+/// 隐私交易的 Key Image 预校验: 至少一个输入, 输入声明的 Key Image 与环签名一致、可解压为曲线点, 交易内不重复
+pub fn transaction_key_images(tx: &PrivacyTransaction) -> Result<Vec<KeyImage>> {
+    if tx.inputs.is_empty() {
+        bail!("privacy transaction has no inputs");
+    }
+    let mut seen = HashSet::with_capacity(tx.inputs.len());
+    for input in &tx.inputs {
+        if input.key_image != input.ring_signature.key_image {
+            bail!("input key image does not match its ring signature");
+        }
+        if CompressedRistretto(input.key_image.0)
+            .decompress()
+            .is_none()
+        {
+            bail!(
+                "invalid key image encoding {}",
+                hex::encode(input.key_image.0)
+            );
+        }
+        if !seen.insert(input.key_image) {
+            bail!(
+                "duplicate key image {} within transaction",
+                hex::encode(input.key_image.0)
+            );
+        }
+    }
+    Ok(tx.inputs.iter().map(|i| i.key_image).collect())
+}
+
+/// 登记前的完整输入校验: [`transaction_key_images`] 之外, 每个输入的环签名须对
+/// [`transaction_signing_message`] 验证通过
+///
+/// 只接受纯授权签名 ([`RingVerifier::verify`]); 带承诺层的签名需要环成员的金额承诺,
+/// 交易本身不携带, 在此一律拒绝
+pub fn verify_transaction_inputs(tx: &PrivacyTransaction) -> Result<Vec<KeyImage>> {
+    let key_images = transaction_key_images(tx)?;
+    let message = transaction_signing_message(tx);
+    let verifier = RingVerifier::new();
+    for (index, input) in tx.inputs.iter().enumerate() {
+        if !verifier.verify(&message, &input.ring_signature)? {
+            bail!("invalid ring signature on input {index}");
+        }
+    }
+    Ok(key_images)
+}
+
+/// 已花费 Key Image 登记表
+///
+/// 每个 MVCC 存储只应对应一个登记表实例 (过滤器不感知其它实例的登记)
+pub struct KeyImageRegistry {
+    store: Arc<MvccStore>,
+    filter: BloomFilter,
+    /// 持久化后端 (重启后内存 MVCC 为空, 已落盘的登记从这里查)
+    storage: Option<StorageHandle>,
+}
+
+impl KeyImageRegistry {
+    /// 创建登记表 (纯内存, 过滤器按默认容量分配)
+    pub fn new(store: Arc<MvccStore>) -> Self {
+        Self::with_capacity(store, DEFAULT_EXPECTED_KEY_IMAGES)
+    }
+
+    /// 创建登记表, 过滤器按预期 Key Image 数量分配 (误报率 0.1%)
+    pub fn with_capacity(store: Arc<MvccStore>, expected_key_images: usize) -> Self {
+        Self {
+            store,
+            filter: BloomFilter::new(expected_key_images.max(1), 0.001),
+            storage: None,
+        }
+    }
+
+    /// 挂接持久化存储, 并以其中已落盘的登记预热过滤器
+    ///
+    /// 登记随 `MvccStore::flush_to_storage` 写入同一存储
+    pub fn with_storage(mut self, storage: StorageHandle) -> Result<Self> {
+        storage.for_each_prefix(KEY_IMAGE_PREFIX, &mut |key, _| {
+            if let Ok(image) = <[u8; 32]>::try_from(&key[KEY_IMAGE_PREFIX.len()..]) {
+                self.filter.insert(&image);
+            }
+            true
+        })?;
+        self.storage = Some(storage);
+        Ok(self)
+    }
+
+    /// 底层 MVCC 存储
+    pub fn store(&self) -> &Arc<MvccStore> {
+        &self.store
+    }
+
+    /// 查询 Key Image 是否已花费 (已提交状态)
+    pub fn is_spent(&self, key_image: &KeyImage) -> Result<bool> {
+        let mut txn = self.store.begin_read_only();
+        self.lookup(&mut txn, key_image)
+    }
+
+    /// 在隐私交易的事务内登记花费; 任一 Key Image 已花费时返回错误 (事务应随之放弃)
+    ///
+    /// 登记写入 `txn`, 随事务提交生效; 并发花费同一 Key Image 的事务在提交时写写冲突。
+    /// 提交成功后须调用 [`Self::confirm`]
+    pub fn spend(&self, txn: &mut Txn, tx_id: TxId, key_images: &[KeyImage]) -> Result<()> {
+        for key_image in key_images {
+            if self.lookup(txn, key_image)? {
+                bail!("key image {} already spent", hex::encode(key_image.0));
+            }
+            txn.write(key_image_key(key_image), tx_id.to_le_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    /// 登记所在事务已提交: 把 Key Image 加入过滤器
+    pub fn confirm(&self, key_images: &[KeyImage]) {
+        for key_image in key_images {
+            self.filter.insert(&key_image.0);
+        }
+    }
+
+    /// 权威查询: 事务快照 (含本事务写入); 过滤器命中时再查持久化存储
+    fn lookup(&self, txn: &mut Txn, key_image: &KeyImage) -> Result<bool> {
+        let key = key_image_key(key_image);
+        if txn.read(&key).is_some() {
+            return Ok(true);
+        }
+        match &self.storage {
+            Some(storage) if self.filter.contains(&key_image.0) => Ok(storage.get(&key)?.is_some()),
+            _ => Ok(false),
+        }
+    }
+
+    #[cfg(test)]
+    fn filter_contains(&self, key_image: &KeyImage) -> bool {
+        self.filter.contains(&key_image.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LockedStorage;
+    use crate::MemoryStorage;
+
+    fn image(byte: u8) -> KeyImage {
+        KeyImage([byte; 32])
+    }
+
+    #[test]
+    fn test_spend_commits_atomically_and_rejects_reuse() {
+        let store = MvccStore::new();
+        let registry = KeyImageRegistry::with_capacity(Arc::clone(&store), 1024);
+
+        // 事务放弃: 登记不生效, 也不进入过滤器
+        let mut txn = store.begin();
+        registry.spend(&mut txn, 1, &[image(1)]).unwrap();
+        drop(txn);
+        assert!(!registry.is_spent(&image(1)).unwrap());
+        assert!(!registry.filter_contains(&image(1)));
+
+        // 提交后、confirm 前仍可由 MVCC 快照查到
+        let mut txn = store.begin();
+        registry.spend(&mut txn, 2, &[image(1), image(2)]).unwrap();
+        txn.commit().unwrap();
+        assert!(registry.is_spent(&image(1)).unwrap());
+        let mut txn = store.begin();
+        assert!(registry.spend(&mut txn, 5, &[image(2)]).is_err());
+        registry.confirm(&[image(1), image(2)]);
+        assert!(registry.filter_contains(&image(2)));
+        assert!(!registry.is_spent(&image(3)).unwrap());
+
+        let mut txn = store.begin();
+        assert!(registry.spend(&mut txn, 3, &[image(3), image(2)]).is_err());
+        let mut txn = store.begin();
+        assert!(registry.spend(&mut txn, 4, &[image(4), image(4)]).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_spends_conflict() {
+        let store = MvccStore::new();
+        let registry = KeyImageRegistry::with_capacity(Arc::clone(&store), 1024);
+        let mut a = store.begin();
+        let mut b = store.begin();
+        registry.spend(&mut a, 1, &[image(7)]).unwrap();
+        registry.spend(&mut b, 2, &[image(7)]).unwrap();
+        a.commit().unwrap();
+        assert!(b.commit().is_err());
+    }
+
+    #[test]
+    fn test_persisted_registry_survives_restart() {
+        let handle = LockedStorage::new(MemoryStorage::new()).into_handle();
+        {
+            let store = MvccStore::new();
+            let registry = KeyImageRegistry::with_capacity(Arc::clone(&store), 1024)
+                .with_storage(Arc::clone(&handle))
+                .unwrap();
+            let mut txn = store.begin();
+            registry.spend(&mut txn, 1, &[image(9)]).unwrap();
+            txn.commit().unwrap();
+            registry.confirm(&[image(9)]);
+            let mut view = Arc::clone(&handle);
+            store.flush_to_storage(&mut view, 3).unwrap();
+        }
+
+        // 重启: 新的内存 MVCC, 登记只在存储中
+        let store = MvccStore::new();
+        let registry = KeyImageRegistry::with_capacity(Arc::clone(&store), 1024)
+            .with_storage(handle)
+            .unwrap();
+        assert!(registry.is_spent(&image(9)).unwrap());
+        let mut txn = store.begin();
+        assert!(registry.spend(&mut txn, 2, &[image(9)]).is_err());
+    }
+}
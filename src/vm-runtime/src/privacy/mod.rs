@@ -16,6 +16,7 @@
 pub mod commitment;
 #[cfg(feature = "groth16-verifier")]
 pub mod groth16_verifier;
+pub mod key_image_registry; // Phase 2.2.1: 已花费 Key Image 登记 (防双花)
 pub mod range_proof;
 pub mod ring_signature;
 #[cfg(feature = "groth16-verifier")]
@@ -32,6 +33,7 @@ pub mod parallel_prover; // Phase 2.2.X: 并行证明生成 (rayon 批量 prove)
 
 #[cfg(feature = "groth16-verifier")]
 pub use groth16_verifier::Groth16Verifier;
+pub use key_image_registry::KeyImageRegistry;
 pub use types::*;
 pub use zksnark::{NoopVerifier, ZkCircuitId, ZkError, ZkVerifier};
 #[cfg(feature = "groth16-verifier")]
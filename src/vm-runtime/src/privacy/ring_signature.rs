@@ -5,24 +5,282 @@
 // 架构师: KING XU (CHINA)
 // Phase 2.2.1: Ring Signatures (Week 9-12)
 //
-// 实现 MLSAG (Multilayered Linkable Spontaneous Anonymous Group) 环签名
-// 或 CLSAG (Concise Linkable Spontaneous Anonymous Group) 环签名
+// 实现 CLSAG (Concise Linkable Spontaneous Anonymous Group) 环签名 (Ristretto)
+// - 密钥: 私钥 x 为规范编码标量, 公钥 P = x*G (压缩 Ristretto)
+// - Key Image: I = x*Hp(P), Hp 即 commitment::hash_to_point (同 privacy-test::hash_to_point)
+// - 双层: 签名密钥层 P_i 与承诺层 C_i - C' (C' 为伪输出承诺, 签名者知道 z: C_l - C' = z*G)
+//   两层以聚合系数 mu_P / mu_C 合并为一个环, 签名为 (c_0, s_0..s_{n-1}, I, D = z*Hp(P_l))
+// - 纯授权签名 (`sign`) 的承诺层全为单位元 (z = 0), 与带承诺签名共用同一套方程
+//
+// RingSignature.signature 规范编码:
+//   c_0 (32) || s_0..s_{n-1} (32*n)                      纯授权签名
+//   c_0 (32) || s_0..s_{n-1} (32*n) || D (32) || C' (32) 带承诺签名
+// 标量须为规范编码, 点须可解压, 否则视为无效签名 (防止签名延展)
 
+use crate::privacy::commitment::hash_to_point;
+use crate::privacy::key_image_registry::KeyImageRegistry;
 use crate::privacy::types::*;
-use anyhow::Result;
+use crate::privacy::{MAX_RING_SIZE, MIN_RING_SIZE};
+use anyhow::{anyhow, bail, ensure, Result};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
+use parking_lot::RwLock;
+use rand::rngs::OsRng;
+use rand::seq::index::sample;
+use rayon::prelude::*;
+use sha2::{Digest, Sha512};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const AGG_P_DOMAIN: &[u8] = b"SuperVM_CLSAG_agg_0";
+const AGG_C_DOMAIN: &[u8] = b"SuperVM_CLSAG_agg_1";
+const ROUND_DOMAIN: &[u8] = b"SuperVM_CLSAG_round";
+const TX_MESSAGE_DOMAIN: &[u8] = b"SuperVM_privacy_tx_0";
+
+/// 生成随机密钥对
+pub fn generate_keypair() -> (SecretKey, PublicKey) {
+    let secret = Scalar::random(&mut OsRng);
+    let public = PublicKey((secret * RISTRETTO_BASEPOINT_POINT).compress().to_bytes());
+    (SecretKey(secret.to_bytes()), public)
+}
+
+/// 由私钥推导公钥 P = x*G
+pub fn public_key_from_secret(secret_key: &SecretKey) -> Result<PublicKey> {
+    let secret = secret_scalar(secret_key)?;
+    Ok(PublicKey(
+        (secret * RISTRETTO_BASEPOINT_POINT).compress().to_bytes(),
+    ))
+}
+
+/// 计算 Key Image: I = x*Hp(P)
+pub fn key_image(secret_key: &SecretKey, public_key: &PublicKey) -> Result<KeyImage> {
+    let secret = secret_scalar(secret_key)?;
+    Ok(KeyImage(
+        (secret * hash_to_point(&public_key.0))
+            .compress()
+            .to_bytes(),
+    ))
+}
+
+fn secret_scalar(secret_key: &SecretKey) -> Result<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(secret_key.0))
+        .ok_or_else(|| anyhow!("non-canonical secret key"))
+}
+
+fn decompress(bytes: &[u8; 32], what: &str) -> Result<RistrettoPoint> {
+    CompressedRistretto(*bytes)
+        .decompress()
+        .ok_or_else(|| anyhow!("invalid {what} encoding"))
+}
+
+fn read_scalar(bytes: &[u8]) -> Option<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(bytes.try_into().ok()?))
+}
+
+/// CLSAG 公共参数: 环公钥、承诺层 (C_i - C')、Key Image 与 D
+struct ClsagContext {
+    ring: Vec<RistrettoPoint>,
+    ring_bytes: Vec<[u8; 32]>,
+    /// 承诺层各成员 C_i - C' (纯授权签名时为单位元)
+    offsets: Vec<RistrettoPoint>,
+    /// 哈希输入: 原始 C_i 与 C' (纯授权签名时为空)
+    commitment_bytes: Vec<[u8; 32]>,
+    pseudo_out: Option<[u8; 32]>,
+    hashed_ring: Vec<RistrettoPoint>,
+    key_image: RistrettoPoint,
+    d: RistrettoPoint,
+}
+
+impl ClsagContext {
+    fn new(
+        ring: &[PublicKey],
+        commitments: Option<(&[Commitment], &Commitment)>,
+        key_image: RistrettoPoint,
+        d: RistrettoPoint,
+    ) -> Result<Self> {
+        ensure!(
+            (MIN_RING_SIZE..=MAX_RING_SIZE).contains(&ring.len()),
+            "ring size {} outside [{MIN_RING_SIZE}, {MAX_RING_SIZE}]",
+            ring.len()
+        );
+        let ring_bytes: Vec<[u8; 32]> = ring.iter().map(|p| p.0).collect();
+        let points = ring_bytes
+            .iter()
+            .map(|p| decompress(p, "ring member"))
+            .collect::<Result<Vec<_>>>()?;
+        let (offsets, commitment_bytes, pseudo_out) = match commitments {
+            Some((ring_commitments, pseudo_out)) => {
+                ensure!(
+                    ring_commitments.len() == ring.len(),
+                    "commitment ring size mismatch"
+                );
+                let pseudo = pseudo_out.to_point()?;
+                let offsets = ring_commitments
+                    .iter()
+                    .map(|c| Ok(c.to_point()? - pseudo))
+                    .collect::<Result<Vec<_>>>()?;
+                (
+                    offsets,
+                    ring_commitments.iter().map(|c| c.0).collect(),
+                    Some(pseudo_out.0),
+                )
+            }
+            None => (
+                vec![RistrettoPoint::identity(); ring.len()],
+                Vec::new(),
+                None,
+            ),
+        };
+        Ok(Self {
+            hashed_ring: ring_bytes.iter().map(|p| hash_to_point(p)).collect(),
+            ring: points,
+            ring_bytes,
+            offsets,
+            commitment_bytes,
+            pseudo_out,
+            key_image,
+            d,
+        })
+    }
+
+    fn hash_keys(&self, domain: &[u8]) -> Sha512 {
+        let mut hasher = Sha512::new();
+        hasher.update(domain);
+        hasher.update((self.ring_bytes.len() as u32).to_le_bytes());
+        for p in &self.ring_bytes {
+            hasher.update(p);
+        }
+        for c in &self.commitment_bytes {
+            hasher.update(c);
+        }
+        if let Some(pseudo) = &self.pseudo_out {
+            hasher.update(pseudo);
+        }
+        hasher
+    }
+
+    /// 聚合系数 (mu_P, mu_C)
+    fn aggregation(&self) -> (Scalar, Scalar) {
+        let agg = |domain: &[u8]| {
+            let mut hasher = self.hash_keys(domain);
+            hasher.update(self.key_image.compress().as_bytes());
+            hasher.update(self.d.compress().as_bytes());
+            Scalar::from_hash(hasher)
+        };
+        (agg(AGG_P_DOMAIN), agg(AGG_C_DOMAIN))
+    }
+
+    fn round_hash(&self, message: &[u8], l: &RistrettoPoint, r: &RistrettoPoint) -> Scalar {
+        let mut hasher = self.hash_keys(ROUND_DOMAIN);
+        hasher.update((message.len() as u64).to_le_bytes());
+        hasher.update(message);
+        hasher.update(l.compress().as_bytes());
+        hasher.update(r.compress().as_bytes());
+        Scalar::from_hash(hasher)
+    }
+
+    /// 由 (c_i, s_i) 推出 c_{i+1}
+    fn next_challenge(
+        &self,
+        message: &[u8],
+        mu: (Scalar, Scalar),
+        i: usize,
+        c: Scalar,
+        s: Scalar,
+    ) -> Scalar {
+        let (mu_p, mu_c) = mu;
+        let w = mu_p * self.ring[i] + mu_c * self.offsets[i];
+        let w_image = mu_p * self.key_image + mu_c * self.d;
+        let l = s * RISTRETTO_BASEPOINT_POINT + c * w;
+        let r = s * self.hashed_ring[i] + c * w_image;
+        self.round_hash(message, &l, &r)
+    }
+
+    fn verify(&self, message: &[u8], c0: Scalar, responses: &[Scalar]) -> bool {
+        if self.key_image.is_identity() {
+            return false;
+        }
+        let mu = self.aggregation();
+        let mut c = c0;
+        for (i, s) in responses.iter().enumerate() {
+            c = self.next_challenge(message, mu, i, c, *s);
+        }
+        c == c0
+    }
+}
+
+/// 解码后的 CLSAG 签名
+struct DecodedSignature {
+    c0: Scalar,
+    responses: Vec<Scalar>,
+    d: RistrettoPoint,
+    pseudo_out: Option<Commitment>,
+}
+
+fn encode_signature(
+    c0: &Scalar,
+    responses: &[Scalar],
+    commitment_layer: Option<(&RistrettoPoint, &Commitment)>,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(32 * (responses.len() + 3));
+    out.extend_from_slice(c0.as_bytes());
+    for s in responses {
+        out.extend_from_slice(s.as_bytes());
+    }
+    if let Some((d, pseudo_out)) = commitment_layer {
+        out.extend_from_slice(d.compress().as_bytes());
+        out.extend_from_slice(&pseudo_out.0);
+    }
+    out
+}
+
+/// 按环大小解码; 编码不规范时返回 None
+fn decode_signature(bytes: &[u8], ring_size: usize) -> Option<DecodedSignature> {
+    let with_commitments = match bytes.len() {
+        n if n == 32 * (ring_size + 1) => false,
+        n if n == 32 * (ring_size + 3) => true,
+        _ => return None,
+    };
+    let mut chunks = bytes.chunks_exact(32);
+    let c0 = read_scalar(chunks.next()?)?;
+    let responses = (0..ring_size)
+        .map(|_| read_scalar(chunks.next()?))
+        .collect::<Option<Vec<_>>>()?;
+    let (d, pseudo_out) = if with_commitments {
+        let d = CompressedRistretto::from_slice(chunks.next()?)
+            .ok()?
+            .decompress()?;
+        let pseudo = Commitment(chunks.next()?.try_into().ok()?);
+        (d, Some(pseudo))
+    } else {
+        (RistrettoPoint::identity(), None)
+    };
+    Some(DecodedSignature {
+        c0,
+        responses,
+        d,
+        pseudo_out,
+    })
+}
 
 /// Ring Signature Signer
 /// 用于生成环签名
 pub struct RingSigner {
-    // TODO: Phase 2.2.1 - 添加密钥管理
+    _private: (),
 }
 
-impl Default for RingSigner { fn default() -> Self { Self::new() } }
+impl Default for RingSigner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl RingSigner {
     /// 创建新的签名器
     pub fn new() -> Self {
-        todo!("Phase 2.2.1: Implement ring signer")
+        Self { _private: () }
     }
 
     /// 生成环签名
@@ -38,28 +296,140 @@ impl RingSigner {
     /// 环签名和 Key Image
     pub fn sign(
         &self,
-        _message: &[u8],
-        _secret_key: &SecretKey,
-        _public_key: &PublicKey,
-        _ring: &[PublicKey],
-        _secret_index: usize,
+        message: &[u8],
+        secret_key: &SecretKey,
+        public_key: &PublicKey,
+        ring: &[PublicKey],
+        secret_index: usize,
+    ) -> Result<RingSignature> {
+        self.sign_inner(message, secret_key, public_key, ring, secret_index, None)
+    }
+
+    /// 生成带承诺层的环签名 (RingCT 输入)
+    ///
+    /// 同时证明: 知道 ring[secret_index] 的私钥, 且 `ring_commitments[secret_index] - pseudo_out`
+    /// 是 `commitment_delta`*G (即伪输出与真实输入承诺的金额相同)
+    ///
+    /// # 参数
+    /// - `ring_commitments`: 环成员各自的金额承诺
+    /// - `pseudo_out`: 伪输出承诺
+    /// - `commitment_delta`: 真实输入与伪输出的致盲因子之差 z
+    #[allow(clippy::too_many_arguments)]
+    pub fn sign_with_commitments(
+        &self,
+        message: &[u8],
+        secret_key: &SecretKey,
+        public_key: &PublicKey,
+        ring: &[PublicKey],
+        secret_index: usize,
+        ring_commitments: &[Commitment],
+        pseudo_out: &Commitment,
+        commitment_delta: &[u8; 32],
+    ) -> Result<RingSignature> {
+        self.sign_inner(
+            message,
+            secret_key,
+            public_key,
+            ring,
+            secret_index,
+            Some((ring_commitments, pseudo_out, commitment_delta)),
+        )
+    }
+
+    fn sign_inner(
+        &self,
+        message: &[u8],
+        secret_key: &SecretKey,
+        public_key: &PublicKey,
+        ring: &[PublicKey],
+        secret_index: usize,
+        commitments: Option<(&[Commitment], &Commitment, &[u8; 32])>,
     ) -> Result<RingSignature> {
-        todo!("Phase 2.2.1: Implement MLSAG/CLSAG signing")
+        ensure!(
+            secret_index < ring.len(),
+            "secret index {secret_index} out of range"
+        );
+        ensure!(
+            ring[secret_index] == *public_key,
+            "public key does not match ring member {secret_index}"
+        );
+        let x = secret_scalar(secret_key)?;
+        ensure!(
+            public_key_from_secret(secret_key)? == *public_key,
+            "secret key does not match public key"
+        );
+        let z = match commitments {
+            Some((_, _, delta)) => Option::from(Scalar::from_canonical_bytes(*delta))
+                .ok_or_else(|| anyhow!("non-canonical commitment delta"))?,
+            None => Scalar::ZERO,
+        };
+
+        let hp = hash_to_point(&public_key.0);
+        let image = x * hp;
+        let d = z * hp;
+        let ctx = ClsagContext::new(ring, commitments.map(|(c, p, _)| (c, p)), image, d)?;
+        ensure!(
+            ctx.offsets[secret_index] == z * RISTRETTO_BASEPOINT_POINT,
+            "commitment delta does not open ring_commitments[secret_index] - pseudo_out"
+        );
+
+        let n = ring.len();
+        let mu = ctx.aggregation();
+        let alpha = Scalar::random(&mut OsRng);
+        let mut responses: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut OsRng)).collect();
+        let mut challenges = vec![Scalar::ZERO; n];
+
+        let mut c = ctx.round_hash(
+            message,
+            &(alpha * RISTRETTO_BASEPOINT_POINT),
+            &(alpha * ctx.hashed_ring[secret_index]),
+        );
+        let mut i = (secret_index + 1) % n;
+        while i != secret_index {
+            challenges[i] = c;
+            c = ctx.next_challenge(message, mu, i, c, responses[i]);
+            i = (i + 1) % n;
+        }
+        challenges[secret_index] = c;
+        responses[secret_index] = alpha - c * (mu.0 * x + mu.1 * z);
+
+        let commitment_layer = commitments.map(|(_, pseudo_out, _)| (&d, pseudo_out));
+        Ok(RingSignature {
+            ring: ring.to_vec(),
+            signature: encode_signature(&challenges[0], &responses, commitment_layer),
+            key_image: KeyImage(image.compress().to_bytes()),
+        })
     }
 }
 
 /// Ring Signature Verifier
 /// 用于验证环签名
 pub struct RingVerifier {
-    // TODO: Phase 2.2.1 - 添加验证逻辑
+    /// 已花费的 Key Image (进程内标记)
+    spent_key_images: RwLock<HashSet<KeyImage>>,
+    /// 持久化登记表 (已提交的隐私交易花费)
+    registry: Option<Arc<KeyImageRegistry>>,
 }
 
-impl Default for RingVerifier { fn default() -> Self { Self::new() } }
+impl Default for RingVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl RingVerifier {
     /// 创建新的验证器
     pub fn new() -> Self {
-        todo!("Phase 2.2.1: Implement ring verifier")
+        Self {
+            spent_key_images: RwLock::new(HashSet::new()),
+            registry: None,
+        }
+    }
+
+    /// 以持久化 Key Image 登记表作为已花费集合的后备
+    pub fn with_registry(mut self, registry: Arc<KeyImageRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
     }
 
     /// 验证环签名
@@ -69,14 +439,81 @@ impl RingVerifier {
     /// - `signature`: 环签名
     ///
     /// # 返回
-    /// 验证是否通过
-    pub fn verify(&self, _message: &[u8], _signature: &RingSignature) -> Result<bool> {
-        todo!("Phase 2.2.1: Implement MLSAG/CLSAG verification")
+    /// 验证是否通过 (带承诺层的签名须经 [`Self::verify_with_commitments`] 验证)
+    pub fn verify(&self, message: &[u8], signature: &RingSignature) -> Result<bool> {
+        self.verify_inner(message, signature, None)
+    }
+
+    /// 验证带承诺层的环签名; `ring_commitments` 为环成员各自的金额承诺
+    ///
+    /// 通过时返回签名携带的伪输出承诺 (供余额校验), 否则返回 None
+    pub fn verify_with_commitments(
+        &self,
+        message: &[u8],
+        signature: &RingSignature,
+        ring_commitments: &[Commitment],
+    ) -> Result<Option<Commitment>> {
+        let Some(decoded) = decode_signature(&signature.signature, signature.ring.len()) else {
+            return Ok(None);
+        };
+        let Some(pseudo_out) = decoded.pseudo_out else {
+            return Ok(None);
+        };
+        Ok(self
+            .verify_inner(message, signature, Some(ring_commitments))?
+            .then_some(pseudo_out))
+    }
+
+    fn verify_inner(
+        &self,
+        message: &[u8],
+        signature: &RingSignature,
+        ring_commitments: Option<&[Commitment]>,
+    ) -> Result<bool> {
+        let Some(decoded) = decode_signature(&signature.signature, signature.ring.len()) else {
+            return Ok(false);
+        };
+        let commitments = match (ring_commitments, &decoded.pseudo_out) {
+            (Some(ring_commitments), Some(pseudo_out)) => Some((ring_commitments, pseudo_out)),
+            (None, None) => None,
+            _ => return Ok(false),
+        };
+        let Some(image) = CompressedRistretto(signature.key_image.0).decompress() else {
+            return Ok(false);
+        };
+        let ctx = ClsagContext::new(&signature.ring, commitments, image, decoded.d)?;
+        Ok(ctx.verify(message, decoded.c0, &decoded.responses))
+    }
+
+    /// 批量验证 (并行), 全部通过时返回 true
+    ///
+    /// 同一批次内出现重复 Key Image 视为双花, 返回 false
+    pub fn verify_batch(&self, items: &[(&[u8], &RingSignature)]) -> Result<bool> {
+        let mut images = HashSet::with_capacity(items.len());
+        if !items.iter().all(|(_, sig)| images.insert(sig.key_image)) {
+            return Ok(false);
+        }
+        let results = items
+            .par_iter()
+            .map(|(message, signature)| self.verify(message, signature))
+            .collect::<Result<Vec<bool>>>()?;
+        Ok(results.into_iter().all(|ok| ok))
     }
 
     /// 检查 Key Image 是否已使用 (防止双花)
-    pub fn is_key_image_spent(&self, _key_image: &KeyImage) -> bool {
-        todo!("Phase 2.2.1: Implement key image tracking")
+    ///
+    /// 登记表查询出错时按已花费处理 (拒绝优先)
+    pub fn is_key_image_spent(&self, key_image: &KeyImage) -> bool {
+        self.spent_key_images.read().contains(key_image)
+            || self
+                .registry
+                .as_ref()
+                .is_some_and(|r| r.is_spent(key_image).unwrap_or(true))
+    }
+
+    /// 标记 Key Image 已花费; 已花费过时返回 false
+    pub fn mark_key_image_spent(&self, key_image: &KeyImage) -> bool {
+        self.spent_key_images.write().insert(*key_image)
     }
 }
 
@@ -88,28 +525,222 @@ impl RingVerifier {
 /// - `total_outputs`: 可选择的总输出数
 ///
 /// # 返回
-/// 环成员索引列表
+/// 环成员索引列表 (升序, 含 real_index, 不泄露真实输出位置)
 pub fn select_ring_members(
-    _real_index: usize,
-    _ring_size: usize,
-    _total_outputs: usize,
+    real_index: usize,
+    ring_size: usize,
+    total_outputs: usize,
 ) -> Result<Vec<usize>> {
-    todo!("Phase 2.2.1: Implement ring member selection")
+    if real_index >= total_outputs {
+        bail!("real index {real_index} out of range ({total_outputs} outputs)");
+    }
+    if !(MIN_RING_SIZE..=MAX_RING_SIZE).contains(&ring_size) || ring_size > total_outputs {
+        bail!("cannot select a ring of {ring_size} from {total_outputs} outputs");
+    }
+    let mut members: Vec<usize> = sample(&mut OsRng, total_outputs - 1, ring_size - 1)
+        .into_iter()
+        .map(|i| if i >= real_index { i + 1 } else { i })
+        .collect();
+    members.push(real_index);
+    members.sort_unstable();
+    Ok(members)
+}
+
+/// 隐私交易的签名消息: 各输入的环签名均对其签名
+///
+/// 覆盖版本、手续费、额外数据、各输入的 Key Image 与承诺、各输出的全部字段;
+/// 环成员与承诺层已由 CLSAG 自身的哈希覆盖, 签名字节本身不在消息中
+pub fn transaction_signing_message(tx: &PrivacyTransaction) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    let bytes = |hasher: &mut Sha512, data: &[u8]| {
+        hasher.update((data.len() as u64).to_le_bytes());
+        hasher.update(data);
+    };
+    hasher.update(TX_MESSAGE_DOMAIN);
+    hasher.update(tx.version.to_le_bytes());
+    hasher.update(tx.fee.to_le_bytes());
+    bytes(&mut hasher, &tx.extra);
+    hasher.update((tx.inputs.len() as u32).to_le_bytes());
+    for input in &tx.inputs {
+        hasher.update(input.key_image.0);
+        hasher.update(input.commitment.0);
+    }
+    hasher.update((tx.outputs.len() as u32).to_le_bytes());
+    for output in &tx.outputs {
+        hasher.update(output.stealth_address.public_key.0);
+        hasher.update(output.stealth_address.tx_public_key.0);
+        hasher.update([output.stealth_address.view_tag]);
+        hasher.update(output.commitment.0);
+        bytes(&mut hasher, &output.range_proof.proof);
+        bytes(&mut hasher, &output.encrypted_amount);
+    }
+    hasher.finalize().into()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::privacy::commitment::CommitmentGenerator;
+
+    fn ring_of(size: usize) -> (Vec<(SecretKey, PublicKey)>, Vec<PublicKey>) {
+        let keys: Vec<_> = (0..size).map(|_| generate_keypair()).collect();
+        let ring = keys.iter().map(|(_, p)| *p).collect();
+        (keys, ring)
+    }
+
+    #[test]
+    fn test_sign_verify_and_linkability() {
+        let (keys, ring) = ring_of(5);
+        let signer = RingSigner::new();
+        let verifier = RingVerifier::new();
+        let (sk, pk) = &keys[2];
+
+        let sig = signer.sign(b"tx-1", sk, pk, &ring, 2).unwrap();
+        assert_eq!(sig.signature.len(), 32 * (ring.len() + 1));
+        assert_eq!(sig.key_image, key_image(sk, pk).unwrap());
+        assert!(verifier.verify(b"tx-1", &sig).unwrap());
+        assert!(!verifier.verify(b"tx-2", &sig).unwrap());
+
+        // 同一私钥的两次签名 Key Image 相同 (可链接), 不同成员位置签名仍可验证
+        let mut other_ring = ring_of(4).1;
+        other_ring.insert(0, *pk);
+        let sig2 = signer.sign(b"tx-3", sk, pk, &other_ring, 0).unwrap();
+        assert!(verifier.verify(b"tx-3", &sig2).unwrap());
+        assert_eq!(sig.key_image, sig2.key_image);
+
+        // 篡改 Key Image / 环成员 / 响应均失败
+        let mut forged = sig.clone();
+        forged.key_image = key_image(&keys[0].0, &keys[0].1).unwrap();
+        assert!(!verifier.verify(b"tx-1", &forged).unwrap());
+        let mut forged = sig.clone();
+        forged.ring[0] = generate_keypair().1;
+        assert!(!verifier.verify(b"tx-1", &forged).unwrap());
+        let mut forged = sig.clone();
+        forged.signature[40] ^= 1;
+        assert!(!verifier.verify(b"tx-1", &forged).unwrap());
+        // 非规范标量编码被拒绝
+        let mut forged = sig;
+        forged.signature[32..64].copy_from_slice(&[0xff; 32]);
+        assert!(!verifier.verify(b"tx-1", &forged).unwrap());
+    }
+
+    #[test]
+    fn test_signer_rejects_mismatched_keys() {
+        let (keys, ring) = ring_of(3);
+        let signer = RingSigner::new();
+        assert!(signer.sign(b"m", &keys[0].0, &keys[0].1, &ring, 1).is_err());
+        assert!(signer.sign(b"m", &keys[1].0, &keys[0].1, &ring, 0).is_err());
+        assert!(signer
+            .sign(b"m", &keys[0].0, &keys[0].1, &ring[..2], 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sign_with_commitments() {
+        let (keys, ring) = ring_of(4);
+        let generator = CommitmentGenerator::new();
+        let blindings: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut OsRng)).collect();
+        let ring_commitments: Vec<Commitment> = blindings
+            .iter()
+            .zip([10u64, 20, 30, 40])
+            .map(|(r, v)| generator.commit(v, &r.to_bytes()).unwrap())
+            .collect();
+
+        // 真实输入 index 1 (金额 20), 伪输出以新的致盲因子承诺同一金额
+        let pseudo_blinding = Scalar::random(&mut OsRng);
+        let pseudo_out = generator.commit(20, &pseudo_blinding.to_bytes()).unwrap();
+        let delta = (blindings[1] - pseudo_blinding).to_bytes();
+        let signer = RingSigner::new();
+        let sig = signer
+            .sign_with_commitments(
+                b"ringct",
+                &keys[1].0,
+                &keys[1].1,
+                &ring,
+                1,
+                &ring_commitments,
+                &pseudo_out,
+                &delta,
+            )
+            .unwrap();
+        assert_eq!(sig.signature.len(), 32 * (ring.len() + 3));
+
+        let verifier = RingVerifier::new();
+        assert_eq!(
+            verifier
+                .verify_with_commitments(b"ringct", &sig, &ring_commitments)
+                .unwrap(),
+            Some(pseudo_out)
+        );
+        assert!(!verifier.verify(b"ringct", &sig).unwrap());
+        let mut tampered = ring_commitments.clone();
+        tampered.swap(0, 2);
+        assert_eq!(
+            verifier
+                .verify_with_commitments(b"ringct", &sig, &tampered)
+                .unwrap(),
+            None
+        );
+
+        // 伪输出金额与真实输入不同: 签名者无法给出 delta
+        let wrong_pseudo = generator.commit(21, &pseudo_blinding.to_bytes()).unwrap();
+        assert!(signer
+            .sign_with_commitments(
+                b"ringct",
+                &keys[1].0,
+                &keys[1].1,
+                &ring,
+                1,
+                &ring_commitments,
+                &wrong_pseudo,
+                &delta,
+            )
+            .is_err());
+    }
 
     #[test]
-    #[should_panic(expected = "not yet implemented")]
-    fn test_ring_signer_placeholder() {
-        let _signer = RingSigner::new();
+    fn test_batch_verify_and_key_image_tracking() {
+        let signer = RingSigner::new();
+        let verifier = RingVerifier::new();
+        let sigs: Vec<(Vec<u8>, RingSignature)> = (0..4)
+            .map(|i| {
+                let (keys, ring) = ring_of(3);
+                let msg = format!("tx-{i}").into_bytes();
+                let sig = signer
+                    .sign(&msg, &keys[i % 3].0, &keys[i % 3].1, &ring, i % 3)
+                    .unwrap();
+                (msg, sig)
+            })
+            .collect();
+        let items: Vec<(&[u8], &RingSignature)> =
+            sigs.iter().map(|(m, s)| (m.as_slice(), s)).collect();
+        assert!(verifier.verify_batch(&items).unwrap());
+
+        let mut with_bad = items.clone();
+        with_bad[3].0 = b"other";
+        assert!(!verifier.verify_batch(&with_bad).unwrap());
+        let mut with_dup = items.clone();
+        with_dup.push(items[0]);
+        assert!(!verifier.verify_batch(&with_dup).unwrap());
+
+        let image = sigs[0].1.key_image;
+        assert!(!verifier.is_key_image_spent(&image));
+        assert!(verifier.mark_key_image_spent(&image));
+        assert!(verifier.is_key_image_spent(&image));
+        assert!(!verifier.mark_key_image_spent(&image));
     }
 
     #[test]
-    #[should_panic(expected = "not yet implemented")]
-    fn test_ring_verifier_placeholder() {
-        let _verifier = RingVerifier::new();
+    fn test_select_ring_members() {
+        for real in [0, 7, 99] {
+            let members = select_ring_members(real, 11, 100).unwrap();
+            assert_eq!(members.len(), 11);
+            assert!(members.contains(&real));
+            assert!(members.windows(2).all(|w| w[0] < w[1]));
+            assert!(members.iter().all(|&m| m < 100));
+        }
+        assert!(select_ring_members(0, 11, 10).is_err());
+        assert!(select_ring_members(10, 3, 10).is_err());
+        assert!(select_ring_members(0, 2, 10).is_err());
     }
 }
@@ -414,6 +414,8 @@ impl SolidityVerifierGenerator {
     }
 
     /// 将 BN254 G2 点转换为 Solidity 格式
+    ///
+    /// EIP-197 配对预编译要求 Fq2 按 (虚部 c1, 实部 c0) 排列
     fn g2_to_solidity_bn(&self, point: &BnG2Affine) -> ([String; 2], [String; 2]) {
         let x = &point.x;
         let y = &point.y;
@@ -426,8 +428,8 @@ impl SolidityVerifierGenerator {
         y.c0.serialize_uncompressed(&mut y_c0_bytes).unwrap();
         y.c1.serialize_uncompressed(&mut y_c1_bytes).unwrap();
         (
-            [self.bytes_to_uint256(&x_c0_bytes), self.bytes_to_uint256(&x_c1_bytes)],
-            [self.bytes_to_uint256(&y_c0_bytes), self.bytes_to_uint256(&y_c1_bytes)],
+            [self.bytes_to_uint256(&x_c1_bytes), self.bytes_to_uint256(&x_c0_bytes)],
+            [self.bytes_to_uint256(&y_c1_bytes), self.bytes_to_uint256(&y_c0_bytes)],
         )
     }
 
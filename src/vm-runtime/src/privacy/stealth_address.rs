@@ -5,23 +5,98 @@
 // 架构师: KING XU (CHINA)
 // Phase 2.2.2: Stealth Addresses (Week 13-16)
 //
-// 实现一次性地址生成,保护接收方隐私
+// 实现一次性地址生成,保护接收方隐私 (CryptoNote 双密钥方案, Ristretto)
+// - 钱包: 花费密钥 (b, B = b*G), 查看密钥 (a, A = a*G)
+// - 发送方: 每个输出随机 r, R = r*G, 共享秘密 D = r*A, 一次性公钥 P = Hs(D)*G + B
+// - 接收方: D = a*R, 检查 P == Hs(D)*G + B, 一次性私钥 x = Hs(D) + b
+// - View tag: H("SuperVM_view_tag" || D) 首字节, 不匹配的输出省去 Hs(D)*G + B 的计算 (约 255/256 输出被快速排除)
+// Ristretto 为素数阶群, 无需 Monero 的 8 倍余因子
+// r 必须每个输出独立: 同一 r 发往同一钱包的两个输出 D 相同, 一次性公钥与金额掩码也相同
+// (两个输出可被关联、只能花费其一, 两份密文异或即泄露两笔明文之差); 此处不做检查, 由调用方保证
+// 一次性密钥与 ring_signature 的密钥格式相同, 扫描得到的私钥可直接用于环签名
+//
+// 加密金额 (PrivacyOutput.encrypted_amount, 40 bytes):
+//   (amount_le (8) || blinding (32)) XOR H("SuperVM_amount_mask" || D)[..40]
+// 接收方解密后须以 Pedersen 承诺校验打开 (commitment == amount*H + blinding*G), 校验同时起到完整性保护作用
 
+use crate::privacy::commitment::CommitmentGenerator;
+use crate::privacy::ring_signature::generate_keypair;
 use crate::privacy::types::*;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rayon::prelude::*;
+use sha2::{Digest, Sha512};
+
+const DERIVATION_DOMAIN: &[u8] = b"SuperVM_stealth_derivation";
+const VIEW_TAG_DOMAIN: &[u8] = b"SuperVM_view_tag";
+const AMOUNT_MASK_DOMAIN: &[u8] = b"SuperVM_amount_mask";
+
+/// 加密金额长度: 金额 (8) + 致盲因子 (32)
+pub const ENCRYPTED_AMOUNT_LEN: usize = 40;
+
+/// 已解密的金额承诺打开
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountOpening {
+    /// 金额
+    pub amount: u64,
+    /// 致盲因子 (规范编码标量)
+    pub blinding_factor: [u8; 32],
+}
+
+fn scalar_from_secret(secret: &SecretKey) -> Result<Scalar> {
+    Option::from(Scalar::from_canonical_bytes(secret.0))
+        .ok_or_else(|| anyhow!("non-canonical secret key"))
+}
+
+fn point_from_public(public: &PublicKey) -> Result<RistrettoPoint> {
+    CompressedRistretto(public.0)
+        .decompress()
+        .ok_or_else(|| anyhow!("invalid public key encoding"))
+}
+
+/// 共享秘密 D 推导的一次性密钥偏移 Hs(D)
+fn derivation_scalar(shared: &RistrettoPoint) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(DERIVATION_DOMAIN);
+    hasher.update(shared.compress().as_bytes());
+    Scalar::from_hash(hasher)
+}
+
+/// 共享秘密 D 的 view tag
+fn view_tag(shared: &RistrettoPoint) -> u8 {
+    let mut hasher = Sha512::new();
+    hasher.update(VIEW_TAG_DOMAIN);
+    hasher.update(shared.compress().as_bytes());
+    hasher.finalize()[0]
+}
+
+/// 加密 / 解密金额 (异或掩码, 两个方向相同)
+fn apply_amount_mask(shared: &RistrettoPoint, data: &[u8; ENCRYPTED_AMOUNT_LEN]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(AMOUNT_MASK_DOMAIN);
+    hasher.update(shared.compress().as_bytes());
+    let mask = hasher.finalize();
+    data.iter().zip(mask.iter()).map(|(d, m)| d ^ m).collect()
+}
 
 /// Stealth Address Generator
 /// 用于生成一次性接收地址
 pub struct StealthAddressGenerator {
-    // TODO: Phase 2.2.2 - 添加 ECDH 密钥交换
+    _private: (),
 }
 
-impl Default for StealthAddressGenerator { fn default() -> Self { Self::new() } }
+impl Default for StealthAddressGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl StealthAddressGenerator {
     /// 创建新的生成器
     pub fn new() -> Self {
-        todo!("Phase 2.2.2: Implement stealth address generator")
+        Self { _private: () }
     }
 
     /// 生成隐形地址
@@ -29,30 +104,136 @@ impl StealthAddressGenerator {
     /// # 参数
     /// - `receiver_spend_public`: 接收方的花费公钥
     /// - `receiver_view_public`: 接收方的查看公钥
-    /// - `tx_secret`: 交易私钥 (随机生成)
+    /// - `tx_secret`: 交易私钥 (随机生成, 每个输出独立)
     ///
     /// # 返回
     /// 隐形地址和交易公钥
+    ///
+    /// 对同一钱包复用 `tx_secret` 得到完全相同的一次性公钥 (及 [`Self::encrypt_amount`] 的掩码),
+    /// 多个输出须各自使用新的交易私钥, 或直接用 [`Self::generate_random`] / [`Self::generate_output`]
     pub fn generate(
         &self,
-        _receiver_spend_public: &PublicKey,
-        _receiver_view_public: &PublicKey,
-        _tx_secret: &SecretKey,
+        receiver_spend_public: &PublicKey,
+        receiver_view_public: &PublicKey,
+        tx_secret: &SecretKey,
     ) -> Result<StealthAddress> {
-        todo!("Phase 2.2.2: Implement stealth address generation")
+        let spend = point_from_public(receiver_spend_public)?;
+        let view = point_from_public(receiver_view_public)?;
+        let r = scalar_from_secret(tx_secret)?;
+
+        let shared = r * view;
+        let one_time = derivation_scalar(&shared) * RISTRETTO_BASEPOINT_POINT + spend;
+        Ok(StealthAddress {
+            public_key: PublicKey(one_time.compress().to_bytes()),
+            tx_public_key: PublicKey((r * RISTRETTO_BASEPOINT_POINT).compress().to_bytes()),
+            view_tag: view_tag(&shared),
+        })
+    }
+
+    /// 以随机交易私钥生成隐形地址
+    ///
+    /// 交易私钥随即丢弃, 之后无法再为该输出加密金额; 需要金额密文时用 [`Self::generate_output`]
+    pub fn generate_random(
+        &self,
+        receiver_spend_public: &PublicKey,
+        receiver_view_public: &PublicKey,
+    ) -> Result<StealthAddress> {
+        let (tx_secret, _) = generate_keypair();
+        self.generate(receiver_spend_public, receiver_view_public, &tx_secret)
+    }
+
+    /// 以随机交易私钥生成隐形地址, 并用同一私钥加密金额与致盲因子
+    ///
+    /// # 返回
+    /// 隐形地址和 `PrivacyOutput::encrypted_amount` (承诺须以同一 `blinding_factor` 生成)
+    pub fn generate_output(
+        &self,
+        receiver_spend_public: &PublicKey,
+        receiver_view_public: &PublicKey,
+        amount: u64,
+        blinding_factor: &[u8; 32],
+    ) -> Result<(StealthAddress, Vec<u8>)> {
+        let (tx_secret, _) = generate_keypair();
+        let address = self.generate(receiver_spend_public, receiver_view_public, &tx_secret)?;
+        let encrypted =
+            self.encrypt_amount(receiver_view_public, &tx_secret, amount, blinding_factor)?;
+        Ok((address, encrypted))
+    }
+
+    /// 为输出加密金额与致盲因子 (填入 `PrivacyOutput::encrypted_amount`)
+    ///
+    /// `tx_secret` 须与生成该输出隐形地址时相同, 接收方以查看私钥解密
+    pub fn encrypt_amount(
+        &self,
+        receiver_view_public: &PublicKey,
+        tx_secret: &SecretKey,
+        amount: u64,
+        blinding_factor: &[u8; 32],
+    ) -> Result<Vec<u8>> {
+        let view = point_from_public(receiver_view_public)?;
+        let r = scalar_from_secret(tx_secret)?;
+        let mut plain = [0u8; ENCRYPTED_AMOUNT_LEN];
+        plain[..8].copy_from_slice(&amount.to_le_bytes());
+        plain[8..].copy_from_slice(blinding_factor);
+        Ok(apply_amount_mask(&(r * view), &plain))
     }
 }
 
 /// Stealth Address Scanner
 /// 用于扫描区块寻找属于自己的交易
 pub struct StealthAddressScanner {
-    // TODO: Phase 2.2.2 - 添加扫描逻辑
+    /// 查看私钥 a
+    view_secret: Scalar,
+    /// 花费公钥 B
+    spend_public: RistrettoPoint,
+    /// 花费私钥 b (仅查看钱包为 None, 只能识别输出而不能恢复私钥)
+    spend_secret: Option<Scalar>,
 }
 
 impl StealthAddressScanner {
     /// 创建新的扫描器
-    pub fn new(_wallet_keys: &WalletKeys) -> Self {
-        todo!("Phase 2.2.2: Implement stealth address scanner")
+    pub fn new(wallet_keys: &WalletKeys) -> Result<Self> {
+        let mut scanner = Self::view_only(&wallet_keys.view_secret, &wallet_keys.spend_public)?;
+        let spend_secret = scalar_from_secret(&wallet_keys.spend_secret)?;
+        if spend_secret * RISTRETTO_BASEPOINT_POINT != scanner.spend_public {
+            bail!("spend secret does not match spend public key");
+        }
+        scanner.spend_secret = Some(spend_secret);
+        Ok(scanner)
+    }
+
+    /// 创建仅查看扫描器 (钱包服务只持有查看私钥与花费公钥)
+    pub fn view_only(view_secret: &SecretKey, spend_public: &PublicKey) -> Result<Self> {
+        Ok(Self {
+            view_secret: scalar_from_secret(view_secret)?,
+            spend_public: point_from_public(spend_public)?,
+            spend_secret: None,
+        })
+    }
+
+    /// 检查输出是否属于本钱包, 属于时返回一次性密钥偏移 Hs(D)
+    ///
+    /// 交易公钥编码非法的输出视为不属于本钱包
+    fn match_output(&self, output: &PrivacyOutput) -> Option<Scalar> {
+        self.match_shared(output).map(|(offset, _)| offset)
+    }
+
+    /// 同 [`Self::match_output`], 另返回共享秘密 D
+    fn match_shared(&self, output: &PrivacyOutput) -> Option<(Scalar, RistrettoPoint)> {
+        let address = &output.stealth_address;
+        let tx_public = CompressedRistretto(address.tx_public_key.0).decompress()?;
+        let shared = self.view_secret * tx_public;
+        if view_tag(&shared) != address.view_tag {
+            return None;
+        }
+        let offset = derivation_scalar(&shared);
+        let expected = offset * RISTRETTO_BASEPOINT_POINT + self.spend_public;
+        (expected.compress().to_bytes() == address.public_key.0).then_some((offset, shared))
+    }
+
+    /// 检查输出是否属于本钱包 (仅需查看私钥)
+    pub fn owns_output(&self, output: &PrivacyOutput) -> bool {
+        self.match_output(output).is_some()
     }
 
     /// 扫描交易输出,检查是否属于自己
@@ -61,45 +242,347 @@ impl StealthAddressScanner {
     /// - `output`: 交易输出
     ///
     /// # 返回
-    /// 如果属于自己,返回用于花费的私钥
-    pub fn scan_output(&self, _output: &PrivacyOutput) -> Result<Option<SecretKey>> {
-        todo!("Phase 2.2.2: Implement output scanning")
+    /// 如果属于自己,返回用于花费的私钥 (一次性私钥 x = Hs(D) + b); 仅查看扫描器返回错误
+    pub fn scan_output(&self, output: &PrivacyOutput) -> Result<Option<SecretKey>> {
+        let spend_secret = self
+            .spend_secret
+            .ok_or_else(|| anyhow!("view-only scanner cannot recover spend keys"))?;
+        Ok(self
+            .match_output(output)
+            .map(|offset| SecretKey((offset + spend_secret).to_bytes())))
     }
 
-    /// 批量扫描多个输出
-    pub fn scan_outputs(&self, _outputs: &[PrivacyOutput]) -> Result<Vec<Option<SecretKey>>> {
-        todo!("Phase 2.2.2: Implement batch scanning")
+    /// 解密输出金额并校验承诺打开 (仅需查看私钥)
+    ///
+    /// 输出不属于本钱包时返回 None; 属于本钱包但密文长度错误或不能打开 `commitment` 时返回错误
+    pub fn decrypt_amount(&self, output: &PrivacyOutput) -> Result<Option<AmountOpening>> {
+        let Some((_, shared)) = self.match_shared(output) else {
+            return Ok(None);
+        };
+        let encrypted: &[u8; ENCRYPTED_AMOUNT_LEN] = output
+            .encrypted_amount
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("encrypted amount must be {ENCRYPTED_AMOUNT_LEN} bytes"))?;
+        let plain = apply_amount_mask(&shared, encrypted);
+        let opening = AmountOpening {
+            amount: u64::from_le_bytes(plain[..8].try_into()?),
+            blinding_factor: plain[8..].try_into()?,
+        };
+        let opens = CommitmentGenerator::new()
+            .verify_opening(&output.commitment, opening.amount, &opening.blinding_factor)
+            .unwrap_or(false);
+        if !opens {
+            bail!("decrypted amount does not open the output commitment");
+        }
+        Ok(Some(opening))
+    }
+
+    /// 批量扫描多个输出 (并行), 结果与输入一一对应
+    pub fn scan_outputs(&self, outputs: &[PrivacyOutput]) -> Result<Vec<Option<SecretKey>>> {
+        outputs
+            .par_iter()
+            .map(|output| self.scan_output(output))
+            .collect()
+    }
+
+    /// 并行扫描, 仅返回属于本钱包的输出索引 (仅需查看私钥)
+    pub fn find_owned_outputs(&self, outputs: &[PrivacyOutput]) -> Vec<usize> {
+        outputs
+            .par_iter()
+            .enumerate()
+            .filter_map(|(index, output)| self.owns_output(output).then_some(index))
+            .collect()
     }
 }
 
 /// 生成查看密钥对 (用于扫描)
 pub fn generate_view_keypair() -> Result<(SecretKey, PublicKey)> {
-    todo!("Phase 2.2.2: Implement view keypair generation")
+    Ok(generate_keypair())
 }
 
 /// 生成花费密钥对 (用于签名)
 pub fn generate_spend_keypair() -> Result<(SecretKey, PublicKey)> {
-    todo!("Phase 2.2.2: Implement spend keypair generation")
+    Ok(generate_keypair())
 }
 
 /// 生成完整的钱包密钥
 pub fn generate_wallet_keys() -> Result<WalletKeys> {
-    todo!("Phase 2.2.2: Implement wallet key generation")
+    let (spend_secret, spend_public) = generate_spend_keypair()?;
+    let (view_secret, view_public) = generate_view_keypair()?;
+    Ok(WalletKeys {
+        spend_secret,
+        spend_public,
+        view_secret,
+        view_public,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::privacy::ring_signature::{
+        generate_keypair, public_key_from_secret, RingSigner, RingVerifier,
+    };
+
+    fn output_to(address: StealthAddress) -> PrivacyOutput {
+        PrivacyOutput {
+            stealth_address: address,
+            commitment: Commitment([0; 32]),
+            range_proof: RangeProof { proof: vec![] },
+            encrypted_amount: vec![],
+        }
+    }
+
+    #[test]
+    fn test_view_tag_vectors() {
+        // D = i*G (i = 1, 2, 3): H("SuperVM_view_tag" || D) 首字节
+        let tags: Vec<u8> = (1..=3u64)
+            .map(|i| view_tag(&(Scalar::from(i) * RISTRETTO_BASEPOINT_POINT)))
+            .collect();
+        assert_eq!(tags, vec![163, 253, 229]);
+    }
+
+    #[test]
+    fn test_generate_and_scan_recovers_one_time_key() {
+        let wallet = generate_wallet_keys().unwrap();
+        let generator = StealthAddressGenerator::new();
+        let address = generator
+            .generate_random(&wallet.spend_public, &wallet.view_public)
+            .unwrap();
+        // 同一接收方的两个输出互不关联
+        let other = generator
+            .generate_random(&wallet.spend_public, &wallet.view_public)
+            .unwrap();
+        assert_ne!(address.public_key, other.public_key);
+        assert_ne!(address.public_key, wallet.spend_public);
+
+        let scanner = StealthAddressScanner::new(&wallet).unwrap();
+        let output = output_to(address.clone());
+        let secret = scanner
+            .scan_output(&output)
+            .unwrap()
+            .expect("output is ours");
+        assert_eq!(public_key_from_secret(&secret).unwrap(), address.public_key);
+
+        // 恢复的一次性私钥可用于环签名
+        let mut ring: Vec<PublicKey> = (0..3).map(|_| generate_keypair().1).collect();
+        ring[1] = address.public_key;
+        let sig = RingSigner::new()
+            .sign(b"spend", &secret, &address.public_key, &ring, 1)
+            .unwrap();
+        assert!(RingVerifier::new().verify(b"spend", &sig).unwrap());
+
+        // 其它钱包扫描不到; 篡改 view tag 或一次性公钥均失配
+        let stranger = StealthAddressScanner::new(&generate_wallet_keys().unwrap()).unwrap();
+        assert!(stranger.scan_output(&output).unwrap().is_none());
+        let mut tagged = output.clone();
+        tagged.stealth_address.view_tag ^= 1;
+        assert!(scanner.scan_output(&tagged).unwrap().is_none());
+        let mut redirected = output;
+        redirected.stealth_address.public_key = other.public_key;
+        assert!(scanner.scan_output(&redirected).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_view_only_and_parallel_scanning() {
+        let wallet = generate_wallet_keys().unwrap();
+        let stranger = generate_wallet_keys().unwrap();
+        let generator = StealthAddressGenerator::new();
+        let outputs: Vec<PrivacyOutput> = (0..64)
+            .map(|i| {
+                let to = if i % 5 == 0 { &wallet } else { &stranger };
+                output_to(
+                    generator
+                        .generate_random(&to.spend_public, &to.view_public)
+                        .unwrap(),
+                )
+            })
+            .collect();
+
+        let view_only =
+            StealthAddressScanner::view_only(&wallet.view_secret, &wallet.spend_public).unwrap();
+        let owned = view_only.find_owned_outputs(&outputs);
+        assert_eq!(owned, (0..64).filter(|i| i % 5 == 0).collect::<Vec<_>>());
+        assert!(view_only.scan_output(&outputs[0]).is_err());
+
+        let scanned = StealthAddressScanner::new(&wallet)
+            .unwrap()
+            .scan_outputs(&outputs)
+            .unwrap();
+        assert_eq!(scanned.iter().filter(|s| s.is_some()).count(), owned.len());
+        for index in owned {
+            let secret = scanned[index].as_ref().unwrap();
+            assert_eq!(
+                public_key_from_secret(secret).unwrap(),
+                outputs[index].stealth_address.public_key
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_mismatched_wallet_keys() {
+        let mut wallet = generate_wallet_keys().unwrap();
+        wallet.spend_secret = generate_keypair().0;
+        assert!(StealthAddressScanner::new(&wallet).is_err());
+    }
 
     #[test]
-    #[should_panic(expected = "not yet implemented")]
-    fn test_stealth_generator_placeholder() {
-        let _generator = StealthAddressGenerator::new();
+    fn test_encrypted_amount_roundtrip() {
+        let wallet = generate_wallet_keys().unwrap();
+        let generator = StealthAddressGenerator::new();
+        let commitments = CommitmentGenerator::new();
+        let (tx_secret, _) = generate_keypair();
+        let blinding = commitments.generate_blinding_factor();
+
+        let mut output = output_to(
+            generator
+                .generate(&wallet.spend_public, &wallet.view_public, &tx_secret)
+                .unwrap(),
+        );
+        output.commitment = commitments.commit(4_200, &blinding).unwrap();
+        output.encrypted_amount = generator
+            .encrypt_amount(&wallet.view_public, &tx_secret, 4_200, &blinding)
+            .unwrap();
+        assert_eq!(output.encrypted_amount.len(), ENCRYPTED_AMOUNT_LEN);
+
+        let view_only =
+            StealthAddressScanner::view_only(&wallet.view_secret, &wallet.spend_public).unwrap();
+        let opening = view_only.decrypt_amount(&output).unwrap().unwrap();
+        assert_eq!(
+            opening,
+            AmountOpening {
+                amount: 4_200,
+                blinding_factor: blinding
+            }
+        );
+
+        // 非接收方无法解密 (输出不属于其钱包)
+        let stranger = generate_wallet_keys().unwrap();
+        let stranger_scanner = StealthAddressScanner::new(&stranger).unwrap();
+        assert_eq!(stranger_scanner.decrypt_amount(&output).unwrap(), None);
+
+        // 密文被篡改或长度错误: 承诺打开校验失败
+        let mut tampered = output.clone();
+        tampered.encrypted_amount[0] ^= 1;
+        assert!(view_only.decrypt_amount(&tampered).is_err());
+        let mut truncated = output.clone();
+        truncated.encrypted_amount.pop();
+        assert!(view_only.decrypt_amount(&truncated).is_err());
+
+        // 以其它钱包的查看公钥加密: 接收方解出的金额打不开承诺
+        let mut misaddressed = output;
+        misaddressed.encrypted_amount = generator
+            .encrypt_amount(&stranger.view_public, &tx_secret, 4_200, &blinding)
+            .unwrap();
+        assert!(view_only.decrypt_amount(&misaddressed).is_err());
     }
 
     #[test]
-    #[should_panic(expected = "not yet implemented")]
-    fn test_wallet_keys_placeholder() {
-        let _ = generate_wallet_keys();
+    fn test_generate_output_encrypts_amount_for_receiver() {
+        let wallet = generate_wallet_keys().unwrap();
+        let generator = StealthAddressGenerator::new();
+        let commitments = CommitmentGenerator::new();
+        let blinding = commitments.generate_blinding_factor();
+
+        let (address, encrypted) = generator
+            .generate_output(&wallet.spend_public, &wallet.view_public, 900, &blinding)
+            .unwrap();
+        let mut output = output_to(address);
+        output.commitment = commitments.commit(900, &blinding).unwrap();
+        output.encrypted_amount = encrypted;
+
+        let scanner = StealthAddressScanner::new(&wallet).unwrap();
+        assert!(scanner.scan_output(&output).unwrap().is_some());
+        assert_eq!(
+            scanner.decrypt_amount(&output).unwrap(),
+            Some(AmountOpening {
+                amount: 900,
+                blinding_factor: blinding
+            })
+        );
+
+        // 每次调用使用新的交易私钥: 同一金额的两个输出互不关联
+        let (other, other_encrypted) = generator
+            .generate_output(&wallet.spend_public, &wallet.view_public, 900, &blinding)
+            .unwrap();
+        assert_ne!(other.public_key, output.stealth_address.public_key);
+        assert_ne!(other_encrypted, output.encrypted_amount);
+    }
+
+    #[test]
+    fn test_stranger_mask_does_not_open_commitment() {
+        let wallet = generate_wallet_keys().unwrap();
+        let stranger = generate_wallet_keys().unwrap();
+        let generator = StealthAddressGenerator::new();
+        let commitments = CommitmentGenerator::new();
+        let (tx_secret, _) = generate_keypair();
+        let blinding = commitments.generate_blinding_factor();
+
+        let mut output = output_to(
+            generator
+                .generate(&wallet.spend_public, &wallet.view_public, &tx_secret)
+                .unwrap(),
+        );
+        output.commitment = commitments.commit(4_200, &blinding).unwrap();
+        output.encrypted_amount = generator
+            .encrypt_amount(&wallet.view_public, &tx_secret, 4_200, &blinding)
+            .unwrap();
+
+        // 绕过 view tag 与一次性公钥匹配, 直接以 a'*R 去掩码
+        let stranger_view = scalar_from_secret(&stranger.view_secret).unwrap();
+        let tx_public = point_from_public(&output.stealth_address.tx_public_key).unwrap();
+        let encrypted: [u8; ENCRYPTED_AMOUNT_LEN] =
+            output.encrypted_amount.as_slice().try_into().unwrap();
+        let plain = apply_amount_mask(&(stranger_view * tx_public), &encrypted);
+        let amount = u64::from_le_bytes(plain[..8].try_into().unwrap());
+        let guessed_blinding: [u8; 32] = plain[8..].try_into().unwrap();
+        assert_ne!((amount, guessed_blinding), (4_200, blinding));
+        assert!(!commitments
+            .verify_opening(&output.commitment, amount, &guessed_blinding)
+            .unwrap_or(false));
+
+        // 接收方的 a*R 能打开
+        let view = scalar_from_secret(&wallet.view_secret).unwrap();
+        let plain = apply_amount_mask(&(view * tx_public), &encrypted);
+        assert!(commitments
+            .verify_opening(
+                &output.commitment,
+                u64::from_le_bytes(plain[..8].try_into().unwrap()),
+                &plain[8..].try_into().unwrap()
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_reused_tx_secret_repeats_one_time_key_and_mask() {
+        let wallet = generate_wallet_keys().unwrap();
+        let generator = StealthAddressGenerator::new();
+        let (tx_secret, _) = generate_keypair();
+        let first = generator
+            .generate(&wallet.spend_public, &wallet.view_public, &tx_secret)
+            .unwrap();
+        let second = generator
+            .generate(&wallet.spend_public, &wallet.view_public, &tx_secret)
+            .unwrap();
+        assert_eq!(first.public_key, second.public_key);
+        assert_eq!(first.tx_public_key, second.tx_public_key);
+        assert_eq!(first.view_tag, second.view_tag);
+
+        // 两份密文异或即两笔明文之差, 与掩码无关
+        let blinding = [0u8; 32];
+        let a = generator
+            .encrypt_amount(&wallet.view_public, &tx_secret, 5, &blinding)
+            .unwrap();
+        let b = generator
+            .encrypt_amount(&wallet.view_public, &tx_secret, 6, &blinding)
+            .unwrap();
+        assert_eq!(a[0] ^ b[0], 5 ^ 6);
+
+        // 独立的交易私钥得到不同的一次性公钥
+        let fresh = generator
+            .generate_random(&wallet.spend_public, &wallet.view_public)
+            .unwrap();
+        assert_ne!(fresh.public_key, first.public_key);
     }
 }
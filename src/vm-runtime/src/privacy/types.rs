@@ -46,10 +46,12 @@ pub struct StealthAddress {
     pub public_key: PublicKey,
     /// 交易公钥 (用于接收方扫描)
     pub tx_public_key: PublicKey,
+    /// View tag (共享秘密哈希首字节, 扫描时快速排除非本钱包输出)
+    pub view_tag: u8,
 }
 
 /// Pedersen Commitment (承诺)
-/// C = aG + bH, 其中 a 是金额, b 是致盲因子
+/// C = aH + bG, 其中 a 是金额, b 是致盲因子 (压缩 Ristretto 点, 见 privacy::commitment)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Commitment(pub [u8; 32]);
 
@@ -81,7 +83,7 @@ pub struct PrivacyOutput {
     pub commitment: Commitment,
     /// 范围证明 (证明金额有效)
     pub range_proof: RangeProof,
-    /// 加密金额 (仅接收方可解密)
+    /// 加密金额与致盲因子 (仅接收方可解密, 见 stealth_address::StealthAddressScanner::decrypt_amount)
     pub encrypted_amount: Vec<u8>,
 }
 
@@ -12,29 +12,44 @@ pub mod proto {
 pub mod service {
     use super::proto::shard_service_server::{ShardService, ShardServiceServer};
     use super::proto::*;
+    use crate::state_commitment::ProvableState;
+    pub use crate::state_commitment::{object_version_key, verify_object_version};
     use crate::{CrossShardMvccExt, MvccScheduler, SuperVM};
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
     use tonic::{Request, Response, Status};
 
-    #[derive(Debug)]
     pub struct ShardNode {
         pub mvcc: Arc<MvccScheduler>,
         pub ext: Arc<CrossShardMvccExt>,
         pub supervm: Option<&'static SuperVM<'static>>, // 可选挂接 SuperVM (含批量 ZK)
         pub shard_id: u16,
+        pub state: Option<Arc<Mutex<dyn ProvableState + Send>>>, // 可选挂接认证状态 (版本查询附带证明)
+    }
+
+    // 调度器 / SuperVM / 认证状态均未实现 Debug，仅输出概要
+    impl std::fmt::Debug for ShardNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ShardNode")
+                .field("shard_id", &self.shard_id)
+                .field("supervm", &self.supervm.is_some())
+                .field("state", &self.state.is_some())
+                .finish_non_exhaustive()
+        }
     }
 
     impl Default for ShardNode {
         fn default() -> Self {
-            Self { mvcc: Arc::new(MvccScheduler::new()), ext: Arc::new(CrossShardMvccExt::new(0)), supervm: None, shard_id: 0 }
+            Self { mvcc: Arc::new(MvccScheduler::new()), ext: Arc::new(CrossShardMvccExt::new(0)), supervm: None, shard_id: 0, state: None }
         }
     }
 
     impl ShardNode {
         pub fn new(shard_id: u16) -> Self {
-            Self { mvcc: Arc::new(MvccScheduler::new()), ext: Arc::new(CrossShardMvccExt::new(shard_id)), supervm: None, shard_id }
+            Self { mvcc: Arc::new(MvccScheduler::new()), ext: Arc::new(CrossShardMvccExt::new(shard_id)), supervm: None, shard_id, state: None }
         }
         pub fn with_supervm(mut self, vm: &'static SuperVM<'static>) -> Self { self.supervm = Some(vm); self }
+        /// 挂接认证状态（通常与自动刷盘共用同一个 `AuthenticatedStorage`）
+        pub fn with_state(mut self, state: Arc<Mutex<dyn ProvableState + Send>>) -> Self { self.state = Some(state); self }
     }
 
     #[tonic::async_trait]
@@ -102,16 +117,26 @@ pub mod service {
             let req = _request.into_inner();
             let mut versions = Vec::with_capacity(req.object_ids.len());
             let mut txn = self.mvcc.store().begin();
-            for oid in req.object_ids {
-                let hex_id = hex::encode(&oid);
-                let version_key = format!("obj_{}_version", hex_id);
-                let ver = txn.read(version_key.as_bytes())
+            for oid in &req.object_ids {
+                let version_key = object_version_key(oid);
+                let ver = txn.read(&version_key)
                     .and_then(|b| String::from_utf8(b).ok())
                     .and_then(|s| s.parse::<u64>().ok())
                     .unwrap_or(0);
-                versions.push(ObjectVersion { object_id: oid, version: ver });
+                versions.push(ObjectVersion { object_id: oid.clone(), version: ver });
             }
-            Ok(Response::new(VersionResponse { versions }))
+            let (state_root, proofs) = match &self.state {
+                Some(state) => {
+                    let state = state.lock().map_err(|_| Status::internal("state lock poisoned"))?;
+                    let proofs = req.object_ids.iter()
+                        .map(|oid| state.prove(&object_version_key(oid)).map(|p| p.to_bytes()))
+                        .collect::<anyhow::Result<Vec<_>>>()
+                        .map_err(|e| Status::internal(e.to_string()))?;
+                    (state.state_root().to_vec(), proofs)
+                }
+                None => (Vec::new(), Vec::new()),
+            };
+            Ok(Response::new(VersionResponse { versions, state_root, proofs }))
         }
 
         type StreamShardEventsStream = 
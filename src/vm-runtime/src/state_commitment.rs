@@ -0,0 +1,781 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 认证状态：基于 [`Storage`] 的稀疏 Merkle 树状态承诺
+//!
+//! - [`AuthenticatedStorage`]: 包装任意 `Storage`，每次写入 / 删除 / 批量写入时增量更新树，
+//!   `MvccStore::flush_to_storage` 写入它时即随刷盘批量更新状态根
+//! - 树结构: 路径 = keccak256(key) 的 256 位；叶子放在与其他叶子不再共享前缀的最浅深度，
+//!   删除后只剩一个叶子的子树向上收缩，因此同一键值集合无论写入顺序都得到同一个根
+//! - 哈希: 叶子 = keccak256(0x00 ‖ key_hash ‖ keccak256(value))，内部节点 = keccak256(0x01 ‖ left ‖ right)，
+//!   空子树为全零
+//! - 节点按哈希内容寻址存放在底层存储的保留前缀 [`STATE_TREE_PREFIX`] 下，根随用户数据同批写入；
+//!   旧节点不删除，因此 [`AuthenticatedStorage::prove_at`] 可对历史根出具证明；
+//!   代价是 `__smt:` 下的过期节点从不回收，节点数量随写入次数单调增长（每次更新约新增树深个节点），
+//!   需要回收空间时只能按当前根重建整棵树
+//! - MVCC 元数据（[`MVCC_META_PREFIX`]，如刷盘水位线）原样透传，不计入状态根
+//! - [`StateProof`] + [`verify_proof`]: 同时支持存在性证明与不存在性证明，可序列化后经 RPC 传给轻客户端
+
+use crate::mvcc::MVCC_META_PREFIX;
+use crate::storage::Storage;
+use anyhow::{anyhow, bail, Result};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+pub type Hash = [u8; 32];
+
+/// 空子树哈希
+pub const EMPTY_ROOT: Hash = [0u8; 32];
+
+/// 底层存储中为状态树保留的键前缀（用户写入被拒绝，扫描时被过滤）
+pub const STATE_TREE_PREFIX: &[u8] = b"__smt:";
+const NODE_PREFIX: &[u8] = b"__smt:n:";
+const ROOT_KEY: &[u8] = b"__smt:root";
+
+/// 树最大深度（路径位数）
+const MAX_DEPTH: usize = 256;
+
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+
+fn keccak(data: &[u8]) -> Hash {
+    Keccak256::digest(data).into()
+}
+
+fn leaf_hash(key_hash: &Hash, value_hash: &Hash) -> Hash {
+    let mut hasher = Keccak256::new();
+    hasher.update([LEAF_TAG]);
+    hasher.update(key_hash);
+    hasher.update(value_hash);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Keccak256::new();
+    hasher.update([NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// 路径第 `depth` 位（高位在前），0 走左子树，1 走右子树
+fn bit(path: &Hash, depth: usize) -> u8 {
+    (path[depth / 8] >> (7 - depth % 8)) & 1
+}
+
+fn shares_prefix(a: &Hash, b: &Hash, bits: usize) -> bool {
+    (0..bits).all(|d| bit(a, d) == bit(b, d))
+}
+
+fn is_reserved(key: &[u8]) -> bool {
+    key.starts_with(STATE_TREE_PREFIX)
+}
+
+/// 是否为保留键（状态树节点 [`STATE_TREE_PREFIX`] 或 MVCC 元数据 [`MVCC_META_PREFIX`]）
+///
+/// 保留键不属于用户状态：不加载进 MVCC、不计入状态根、不参与状态同步。
+pub(crate) fn is_reserved_key(key: &[u8]) -> bool {
+    is_reserved(key) || key.starts_with(MVCC_META_PREFIX)
+}
+
+fn node_key(hash: &Hash) -> Vec<u8> {
+    let mut key = NODE_PREFIX.to_vec();
+    key.extend_from_slice(hash);
+    key
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Empty,
+    Leaf { key_hash: Hash, value_hash: Hash },
+    Internal { left: Hash, right: Hash },
+}
+
+impl Node {
+    fn encode(&self) -> Vec<u8> {
+        let (tag, a, b) = match self {
+            Node::Empty => return Vec::new(),
+            Node::Leaf {
+                key_hash,
+                value_hash,
+            } => (LEAF_TAG, key_hash, value_hash),
+            Node::Internal { left, right } => (NODE_TAG, left, right),
+        };
+        let mut out = Vec::with_capacity(65);
+        out.push(tag);
+        out.extend_from_slice(a);
+        out.extend_from_slice(b);
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 65 {
+            bail!("corrupted state tree node: {} bytes", bytes.len());
+        }
+        let a: Hash = bytes[1..33].try_into().unwrap();
+        let b: Hash = bytes[33..65].try_into().unwrap();
+        match bytes[0] {
+            LEAF_TAG => Ok(Node::Leaf {
+                key_hash: a,
+                value_hash: b,
+            }),
+            NODE_TAG => Ok(Node::Internal { left: a, right: b }),
+            tag => bail!("corrupted state tree node: unknown tag {tag:#04x}"),
+        }
+    }
+}
+
+/// 证明终点处的叶子（存在性证明时即目标键；不存在性证明时为占据该路径的其他键）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofLeaf {
+    pub key_hash: Hash,
+    pub value_hash: Hash,
+}
+
+/// 单键状态证明：从根往下的兄弟哈希 + 终点叶子（终点为空子树时为 None）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateProof {
+    pub siblings: Vec<Hash>,
+    pub leaf: Option<ProofLeaf>,
+}
+
+impl StateProof {
+    /// 紧凑编码：`has_leaf(1)` [`key_hash(32)` `value_hash(32)`] `count(2, be)` `bitmap` 非空兄弟哈希...
+    ///
+    /// bitmap 每位标记对应兄弟是否非空，空子树不占空间
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(67 + self.siblings.len() * 32);
+        match &self.leaf {
+            Some(leaf) => {
+                out.push(1);
+                out.extend_from_slice(&leaf.key_hash);
+                out.extend_from_slice(&leaf.value_hash);
+            }
+            None => out.push(0),
+        }
+        out.extend_from_slice(&(self.siblings.len() as u16).to_be_bytes());
+        let mut bitmap = vec![0u8; self.siblings.len().div_ceil(8)];
+        for (i, sibling) in self.siblings.iter().enumerate() {
+            if *sibling != EMPTY_ROOT {
+                bitmap[i / 8] |= 1 << (7 - i % 8);
+            }
+        }
+        out.extend_from_slice(&bitmap);
+        for sibling in self.siblings.iter().filter(|s| **s != EMPTY_ROOT) {
+            out.extend_from_slice(sibling);
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut cursor = bytes;
+        let mut take = |n: usize| -> Result<&[u8]> {
+            if cursor.len() < n {
+                bail!("truncated state proof");
+            }
+            let (head, rest) = cursor.split_at(n);
+            cursor = rest;
+            Ok(head)
+        };
+        let leaf = match take(1)?[0] {
+            0 => None,
+            1 => Some(ProofLeaf {
+                key_hash: take(32)?.try_into().unwrap(),
+                value_hash: take(32)?.try_into().unwrap(),
+            }),
+            flag => bail!("invalid state proof leaf flag {flag}"),
+        };
+        let count = u16::from_be_bytes(take(2)?.try_into().unwrap()) as usize;
+        if count > MAX_DEPTH {
+            bail!("state proof too deep: {count}");
+        }
+        let bitmap = take(count.div_ceil(8))?.to_vec();
+        let mut siblings = Vec::with_capacity(count);
+        for i in 0..count {
+            if bitmap[i / 8] & (1 << (7 - i % 8)) != 0 {
+                siblings.push(take(32)?.try_into().unwrap());
+            } else {
+                siblings.push(EMPTY_ROOT);
+            }
+        }
+        if !cursor.is_empty() {
+            bail!("trailing bytes after state proof");
+        }
+        Ok(Self { siblings, leaf })
+    }
+}
+
+/// 校验 `key` 在 `root` 下的取值：`value = Some(v)` 校验存在性，`None` 校验不存在性
+///
+/// 只依赖根、键值与证明本身，轻客户端与跨分片远程读验证均可直接调用
+pub fn verify_proof(root: &Hash, key: &[u8], value: Option<&[u8]>, proof: &StateProof) -> bool {
+    if proof.siblings.len() > MAX_DEPTH {
+        return false;
+    }
+    let path = keccak(key);
+    match (value, &proof.leaf) {
+        (Some(v), Some(leaf)) => {
+            if leaf.key_hash != path || leaf.value_hash != keccak(v) {
+                return false;
+            }
+        }
+        (Some(_), None) => return false,
+        (None, Some(leaf)) => {
+            // 路径终点被另一个键占据：该叶子必须确实位于目标键的路径上
+            if leaf.key_hash == path || !shares_prefix(&leaf.key_hash, &path, proof.siblings.len())
+            {
+                return false;
+            }
+        }
+        (None, None) => {}
+    }
+    let mut hash = proof
+        .leaf
+        .map_or(EMPTY_ROOT, |l| leaf_hash(&l.key_hash, &l.value_hash));
+    for (depth, sibling) in proof.siblings.iter().enumerate().rev() {
+        hash = if bit(&path, depth) == 0 {
+            node_hash(&hash, sibling)
+        } else {
+            node_hash(sibling, &hash)
+        };
+    }
+    hash == *root
+}
+
+/// 对象版本在存储中的键（十进制字符串值）
+pub fn object_version_key(object_id: &[u8]) -> Vec<u8> {
+    format!("obj_{}_version", hex::encode(object_id)).into_bytes()
+}
+
+/// 远程读验证：校验 `GetObjectVersions` 返回的版本与证明是否与状态根一致
+///
+/// 版本 0 表示对象不存在，此时校验的是不存在性证明。证明针对最近一次刷盘后的
+/// 状态；尚未刷盘的新版本校验失败，应视为未经证明。
+pub fn verify_object_version(
+    state_root: &[u8],
+    object_id: &[u8],
+    version: u64,
+    proof: &[u8],
+) -> bool {
+    let Ok(root) = <Hash>::try_from(state_root) else {
+        return false;
+    };
+    let Ok(proof) = StateProof::from_bytes(proof) else {
+        return false;
+    };
+    let key = object_version_key(object_id);
+    let value = (version != 0).then(|| version.to_string().into_bytes());
+    verify_proof(&root, &key, value.as_deref(), &proof)
+}
+
+/// 带状态承诺的存储包装器
+///
+/// 用户键值原样写入底层存储，树节点与根写在 [`STATE_TREE_PREFIX`] 下；
+/// 底层支持批量写入时，一次更新的用户数据、新节点与新根在同一批次内原子落盘。
+pub struct AuthenticatedStorage<S: Storage> {
+    inner: S,
+    root: Hash,
+    /// 本次更新新建、尚未落盘的节点
+    pending: HashMap<Hash, Node>,
+}
+
+impl<S: Storage> AuthenticatedStorage<S> {
+    /// 打开认证存储：读取已持久化的根；若底层已有数据但从未建树，则全量扫描一次构建
+    pub fn new(inner: S) -> Result<Self> {
+        let mut this = Self {
+            inner,
+            root: EMPTY_ROOT,
+            pending: HashMap::new(),
+        };
+        match stored_state_root(&this.inner)? {
+            Some(root) => this.root = root,
+            None => {
+                let existing: Vec<_> = this
+                    .inner
+                    .scan(&[])?
+                    .into_iter()
+                    .filter(|(k, _)| !is_reserved_key(k))
+                    .map(|(k, v)| (k, Some(v)))
+                    .collect();
+                if !existing.is_empty() {
+                    let root = this.update_tree(&existing)?;
+                    this.persist(Vec::new(), root)?;
+                }
+            }
+        }
+        Ok(this)
+    }
+
+    /// 当前状态根
+    pub fn state_root(&self) -> Hash {
+        self.root
+    }
+
+    /// 对当前根出具 `key` 的存在性 / 不存在性证明
+    pub fn prove(&self, key: &[u8]) -> Result<StateProof> {
+        self.prove_at(&self.root, key)
+    }
+
+    /// 对任意历史根出具证明（节点不删除，历史根始终可证明）
+    pub fn prove_at(&self, root: &Hash, key: &[u8]) -> Result<StateProof> {
+        let path = keccak(key);
+        let mut proof = StateProof::default();
+        let mut current = *root;
+        for depth in 0..=MAX_DEPTH {
+            match self.load(&current)? {
+                Node::Empty => return Ok(proof),
+                Node::Leaf {
+                    key_hash,
+                    value_hash,
+                } => {
+                    proof.leaf = Some(ProofLeaf {
+                        key_hash,
+                        value_hash,
+                    });
+                    return Ok(proof);
+                }
+                Node::Internal { left, right } => {
+                    if depth == MAX_DEPTH {
+                        break;
+                    }
+                    if bit(&path, depth) == 0 {
+                        proof.siblings.push(right);
+                        current = left;
+                    } else {
+                        proof.siblings.push(left);
+                        current = right;
+                    }
+                }
+            }
+        }
+        bail!("corrupted state tree: path exceeds {MAX_DEPTH} levels")
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// 写入一批用户键值并更新状态根
+    fn apply(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        if let Some((key, _)) = batch.iter().find(|(k, _)| is_reserved(k)) {
+            bail!(
+                "key {:?} is reserved for the state tree",
+                String::from_utf8_lossy(key)
+            );
+        }
+        let state: Vec<_> = batch
+            .iter()
+            .filter(|(k, _)| !k.starts_with(MVCC_META_PREFIX))
+            .cloned()
+            .collect();
+        let root = self.update_tree(&state)?;
+        self.persist(batch, root)
+    }
+
+    fn update_tree(&mut self, batch: &[(Vec<u8>, Option<Vec<u8>>)]) -> Result<Hash> {
+        let mut root = self.root;
+        for (key, value) in batch {
+            let path = keccak(key);
+            root = match value {
+                Some(v) => {
+                    let leaf = self.store(Node::Leaf {
+                        key_hash: path,
+                        value_hash: keccak(v),
+                    });
+                    self.insert(root, 0, &path, leaf)?
+                }
+                None => self.remove(root, 0, &path)?,
+            };
+        }
+        Ok(root)
+    }
+
+    fn persist(&mut self, mut batch: Vec<(Vec<u8>, Option<Vec<u8>>)>, root: Hash) -> Result<()> {
+        for (hash, node) in self.pending.drain() {
+            batch.push((node_key(&hash), Some(node.encode())));
+        }
+        batch.push((ROOT_KEY.to_vec(), Some(root.to_vec())));
+        if !self.inner.write_batch_if_supported(batch.clone())? {
+            for (key, value) in batch {
+                match value {
+                    Some(v) => self.inner.set(&key, &v)?,
+                    None => self.inner.delete(&key)?,
+                }
+            }
+        }
+        self.root = root;
+        Ok(())
+    }
+
+    fn load(&self, hash: &Hash) -> Result<Node> {
+        if *hash == EMPTY_ROOT {
+            return Ok(Node::Empty);
+        }
+        if let Some(node) = self.pending.get(hash) {
+            return Ok(*node);
+        }
+        let bytes = self
+            .inner
+            .get(&node_key(hash))?
+            .ok_or_else(|| anyhow!("missing state tree node {}", hex::encode(hash)))?;
+        Node::decode(&bytes)
+    }
+
+    fn store(&mut self, node: Node) -> Hash {
+        let hash = match node {
+            Node::Empty => return EMPTY_ROOT,
+            Node::Leaf {
+                key_hash,
+                value_hash,
+            } => leaf_hash(&key_hash, &value_hash),
+            Node::Internal { left, right } => node_hash(&left, &right),
+        };
+        self.pending.insert(hash, node);
+        hash
+    }
+
+    fn insert(&mut self, node: Hash, depth: usize, path: &Hash, leaf: Hash) -> Result<Hash> {
+        match self.load(&node)? {
+            Node::Empty => Ok(leaf),
+            Node::Leaf { key_hash, .. } if key_hash == *path => Ok(leaf),
+            Node::Leaf { key_hash, .. } => self.split(depth, (&key_hash, node), (path, leaf)),
+            Node::Internal { left, right } => {
+                if depth >= MAX_DEPTH {
+                    bail!("corrupted state tree: path exceeds {MAX_DEPTH} levels");
+                }
+                let (left, right) = if bit(path, depth) == 0 {
+                    (self.insert(left, depth + 1, path, leaf)?, right)
+                } else {
+                    (left, self.insert(right, depth + 1, path, leaf)?)
+                };
+                Ok(self.store(Node::Internal { left, right }))
+            }
+        }
+    }
+
+    /// 两个叶子路径在 `depth` 处仍相同：逐层下沉直到第一个分叉位
+    fn split(&mut self, depth: usize, a: (&Hash, Hash), b: (&Hash, Hash)) -> Result<Hash> {
+        if depth >= MAX_DEPTH {
+            bail!("keccak256 path collision");
+        }
+        let (bit_a, bit_b) = (bit(a.0, depth), bit(b.0, depth));
+        let (left, right) = if bit_a == bit_b {
+            let child = self.split(depth + 1, a, b)?;
+            if bit_a == 0 {
+                (child, EMPTY_ROOT)
+            } else {
+                (EMPTY_ROOT, child)
+            }
+        } else if bit_a == 0 {
+            (a.1, b.1)
+        } else {
+            (b.1, a.1)
+        };
+        Ok(self.store(Node::Internal { left, right }))
+    }
+
+    fn remove(&mut self, node: Hash, depth: usize, path: &Hash) -> Result<Hash> {
+        match self.load(&node)? {
+            Node::Empty => Ok(node),
+            Node::Leaf { key_hash, .. } => Ok(if key_hash == *path { EMPTY_ROOT } else { node }),
+            Node::Internal { left, right } => {
+                if depth >= MAX_DEPTH {
+                    bail!("corrupted state tree: path exceeds {MAX_DEPTH} levels");
+                }
+                let (new_left, new_right) = if bit(path, depth) == 0 {
+                    (self.remove(left, depth + 1, path)?, right)
+                } else {
+                    (left, self.remove(right, depth + 1, path)?)
+                };
+                if (new_left, new_right) == (left, right) {
+                    return Ok(node);
+                }
+                // 只剩一个叶子的子树收缩为该叶子，保证结构与写入历史无关
+                match (new_left == EMPTY_ROOT, new_right == EMPTY_ROOT) {
+                    (true, true) => Ok(EMPTY_ROOT),
+                    (true, false) if matches!(self.load(&new_right)?, Node::Leaf { .. }) => {
+                        Ok(new_right)
+                    }
+                    (false, true) if matches!(self.load(&new_left)?, Node::Leaf { .. }) => {
+                        Ok(new_left)
+                    }
+                    _ => Ok(self.store(Node::Internal {
+                        left: new_left,
+                        right: new_right,
+                    })),
+                }
+            }
+        }
+    }
+}
+
+impl<S: Storage> Storage for AuthenticatedStorage<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.apply(vec![(key.to_vec(), Some(value.to_vec()))])
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.apply(vec![(key.to_vec(), None)])
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut entries = self.inner.scan(prefix)?;
+        entries.retain(|(k, _)| !is_reserved(k));
+        Ok(entries)
+    }
+
+    /// 批量写入总是由本层处理：用户数据、新节点与新根合并为一批交给底层
+    fn write_batch_if_supported(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<bool> {
+        self.apply(batch)?;
+        Ok(true)
+    }
+}
+
+/// 读取底层存储中已持久化的状态根（从未由 [`AuthenticatedStorage`] 写入时为 None）
+pub fn stored_state_root(storage: &dyn Storage) -> Result<Option<Hash>> {
+    match storage.get(ROOT_KEY)? {
+        Some(root) => {
+            Ok(Some(root.as_slice().try_into().map_err(|_| {
+                anyhow!("corrupted state root: {} bytes", root.len())
+            })?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 可出具状态证明的存储（供跨分片 RPC 等以 trait 对象持有）
+pub trait ProvableState {
+    fn state_root(&self) -> Hash;
+    fn prove(&self, key: &[u8]) -> Result<StateProof>;
+}
+
+impl<S: Storage> ProvableState for AuthenticatedStorage<S> {
+    fn state_root(&self) -> Hash {
+        AuthenticatedStorage::state_root(self)
+    }
+
+    fn prove(&self, key: &[u8]) -> Result<StateProof> {
+        AuthenticatedStorage::prove(self, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mvcc::{MvccStore, FLUSH_WATERMARK_KEY};
+    use crate::storage::MemoryStorage;
+
+    fn build(entries: &[(&[u8], &[u8])]) -> AuthenticatedStorage<MemoryStorage> {
+        let mut storage = AuthenticatedStorage::new(MemoryStorage::new()).unwrap();
+        for (k, v) in entries {
+            storage.set(k, v).unwrap();
+        }
+        storage
+    }
+
+    #[test]
+    fn test_root_independent_of_history() {
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = (0..64u32)
+            .map(|i| (format!("key{i}").into_bytes(), i.to_be_bytes().to_vec()))
+            .collect();
+        let refs: Vec<(&[u8], &[u8])> = entries
+            .iter()
+            .map(|(k, v)| (k.as_slice(), v.as_slice()))
+            .collect();
+        let forward = build(&refs);
+        let mut reversed_refs = refs.clone();
+        reversed_refs.reverse();
+        let mut reversed = build(&reversed_refs);
+        assert_ne!(forward.state_root(), EMPTY_ROOT);
+        assert_eq!(forward.state_root(), reversed.state_root());
+
+        // 写入再删除额外的键：根回到原值
+        let before = reversed.state_root();
+        reversed.set(b"extra", b"1").unwrap();
+        reversed.set(b"key3", b"changed").unwrap();
+        assert_ne!(reversed.state_root(), before);
+        reversed.delete(b"extra").unwrap();
+        reversed.set(b"key3", &3u32.to_be_bytes()).unwrap();
+        assert_eq!(reversed.state_root(), before);
+
+        for (k, _) in &refs {
+            reversed.delete(k).unwrap();
+        }
+        assert_eq!(reversed.state_root(), EMPTY_ROOT);
+        assert!(reversed.scan(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verify_object_version() {
+        let id = [0xabu8; 32];
+        let other = [0xcdu8; 32];
+        let storage = build(&[(&object_version_key(&id), b"5"), (b"unrelated", b"x")]);
+        let root = storage.state_root();
+
+        let proof = storage.prove(&object_version_key(&id)).unwrap().to_bytes();
+        assert!(verify_object_version(&root, &id, 5, &proof));
+        assert!(!verify_object_version(&root, &id, 6, &proof));
+        assert!(!verify_object_version(&root, &id, 0, &proof));
+        assert!(!verify_object_version(&root, &other, 5, &proof));
+
+        // 版本 0：不存在性证明
+        let absent = storage
+            .prove(&object_version_key(&other))
+            .unwrap()
+            .to_bytes();
+        assert!(verify_object_version(&root, &other, 0, &absent));
+        assert!(!verify_object_version(&root, &other, 1, &absent));
+
+        // 畸形根或证明一律拒绝
+        assert!(!verify_object_version(&root[..31], &id, 5, &proof));
+        assert!(!verify_object_version(&EMPTY_ROOT, &id, 5, &proof));
+        assert!(!verify_object_version(
+            &root,
+            &id,
+            5,
+            &proof[..proof.len() - 1]
+        ));
+    }
+
+    #[test]
+    fn test_inclusion_and_exclusion_proofs() {
+        let storage = build(&[(b"alice", b"100"), (b"bob", b"50"), (b"carol", b"7")]);
+        let root = storage.state_root();
+
+        let proof = storage.prove(b"bob").unwrap();
+        assert!(verify_proof(&root, b"bob", Some(b"50"), &proof));
+        assert!(!verify_proof(&root, b"bob", Some(b"51"), &proof));
+        assert!(!verify_proof(&root, b"bob", None, &proof));
+        assert!(!verify_proof(&root, b"alice", Some(b"50"), &proof));
+
+        for missing in [&b"dave"[..], b"eve", b"mallory"] {
+            let proof = storage.prove(missing).unwrap();
+            assert!(verify_proof(&root, missing, None, &proof));
+            assert!(!verify_proof(&root, missing, Some(b"1"), &proof));
+        }
+
+        // 篡改兄弟哈希或换用其他根均失败
+        let mut forged = storage.prove(b"carol").unwrap();
+        assert!(verify_proof(&root, b"carol", Some(b"7"), &forged));
+        forged.siblings[0][0] ^= 1;
+        assert!(!verify_proof(&root, b"carol", Some(b"7"), &forged));
+        assert!(!verify_proof(
+            &EMPTY_ROOT,
+            b"carol",
+            Some(b"7"),
+            &storage.prove(b"carol").unwrap()
+        ));
+
+        // 空树上的不存在性证明
+        let empty = build(&[]);
+        assert!(verify_proof(
+            &EMPTY_ROOT,
+            b"x",
+            None,
+            &empty.prove(b"x").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_proof_encoding_roundtrip_and_history() {
+        let mut storage = build(&[(b"a", b"1"), (b"b", b"2"), (b"c", b"3"), (b"d", b"4")]);
+        let old_root = storage.state_root();
+        storage.set(b"a", b"9").unwrap();
+
+        for key in [&b"a"[..], b"b", b"zzz"] {
+            let proof = storage.prove(key).unwrap();
+            let decoded = StateProof::from_bytes(&proof.to_bytes()).unwrap();
+            assert_eq!(decoded, proof);
+        }
+        assert!(StateProof::from_bytes(&[1, 2]).is_err());
+
+        let old = storage.prove_at(&old_root, b"a").unwrap();
+        assert!(verify_proof(&old_root, b"a", Some(b"1"), &old));
+        let new = storage.prove(b"a").unwrap();
+        assert!(verify_proof(&storage.state_root(), b"a", Some(b"9"), &new));
+    }
+
+    #[test]
+    fn test_flush_updates_root_incrementally() {
+        let store = MvccStore::new();
+        let mut storage = AuthenticatedStorage::new(MemoryStorage::new()).unwrap();
+
+        let mut txn = store.begin();
+        txn.write(b"k1".to_vec(), b"v1".to_vec());
+        txn.write(b"k2".to_vec(), b"v2".to_vec());
+        txn.commit().unwrap();
+        store.flush_to_storage(&mut storage, 1).unwrap();
+        assert_eq!(
+            storage.state_root(),
+            build(&[(b"k1", b"v1"), (b"k2", b"v2")]).state_root()
+        );
+
+        let mut txn = store.begin();
+        txn.write(b"k1".to_vec(), b"v1'".to_vec());
+        txn.write(b"k3".to_vec(), b"v3".to_vec());
+        txn.commit().unwrap();
+        store.flush_to_storage(&mut storage, 1).unwrap();
+        let root = storage.state_root();
+        assert_eq!(
+            root,
+            build(&[(b"k1", b"v1'"), (b"k2", b"v2"), (b"k3", b"v3")]).state_root()
+        );
+        assert!(verify_proof(
+            &root,
+            b"k1",
+            Some(b"v1'"),
+            &storage.prove(b"k1").unwrap()
+        ));
+        assert!(verify_proof(
+            &root,
+            b"k4",
+            None,
+            &storage.prove(b"k4").unwrap()
+        ));
+
+        // 树节点不出现在用户扫描中，保留键不可写入；刷盘水位线透传且不计入状态根
+        assert!(storage.scan(STATE_TREE_PREFIX).unwrap().is_empty());
+        assert_eq!(storage.scan(b"").unwrap().len(), 4);
+        assert_eq!(
+            MvccStore::stored_flush_watermark(&storage).unwrap(),
+            Some(store.flush_watermark())
+        );
+        assert!(storage.set(b"__smt:root", b"x").is_err());
+
+        // 重新打开：根从存储恢复
+        let mut reopened = AuthenticatedStorage::new(storage.into_inner()).unwrap();
+        assert_eq!(reopened.state_root(), root);
+
+        // MVCC 删除随刷盘从树中移除
+        let mut txn = store.begin();
+        txn.delete(b"k1".to_vec());
+        txn.commit().unwrap();
+        store.flush_to_storage(&mut reopened, 1).unwrap();
+        let root = reopened.state_root();
+        assert_eq!(root, build(&[(b"k2", b"v2"), (b"k3", b"v3")]).state_root());
+        assert!(verify_proof(
+            &root,
+            b"k1",
+            None,
+            &reopened.prove(b"k1").unwrap()
+        ));
+
+        // 未建树的已有数据打开时全量构建（元数据不计入）
+        let mut plain = MemoryStorage::new();
+        plain.set(b"k2", b"v2").unwrap();
+        plain.set(b"k3", b"v3").unwrap();
+        plain.set(FLUSH_WATERMARK_KEY, &7u64.to_be_bytes()).unwrap();
+        assert_eq!(AuthenticatedStorage::new(plain).unwrap().state_root(), root);
+    }
+}
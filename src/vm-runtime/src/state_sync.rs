@@ -0,0 +1,538 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 状态同步：可移植的分块状态快照
+//!
+//! 新节点无需对端的 RocksDB 目录，只凭一组文件即可自举：
+//! - 导出 [`export_state`] / [`export_shared_state`]: 按键序遍历用户状态（不含状态树节点与 MVCC 元数据），
+//!   切成约 `chunk_bytes` 大小的块，每块一个文件并记录 keccak256；清单最后写入，存在即表示导出完整
+//! - 导入 [`import_state`]: 逐块校验大小、哈希、键严格递增，经 [`AuthenticatedStorage`] 写入目标存储并重建状态树，
+//!   全部导入后状态根必须等于清单中的根（以及调用方从可信区块头取得的根）
+//!
+//! 文件布局: `STATE_SYNC.manifest` + `chunk_{index:06}.bin`；
+//! 块编码为若干 `key_len(u32 le) ‖ key ‖ val_len(u32 le) ‖ val`。
+//! 清单为文本：
+//!
+//! ```text
+//! supervm-state-sync 1
+//! height <区块高度>
+//! state_root <hex>
+//! chunk <index> <entries> <bytes> <keccak256 hex>
+//! ```
+
+use crate::state_commitment::{
+    is_reserved_key, stored_state_root, AuthenticatedStorage, Hash, EMPTY_ROOT, STATE_TREE_PREFIX,
+};
+use crate::storage::{MemoryStorage, SharedStorage, Storage};
+use anyhow::{anyhow, bail, Context, Result};
+use sha3::{Digest, Keccak256};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 清单文件名
+pub const STATE_SYNC_MANIFEST: &str = "STATE_SYNC.manifest";
+/// 默认块大小（字节）
+pub const DEFAULT_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+
+const MANIFEST_HEADER: &str = "supervm-state-sync 1";
+
+/// 一个状态块
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkInfo {
+    pub index: u32,
+    pub entries: u64,
+    pub bytes: u64,
+    pub hash: Hash,
+}
+
+/// 状态同步清单
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSyncManifest {
+    pub height: u64,
+    pub state_root: Hash,
+    pub chunks: Vec<ChunkInfo>,
+}
+
+impl StateSyncManifest {
+    pub fn total_entries(&self) -> u64 {
+        self.chunks.iter().map(|c| c.entries).sum()
+    }
+
+    pub fn encode(&self) -> String {
+        let mut out = format!(
+            "{MANIFEST_HEADER}\nheight {}\nstate_root {}\n",
+            self.height,
+            hex::encode(self.state_root)
+        );
+        for c in &self.chunks {
+            out.push_str(&format!(
+                "chunk {} {} {} {}\n",
+                c.index,
+                c.entries,
+                c.bytes,
+                hex::encode(c.hash)
+            ));
+        }
+        out
+    }
+
+    pub fn decode(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            bail!("unsupported state sync manifest header");
+        }
+        let mut height = None;
+        let mut state_root = None;
+        let mut chunks = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields.as_slice() {
+                ["height", h] => height = Some(h.parse().context("invalid manifest height")?),
+                ["state_root", root] => state_root = Some(parse_hash(root)?),
+                ["chunk", index, entries, bytes, hash] => {
+                    let index: u32 = index.parse().context("invalid chunk index")?;
+                    if index as usize != chunks.len() {
+                        bail!("chunk {index} out of order in manifest");
+                    }
+                    chunks.push(ChunkInfo {
+                        index,
+                        entries: entries.parse().context("invalid chunk entry count")?,
+                        bytes: bytes.parse().context("invalid chunk size")?,
+                        hash: parse_hash(hash)?,
+                    });
+                }
+                [] | [""] => {}
+                _ => bail!("invalid manifest line: {line}"),
+            }
+        }
+        Ok(Self {
+            height: height.ok_or_else(|| anyhow!("manifest missing height"))?,
+            state_root: state_root.ok_or_else(|| anyhow!("manifest missing state_root"))?,
+            chunks,
+        })
+    }
+
+    /// 读取导出目录中的清单
+    pub fn read_from(dir: &Path) -> Result<Self> {
+        let path = dir.join(STATE_SYNC_MANIFEST);
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read state sync manifest {}", path.display()))?;
+        Self::decode(&text)
+    }
+}
+
+fn parse_hash(text: &str) -> Result<Hash> {
+    hex::decode(text)
+        .ok()
+        .and_then(|bytes| Hash::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| anyhow!("invalid hash {text:?} in manifest"))
+}
+
+fn chunk_file(index: u32) -> String {
+    format!("chunk_{index:06}.bin")
+}
+
+/// 状态树节点与 MVCC 元数据不属于可同步的用户状态
+fn is_state_key(key: &[u8]) -> bool {
+    !is_reserved_key(key)
+}
+
+fn encode_entry(out: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+    out.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    out.extend_from_slice(key);
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+fn decode_chunk(mut bytes: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut take = |n: usize| -> Result<&[u8]> {
+        if bytes.len() < n {
+            bail!("truncated state chunk");
+        }
+        let (head, rest) = bytes.split_at(n);
+        bytes = rest;
+        Ok(head)
+    };
+    let mut entries = Vec::new();
+    loop {
+        let Ok(len) = take(4) else { break };
+        let key = take(u32::from_le_bytes(len.try_into().unwrap()) as usize)?.to_vec();
+        let len = take(4)?;
+        let value = take(u32::from_le_bytes(len.try_into().unwrap()) as usize)?.to_vec();
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+/// 只保留状态树节点的存储：导出时为没有持久化根的源计算状态根，不复制用户数据
+#[derive(Default)]
+struct TreeNodesOnly(MemoryStorage);
+
+impl Storage for TreeNodesOnly {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.0.get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        if key.starts_with(STATE_TREE_PREFIX) {
+            self.0.set(key, value)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.0.delete(key)
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.0.scan(prefix)
+    }
+
+    fn write_batch_if_supported(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<bool> {
+        let nodes = batch
+            .into_iter()
+            .filter(|(k, _)| k.starts_with(STATE_TREE_PREFIX))
+            .collect();
+        self.0.write_batch_if_supported(nodes)
+    }
+}
+
+/// 导出时的块写出器：攒满 `chunk_bytes` 即落盘一块，同时把条目喂给影子状态树（需要计算根时）
+struct ChunkWriter<'a> {
+    dir: &'a Path,
+    chunk_bytes: usize,
+    buffer: Vec<u8>,
+    batch: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    chunks: Vec<ChunkInfo>,
+    shadow: Option<AuthenticatedStorage<TreeNodesOnly>>,
+}
+
+impl ChunkWriter<'_> {
+    fn push(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        encode_entry(&mut self.buffer, &key, &value);
+        self.batch.push((key, Some(value)));
+        if self.buffer.len() >= self.chunk_bytes {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let index = self.chunks.len() as u32;
+        fs::write(self.dir.join(chunk_file(index)), &self.buffer)?;
+        self.chunks.push(ChunkInfo {
+            index,
+            entries: self.batch.len() as u64,
+            bytes: self.buffer.len() as u64,
+            hash: Keccak256::digest(&self.buffer).into(),
+        });
+        let batch = std::mem::take(&mut self.batch);
+        if let Some(shadow) = self.shadow.as_mut() {
+            shadow.write_batch_if_supported(batch)?;
+        }
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// 把 `source` 的用户状态导出到 `out_dir`（须不存在或为空）
+///
+/// 状态根取源存储中持久化的根（源由 [`AuthenticatedStorage`] 维护时），否则在导出过程中计算，
+/// 此时整棵影子状态树的节点驻留内存（与状态规模成正比）。
+/// [`Storage`] 只能整段扫描：按键首字节分 256 段，每次物化一段，键共用首字节（如 `acct:`）时即为全部状态；
+/// 大状态请用游标遍历的 [`export_shared_state`]。调用方需保证导出期间源存储不被写入
+/// （RocksDB 可用 `RocksDBStorage::export_state_snapshot` 基于 checkpoint 导出）。
+pub fn export_state(
+    source: &dyn Storage,
+    height: u64,
+    out_dir: &Path,
+    chunk_bytes: usize,
+) -> Result<StateSyncManifest> {
+    export_with(
+        stored_state_root(source)?,
+        height,
+        out_dir,
+        chunk_bytes,
+        |writer| {
+            // 空键排在最前，其余按首字节分段（段内按键排序），整体即为全局键序
+            if let Some(value) = source.get(&[])? {
+                writer.push(Vec::new(), value)?;
+            }
+            for byte in 0..=u8::MAX {
+                let mut segment = source.scan(&[byte])?;
+                segment.retain(|(k, _)| is_state_key(k));
+                segment.sort_by(|a, b| a.0.cmp(&b.0));
+                for (key, value) in segment {
+                    writer.push(key, value)?;
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// 同 [`export_state`]，但经 [`SharedStorage::for_each_prefix`] 以游标遍历源存储
+///
+/// 后端支持游标时（如 RocksDB）除待写出的一块外不物化状态数据；源中没有持久化的状态根时，
+/// 影子状态树的节点仍驻留内存。
+pub fn export_shared_state<S: SharedStorage + ?Sized>(
+    source: &Arc<S>,
+    height: u64,
+    out_dir: &Path,
+    chunk_bytes: usize,
+) -> Result<StateSyncManifest> {
+    export_with(
+        stored_state_root(source)?,
+        height,
+        out_dir,
+        chunk_bytes,
+        |writer| {
+            let mut result = Ok(());
+            source.for_each_prefix(&[], &mut |key, value| {
+                if !is_state_key(key) {
+                    return true;
+                }
+                result = writer.push(key.to_vec(), value.to_vec());
+                result.is_ok()
+            })?;
+            result
+        },
+    )
+}
+
+fn export_with(
+    stored_root: Option<Hash>,
+    height: u64,
+    out_dir: &Path,
+    chunk_bytes: usize,
+    walk: impl FnOnce(&mut ChunkWriter) -> Result<()>,
+) -> Result<StateSyncManifest> {
+    if chunk_bytes == 0 {
+        bail!("chunk_bytes must be positive");
+    }
+    if out_dir.exists() && fs::read_dir(out_dir)?.next().is_some() {
+        bail!("state sync export dir {} is not empty", out_dir.display());
+    }
+    fs::create_dir_all(out_dir)?;
+
+    let mut writer = ChunkWriter {
+        dir: out_dir,
+        chunk_bytes,
+        buffer: Vec::new(),
+        batch: Vec::new(),
+        chunks: Vec::new(),
+        shadow: match stored_root {
+            Some(_) => None,
+            None => Some(AuthenticatedStorage::new(TreeNodesOnly::default())?),
+        },
+    };
+    walk(&mut writer)?;
+    writer.flush()?;
+
+    let state_root = match (stored_root, &writer.shadow) {
+        (Some(root), _) => root,
+        (None, Some(shadow)) => shadow.state_root(),
+        (None, None) => EMPTY_ROOT,
+    };
+    let manifest = StateSyncManifest {
+        height,
+        state_root,
+        chunks: writer.chunks,
+    };
+    // 清单最后写入并原子改名：目录中出现清单即表示全部块已落盘
+    let tmp = out_dir.join(format!("{STATE_SYNC_MANIFEST}.tmp"));
+    fs::write(&tmp, manifest.encode())?;
+    fs::rename(&tmp, out_dir.join(STATE_SYNC_MANIFEST))?;
+    Ok(manifest)
+}
+
+/// 从 `dir` 导入状态到空的 `target`，返回维护好状态树的 [`AuthenticatedStorage`]
+///
+/// 每块在写入前校验大小与 keccak256，键必须跨块严格递增且不得落在保留前缀下；
+/// 导入完成后状态根须等于清单中的根，传入 `expected_root`（来自可信区块头）时还须与之相等。
+/// 校验失败时 `target` 中可能残留部分已导入的块，调用方应丢弃该存储。
+pub fn import_state<S: Storage>(
+    dir: &Path,
+    target: S,
+    expected_root: Option<&Hash>,
+) -> Result<(StateSyncManifest, AuthenticatedStorage<S>)> {
+    let manifest = StateSyncManifest::read_from(dir)?;
+    if let Some(expected) = expected_root {
+        if *expected != manifest.state_root {
+            bail!(
+                "state sync manifest root {} does not match expected root {}",
+                hex::encode(manifest.state_root),
+                hex::encode(expected)
+            );
+        }
+    }
+    let mut storage = AuthenticatedStorage::new(target)?;
+    if storage.state_root() != EMPTY_ROOT {
+        bail!("state sync target storage is not empty");
+    }
+
+    let mut last_key: Option<Vec<u8>> = None;
+    for chunk in &manifest.chunks {
+        let path = dir.join(chunk_file(chunk.index));
+        let bytes = fs::read(&path)
+            .with_context(|| format!("Failed to read state chunk {}", path.display()))?;
+        if bytes.len() as u64 != chunk.bytes {
+            bail!(
+                "state chunk {} size mismatch: {} != {}",
+                chunk.index,
+                bytes.len(),
+                chunk.bytes
+            );
+        }
+        let hash: Hash = Keccak256::digest(&bytes).into();
+        if hash != chunk.hash {
+            bail!("state chunk {} hash mismatch", chunk.index);
+        }
+        let entries = decode_chunk(&bytes)
+            .with_context(|| format!("Failed to decode state chunk {}", chunk.index))?;
+        if entries.len() as u64 != chunk.entries {
+            bail!("state chunk {} entry count mismatch", chunk.index);
+        }
+        let mut batch = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            if !is_state_key(&key) {
+                bail!("state chunk {} contains reserved key", chunk.index);
+            }
+            if last_key.as_ref().is_some_and(|last| *last >= key) {
+                bail!(
+                    "state chunk {} keys are not strictly increasing",
+                    chunk.index
+                );
+            }
+            last_key = Some(key.clone());
+            batch.push((key, Some(value)));
+        }
+        storage.write_batch_if_supported(batch)?;
+    }
+
+    if storage.state_root() != manifest.state_root {
+        bail!(
+            "imported state root {} does not match manifest root {}",
+            hex::encode(storage.state_root()),
+            hex::encode(manifest.state_root)
+        );
+    }
+    Ok((manifest, storage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_state(n: usize) -> AuthenticatedStorage<MemoryStorage> {
+        let mut storage = AuthenticatedStorage::new(MemoryStorage::new()).unwrap();
+        let batch = (0..n)
+            .map(|i| {
+                (
+                    format!("acct:{i:04}").into_bytes(),
+                    Some(vec![i as u8; 16 + i % 7]),
+                )
+            })
+            .collect();
+        storage.write_batch_if_supported(batch).unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_state_sync_roundtrip_verifies_root() {
+        let mut source = sample_state(200);
+        let root = source.state_root();
+        // MVCC 元数据不计入状态根，也不随状态同步导出
+        source.set(b"__mvcc:flush_ts", b"meta").unwrap();
+        assert_eq!(source.state_root(), root);
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("sync");
+
+        let manifest = export_state(&source, 42, &out, 256).unwrap();
+        assert!(manifest.chunks.len() > 1);
+        assert_eq!(manifest.total_entries(), 200);
+        assert_eq!(manifest.state_root, root);
+        assert_eq!(StateSyncManifest::read_from(&out).unwrap(), manifest);
+
+        let (imported_manifest, imported) =
+            import_state(&out, MemoryStorage::new(), Some(&root)).unwrap();
+        assert_eq!(imported_manifest.height, 42);
+        assert_eq!(imported.state_root(), root);
+        assert_eq!(
+            imported.scan(b"acct:").unwrap(),
+            source.scan(b"acct:").unwrap()
+        );
+        assert_eq!(imported.get(b"__mvcc:flush_ts").unwrap(), None);
+
+        // 非空目标与错误的可信根均被拒绝
+        assert!(import_state(&out, imported.into_inner(), None).is_err());
+        assert!(import_state(&out, MemoryStorage::new(), Some(&[7u8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_state_sync_rejects_tampered_chunk() {
+        let source = sample_state(50);
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = export_state(&source, 1, dir.path(), 128).unwrap();
+
+        let path = dir.path().join(chunk_file(manifest.chunks[1].index));
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        match import_state(dir.path(), MemoryStorage::new(), None) {
+            Err(err) => assert!(err.to_string().contains("hash mismatch")),
+            Ok(_) => panic!("tampered chunk must be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_state_sync_computes_root_for_plain_storage() {
+        let mut plain = MemoryStorage::new();
+        plain.set(b"", b"empty key").unwrap();
+        for i in 0..40u8 {
+            plain.set(&[i, 0xaa], &[i]).unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = export_state(&plain, 9, dir.path(), 64).unwrap();
+
+        let mut scanned = MemoryStorage::new();
+        for (k, v) in plain.scan(&[]).unwrap() {
+            scanned.set(&k, &v).unwrap();
+        }
+        let expected = AuthenticatedStorage::new(scanned).unwrap().state_root();
+        assert_eq!(manifest.state_root, expected);
+        assert_eq!(manifest.total_entries(), 41);
+
+        let (_, imported) = import_state(dir.path(), MemoryStorage::new(), None).unwrap();
+        assert_eq!(imported.get(b"").unwrap(), Some(b"empty key".to_vec()));
+    }
+
+    #[test]
+    fn test_shared_export_matches_segmented_export() {
+        let mut source = sample_state(120);
+        source.set(b"__mvcc:flush_ts", b"meta").unwrap();
+        source.set(b"", b"empty key").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let segmented = export_state(&source, 5, &dir.path().join("scan"), 200).unwrap();
+
+        let shared = Arc::new(crate::LockedStorage::new(source.into_inner()));
+        let streamed = export_shared_state(&shared, 5, &dir.path().join("cursor"), 200).unwrap();
+        assert_eq!(streamed, segmented);
+        let (_, imported) = import_state(
+            &dir.path().join("cursor"),
+            MemoryStorage::new(),
+            Some(&segmented.state_root),
+        )
+        .unwrap();
+        assert_eq!(imported.get(b"acct:0007").unwrap(), Some(vec![7; 16]));
+    }
+}
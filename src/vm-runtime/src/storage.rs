@@ -16,8 +16,19 @@ use anyhow::Result;
 #[cfg(feature = "rocksdb-storage")]
 pub mod rocksdb_storage;
 
+// 快照清单 / 增量快照 / 压缩归档（纯文件操作，不依赖 RocksDB）
+pub mod snapshot;
+
+// 线程安全的共享存储（&self 读写，可跨 WASM 运行时 / 刷盘线程 / 服务共享）
+pub mod shared;
+pub use shared::{LockedStorage, SharedStorage, StorageHandle};
+#[cfg(feature = "async-storage")]
+pub use shared::AsyncStorage;
+
 #[cfg(feature = "rocksdb-storage")]
 pub use rocksdb_storage::{RocksDBStorage, RocksDBConfig, AdaptiveBatchConfig, AdaptiveBatchResult, RocksDBMetrics};
+#[cfg(feature = "rocksdb-storage")]
+pub use rocksdb_storage::{ColumnFamilyConfig, ColumnFamilyOptions, ColumnStorage, RocksDBColumn, SnapshotConfig};
 
 /// 存储接口，定义了虚拟机可以使用的存储操作
 pub trait Storage {
@@ -14,14 +14,151 @@
 //! - WriteBatch: 原子批量写入
 //! - Checkpoint: 快照管理
 //! - Pruning: 状态裁剪
+//! - 列族: 状态 / 元数据 / 回执 / 索引分列存储，各列族独立配置（见 [`RocksDBColumn`]）
 
+use super::snapshot::{self, SnapshotInfo, SnapshotManifest};
 use crate::Storage;
 use anyhow::{Context, Result};
 use std::path::Path;
 use std::sync::Arc;
 
 #[cfg(feature = "rocksdb-storage")]
-use rocksdb::{IteratorMode, Options, WriteBatch, WriteOptions, DB};
+use rocksdb::{ColumnFamilyDescriptor, IteratorMode, Options, WriteBatch, WriteOptions, DB};
+
+/// 存储列族
+///
+/// [`Storage`] 接口按键前缀自动路由，调用方无需感知列族：
+/// - `State`（RocksDB 默认列族）: 用户状态与其他未路由的键
+/// - `Meta`: MVCC 元数据（[`crate::MVCC_META_PREFIX`]：刷盘水位线、归档版本与区块水位线）
+/// - `Index`: 分片节点写入的对象版本索引（`obj_{hex}_version`）
+/// - `Receipts`: 不参与路由，仅经 [`RocksDBStorage::column`] 访问
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RocksDBColumn {
+    State,
+    Meta,
+    Receipts,
+    Index,
+}
+
+/// 对象版本索引键前缀（见 `state_commitment::object_version_key`）
+const OBJECT_INDEX_PREFIX: &[u8] = b"obj_";
+
+impl RocksDBColumn {
+    pub const ALL: [RocksDBColumn; 4] = [
+        RocksDBColumn::State,
+        RocksDBColumn::Meta,
+        RocksDBColumn::Receipts,
+        RocksDBColumn::Index,
+    ];
+
+    /// RocksDB 列族名（`State` 即默认列族，旧的单列族数据库原有数据都在其中）
+    pub fn name(self) -> &'static str {
+        match self {
+            RocksDBColumn::State => "default",
+            RocksDBColumn::Meta => "meta",
+            RocksDBColumn::Receipts => "receipts",
+            RocksDBColumn::Index => "index",
+        }
+    }
+
+    /// 路由到该列族的键前缀
+    fn routed_prefixes(self) -> &'static [&'static [u8]] {
+        match self {
+            RocksDBColumn::Meta => &[crate::mvcc::MVCC_META_PREFIX],
+            RocksDBColumn::Index => &[OBJECT_INDEX_PREFIX],
+            RocksDBColumn::State | RocksDBColumn::Receipts => &[],
+        }
+    }
+
+    /// 键所属的列族
+    pub fn for_key(key: &[u8]) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.routed_prefixes().iter().any(|p| key.starts_with(p)))
+            .unwrap_or(RocksDBColumn::State)
+    }
+
+    /// 以 `prefix` 开头的键是否可能落在该列族（决定前缀扫描覆盖哪些列族）
+    fn may_contain_prefix(self, prefix: &[u8]) -> bool {
+        match self {
+            RocksDBColumn::State => Self::for_key(prefix) == RocksDBColumn::State,
+            _ => self
+                .routed_prefixes()
+                .iter()
+                .any(|p| p.starts_with(prefix) || prefix.starts_with(p)),
+        }
+    }
+}
+
+/// 单个列族的选项
+#[derive(Debug, Clone)]
+pub struct ColumnFamilyOptions {
+    /// 布隆过滤器每键位数（0 表示不启用）
+    pub bloom_bits_per_key: f64,
+    /// 是否启用压缩（全局 `enable_compression` 关闭或未链接压缩库时不生效）
+    pub enable_compression: bool,
+    /// 占 `block_cache_size` 的份额（0.0 ~ 1.0），每个列族独立一份 LRU 块缓存
+    pub block_cache_share: f64,
+}
+
+/// 各列族选项
+#[derive(Debug, Clone)]
+pub struct ColumnFamilyConfig {
+    pub state: ColumnFamilyOptions,
+    pub meta: ColumnFamilyOptions,
+    pub receipts: ColumnFamilyOptions,
+    pub index: ColumnFamilyOptions,
+}
+
+impl Default for ColumnFamilyConfig {
+    fn default() -> Self {
+        Self {
+            // 状态：点查为主，占大部分缓存
+            state: ColumnFamilyOptions {
+                bloom_bits_per_key: 10.0,
+                enable_compression: true,
+                block_cache_share: 0.6,
+            },
+            // 元数据：体量小，读频繁
+            meta: ColumnFamilyOptions {
+                bloom_bits_per_key: 10.0,
+                enable_compression: false,
+                block_cache_share: 0.1,
+            },
+            // 回执：只追加、冷读，压缩优先
+            receipts: ColumnFamilyOptions {
+                bloom_bits_per_key: 0.0,
+                enable_compression: true,
+                block_cache_share: 0.1,
+            },
+            index: ColumnFamilyOptions {
+                bloom_bits_per_key: 10.0,
+                enable_compression: false,
+                block_cache_share: 0.2,
+            },
+        }
+    }
+}
+
+impl ColumnFamilyConfig {
+    pub fn get(&self, column: RocksDBColumn) -> &ColumnFamilyOptions {
+        match column {
+            RocksDBColumn::State => &self.state,
+            RocksDBColumn::Meta => &self.meta,
+            RocksDBColumn::Receipts => &self.receipts,
+            RocksDBColumn::Index => &self.index,
+        }
+    }
+
+    pub fn get_mut(&mut self, column: RocksDBColumn) -> &mut ColumnFamilyOptions {
+        match column {
+            RocksDBColumn::State => &mut self.state,
+            RocksDBColumn::Meta => &mut self.meta,
+            RocksDBColumn::Receipts => &mut self.receipts,
+            RocksDBColumn::Index => &mut self.index,
+        }
+    }
+}
 
 /// RocksDB 存储配置
 #[derive(Debug, Clone)]
@@ -46,6 +183,9 @@ pub struct RocksDBConfig {
 
     /// 最大后台压缩线程数 (默认 4)
     pub max_background_jobs: i32,
+
+    /// 各列族选项（布隆过滤器 / 压缩 / 块缓存份额）
+    pub column_families: ColumnFamilyConfig,
 }
 
 impl Default for RocksDBConfig {
@@ -58,6 +198,7 @@ impl Default for RocksDBConfig {
             enable_compression: false,
             create_if_missing: true,
             max_background_jobs: 4,
+            column_families: ColumnFamilyConfig::default(),
         }
     }
 }
@@ -73,6 +214,7 @@ impl RocksDBConfig {
             enable_compression: false,
             create_if_missing: true,
             max_background_jobs: 8,
+            column_families: ColumnFamilyConfig::default(),
         }
     }
 
@@ -81,6 +223,12 @@ impl RocksDBConfig {
         self.path = path.into();
         self
     }
+
+    /// 设置单个列族的选项并返回
+    pub fn with_column_options(mut self, column: RocksDBColumn, options: ColumnFamilyOptions) -> Self {
+        *self.column_families.get_mut(column) = options;
+        self
+    }
 }
 
 /// RocksDB 存储实现
@@ -119,8 +267,14 @@ pub struct SnapshotConfig {
     /// 最多保留多少个快照 (旧快照会被自动删除)
     pub max_snapshots: usize,
 
-    /// 是否压缩旧快照 (暂时保留配置,未实现)
+    /// 是否压缩旧快照：除最近 `uncompressed_snapshots` 个之外的快照目录打包压缩为单个归档文件
     pub compress_old_snapshots: bool,
+
+    /// 压缩旧快照时保留为目录的最近快照数 (默认 1)
+    pub uncompressed_snapshots: usize,
+
+    /// 每隔多少个快照做一次全量快照，其余为相对上一个快照的增量快照 (默认 1 即总是全量)
+    pub full_snapshot_interval: usize,
 }
 
 #[cfg(feature = "rocksdb-storage")]
@@ -132,6 +286,8 @@ impl Default for SnapshotConfig {
             seconds_per_snapshot: 0, // 默认禁用时间快照
             max_snapshots: 10,
             compress_old_snapshots: false,
+            uncompressed_snapshots: 1,
+            full_snapshot_interval: 1,
         }
     }
 }
@@ -188,36 +344,18 @@ pub struct AdaptiveBatchResult {
 #[cfg(feature = "rocksdb-storage")]
 impl RocksDBStorage {
     /// 创建新的 RocksDB 存储实例
+    ///
+    /// 打开全部列族（缺失的自动创建），并把旧单列族布局中应路由到其他列族的键迁移过去
     pub fn new(mut config: RocksDBConfig) -> Result<Self> {
-        let mut opts = Options::default();
-        opts.create_if_missing(config.create_if_missing);
-        opts.set_max_open_files(config.max_open_files);
-        opts.set_write_buffer_size(config.write_buffer_size);
-        opts.set_max_background_jobs(config.max_background_jobs);
-
-        // 配置块缓存
-        let cache = rocksdb::Cache::new_lru_cache(config.block_cache_size);
-        let mut block_opts = rocksdb::BlockBasedOptions::default();
-        block_opts.set_block_cache(&cache);
-        opts.set_block_based_table_factory(&block_opts);
-
-        // 配置压缩
-        if config.enable_compression {
-            opts.set_compression_type(rocksdb::DBCompressionType::Snappy);
-        } else {
-            opts.set_compression_type(rocksdb::DBCompressionType::None);
-        }
-
         // 打开数据库（如因未链接压缩库失败则自动降级）
-        let db = match DB::open(&opts, &config.path) {
+        let db = match Self::open_db(&config) {
             Ok(db) => db,
             Err(e) => {
                 let msg = e.to_string();
                 if msg.contains("Compression type") && msg.contains("not linked with the binary") {
                     // 自动降级为不压缩
-                    opts.set_compression_type(rocksdb::DBCompressionType::None);
                     config.enable_compression = false;
-                    DB::open(&opts, &config.path).context(format!(
+                    Self::open_db(&config).context(format!(
                         "Failed to open RocksDB at {} after falling back to no compression",
                         config.path
                     ))?
@@ -227,10 +365,131 @@ impl RocksDBStorage {
             }
         };
 
-        Ok(Self {
+        let storage = Self {
             db: Arc::new(db),
             config,
-        })
+        };
+        storage.migrate_legacy_layout()?;
+        Ok(storage)
+    }
+
+    fn open_db(config: &RocksDBConfig) -> std::result::Result<DB, rocksdb::Error> {
+        let mut opts = Options::default();
+        opts.create_if_missing(config.create_if_missing);
+        opts.create_missing_column_families(true);
+        opts.set_max_open_files(config.max_open_files);
+        opts.set_max_background_jobs(config.max_background_jobs);
+
+        let mut descriptors: Vec<ColumnFamilyDescriptor> = RocksDBColumn::ALL
+            .into_iter()
+            .map(|column| ColumnFamilyDescriptor::new(column.name(), Self::column_options(config, column)))
+            .collect();
+        // 打开时必须列出库中已有的全部列族；未知列族按默认选项打开
+        // （库尚不存在时 list_cf 报错，视为没有已有列族）
+        for name in DB::list_cf(&opts, &config.path).unwrap_or_default() {
+            if !RocksDBColumn::ALL.iter().any(|c| c.name() == name) {
+                descriptors.push(ColumnFamilyDescriptor::new(name, Options::default()));
+            }
+        }
+        DB::open_cf_descriptors(&opts, &config.path, descriptors)
+    }
+
+    fn column_options(config: &RocksDBConfig, column: RocksDBColumn) -> Options {
+        let cf = config.column_families.get(column);
+        let mut opts = Options::default();
+        opts.set_write_buffer_size(config.write_buffer_size);
+
+        // 配置块缓存与布隆过滤器
+        let cache_bytes = (config.block_cache_size as f64 * cf.block_cache_share.clamp(0.0, 1.0)) as usize;
+        let cache = rocksdb::Cache::new_lru_cache(cache_bytes.max(1024 * 1024));
+        let mut block_opts = rocksdb::BlockBasedOptions::default();
+        block_opts.set_block_cache(&cache);
+        if cf.bloom_bits_per_key > 0.0 {
+            block_opts.set_bloom_filter(cf.bloom_bits_per_key, false);
+        }
+        opts.set_block_based_table_factory(&block_opts);
+
+        // 配置压缩
+        if config.enable_compression && cf.enable_compression {
+            opts.set_compression_type(rocksdb::DBCompressionType::Snappy);
+        } else {
+            opts.set_compression_type(rocksdb::DBCompressionType::None);
+        }
+        opts
+    }
+
+    /// 列族句柄（全部列族在打开时已创建）
+    fn cf(&self, column: RocksDBColumn) -> &rocksdb::ColumnFamily {
+        column_handle(&self.db, column)
+    }
+
+    /// 按列族访问（不做前缀路由），同样实现 [`Storage`]
+    pub fn column(&self, column: RocksDBColumn) -> ColumnStorage<'_> {
+        ColumnStorage {
+            storage: self,
+            column,
+        }
+    }
+
+    /// 迁移旧的单列族数据库：把默认列族中应路由到其他列族的键移入目标列族
+    ///
+    /// 每块在同一 WriteBatch 中写入目标列族并删除原键，中途崩溃后重新打开会继续迁移；
+    /// 已迁移的库只做一次前缀定位。返回迁移的键数。
+    pub fn migrate_legacy_layout(&self) -> Result<usize> {
+        const MIGRATION_CHUNK: usize = 10_000;
+        let default = self.cf(RocksDBColumn::State);
+        let mut moved = 0;
+        for column in RocksDBColumn::ALL {
+            let target = self.cf(column);
+            for &prefix in column.routed_prefixes() {
+                loop {
+                    let mut batch = WriteBatch::default();
+                    let mut chunk = 0;
+                    for item in self
+                        .db
+                        .iterator_cf(default, IteratorMode::From(prefix, rocksdb::Direction::Forward))
+                    {
+                        let (key, value) = item.context("Failed to read legacy key")?;
+                        if !key.starts_with(prefix) || chunk == MIGRATION_CHUNK {
+                            break;
+                        }
+                        batch.put_cf(target, &key, &value);
+                        batch.delete_cf(default, &key);
+                        chunk += 1;
+                    }
+                    if chunk == 0 {
+                        break;
+                    }
+                    self.db
+                        .write(batch)
+                        .context("Failed to migrate legacy keys into column family")?;
+                    moved += chunk;
+                }
+            }
+        }
+        Ok(moved)
+    }
+
+    /// 按列族前缀扫描
+    fn scan_column(&self, column: RocksDBColumn, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut results = Vec::new();
+        let iter = self.db.iterator_cf(
+            self.cf(column),
+            IteratorMode::From(prefix, rocksdb::Direction::Forward),
+        );
+
+        for item in iter {
+            let (key, value) = item.context("Failed to read iterator item")?;
+
+            // 检查是否仍然匹配前缀
+            if !key.starts_with(prefix) {
+                break;
+            }
+
+            results.push((key.to_vec(), value.to_vec()));
+        }
+
+        Ok(results)
     }
 
     /// 使用默认配置创建
@@ -250,10 +509,7 @@ impl RocksDBStorage {
         let mut write_batch = WriteBatch::default();
 
         for (key, value) in batch {
-            match value {
-                Some(v) => write_batch.put(&key, &v),
-                None => write_batch.delete(&key),
-            }
+            put_routed(&self.db, &mut write_batch, &key, value.as_deref());
         }
 
         self.db
@@ -270,10 +526,7 @@ impl RocksDBStorage {
         let mut write_batch = WriteBatch::default();
 
         for (key, value) in batch {
-            match value {
-                Some(v) => write_batch.put(&key, &v),
-                None => write_batch.delete(&key),
-            }
+            put_routed(&self.db, &mut write_batch, &key, value.as_deref());
         }
 
         self.db
@@ -291,10 +544,7 @@ impl RocksDBStorage {
     ) -> Result<()> {
         let mut write_batch = WriteBatch::default();
         for (key, value) in batch {
-            match value {
-                Some(v) => write_batch.put(&key, &v),
-                None => write_batch.delete(&key),
-            }
+            put_routed(&self.db, &mut write_batch, &key, value.as_deref());
         }
         let mut opts = WriteOptions::default();
         opts.disable_wal(disable_wal);
@@ -324,10 +574,7 @@ impl RocksDBStorage {
         for chunk in batch.chunks(chunk_size) {
             let mut write_batch = WriteBatch::default();
             for (key, value) in chunk {
-                match value {
-                    Some(v) => write_batch.put(&key, &v),
-                    None => write_batch.delete(&key),
-                }
+                put_routed(&self.db, &mut write_batch, key, value.as_deref());
             }
             self.db
                 .write_opt(write_batch, &opts)
@@ -381,10 +628,7 @@ impl RocksDBStorage {
             let slice = &batch[idx..end];
             let mut wb = WriteBatch::default();
             for (k, v) in slice.iter() {
-                match v {
-                    Some(val) => wb.put(k, val),
-                    None => wb.delete(k),
-                }
+                put_routed(&self.db, &mut wb, k, v.as_deref());
             }
             let start = std::time::Instant::now();
             self.db
@@ -479,7 +723,9 @@ impl RocksDBStorage {
 
     /// 从快照恢复数据库
     ///
-    /// 注意: 这会创建一个新的数据库实例,指向快照路径
+    /// 注意: 无清单的 checkpoint（`create_checkpoint` 创建）直接作为数据库打开,新实例指向快照路径;
+    /// `maybe_create_snapshot` 创建的快照（全量 / 增量链 / 压缩归档）先重组到 `config.path`
+    /// （须为空或不存在）再打开,快照本身保持不变
     /// 使用场景: 灾难恢复、回滚到历史状态
     pub fn restore_from_checkpoint<P: AsRef<Path>>(
         checkpoint_path: P,
@@ -495,6 +741,14 @@ impl RocksDBStorage {
             );
         }
 
+        if SnapshotInfo::open(checkpoint_path_ref)?.manifest.is_some() {
+            snapshot::materialize(checkpoint_path_ref, Path::new(&config.path)).context(format!(
+                "Failed to reassemble snapshot {}",
+                checkpoint_path_ref.display()
+            ))?;
+            return Self::new(config);
+        }
+
         // 直接打开快照目录作为数据库
         let mut restore_config = config.clone();
         restore_config.path = checkpoint_path_ref.to_string_lossy().to_string();
@@ -530,9 +784,43 @@ impl RocksDBStorage {
         Ok(checkpoints)
     }
 
+    /// 列出指定目录下的全部快照（含增量快照与压缩归档），按区块号排序
+    pub fn list_snapshots<P: AsRef<Path>>(base_path: P) -> Result<Vec<SnapshotInfo>> {
+        snapshot::list_snapshots(base_path.as_ref())
+    }
+
+    /// 导出可移植的分块状态快照（见 [`crate::state_sync`]）
+    ///
+    /// 先在 `out_dir` 旁创建临时 checkpoint,从中以游标导出一致的状态视图,导出期间本库可照常写入;
+    /// 临时 checkpoint 导出后删除
+    pub fn export_state_snapshot<P: AsRef<Path>>(
+        &self,
+        height: u64,
+        out_dir: P,
+        chunk_bytes: usize,
+    ) -> Result<crate::state_sync::StateSyncManifest> {
+        let out_dir = out_dir.as_ref();
+        let checkpoint_path = out_dir.with_extension("checkpoint");
+        if checkpoint_path.exists() {
+            std::fs::remove_dir_all(&checkpoint_path)?;
+        }
+        self.create_checkpoint(&checkpoint_path)?;
+
+        let result = (|| {
+            let checkpoint = std::sync::Arc::new(Self::new(RocksDBConfig {
+                path: checkpoint_path.to_string_lossy().to_string(),
+                ..self.config.clone()
+            })?);
+            crate::state_sync::export_shared_state(&checkpoint, height, out_dir, chunk_bytes)
+        })();
+        std::fs::remove_dir_all(&checkpoint_path)?;
+        result
+    }
+
     /// 定期快照管理器 - 基于区块数
     ///
-    /// 根据配置的区块间隔创建快照,并自动清理旧快照
+    /// 根据配置的区块间隔创建快照（按 `full_snapshot_interval` 决定全量或增量）,
+    /// 自动清理旧快照,并按配置压缩旧快照
     pub fn maybe_create_snapshot(
         &self,
         block_number: u64,
@@ -556,42 +844,117 @@ impl RocksDBStorage {
         let snapshot_name = format!("snapshot_block_{}_ts_{}", block_number, timestamp);
         let snapshot_path = config.base_path.join(&snapshot_name);
 
-        // 创建快照
-        self.create_checkpoint(&snapshot_path)?;
+        // 上一个快照距全量快照的链长决定本次是否做增量
+        let snapshots = Self::list_snapshots(&config.base_path)?;
+        let base = snapshots
+            .iter()
+            .rev()
+            .find(|s| s.manifest.is_some())
+            .filter(|prev| Self::chain_depth(&snapshots, prev) + 1 < config.full_snapshot_interval);
+
+        // 先在临时目录中创建快照并写入清单,完成后改名,中途失败不会留下无效快照
+        let partial_path = config.base_path.join(format!("{}.partial", snapshot_name));
+        if partial_path.exists() {
+            std::fs::remove_dir_all(&partial_path)?;
+        }
+        self.create_checkpoint(&partial_path)?;
+        match base {
+            Some(base) => {
+                snapshot::make_incremental(&partial_path, block_number, base)?;
+            }
+            None => {
+                let manifest = SnapshotManifest::for_full_dir(&partial_path, block_number)?;
+                snapshot::write_manifest(&partial_path, &manifest)?;
+            }
+        }
+        std::fs::rename(&partial_path, &snapshot_path)?;
 
         // 清理旧快照
         self.cleanup_old_snapshots(config)?;
 
+        // 压缩旧快照
+        if config.compress_old_snapshots {
+            Self::compress_old_snapshots(config)?;
+        }
+
         Ok(Some(snapshot_path))
     }
 
-    /// 清理旧快照,保留最近的 N 个
+    /// 增量快照到最近全量快照的链长（全量为 0）
+    fn chain_depth(snapshots: &[SnapshotInfo], snapshot: &SnapshotInfo) -> usize {
+        let mut depth = 0;
+        let mut current = snapshot;
+        while let Some(base) = current.base() {
+            match snapshots.iter().find(|s| s.name == base) {
+                Some(next) => {
+                    depth += 1;
+                    current = next;
+                }
+                None => break,
+            }
+        }
+        depth
+    }
+
+    /// 把较旧的快照目录压缩为归档文件（无清单的 checkpoint 不处理）
+    fn compress_old_snapshots(config: &SnapshotConfig) -> Result<()> {
+        let snapshots = Self::list_snapshots(&config.base_path)?;
+        let aged = snapshots.len().saturating_sub(config.uncompressed_snapshots);
+        for info in &snapshots[..aged] {
+            if !info.compressed && info.manifest.is_some() {
+                snapshot::compress_snapshot(&info.path)
+                    .context(format!("Failed to compress snapshot {}", info.name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 清理旧快照,保留最近的 N 个（以及它们的增量基线链）
     fn cleanup_old_snapshots(&self, config: &SnapshotConfig) -> Result<()> {
-        let mut snapshots = Self::list_checkpoints(&config.base_path)?;
-
-        // 按修改时间排序(最新的在后面)
-        snapshots.sort_by_cached_key(|path| {
-            std::fs::metadata(path)
-                .and_then(|m| m.modified())
-                .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-        });
-
-        // 删除超出限制的旧快照
-        if snapshots.len() > config.max_snapshots {
-            let to_delete = &snapshots[..snapshots.len() - config.max_snapshots];
-            for path in to_delete {
-                if let Err(e) = std::fs::remove_dir_all(path) {
-                    eprintln!("Warning: Failed to remove old snapshot {:?}: {}", path, e);
+        // 按区块号排序(最新的在后面)
+        let snapshots = Self::list_snapshots(&config.base_path)?;
+        if snapshots.len() <= config.max_snapshots {
+            return Ok(());
+        }
+
+        let mut keep: std::collections::HashSet<&str> = snapshots
+            [snapshots.len() - config.max_snapshots..]
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        let mut pending: Vec<&str> = keep.iter().copied().collect();
+        while let Some(name) = pending.pop() {
+            let base = snapshots
+                .iter()
+                .find(|s| s.name == name)
+                .and_then(|s| s.base());
+            if let Some(base) = base {
+                if keep.insert(base) {
+                    pending.push(base);
                 }
             }
         }
 
+        // 删除超出限制且不被保留快照引用的旧快照
+        for info in snapshots.iter().filter(|s| !keep.contains(s.name.as_str())) {
+            let result = if info.compressed {
+                std::fs::remove_file(&info.path)
+            } else {
+                std::fs::remove_dir_all(&info.path)
+            };
+            if let Err(e) = result {
+                eprintln!("Warning: Failed to remove old snapshot {:?}: {}", info.path, e);
+            }
+        }
+
         Ok(())
     }
 
     /// 压缩数据库
     pub fn compact_range(&self, start: Option<&[u8]>, end: Option<&[u8]>) -> Result<()> {
-        self.db.compact_range(start, end);
+        for column in RocksDBColumn::ALL {
+            self.db.compact_range_cf(self.cf(column), start, end);
+        }
         Ok(())
     }
 
@@ -623,12 +986,12 @@ pub struct BatchTransaction {
 impl BatchTransaction {
     /// 向批量事务中写入键值
     pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&mut self, key: K, value: V) {
-        self.batch.put(key.as_ref(), value.as_ref());
+        put_routed(&self.db, &mut self.batch, key.as_ref(), Some(value.as_ref()));
     }
 
     /// 在批量事务中删除键
     pub fn delete<K: AsRef<[u8]>>(&mut self, key: K) {
-        self.batch.delete(key.as_ref());
+        put_routed(&self.db, &mut self.batch, key.as_ref(), None);
     }
 
     /// 提交批量事务
@@ -658,46 +1021,195 @@ impl BatchTransaction {
     }
 }
 
+#[cfg(feature = "rocksdb-storage")]
+fn column_handle(db: &DB, column: RocksDBColumn) -> &rocksdb::ColumnFamily {
+    db.cf_handle(column.name())
+        .expect("column family is created when the database is opened")
+}
+
+/// 按键前缀路由写入 WriteBatch（`None` 表示删除）
+#[cfg(feature = "rocksdb-storage")]
+fn put_routed(db: &DB, batch: &mut WriteBatch, key: &[u8], value: Option<&[u8]>) {
+    let cf = column_handle(db, RocksDBColumn::for_key(key));
+    match value {
+        Some(v) => batch.put_cf(cf, key, v),
+        None => batch.delete_cf(cf, key),
+    }
+}
+
 #[cfg(feature = "rocksdb-storage")]
 impl Storage for RocksDBStorage {
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        self.db.get(key).context("Failed to get value from RocksDB")
+        self.column(RocksDBColumn::for_key(key)).get(key)
     }
 
     fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
-        self.db
-            .put(key, value)
-            .context("Failed to set value in RocksDB")
+        self.column(RocksDBColumn::for_key(key)).set(key, value)
     }
 
     fn delete(&mut self, key: &[u8]) -> Result<()> {
-        self.db
-            .delete(key)
-            .context("Failed to delete key from RocksDB")
+        self.column(RocksDBColumn::for_key(key)).delete(key)
     }
 
+    /// 前缀可能跨多个列族时（如空前缀）合并各列族结果并按键排序
     fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let columns: Vec<RocksDBColumn> = RocksDBColumn::ALL
+            .into_iter()
+            .filter(|c| c.may_contain_prefix(prefix))
+            .collect();
         let mut results = Vec::new();
-        let iter = self
-            .db
-            .iterator(IteratorMode::From(prefix, rocksdb::Direction::Forward));
+        for column in &columns {
+            results.extend(self.scan_column(*column, prefix)?);
+        }
+        if columns.len() > 1 {
+            results.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        Ok(results)
+    }
 
-        for item in iter {
-            let (key, value) = item.context("Failed to read iterator item")?;
+    fn write_batch_if_supported(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<bool> {
+        self.write_batch_optimized(batch)?;
+        Ok(true)
+    }
+}
 
-            // 检查是否仍然匹配前缀
-            if !key.starts_with(prefix) {
+/// RocksDB 原生支持并发读写：`Arc<RocksDBStorage>` 可在运行时、刷盘线程与服务间共享而无需外层锁
+#[cfg(feature = "rocksdb-storage")]
+impl super::shared::SharedStorage for RocksDBStorage {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Storage::get(self, key)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.column(RocksDBColumn::for_key(key)).set(key, value)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        self.column(RocksDBColumn::for_key(key)).delete(key)
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Storage::scan(self, prefix)
+    }
+
+    fn write_batch(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        self.write_batch_optimized(batch)
+    }
+
+    /// 以游标遍历，不物化整段数据；前缀跨多个列族时按键序归并各列族的游标
+    fn for_each_prefix(
+        &self,
+        prefix: &[u8],
+        visit: &mut dyn FnMut(&[u8], &[u8]) -> bool,
+    ) -> Result<()> {
+        let mut cursors = Vec::new();
+        for column in RocksDBColumn::ALL
+            .into_iter()
+            .filter(|c| c.may_contain_prefix(prefix))
+        {
+            let mut iter = self.db.iterator_cf(
+                self.cf(column),
+                IteratorMode::From(prefix, rocksdb::Direction::Forward),
+            );
+            let head = next_in_prefix(&mut iter, prefix)?;
+            cursors.push((iter, head));
+        }
+        loop {
+            let next = cursors
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (_, head))| head.as_ref().map(|(key, _)| (i, key)))
+                .min_by(|a, b| a.1.cmp(b.1))
+                .map(|(i, _)| i);
+            let Some(i) = next else { break };
+            let (iter, head) = &mut cursors[i];
+            let (key, value) = head.take().expect("selected cursor has a head");
+            if !visit(&key, &value) {
                 break;
             }
+            *head = next_in_prefix(iter, prefix)?;
+        }
+        Ok(())
+    }
+}
 
-            results.push((key.to_vec(), value.to_vec()));
+#[cfg(feature = "rocksdb-storage")]
+type RawEntry = (Box<[u8]>, Box<[u8]>);
+
+/// 游标的下一条仍以 `prefix` 开头的键值
+#[cfg(feature = "rocksdb-storage")]
+fn next_in_prefix(
+    iter: &mut impl Iterator<Item = Result<RawEntry, rocksdb::Error>>,
+    prefix: &[u8],
+) -> Result<Option<RawEntry>> {
+    match iter.next() {
+        Some(item) => {
+            let (key, value) = item.context("Failed to read iterator item")?;
+            Ok(key.starts_with(prefix).then_some((key, value)))
         }
+        None => Ok(None),
+    }
+}
 
-        Ok(results)
+/// 单个列族的存储视图（见 [`RocksDBStorage::column`]）
+#[cfg(feature = "rocksdb-storage")]
+pub struct ColumnStorage<'a> {
+    storage: &'a RocksDBStorage,
+    column: RocksDBColumn,
+}
+
+#[cfg(feature = "rocksdb-storage")]
+impl ColumnStorage<'_> {
+    pub fn column(&self) -> RocksDBColumn {
+        self.column
+    }
+
+    /// 列族内原子批量写入
+    pub fn write_batch(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        let cf = self.storage.cf(self.column);
+        let mut write_batch = WriteBatch::default();
+        for (key, value) in batch {
+            match value {
+                Some(v) => write_batch.put_cf(cf, &key, &v),
+                None => write_batch.delete_cf(cf, &key),
+            }
+        }
+        self.storage
+            .db
+            .write(write_batch)
+            .context("Failed to write column family batch to RocksDB")
+    }
+}
+
+#[cfg(feature = "rocksdb-storage")]
+impl Storage for ColumnStorage<'_> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.storage
+            .db
+            .get_cf(self.storage.cf(self.column), key)
+            .context("Failed to get value from RocksDB")
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.storage
+            .db
+            .put_cf(self.storage.cf(self.column), key, value)
+            .context("Failed to set value in RocksDB")
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.storage
+            .db
+            .delete_cf(self.storage.cf(self.column), key)
+            .context("Failed to delete key from RocksDB")
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.storage.scan_column(self.column, prefix)
     }
 
     fn write_batch_if_supported(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<bool> {
-        self.write_batch_optimized(batch)?;
+        self.write_batch(batch)?;
         Ok(true)
     }
 }
@@ -724,10 +1236,19 @@ impl RocksDBStorage {
             .map_err(|e| anyhow::anyhow!("parse {} failed: {} => {}", name, s, e))
     }
 
-    /// 获取基础统计信息
+    /// 各列族属性之和（属性缺失的列族按 0 计）
+    fn prop_u64_all_columns(&self, name: &str) -> u64 {
+        RocksDBColumn::ALL
+            .into_iter()
+            .filter_map(|column| self.db.property_value_cf(self.cf(column), name).ok().flatten())
+            .filter_map(|s| s.trim().parse::<u64>().ok())
+            .sum()
+    }
+
+    /// 获取基础统计信息（键数与 SST 大小为全部列族之和）
     pub fn get_stats(&self) -> Result<RocksDBStats> {
-        let num_keys = self.prop_u64("rocksdb.estimate-num-keys").unwrap_or(0);
-        let total_sst_size_bytes = self.prop_u64("rocksdb.total-sst-files-size").unwrap_or(0);
+        let num_keys = self.prop_u64_all_columns("rocksdb.estimate-num-keys");
+        let total_sst_size_bytes = self.prop_u64_all_columns("rocksdb.total-sst-files-size");
         let cache_hit = self.prop_u64("rocksdb.block.cache.hit").unwrap_or(0);
         let cache_miss = self.prop_u64("rocksdb.block.cache.miss").unwrap_or(0);
         Ok(RocksDBStats {
@@ -759,8 +1280,8 @@ impl RocksDBStorage {
     /// 采集RocksDB内部指标用于Prometheus导出
     pub fn collect_metrics(&self) -> RocksDBMetrics {
         RocksDBMetrics {
-            estimate_num_keys: self.prop_u64("rocksdb.estimate-num-keys").unwrap_or(0),
-            total_sst_size_bytes: self.prop_u64("rocksdb.total-sst-files-size").unwrap_or(0),
+            estimate_num_keys: self.prop_u64_all_columns("rocksdb.estimate-num-keys"),
+            total_sst_size_bytes: self.prop_u64_all_columns("rocksdb.total-sst-files-size"),
             cache_hit: self.prop_u64("rocksdb.block.cache.hit").unwrap_or(0),
             cache_miss: self.prop_u64("rocksdb.block.cache.miss").unwrap_or(0),
             compaction_cpu_micros: self.prop_u64("rocksdb.compaction.sum.cpu.micros").unwrap_or(0),
@@ -834,6 +1355,58 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rocksdb_column_family_routing_and_migration() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_cf_db");
+
+        // 旧布局：单一默认列族
+        {
+            let mut opts = Options::default();
+            opts.create_if_missing(true);
+            let legacy = DB::open(&opts, &db_path)?;
+            legacy.put(b"user:alice", b"1")?;
+            legacy.put(crate::FLUSH_WATERMARK_KEY, 7u64.to_be_bytes())?;
+            legacy.put(b"obj_ab_version", b"3")?;
+        }
+
+        let mut storage = RocksDBStorage::new_with_path(&db_path)?;
+        assert_eq!(storage.migrate_legacy_layout()?, 0, "migrated on open");
+        assert_eq!(
+            storage.column(RocksDBColumn::Meta).get(crate::FLUSH_WATERMARK_KEY)?,
+            Some(7u64.to_be_bytes().to_vec())
+        );
+        assert_eq!(storage.column(RocksDBColumn::Index).get(b"obj_ab_version")?, Some(b"3".to_vec()));
+        assert_eq!(storage.column(RocksDBColumn::State).get(b"obj_ab_version")?, None);
+
+        // Storage 接口按前缀路由，对调用方透明
+        assert_eq!(storage.get(b"obj_ab_version")?, Some(b"3".to_vec()));
+        storage.set(b"__mvcc:x", b"m")?;
+        assert_eq!(storage.column(RocksDBColumn::Meta).get(b"__mvcc:x")?, Some(b"m".to_vec()));
+        let all: Vec<Vec<u8>> = storage.scan(b"")?.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            all,
+            vec![
+                b"__mvcc:flush_watermark".to_vec(),
+                b"__mvcc:x".to_vec(),
+                b"obj_ab_version".to_vec(),
+                b"user:alice".to_vec(),
+            ]
+        );
+
+        // 回执列族只能经列族视图访问
+        storage.column(RocksDBColumn::Receipts).set(b"user:alice", b"receipt")?;
+        assert_eq!(storage.get(b"user:alice")?, Some(b"1".to_vec()));
+        drop(storage);
+
+        let storage = RocksDBStorage::new_with_path(&db_path)?;
+        assert_eq!(
+            storage.column(RocksDBColumn::Receipts).get(b"user:alice")?,
+            Some(b"receipt".to_vec())
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_rocksdb_scan() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -1022,6 +1595,8 @@ mod tests {
             seconds_per_snapshot: 0,
             max_snapshots: 3,
             compress_old_snapshots: false,
+            uncompressed_snapshots: 1,
+            full_snapshot_interval: 1,
         };
 
         // 模拟区块进度,创建多个快照
@@ -1045,4 +1620,80 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_rocksdb_incremental_compressed_snapshot_restore() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_incr_db");
+        let snapshot_base = temp_dir.path().join("snapshots");
+
+        let mut storage = RocksDBStorage::new_with_path(&db_path)?;
+
+        // 每 3 个快照一个全量，其余增量；只保留最新快照为目录
+        let config = SnapshotConfig {
+            base_path: snapshot_base.clone(),
+            blocks_per_snapshot: 10,
+            seconds_per_snapshot: 0,
+            max_snapshots: 2,
+            compress_old_snapshots: true,
+            uncompressed_snapshots: 1,
+            full_snapshot_interval: 3,
+        };
+
+        for block in 0..=20 {
+            storage.set(format!("block_{:02}", block).as_bytes(), b"data")?;
+            // 落盘为 SST，使增量快照可以复用之前的文件
+            storage.compact_range(None, None)?;
+            storage.maybe_create_snapshot(block, &config)?;
+        }
+
+        let snapshots = RocksDBStorage::list_snapshots(&snapshot_base)?;
+        let blocks: Vec<Option<u64>> = snapshots.iter().map(|s| s.block()).collect();
+        assert_eq!(blocks, vec![Some(0), Some(10), Some(20)], "base chain is retained");
+        assert!(snapshots[0].compressed && snapshots[1].compressed && !snapshots[2].compressed);
+        assert!(snapshots[2].manifest.as_ref().unwrap().is_incremental());
+
+        let restore_path = temp_dir.path().join("restored");
+        let restored = RocksDBStorage::restore_from_checkpoint(
+            &snapshots[2].path,
+            RocksDBConfig::default().with_path(restore_path.to_string_lossy()),
+        )?;
+        for block in 0..=20 {
+            assert_eq!(restored.get(format!("block_{:02}", block).as_bytes())?, Some(b"data".to_vec()));
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_rocksdb_export_state_snapshot_roundtrip() -> Result<()> {
+        use crate::state_commitment::AuthenticatedStorage;
+
+        let temp_dir = tempdir()?;
+        let storage = RocksDBStorage::new_with_path(temp_dir.path().join("db"))?;
+        let mut authed = AuthenticatedStorage::new(storage)?;
+        let batch = (0..300u32)
+            .map(|i| (format!("acct:{i:04}").into_bytes(), Some(i.to_le_bytes().to_vec())))
+            .collect();
+        authed.write_batch_if_supported(batch)?;
+        // 与元数据列族共用首字节的状态键，导出时需与各列族按键序归并
+        authed.set(b"_balance", b"1")?;
+        authed.set(b"__mvcc:flush_ts", b"meta")?;
+        let root = authed.state_root();
+        let storage = authed.into_inner();
+
+        let out = temp_dir.path().join("sync");
+        let manifest = storage.export_state_snapshot(7, &out, 512)?;
+        assert!(manifest.chunks.len() > 1);
+        assert_eq!(manifest.total_entries(), 301);
+        assert_eq!(manifest.state_root, root);
+        assert!(!out.with_extension("checkpoint").exists());
+
+        let (imported_manifest, imported) =
+            crate::state_sync::import_state(&out, crate::MemoryStorage::new(), Some(&root))?;
+        assert_eq!(imported_manifest, manifest);
+        assert_eq!(imported.state_root(), root);
+        assert_eq!(imported.get(b"acct:0042")?, Some(42u32.to_le_bytes().to_vec()));
+        assert_eq!(imported.get(b"_balance")?, Some(b"1".to_vec()));
+        assert_eq!(imported.get(b"__mvcc:flush_ts")?, None);
+        Ok(())
+    }
 }
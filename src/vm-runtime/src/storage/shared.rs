@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 线程安全的共享存储抽象
+//!
+//! [`Storage`] 的写方法取 `&mut self`，跨线程共享只能再包一层全局 `Mutex`。
+//! [`SharedStorage`] 全部方法取 `&self`（内部可变），要求 `Send + Sync`，
+//! 同一个实例可以 `Arc` 形式同时交给 WASM 运行时、MVCC 自动刷盘线程与 gRPC 服务：
+//! - `RocksDBStorage` 原生实现（RocksDB 自身支持并发读写）
+//! - 其它 [`Storage`] 经 [`LockedStorage`] 适配（读写锁，读操作可并发）
+//! - `Arc<T: SharedStorage>` 反过来实现 [`Storage`]，可直接用于 `Runtime<S>`、
+//!   `AuthenticatedStorage<S>`、`MvccStore::flush_to_storage` 等现有接口
+//!
+//! 启用 `async-storage` 特性时，[`AsyncStorage`] 为 [`StorageHandle`] 提供基于
+//! `tokio::task::spawn_blocking` 的异步读写，避免阻塞异步运行时的工作线程。
+
+use super::Storage;
+use anyhow::Result;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// 可跨线程共享的存储：读写均取 `&self`
+pub trait SharedStorage: Send + Sync {
+    /// 根据键获取值
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// 设置键值对
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()>;
+
+    /// 删除键值对（与 [`Storage::delete`] 区分命名，避免 `Arc` 句柄上的方法解析歧义）
+    fn remove(&self, key: &[u8]) -> Result<()>;
+
+    /// 根据前缀扫描键值对（按键排序）
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// 原子批量写入（`None` 为删除）
+    fn write_batch(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()>;
+
+    /// 按键序遍历前缀下的键值，`visit` 返回 false 时提前结束
+    ///
+    /// 默认实现基于 [`scan`](Self::scan)；后端支持游标时可覆盖以避免一次性物化整段数据。
+    fn for_each_prefix(
+        &self,
+        prefix: &[u8],
+        visit: &mut dyn FnMut(&[u8], &[u8]) -> bool,
+    ) -> Result<()> {
+        for (key, value) in self.scan(prefix)? {
+            if !visit(&key, &value) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 共享存储句柄
+pub type StorageHandle = Arc<dyn SharedStorage>;
+
+/// 共享句柄的克隆即为同一存储的又一个 `&mut` 视图（写入经内部可变性落到同一实例）
+impl<T: SharedStorage + ?Sized> Storage for Arc<T> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        (**self).get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        (**self).put(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        (**self).remove(key)
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        (**self).scan(prefix)
+    }
+
+    fn write_batch_if_supported(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<bool> {
+        (**self).write_batch(batch)?;
+        Ok(true)
+    }
+}
+
+/// 以读写锁把任意 [`Storage`] 适配为 [`SharedStorage`]
+#[derive(Default)]
+pub struct LockedStorage<S: Storage> {
+    inner: RwLock<S>,
+}
+
+impl<S: Storage> LockedStorage<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: RwLock::new(inner),
+        }
+    }
+
+    /// 转为共享句柄
+    pub fn into_handle(self) -> StorageHandle
+    where
+        S: Send + Sync + 'static,
+    {
+        Arc::new(self)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner.into_inner()
+    }
+}
+
+impl<S: Storage + Send + Sync> SharedStorage for LockedStorage<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.read().get(key)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.inner.write().set(key, value)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        self.inner.write().delete(key)
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner.read().scan(prefix)
+    }
+
+    /// 底层不支持批量写入时在同一把写锁内逐条应用，对其它线程仍表现为原子
+    fn write_batch(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        let mut inner = self.inner.write();
+        if !inner.write_batch_if_supported(batch.clone())? {
+            for (key, value) in batch {
+                match value {
+                    Some(v) => inner.set(&key, &v)?,
+                    None => inner.delete(&key)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 共享存储的异步接口：阻塞 I/O 移交 `spawn_blocking` 线程池执行
+#[cfg(feature = "async-storage")]
+pub trait AsyncStorage {
+    fn get_async(
+        &self,
+        key: Vec<u8>,
+    ) -> impl std::future::Future<Output = Result<Option<Vec<u8>>>> + Send;
+
+    fn put_async(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn remove_async(&self, key: Vec<u8>) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn scan_async(
+        &self,
+        prefix: Vec<u8>,
+    ) -> impl std::future::Future<Output = Result<Vec<(Vec<u8>, Vec<u8>)>>> + Send;
+
+    fn write_batch_async(
+        &self,
+        batch: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+}
+
+#[cfg(feature = "async-storage")]
+async fn blocking<T, F>(storage: &StorageHandle, op: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn SharedStorage) -> Result<T> + Send + 'static,
+{
+    let storage = Arc::clone(storage);
+    tokio::task::spawn_blocking(move || op(&*storage))
+        .await
+        .map_err(|e| anyhow::anyhow!("storage task failed: {e}"))?
+}
+
+#[cfg(feature = "async-storage")]
+impl AsyncStorage for StorageHandle {
+    async fn get_async(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        blocking(self, move |s| s.get(&key)).await
+    }
+
+    async fn put_async(&self, key: Vec<u8>, value: Vec<u8>) -> Result<()> {
+        blocking(self, move |s| s.put(&key, &value)).await
+    }
+
+    async fn remove_async(&self, key: Vec<u8>) -> Result<()> {
+        blocking(self, move |s| s.remove(&key)).await
+    }
+
+    async fn scan_async(&self, prefix: Vec<u8>) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        blocking(self, move |s| s.scan(&prefix)).await
+    }
+
+    async fn write_batch_async(&self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        blocking(self, move |s| s.write_batch(batch)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+
+    #[test]
+    fn test_locked_storage_shared_across_threads() {
+        let handle = LockedStorage::new(MemoryStorage::new()).into_handle();
+        let workers: Vec<_> = (0..4u8)
+            .map(|t| {
+                let handle = Arc::clone(&handle);
+                std::thread::spawn(move || {
+                    for i in 0..50u8 {
+                        handle.put(&[b'k', t, i], &[i]).unwrap();
+                    }
+                    handle
+                        .write_batch(vec![
+                            (vec![b'k', t, 0], None),
+                            (vec![b'd', t], Some(vec![t])),
+                        ])
+                        .unwrap();
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(handle.scan(b"k").unwrap().len(), 4 * 49);
+        assert_eq!(handle.get(&[b'd', 3]).unwrap(), Some(vec![3]));
+        let mut seen = Vec::new();
+        handle
+            .for_each_prefix(b"d", &mut |k, _| {
+                seen.push(k.to_vec());
+                seen.len() < 2
+            })
+            .unwrap();
+        assert_eq!(seen, vec![vec![b'd', 0], vec![b'd', 1]]);
+
+        // 句柄本身即是 Storage，可交给现有的 &mut Storage 接口
+        let mut view = Arc::clone(&handle);
+        Storage::set(&mut view, b"via_storage", b"1").unwrap();
+        assert_eq!(handle.get(b"via_storage").unwrap(), Some(b"1".to_vec()));
+    }
+
+    #[cfg(feature = "async-storage")]
+    #[test]
+    fn test_async_storage_roundtrip() {
+        let handle = LockedStorage::new(MemoryStorage::new()).into_handle();
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            handle
+                .put_async(b"a".to_vec(), b"1".to_vec())
+                .await
+                .unwrap();
+            handle
+                .write_batch_async(vec![(b"b".to_vec(), Some(b"2".to_vec()))])
+                .await
+                .unwrap();
+            assert_eq!(
+                handle.get_async(b"a".to_vec()).await.unwrap(),
+                Some(b"1".to_vec())
+            );
+            assert_eq!(handle.scan_async(Vec::new()).await.unwrap().len(), 2);
+            handle.remove_async(b"a".to_vec()).await.unwrap();
+            assert_eq!(handle.get_async(b"a".to_vec()).await.unwrap(), None);
+        });
+    }
+}
@@ -0,0 +1,658 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! 状态快照清单、增量快照与压缩归档
+//!
+//! 快照是一个 RocksDB checkpoint 目录，附带清单文件 [`MANIFEST_FILE`]：
+//! - 全量快照: 目录包含全部文件，清单中每个文件均为 `own`
+//! - 增量快照: 与基线快照同名同大小的 SST（SST 文件不可变）不再保存，清单中标为 `base`，
+//!   基线本身可以是增量快照，恢复时沿链逐级取回
+//! - 压缩归档: 快照目录打包为单个 `{name}.snapshot.zst` 文件
+//!
+//! 清单为文本格式，每个文件记录大小与 crc32；归档格式（整体 zstd 压缩）:
+//! `magic ‖ manifest_len(u32 le) ‖ manifest ‖ own 文件内容（按清单顺序）‖ crc32(u32 le，覆盖之前全部字节)`。
+//!
+//! 本模块只处理文件，与 RocksDB 无关；创建与打开见 `RocksDBStorage::maybe_create_snapshot` /
+//! `RocksDBStorage::restore_from_checkpoint`。
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 快照目录内的清单文件名
+pub const MANIFEST_FILE: &str = "SNAPSHOT.manifest";
+/// 压缩归档文件后缀
+pub const ARCHIVE_SUFFIX: &str = ".snapshot.zst";
+
+const MANIFEST_HEADER: &str = "supervm-snapshot 1";
+const ARCHIVE_MAGIC: &[u8; 8] = b"SVMSNAP1";
+const ZSTD_LEVEL: i32 = 3;
+
+/// 清单中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub size: u64,
+    pub crc32: u32,
+    /// true 表示文件由基线快照提供，本快照不保存
+    pub inherited: bool,
+}
+
+/// 快照清单
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotManifest {
+    pub block: u64,
+    /// 基线快照名（全量快照为 None）
+    pub base: Option<String>,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl SnapshotManifest {
+    pub fn is_incremental(&self) -> bool {
+        self.base.is_some()
+    }
+
+    /// 为完整的 checkpoint 目录生成全量清单
+    pub fn for_full_dir(dir: &Path, block: u64) -> Result<Self> {
+        let mut files = Vec::new();
+        for name in list_files(dir)? {
+            let (size, crc32) = checksum_file(&dir.join(&name))?;
+            files.push(ManifestEntry {
+                name,
+                size,
+                crc32,
+                inherited: false,
+            });
+        }
+        Ok(Self {
+            block,
+            base: None,
+            files,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        let mut out = format!("{MANIFEST_HEADER}\nblock {}\n", self.block);
+        out.push_str(&format!("base {}\n", self.base.as_deref().unwrap_or("-")));
+        for f in &self.files {
+            out.push_str(&format!(
+                "file {} {} {:08x} {}\n",
+                f.name,
+                f.size,
+                f.crc32,
+                if f.inherited { "base" } else { "own" }
+            ));
+        }
+        out
+    }
+
+    pub fn decode(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            bail!("unsupported snapshot manifest header");
+        }
+        let mut block = None;
+        let mut base = None;
+        let mut files = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields.as_slice() {
+                ["block", n] => block = Some(n.parse().context("invalid manifest block")?),
+                ["base", "-"] => base = None,
+                ["base", name] => base = Some(checked_name(name)?.to_string()),
+                ["file", name, size, crc, origin] => files.push(ManifestEntry {
+                    name: checked_name(name)?.to_string(),
+                    size: size.parse().context("invalid manifest file size")?,
+                    crc32: u32::from_str_radix(crc, 16).context("invalid manifest checksum")?,
+                    inherited: match *origin {
+                        "own" => false,
+                        "base" => true,
+                        other => bail!("invalid manifest file origin {other}"),
+                    },
+                }),
+                [] | [""] => {}
+                _ => bail!("invalid manifest line: {line}"),
+            }
+        }
+        let manifest = Self {
+            block: block.ok_or_else(|| anyhow!("manifest missing block"))?,
+            base,
+            files,
+        };
+        if manifest.base.is_none() && manifest.files.iter().any(|f| f.inherited) {
+            bail!("full snapshot manifest references base files");
+        }
+        Ok(manifest)
+    }
+
+    fn own_files(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.files.iter().filter(|f| !f.inherited)
+    }
+}
+
+/// 快照目录或归档
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub compressed: bool,
+    /// 早期版本创建的 checkpoint 没有清单（视为全量）
+    pub manifest: Option<SnapshotManifest>,
+}
+
+impl SnapshotInfo {
+    /// 读取单个快照（目录或归档文件）
+    pub fn open(path: &Path) -> Result<Self> {
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow!("invalid snapshot path {}", path.display()))?;
+        if let Some(name) = file_name.strip_suffix(ARCHIVE_SUFFIX) {
+            let mut reader = ArchiveReader::open(path)?;
+            return Ok(Self {
+                name: name.to_string(),
+                path: path.to_path_buf(),
+                compressed: true,
+                manifest: Some(reader.read_manifest()?),
+            });
+        }
+        let manifest_path = path.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            Some(SnapshotManifest::decode(&fs::read_to_string(
+                &manifest_path,
+            )?)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            name: file_name.to_string(),
+            path: path.to_path_buf(),
+            compressed: false,
+            manifest,
+        })
+    }
+
+    pub fn block(&self) -> Option<u64> {
+        self.manifest.as_ref().map(|m| m.block)
+    }
+
+    pub fn base(&self) -> Option<&str> {
+        self.manifest.as_ref().and_then(|m| m.base.as_deref())
+    }
+}
+
+/// 列出目录下的全部快照（有清单的按区块号排序，无清单的 checkpoint 按名称排在前面）
+pub fn list_snapshots(base_path: &Path) -> Result<Vec<SnapshotInfo>> {
+    let mut snapshots = Vec::new();
+    if !base_path.exists() {
+        return Ok(snapshots);
+    }
+    for entry in fs::read_dir(base_path)
+        .with_context(|| format!("Failed to read snapshot directory: {}", base_path.display()))?
+    {
+        let path = entry?.path();
+        let is_archive = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(ARCHIVE_SUFFIX));
+        // 有效快照: 归档文件，或包含 CURRENT 的 RocksDB 目录
+        if (path.is_file() && is_archive) || (path.is_dir() && path.join("CURRENT").exists()) {
+            snapshots.push(SnapshotInfo::open(&path)?);
+        }
+    }
+    snapshots.sort_by(|a, b| (a.block(), &a.name).cmp(&(b.block(), &b.name)));
+    Ok(snapshots)
+}
+
+/// 把完整的 checkpoint 目录转为相对 `base` 的增量快照：删除基线中已有的 SST 并写入清单
+pub fn make_incremental(dir: &Path, block: u64, base: &SnapshotInfo) -> Result<SnapshotManifest> {
+    let base_manifest = base
+        .manifest
+        .as_ref()
+        .ok_or_else(|| anyhow!("base snapshot {} has no manifest", base.name))?;
+    let base_files: HashMap<&str, &ManifestEntry> = base_manifest
+        .files
+        .iter()
+        .map(|f| (f.name.as_str(), f))
+        .collect();
+
+    let mut files = Vec::new();
+    for name in list_files(dir)? {
+        let path = dir.join(&name);
+        let size = fs::metadata(&path)?.len();
+        match base_files.get(name.as_str()) {
+            Some(f) if name.ends_with(".sst") && f.size == size => {
+                files.push(ManifestEntry {
+                    name,
+                    size,
+                    crc32: f.crc32,
+                    inherited: true,
+                });
+                fs::remove_file(&path)?;
+            }
+            _ => {
+                let (size, crc32) = checksum_file(&path)?;
+                files.push(ManifestEntry {
+                    name,
+                    size,
+                    crc32,
+                    inherited: false,
+                });
+            }
+        }
+    }
+    let manifest = SnapshotManifest {
+        block,
+        base: Some(base.name.clone()),
+        files,
+    };
+    write_manifest(dir, &manifest)?;
+    Ok(manifest)
+}
+
+/// 写入清单文件（先写临时文件再改名）
+pub fn write_manifest(dir: &Path, manifest: &SnapshotManifest) -> Result<()> {
+    let tmp = dir.join(format!("{MANIFEST_FILE}.tmp"));
+    fs::write(&tmp, manifest.encode())?;
+    fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+    Ok(())
+}
+
+/// 把快照目录压缩为同级的单个归档文件，成功后删除原目录；返回归档路径
+pub fn compress_snapshot(dir: &Path) -> Result<PathBuf> {
+    let info = SnapshotInfo::open(dir)?;
+    let manifest = info
+        .manifest
+        .ok_or_else(|| anyhow!("snapshot {} has no manifest", info.name))?;
+    let archive = dir.with_file_name(format!("{}{ARCHIVE_SUFFIX}", info.name));
+    let tmp = archive.with_extension("zst.tmp");
+
+    let file = File::create(&tmp)?;
+    let mut out = Crc32Writer::new(zstd::stream::write::Encoder::new(
+        BufWriter::new(file),
+        ZSTD_LEVEL,
+    )?);
+    let encoded = manifest.encode();
+    out.write_all(ARCHIVE_MAGIC)?;
+    out.write_all(&(encoded.len() as u32).to_le_bytes())?;
+    out.write_all(encoded.as_bytes())?;
+    for entry in manifest.own_files() {
+        let copied = io::copy(&mut File::open(dir.join(&entry.name))?, &mut out)?;
+        if copied != entry.size {
+            bail!("snapshot file {} changed while compressing", entry.name);
+        }
+    }
+    let (mut encoder, crc) = out.finish();
+    encoder.write_all(&crc.to_le_bytes())?;
+    let writer = encoder.finish()?;
+    writer
+        .into_inner()
+        .map_err(|e| anyhow!("Failed to flush snapshot archive: {}", e.error()))?
+        .sync_all()?;
+
+    fs::rename(&tmp, &archive)?;
+    fs::remove_dir_all(dir)?;
+    Ok(archive)
+}
+
+/// 把快照（目录或归档，全量或增量）重组为 `target` 下可直接打开的完整 checkpoint
+///
+/// 增量快照沿基线链逐级取回继承的文件，基线按名称在同一目录下查找（目录或归档均可）。
+/// 每个文件都按清单校验大小与 crc32；`target` 须不存在或为空目录。
+pub fn materialize(snapshot: &Path, target: &Path) -> Result<SnapshotManifest> {
+    if target.exists() && fs::read_dir(target)?.next().is_some() {
+        bail!("restore target {} is not empty", target.display());
+    }
+    fs::create_dir_all(target)?;
+    let parent = snapshot
+        .parent()
+        .ok_or_else(|| anyhow!("invalid snapshot path {}", snapshot.display()))?;
+
+    let top = SnapshotInfo::open(snapshot)?;
+    let top_manifest = top
+        .manifest
+        .clone()
+        .ok_or_else(|| anyhow!("snapshot {} has no manifest", top.name))?;
+    let mut wanted: Vec<ManifestEntry> = top_manifest.files.clone();
+    let mut current = top;
+    loop {
+        let manifest = current
+            .manifest
+            .as_ref()
+            .expect("checked when resolving chain");
+        let origin: HashMap<&str, &ManifestEntry> = manifest
+            .files
+            .iter()
+            .map(|f| (f.name.as_str(), f))
+            .collect();
+        let mut own = Vec::new();
+        let mut inherited = Vec::new();
+        for entry in wanted {
+            match origin.get(entry.name.as_str()) {
+                Some(f) if f.size == entry.size && f.crc32 == entry.crc32 => {
+                    if f.inherited {
+                        inherited.push(entry);
+                    } else {
+                        own.push(entry);
+                    }
+                }
+                _ => bail!("snapshot {} does not contain {}", current.name, entry.name),
+            }
+        }
+        extract(&current, &own, target)?;
+        if inherited.is_empty() {
+            break;
+        }
+        let base = manifest
+            .base
+            .clone()
+            .ok_or_else(|| anyhow!("snapshot {} references a missing base", current.name))?;
+        current = resolve(parent, &base)?;
+        wanted = inherited;
+    }
+    write_manifest(
+        target,
+        &SnapshotManifest {
+            block: top_manifest.block,
+            base: None,
+            files: top_manifest
+                .files
+                .iter()
+                .map(|f| ManifestEntry {
+                    inherited: false,
+                    ..f.clone()
+                })
+                .collect(),
+        },
+    )?;
+    Ok(top_manifest)
+}
+
+/// 按名称查找基线快照（目录优先，其次归档）
+fn resolve(parent: &Path, name: &str) -> Result<SnapshotInfo> {
+    let dir = parent.join(name);
+    let info = if dir.is_dir() {
+        SnapshotInfo::open(&dir)?
+    } else {
+        let archive = parent.join(format!("{name}{ARCHIVE_SUFFIX}"));
+        if !archive.is_file() {
+            bail!("base snapshot {name} not found in {}", parent.display());
+        }
+        SnapshotInfo::open(&archive)?
+    };
+    if info.manifest.is_none() {
+        bail!("base snapshot {name} has no manifest");
+    }
+    Ok(info)
+}
+
+/// 从快照中取出 `entries`（均为该快照自有文件）写入 `target` 并校验
+fn extract(snapshot: &SnapshotInfo, entries: &[ManifestEntry], target: &Path) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    if !snapshot.compressed {
+        for entry in entries {
+            let dest = target.join(&entry.name);
+            fs::copy(snapshot.path.join(&entry.name), &dest)?;
+            verify_file(&dest, entry)?;
+        }
+        return Ok(());
+    }
+
+    let wanted: HashMap<&str, &ManifestEntry> =
+        entries.iter().map(|e| (e.name.as_str(), e)).collect();
+    let mut reader = ArchiveReader::open(&snapshot.path)?;
+    let manifest = reader.read_manifest()?;
+    for entry in manifest.own_files() {
+        let mut body = (&mut reader.inner).take(entry.size);
+        if wanted.contains_key(entry.name.as_str()) {
+            let dest = target.join(&entry.name);
+            let mut out = BufWriter::new(File::create(&dest)?);
+            io::copy(&mut body, &mut out)?;
+            out.flush()?;
+            verify_file(&dest, entry)?;
+        } else {
+            io::copy(&mut body, &mut io::sink())?;
+        }
+    }
+    reader.verify_trailer()
+}
+
+fn verify_file(path: &Path, entry: &ManifestEntry) -> Result<()> {
+    let (size, crc32) = checksum_file(path)?;
+    if size != entry.size || crc32 != entry.crc32 {
+        bail!("snapshot file {} failed checksum verification", entry.name);
+    }
+    Ok(())
+}
+
+/// 清单中的文件名必须是单层普通文件名（防止恢复时写出目标目录）
+fn checked_name(name: &str) -> Result<&str> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        bail!("invalid snapshot file name {name:?}");
+    }
+    Ok(name)
+}
+
+/// 目录下的普通文件名（不含清单本身），按名称排序
+fn list_files(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            bail!("unexpected non-file entry {:?} in snapshot", entry.path());
+        }
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|n| anyhow!("non UTF-8 snapshot file name {n:?}"))?;
+        if name != MANIFEST_FILE {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn checksum_file(path: &Path) -> Result<(u64, u32)> {
+    let mut reader = Crc32Reader::new(BufReader::new(File::open(path)?));
+    let size = io::copy(&mut reader, &mut io::sink())?;
+    Ok((size, reader.hasher.finalize()))
+}
+
+/// 顺序读取归档：解压流外包一层 crc32 计算
+struct ArchiveReader {
+    inner: Crc32Reader<zstd::stream::read::Decoder<'static, BufReader<File>>>,
+}
+
+impl ArchiveReader {
+    fn open(path: &Path) -> Result<Self> {
+        let decoder = zstd::stream::read::Decoder::new(File::open(path)?)
+            .with_context(|| format!("Failed to open snapshot archive {}", path.display()))?;
+        Ok(Self {
+            inner: Crc32Reader::new(decoder),
+        })
+    }
+
+    fn read_manifest(&mut self) -> Result<SnapshotManifest> {
+        let mut magic = [0u8; 8];
+        self.inner.read_exact(&mut magic)?;
+        if &magic != ARCHIVE_MAGIC {
+            bail!("not a snapshot archive");
+        }
+        let mut len = [0u8; 4];
+        self.inner.read_exact(&mut len)?;
+        let mut manifest = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.inner.read_exact(&mut manifest)?;
+        SnapshotManifest::decode(std::str::from_utf8(&manifest).context("manifest is not UTF-8")?)
+    }
+
+    fn verify_trailer(mut self) -> Result<()> {
+        let expected = self.inner.hasher.clone().finalize();
+        let mut trailer = [0u8; 4];
+        self.inner.inner.read_exact(&mut trailer)?;
+        if u32::from_le_bytes(trailer) != expected {
+            bail!("snapshot archive checksum mismatch");
+        }
+        Ok(())
+    }
+}
+
+struct Crc32Reader<R> {
+    inner: R,
+    hasher: crc32fast::Hasher,
+}
+
+impl<R: Read> Crc32Reader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Crc32Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+struct Crc32Writer<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Crc32Writer<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    fn finish(self) -> (W, u32) {
+        (self.inner, self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for Crc32Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_files(dir: &Path, files: &[(&str, &[u8])]) {
+        fs::create_dir_all(dir).unwrap();
+        for (name, body) in files {
+            fs::write(dir.join(name), body).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_incremental_chain_and_archive_restore() {
+        let root = tempfile::tempdir().unwrap();
+        let snaps = root.path().join("snapshots");
+
+        let full = snaps.join("snapshot_block_10");
+        write_files(
+            &full,
+            &[
+                ("000001.sst", b"aaaa"),
+                ("CURRENT", b"MANIFEST-1\n"),
+                ("MANIFEST-1", b"m1"),
+            ],
+        );
+        write_manifest(&full, &SnapshotManifest::for_full_dir(&full, 10).unwrap()).unwrap();
+
+        // 增量 1：保留 000001.sst，新增 000002.sst
+        let inc1 = snaps.join("snapshot_block_20");
+        write_files(
+            &inc1,
+            &[
+                ("000001.sst", b"aaaa"),
+                ("000002.sst", b"bbbb"),
+                ("CURRENT", b"MANIFEST-2\n"),
+                ("MANIFEST-2", b"m2"),
+            ],
+        );
+        let m1 = make_incremental(&inc1, 20, &SnapshotInfo::open(&full).unwrap()).unwrap();
+        assert!(!inc1.join("000001.sst").exists());
+        assert!(m1
+            .files
+            .iter()
+            .any(|f| f.name == "000001.sst" && f.inherited));
+
+        // 增量 2 以增量 1 为基线，000001.sst 需沿链回到全量快照
+        let inc2 = snaps.join("snapshot_block_30");
+        write_files(
+            &inc2,
+            &[
+                ("000001.sst", b"aaaa"),
+                ("000002.sst", b"bbbb"),
+                ("000003.sst", b"cc"),
+                ("CURRENT", b"MANIFEST-3\n"),
+            ],
+        );
+        make_incremental(&inc2, 30, &SnapshotInfo::open(&inc1).unwrap()).unwrap();
+
+        // 压缩较旧的两个快照后仍可重组
+        let full_archive = compress_snapshot(&full).unwrap();
+        compress_snapshot(&inc1).unwrap();
+        assert!(!full.exists());
+        let listed = list_snapshots(&snaps).unwrap();
+        assert_eq!(
+            listed.iter().map(|s| s.block()).collect::<Vec<_>>(),
+            vec![Some(10), Some(20), Some(30)]
+        );
+        assert!(listed[0].compressed && listed[1].compressed && !listed[2].compressed);
+        assert_eq!(listed[2].base(), Some("snapshot_block_20"));
+
+        let target = root.path().join("restored");
+        let manifest = materialize(&inc2, &target).unwrap();
+        assert_eq!(manifest.block, 30);
+        assert_eq!(fs::read(target.join("000001.sst")).unwrap(), b"aaaa");
+        assert_eq!(fs::read(target.join("000002.sst")).unwrap(), b"bbbb");
+        assert_eq!(fs::read(target.join("000003.sst")).unwrap(), b"cc");
+        assert_eq!(fs::read(target.join("CURRENT")).unwrap(), b"MANIFEST-3\n");
+        assert!(!target.join("MANIFEST-2").exists());
+        let restored = SnapshotInfo::open(&target).unwrap();
+        assert!(!restored.manifest.unwrap().is_incremental());
+
+        // 归档损坏时校验失败
+        let mut bytes = zstd::stream::decode_all(File::open(&full_archive).unwrap()).unwrap();
+        let last = bytes.len() - 5;
+        bytes[last] ^= 0xff;
+        fs::write(
+            &full_archive,
+            zstd::stream::encode_all(&bytes[..], ZSTD_LEVEL).unwrap(),
+        )
+        .unwrap();
+        let err = materialize(&inc2, &root.path().join("restored2")).unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{err}");
+    }
+
+    #[test]
+    fn test_manifest_rejects_path_traversal() {
+        let text = format!("{MANIFEST_HEADER}\nblock 1\nbase -\nfile ../evil 1 00000000 own\n");
+        assert!(SnapshotManifest::decode(&text).is_err());
+    }
+}
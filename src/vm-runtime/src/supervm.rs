@@ -13,7 +13,10 @@ use crate::parallel_mvcc::{BatchTxnResult, MvccScheduler, TxId};
 #[cfg(feature = "groth16-verifier")]
 use crate::zk_verifier::ZkVerifier;
 use crate::adaptive_router::AdaptiveRouter; // 自适应路由器
+use crate::privacy::key_image_registry::{verify_transaction_inputs, KeyImageRegistry};
+use crate::privacy::PrivacyTransaction;
 use crate::{Address, ObjectId, OwnershipManager};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -67,6 +70,8 @@ pub struct SuperVM<'a> {
     privacy_path_txns: AtomicU64,
     /// 自适应路由器（可选）
     adaptive: Option<AdaptiveRouter>,
+    /// 已花费 Key Image 登记表（隐私交易防双花）
+    key_images: Option<&'a KeyImageRegistry>,
     /// ZK 验证统计：次数、累计耗时(ns)、最近一次耗时(ns)（feature gated）
     #[cfg(feature = "groth16-verifier")]
     zk_verify_count: AtomicU64,
@@ -110,6 +115,7 @@ impl<'a> SuperVM<'a> {
             consensus_path_txns: AtomicU64::new(0),
             privacy_path_txns: AtomicU64::new(0),
             adaptive: None,
+            key_images: None,
             #[cfg(feature = "groth16-verifier")]
             zk_verify_count: AtomicU64::new(0),
             #[cfg(feature = "groth16-verifier")]
@@ -138,6 +144,11 @@ impl<'a> SuperVM<'a> {
         self
     }
 
+    /// 获取已配置的调度器
+    pub fn scheduler(&self) -> Option<&'a MvccScheduler> {
+        self.scheduler
+    }
+
     /// 注入可选的 ZK 验证器（最小接入）
     #[cfg(feature = "groth16-verifier")]
     pub fn with_verifier(mut self, verifier: &'a dyn crate::zk_verifier::ZkVerifier) -> Self {
@@ -145,6 +156,12 @@ impl<'a> SuperVM<'a> {
         self
     }
 
+    /// 注入 Key Image 登记表（须与调度器共用同一 MvccStore）
+    pub fn with_key_image_registry(mut self, registry: &'a KeyImageRegistry) -> Self {
+        self.key_images = Some(registry);
+        self
+    }
+
     /// 注入自适应路由器
     pub fn with_adaptive_router(mut self, router: AdaptiveRouter) -> Self {
         self.adaptive = Some(router);
@@ -586,6 +603,119 @@ impl<'a> SuperVM<'a> {
         }
     }
 
+    /// 执行隐私交易：Key Image 登记与业务写入在同一 MVCC 事务内提交
+    ///
+    /// 环签名验证失败、Key Image 无效 / 交易内重复 / 与环签名不一致的输入在登记前直接拒绝；
+    /// 已花费的 Key Image 使事务失败。并发花费同一 Key Image 的交易写写冲突，重试时读到已提交的登记而失败。
+    /// 只有提交成功的交易的 Key Image 进入登记表的过滤器
+    pub fn execute_privacy_transaction<F>(
+        &self,
+        tx_id: TxId,
+        ptx: &PrivacyTransaction,
+        f: F,
+    ) -> ExecutionReceipt
+    where
+        F: Fn(&mut Txn) -> anyhow::Result<i32>,
+    {
+        let start = std::time::Instant::now();
+        self.privacy_path_txns.fetch_add(1, Ordering::Relaxed);
+        let (scheduler, registry) = self.privacy_executors();
+        let rejected = |reason: String| ExecutionReceipt {
+            path: ExecutionPath::PrivatePath,
+            accepted: false,
+            reason: Some(reason),
+            success: false,
+            fallback_to_consensus: false,
+            return_value: None,
+            latency_ms: start.elapsed().as_millis() as u64,
+        };
+
+        let key_images = match verify_transaction_inputs(ptx) {
+            Ok(images) => images,
+            Err(e) => return rejected(e.to_string()),
+        };
+        if !self.verify_zk_proof(None, None) {
+            return rejected("zk proof invalid".into());
+        }
+
+        let r = scheduler.execute_txn(tx_id, |txn| {
+            registry.spend(txn, tx_id, &key_images)?;
+            f(txn)
+        });
+        if r.success {
+            registry.confirm(&key_images);
+        }
+        ExecutionReceipt {
+            path: ExecutionPath::PrivatePath,
+            accepted: true,
+            reason: r.error.clone(),
+            success: r.success,
+            fallback_to_consensus: false,
+            return_value: r.return_value,
+            latency_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// 并行执行一批隐私交易；同批或跨批花费同一 Key Image 的交易至多一笔成功
+    ///
+    /// 预校验（含环签名验证）失败的交易计入失败结果，不进入调度器
+    pub fn execute_privacy_batch<F>(
+        &self,
+        txs: Vec<(TxId, PrivacyTransaction, F)>,
+    ) -> BatchTxnResult
+    where
+        F: Fn(&mut Txn) -> anyhow::Result<i32> + Send + Sync,
+    {
+        let (scheduler, registry) = self.privacy_executors();
+        self.privacy_path_txns.fetch_add(txs.len() as u64, Ordering::Relaxed);
+
+        let mut rejected = Vec::new();
+        let mut items = Vec::with_capacity(txs.len());
+        let mut spent = HashMap::with_capacity(txs.len());
+        for (id, ptx, f) in txs {
+            match verify_transaction_inputs(&ptx) {
+                Ok(key_images) => {
+                    spent.insert(id, key_images.clone());
+                    items.push((id, move |txn: &mut Txn| {
+                        registry.spend(txn, id, &key_images)?;
+                        f(txn)
+                    }))
+                }
+                Err(e) => rejected.push(crate::parallel_mvcc::TxnResult {
+                    tx_id: id,
+                    return_value: None,
+                    success: false,
+                    error: Some(e.to_string()),
+                    commit_ts: None,
+                }),
+            }
+        }
+
+        let mut result = if items.is_empty() {
+            BatchTxnResult { successful: 0, failed: 0, conflicts: 0, results: vec![] }
+        } else {
+            scheduler.execute_batch(items)
+        };
+        for r in result.results.iter().filter(|r| r.success) {
+            if let Some(key_images) = spent.get(&r.tx_id) {
+                registry.confirm(key_images);
+            }
+        }
+        result.failed += rejected.len() as u64;
+        result.results.extend(rejected);
+        result
+    }
+
+    fn privacy_executors(&self) -> (&'a MvccScheduler, &'a KeyImageRegistry) {
+        let scheduler = self
+            .scheduler
+            .expect("SuperVM: scheduler not configured, call with_scheduler()");
+        let registry = self
+            .key_images
+            .expect("SuperVM: key image registry not configured, call with_key_image_registry()");
+        (scheduler, registry)
+    }
+
     /// 新接口：根据路径物理分离执行
     /// - FastPath 使用 FastPathExecutor（无事务闭包）
     /// - Consensus/Private 使用 MvccScheduler（带事务闭包）
@@ -1454,3 +1584,148 @@ mod zk_batch_buffer_tests {
         assert!(prom.contains("vm_privacy_zk_batch_verify_batches_total 1"));
     }
 }
+
+#[cfg(test)]
+mod privacy_key_image_tests {
+    use super::*;
+    use crate::parallel_mvcc::MvccScheduler;
+    use crate::privacy::ring_signature::{
+        generate_keypair, key_image, public_key_from_secret, transaction_signing_message, RingSigner,
+    };
+    use crate::privacy::{Commitment, KeyImage, PrivacyInput, PublicKey, RingSignature, SecretKey};
+    use curve25519_dalek::scalar::Scalar;
+
+    /// 每个字节对应一把固定的花费私钥, 相同字节即花费同一 Key Image
+    fn spend_key(b: u8) -> (SecretKey, PublicKey) {
+        let secret = SecretKey(Scalar::from(b as u64 + 1).to_bytes());
+        let public = public_key_from_secret(&secret).unwrap();
+        (secret, public)
+    }
+
+    fn image_of(b: u8) -> KeyImage {
+        let (secret, public) = spend_key(b);
+        key_image(&secret, &public).unwrap()
+    }
+
+    /// 各输入以 3 人环对交易签名消息签名
+    fn ptx(images: &[u8]) -> PrivacyTransaction {
+        let inputs = images
+            .iter()
+            .map(|&b| PrivacyInput {
+                key_image: image_of(b),
+                ring_signature: RingSignature { ring: vec![], signature: vec![], key_image: image_of(b) },
+                commitment: Commitment([0; 32]),
+            })
+            .collect();
+        let mut tx = PrivacyTransaction { version: 1, inputs, outputs: vec![], fee: 0, extra: vec![] };
+        let message = transaction_signing_message(&tx);
+        for (input, &b) in tx.inputs.iter_mut().zip(images) {
+            let (secret, public) = spend_key(b);
+            let ring = vec![generate_keypair().1, public, generate_keypair().1];
+            input.ring_signature = RingSigner::new().sign(&message, &secret, &public, &ring, 1).unwrap();
+        }
+        tx
+    }
+
+    #[test]
+    fn rejects_spent_and_malformed_key_images() {
+        let ownership = OwnershipManager::new();
+        let sched = MvccScheduler::new();
+        let registry = KeyImageRegistry::with_capacity(Arc::clone(sched.store()), 1024);
+        let vm = SuperVM::new(&ownership).with_scheduler(&sched).with_key_image_registry(&registry);
+        let op = |txn: &mut Txn| { txn.write(b"balance".to_vec(), b"1".to_vec()); Ok(1) };
+
+        let r = vm.execute_privacy_transaction(1, &ptx(&[1, 2]), op);
+        assert!(r.accepted && r.success, "{:?}", r.reason);
+        assert!(registry.is_spent(&image_of(1)).unwrap());
+
+        let r = vm.execute_privacy_transaction(2, &ptx(&[3, 2]), op);
+        assert!(!r.success && r.reason.unwrap().contains("already spent"));
+        // 失败交易的登记随事务放弃
+        assert!(!registry.is_spent(&image_of(3)).unwrap());
+
+        assert!(!vm.execute_privacy_transaction(3, &ptx(&[4, 4]), op).accepted);
+        let mut mismatched = ptx(&[5]);
+        mismatched.inputs[0].ring_signature.key_image = image_of(6);
+        assert!(!vm.execute_privacy_transaction(4, &mismatched, op).accepted);
+        assert!(!vm.execute_privacy_transaction(5, &ptx(&[]), op).accepted);
+
+        // 无法解压为曲线点的 Key Image
+        let mut invalid = ptx(&[7]);
+        invalid.inputs[0].key_image = KeyImage([1; 32]);
+        invalid.inputs[0].ring_signature.key_image = KeyImage([1; 32]);
+        let r = vm.execute_privacy_transaction(6, &invalid, op);
+        assert!(!r.accepted && r.reason.unwrap().contains("invalid key image"));
+    }
+
+    #[test]
+    fn rejects_unsigned_and_forged_inputs() {
+        let ownership = OwnershipManager::new();
+        let sched = MvccScheduler::new();
+        let registry = KeyImageRegistry::with_capacity(Arc::clone(sched.store()), 1024);
+        let vm = SuperVM::new(&ownership).with_scheduler(&sched).with_key_image_registry(&registry);
+        let op = |txn: &mut Txn| { txn.write(b"balance".to_vec(), b"1".to_vec()); Ok(1) };
+
+        // 未签名: 只声明了别人的 Key Image
+        let mut unsigned = ptx(&[1]);
+        unsigned.inputs[0].ring_signature.ring = vec![];
+        unsigned.inputs[0].ring_signature.signature = vec![];
+        let r = vm.execute_privacy_transaction(1, &unsigned, op);
+        assert!(!r.accepted && r.reason.unwrap().contains("invalid ring signature"));
+
+        // 签名后篡改交易内容 (签名消息随之改变)
+        let mut tampered = ptx(&[1]);
+        tampered.fee = 1_000;
+        assert!(!vm.execute_privacy_transaction(2, &tampered, op).accepted);
+
+        // 环内不含签名者公钥的伪造签名
+        let mut forged = ptx(&[1]);
+        let message = transaction_signing_message(&forged);
+        let (secret, public) = generate_keypair();
+        let ring = vec![public, generate_keypair().1, generate_keypair().1];
+        let mut signature = RingSigner::new().sign(&message, &secret, &public, &ring, 0).unwrap();
+        signature.ring[0] = spend_key(1).1;
+        signature.key_image = image_of(1);
+        forged.inputs[0].ring_signature = signature;
+        assert!(!vm.execute_privacy_transaction(3, &forged, op).accepted);
+
+        let batch = vm.execute_privacy_batch(vec![(4, unsigned, op), (5, forged, op)]);
+        assert_eq!((batch.successful, batch.failed), (0, 2));
+
+        // 被拒交易既不登记 Key Image, 也不写入业务状态
+        assert!(!registry.is_spent(&image_of(1)).unwrap());
+        assert_eq!(sched.store().begin_read_only().read(b"balance"), None);
+        let r = vm.execute_privacy_transaction(6, &ptx(&[1]), op);
+        assert!(r.accepted && r.success, "{:?}", r.reason);
+    }
+
+    #[test]
+    fn concurrent_batches_spend_each_key_image_once() {
+        let ownership = OwnershipManager::new();
+        let sched = MvccScheduler::new();
+        let registry = KeyImageRegistry::with_capacity(Arc::clone(sched.store()), 1024);
+        let vm = SuperVM::new(&ownership).with_scheduler(&sched).with_key_image_registry(&registry);
+
+        // 两个线程各提交一批, 每个 Key Image 在每批中出现两次
+        let results: Vec<BatchTxnResult> = std::thread::scope(|s| {
+            let workers: Vec<_> = (0..2u64)
+                .map(|w| {
+                    let vm = &vm;
+                    s.spawn(move || {
+                        let txs = (0..16u64)
+                            .map(|i| (w * 100 + i, ptx(&[(i % 8) as u8 + 10]), |_: &mut Txn| Ok(0)))
+                            .collect();
+                        vm.execute_privacy_batch(txs)
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let successful: u64 = results.iter().map(|r| r.successful).sum();
+        assert_eq!(successful, 8);
+        for b in 10..18u8 {
+            assert!(registry.is_spent(&image_of(b)).unwrap());
+        }
+    }
+}
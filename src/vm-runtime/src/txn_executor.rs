@@ -0,0 +1,451 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! MVCC 事务化 WASM 执行
+//!
+//! [`Runtime`](crate::Runtime) 通过 `Arc<Mutex<S>>` 直接写穿底层存储，trap 时已写入的键不会撤销。
+//! 本模块让 `HostState` 的读写经由 [`Txn`]:
+//! - 读: 快照读（`start_ts` 时刻可见的版本，优先返回本事务未提交的写），并记入 Txn 读集合
+//! - 写: 执行期间缓冲在 [`TxnStorage`] 中，导出函数返回 0 后才写入 Txn
+//! - 返回非 0（回滚，与 `WasmExecutor` / `contract_api::call` 约定一致）/ trap / gas 耗尽:
+//!   `success = false`，丢弃缓冲，Txn 保持原样，由调用方 abort（调度器闭包返回 `Err`）
+//!
+//! [`TxnExecutor`] 是 `Send + Sync` 的，[`TxnExecutor::txn_fn`] 产出的闭包可直接放入
+//! `MvccScheduler` / `OptimizedMvccScheduler` 的 `execute_batch`，冲突重试时整段 WASM 重新执行。
+
+use crate::contract_registry::{CallConfig, ModuleCache};
+use crate::gas::{self, GasSchedule, DEFAULT_GAS_LIMIT};
+use crate::host::HostState;
+use crate::mvcc::{KeyRange, MvccStore, Txn};
+use crate::parallel::ExecutionResult;
+use crate::storage::Storage;
+use anyhow::{anyhow, bail, Result};
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use wasmtime::{Engine, Module, Store};
+
+/// 以 [`Txn`] 快照为底的 [`Storage`] 覆盖层
+///
+/// 初始写集合取自 Txn 已缓冲的写入（保证“读自己的写”），执行结束后由
+/// [`TxnStorage::apply_to`] 把读集合与新写入回灌到 Txn。
+pub struct TxnStorage {
+    store: Arc<MvccStore>,
+    start_ts: u64,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    // Storage::get 只拿到 &self，读集合需内部可变
+    reads: RefCell<HashSet<Vec<u8>>>,
+    scanned_prefixes: RefCell<HashSet<Vec<u8>>>,
+}
+
+impl TxnStorage {
+    pub fn new(txn: &Txn) -> Self {
+        Self {
+            store: txn.store().clone(),
+            start_ts: txn.start_ts,
+            writes: txn
+                .writes()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            reads: RefCell::new(HashSet::new()),
+            scanned_prefixes: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// 执行期间读取过的键
+    pub fn reads(&self) -> HashSet<Vec<u8>> {
+        self.reads.borrow().clone()
+    }
+
+    /// 执行期间缓冲的写入（`None` 表示删除）
+    pub fn writes(&self) -> &BTreeMap<Vec<u8>, Option<Vec<u8>>> {
+        &self.writes
+    }
+
+    /// 将读集合、扫描区间与写入回灌到 Txn（只读事务存在写入时报错）
+    pub fn apply_to(self, txn: &mut Txn) -> Result<()> {
+        if txn.is_read_only() && !self.writes.is_empty() {
+            bail!("cannot write in read-only transaction");
+        }
+        for key in self.reads.into_inner() {
+            txn.read(&key);
+        }
+        for prefix in self.scanned_prefixes.into_inner() {
+            txn.track_range_read(KeyRange::prefix(&prefix));
+        }
+        for (key, value) in self.writes {
+            match value {
+                Some(v) => txn.write(key, v),
+                None => txn.delete(key),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Storage for TxnStorage {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.reads.borrow_mut().insert(key.to_vec());
+        if let Some(v) = self.writes.get(key) {
+            return Ok(v.clone());
+        }
+        self.store.read_at(key, self.start_ts).map_err(|e| anyhow!(e))
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.writes.insert(key.to_vec(), None);
+        Ok(())
+    }
+
+    /// 快照前缀扫描叠加本事务写入；返回的键计入读集合，前缀计入范围读集合（可串行化事务据此防幻读）
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> = self
+            .store
+            .scan_prefix_at(prefix, self.start_ts)
+            .map_err(|e| anyhow!(e))?
+            .into_iter()
+            .collect();
+        for (key, value) in self.writes.range(prefix.to_vec()..) {
+            if !key.starts_with(prefix) {
+                break;
+            }
+            match value {
+                Some(v) => merged.insert(key.clone(), v.clone()),
+                None => merged.remove(key),
+            };
+        }
+        self.reads.borrow_mut().extend(merged.keys().cloned());
+        self.scanned_prefixes.borrow_mut().insert(prefix.to_vec());
+        Ok(merged.into_iter().collect())
+    }
+}
+
+/// 可跨线程共享的事务化 WASM 执行器
+///
+/// 通过 [`Runtime::txn_executor`](crate::Runtime::txn_executor) 获取时与运行时共享
+/// wasmtime 引擎、模块缓存、计费表与调用配置。
+#[derive(Clone)]
+pub struct TxnExecutor {
+    engine: Engine,
+    modules: Arc<ModuleCache>,
+    gas_schedule: GasSchedule,
+    gas_limit: u64,
+    call_config: CallConfig,
+}
+
+impl Default for TxnExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TxnExecutor {
+    pub fn new() -> Self {
+        Self {
+            engine: gas::metered_engine().expect("fuel-metered engine config is valid"),
+            modules: Arc::new(ModuleCache::new()),
+            gas_schedule: GasSchedule::default(),
+            gas_limit: DEFAULT_GAS_LIMIT,
+            call_config: CallConfig::default(),
+        }
+    }
+
+    pub(crate) fn from_parts(
+        engine: Engine,
+        modules: Arc<ModuleCache>,
+        gas_schedule: GasSchedule,
+        gas_limit: u64,
+        call_config: CallConfig,
+    ) -> Self {
+        Self {
+            engine,
+            modules,
+            gas_schedule,
+            gas_limit,
+            call_config,
+        }
+    }
+
+    /// 设置 host 调用计费表
+    pub fn with_gas_schedule(mut self, schedule: GasSchedule) -> Self {
+        self.gas_schedule = schedule;
+        self
+    }
+
+    /// 设置单次执行 gas 上限
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    /// 设置合约间调用配置
+    pub fn with_call_config(mut self, config: CallConfig) -> Self {
+        self.call_config = config;
+        self
+    }
+
+    /// 编译模块（命中缓存时直接复用）
+    pub fn compile(&self, module_bytes: &[u8]) -> Result<Module> {
+        self.modules.get_or_compile(&self.engine, module_bytes)
+    }
+
+    /// 在任意存储覆盖层上执行 `func_name() -> i32`（store 随返回释放，覆盖层只剩调用方持有）
+    pub(crate) fn run<T: Storage + 'static>(
+        &self,
+        storage: Arc<Mutex<T>>,
+        module: &Module,
+        func_name: &str,
+        block_number: u64,
+        timestamp: u64,
+    ) -> Result<ExecutionResult> {
+        let mut state = HostState::new(storage, block_number, timestamp);
+        state.gas_schedule = self.gas_schedule.clone();
+        state.gas_limit = self.gas_limit;
+        state.modules = self.modules.clone();
+        state.call_config = self.call_config;
+        let mut store = Store::new(&self.engine, state);
+        store.set_fuel(self.gas_limit)?;
+        crate::run_metered(module, func_name, store)
+    }
+
+    /// 在 Txn 内执行 `func_name() -> i32`
+    ///
+    /// 返回 0 时读集合与写入回灌到 Txn；返回非 0（回滚，`return_value` 与 `return_data` 保留）、
+    /// trap 或 gas 耗尽时返回 `success = false` 且 Txn 不受影响。是否提交由调用方决定。
+    pub fn execute(
+        &self,
+        txn: &mut Txn,
+        module: &Module,
+        func_name: &str,
+        block_number: u64,
+        timestamp: u64,
+    ) -> Result<ExecutionResult> {
+        let overlay = Arc::new(Mutex::new(TxnStorage::new(txn)));
+        let mut result = self.run(overlay.clone(), module, func_name, block_number, timestamp)?;
+        if result.success && result.return_value != 0 {
+            result.success = false;
+            result.error = Some(format!("reverted with status {}", result.return_value));
+        }
+        if result.success {
+            let overlay = Arc::try_unwrap(overlay)
+                .map_err(|_| anyhow!("txn overlay still borrowed after execution"))?
+                .into_inner();
+            overlay.apply_to(txn)?;
+        }
+        Ok(result)
+    }
+
+    /// 开启事务执行，返回 0 则提交，回滚 / trap 则 abort
+    ///
+    /// 返回执行结果与提交时间戳（执行失败时为 `None`）；提交冲突以 `Err` 返回
+    pub fn execute_and_commit(
+        &self,
+        store: &Arc<MvccStore>,
+        module_bytes: &[u8],
+        func_name: &str,
+        block_number: u64,
+        timestamp: u64,
+    ) -> Result<(ExecutionResult, Option<u64>)> {
+        let module = self.compile(module_bytes)?;
+        let mut txn = store.begin();
+        let result = self.execute(&mut txn, &module, func_name, block_number, timestamp)?;
+        if !result.success {
+            txn.abort();
+            return Ok((result, None));
+        }
+        let commit_ts = txn.commit().map_err(|e| anyhow!(e))?;
+        Ok((result, Some(commit_ts)))
+    }
+
+    /// 生成可放入 `MvccScheduler` / `OptimizedMvccScheduler` 批次的事务闭包
+    ///
+    /// 模块在此处编译一次；闭包在执行失败（返回非 0 / trap / gas 耗尽）时返回 `Err`，调度器据此放弃提交
+    pub fn txn_fn(
+        &self,
+        module_bytes: &[u8],
+        func_name: &str,
+        block_number: u64,
+        timestamp: u64,
+    ) -> Result<impl Fn(&mut Txn) -> Result<i32> + Send + Sync + 'static> {
+        let module = self.compile(module_bytes)?;
+        let executor = self.clone();
+        let func_name = func_name.to_string();
+        Ok(move |txn: &mut Txn| {
+            let result = executor.execute(txn, &module, &func_name, block_number, timestamp)?;
+            if result.success {
+                Ok(result.return_value)
+            } else {
+                Err(anyhow!(result
+                    .error
+                    .unwrap_or_else(|| "wasm execution failed".to_string())))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel_mvcc::{MvccScheduler, MvccSchedulerConfig};
+
+    /// 写入 storage["k"] = "v"；入口 `revert` 写入后返回非 0，`trap` 写入后触发 unreachable
+    const WRITE_WAT: &str = r#"
+    (module
+        (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "kv")
+        (func $write
+            (call $storage_set (i32.const 0) (i32.const 1) (i32.const 1) (i32.const 1))
+            drop
+        )
+        (func (export "ok") (result i32)
+            (call $write)
+            (i32.const 0)
+        )
+        (func (export "revert") (result i32)
+            (call $write)
+            (i32.const 7)
+        )
+        (func (export "trap") (result i32)
+            (call $write)
+            unreachable
+        )
+    )
+    "#;
+
+    /// 单字节计数器 storage["counter"] += 1，新值作为返回数据
+    const COUNTER_WAT: &str = r#"
+    (module
+        (import "storage_api" "storage_get" (func $storage_get (param i32 i32) (result i64)))
+        (import "storage_api" "storage_read_value" (func $storage_read_value (param i32 i32) (result i32)))
+        (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+        (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "counter")
+        (func (export "incr") (result i32)
+            (if (i64.ne (call $storage_get (i32.const 0) (i32.const 7)) (i64.const 0))
+                (then
+                    (call $storage_read_value (i32.const 16) (i32.const 1))
+                    drop
+                )
+            )
+            (i32.store8 (i32.const 16) (i32.add (i32.load8_u (i32.const 16)) (i32.const 1)))
+            (call $storage_set (i32.const 0) (i32.const 7) (i32.const 16) (i32.const 1))
+            drop
+            (call $set_return (i32.const 16) (i32.const 1))
+        )
+    )
+    "#;
+
+    #[test]
+    fn test_commit_on_zero_abort_on_revert_or_trap() -> Result<()> {
+        let store = MvccStore::new();
+        let executor = TxnExecutor::new();
+        let wasm = wat::parse_str(WRITE_WAT)?;
+
+        let (trapped, ts) = executor.execute_and_commit(&store, &wasm, "trap", 1, 0)?;
+        assert!(!trapped.success);
+        assert!(ts.is_none());
+        assert_eq!(store.begin_read_only().read(b"k"), None);
+
+        // 非 0 返回值视为回滚：写入丢弃，返回值保留
+        let (reverted, ts) = executor.execute_and_commit(&store, &wasm, "revert", 1, 0)?;
+        assert!(!reverted.success);
+        assert_eq!(reverted.return_value, 7);
+        assert!(ts.is_none());
+        assert_eq!(store.begin_read_only().read(b"k"), None);
+        let mut txn = store.begin();
+        let module = executor.compile(&wasm)?;
+        assert!(!executor.execute(&mut txn, &module, "revert", 1, 0)?.success);
+        assert!(txn.writes().is_empty());
+
+        let (ok, ts) = executor.execute_and_commit(&store, &wasm, "ok", 1, 0)?;
+        assert!(ok.success);
+        assert_eq!(ok.return_value, 0);
+        assert!(ts.is_some());
+        assert_eq!(store.begin_read_only().read(b"k"), Some(b"v".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_reads_and_buffered_writes() -> Result<()> {
+        let store = MvccStore::new();
+        let executor = TxnExecutor::new();
+        let module = executor.compile(&wat::parse_str(COUNTER_WAT)?)?;
+
+        // 两个并发事务基于同一快照：都读到 0，先提交者胜出
+        let mut t1 = store.begin();
+        let mut t2 = store.begin();
+        assert_eq!(
+            executor
+                .execute(&mut t1, &module, "incr", 1, 0)?
+                .return_data,
+            vec![1]
+        );
+        assert_eq!(
+            executor
+                .execute(&mut t2, &module, "incr", 1, 0)?
+                .return_data,
+            vec![1]
+        );
+        assert!(t1.reads().contains(&b"counter".to_vec()));
+        assert_eq!(store.begin_read_only().read(b"counter"), None);
+        t1.commit().map_err(|e| anyhow!(e))?;
+        assert!(t2.commit().is_err());
+
+        // 同一事务内再次执行读到自己的写
+        let mut t3 = store.begin();
+        executor.execute(&mut t3, &module, "incr", 1, 0)?;
+        assert_eq!(
+            executor
+                .execute(&mut t3, &module, "incr", 1, 0)?
+                .return_data,
+            vec![3]
+        );
+        t3.commit().map_err(|e| anyhow!(e))?;
+        assert_eq!(store.begin_read_only().read(b"counter"), Some(vec![3]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_txn_fn_in_mvcc_scheduler_batch() -> Result<()> {
+        // 同键计数器必然冲突，放宽重试次数以保证全部提交
+        let scheduler = MvccScheduler::new_with_config(MvccSchedulerConfig {
+            max_retries: 1_000,
+            ..MvccSchedulerConfig::default()
+        });
+        let executor = TxnExecutor::new();
+        let incr = executor.txn_fn(&wat::parse_str(COUNTER_WAT)?, "incr", 1, 0)?;
+        let trap = executor.txn_fn(&wat::parse_str(WRITE_WAT)?, "trap", 1, 0)?;
+        let revert = executor.txn_fn(&wat::parse_str(WRITE_WAT)?, "revert", 1, 0)?;
+
+        let (incr, trap, revert) = (&incr, &trap, &revert);
+        let batch: Vec<_> = (0..10u64)
+            .map(|i| {
+                (i, move |txn: &mut Txn| match i {
+                    0..=7 => incr(txn),
+                    8 => trap(txn),
+                    _ => revert(txn),
+                })
+            })
+            .collect();
+        let result = scheduler.execute_batch(batch);
+
+        assert_eq!(result.successful, 8);
+        assert_eq!(result.failed, 2);
+        let txn = scheduler.store().begin_read_only();
+        assert_eq!(txn.store().read_at(b"counter", u64::MAX).unwrap(), Some(vec![8]));
+        assert_eq!(txn.store().read_at(b"k", u64::MAX).unwrap(), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,601 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! MVCC 提交日志（WAL）
+//!
+//! 两次刷盘之间已提交的事务只存在于内存版本链中，崩溃即丢失。[`CommitLog`] 在
+//! `Txn::commit` 写入版本链之前把写集合追加到日志并落盘，成功返回才视为提交。
+//!
+//! - 段文件: 目录下按序号命名的 `{seq:016}.wal`，超过 `segment_bytes` 后滚动到新段
+//! - 记录格式: `len(u32 le) ‖ crc32(u32 le) ‖ payload`，payload =
+//!   `commit_ts(u64 le) ‖ flags(u8) [‖ height(u64 le)] ‖ n(u32 le) ‖ n × (key_len(u32) ‖ key ‖ tag(0 删除 / 1 写入) [‖ val_len(u32) ‖ val])`；
+//!   flags 位 0 = 归档模式下的提交，位 1 = 区块水位线标记（带 height，无写入）
+//! - 组提交: 并发提交把记录放入共享缓冲，由一个 leader 一次写入并 fsync，其余提交等待其完成
+//! - 重放: [`CommitLog::replay`] 按段序读取全部记录。只有最后一段末尾的残缺记录视为崩溃时的撕裂写入并忽略；
+//!   其他段中（或最后一段中间）长度 / 校验和不符即为损坏，返回错误。[`CommitLog::open`] 把最后一段的撕裂尾部截掉后
+//!   再新建活跃段，因此之前的段总是完整的。[`crate::MvccStore::replay_wal`] 把水位线之后的记录（含归档版本与区块水位线）
+//!   写回持久化存储，随后再 `load_from_storage`
+//! - 截断: `flush_to_storage` 成功后删除所有记录 ts 都不超过水位线的段
+//!
+//! 任一次写盘失败后日志进入失败状态，之后的追加全部返回错误（提交随之中止）。
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+
+/// 单条记录上限（防止损坏的长度字段导致超大分配）
+const MAX_RECORD_BYTES: usize = 256 * 1024 * 1024;
+const HEADER_BYTES: usize = 8;
+const SEGMENT_EXT: &str = "wal";
+
+/// WAL 配置
+#[derive(Debug, Clone)]
+pub struct WalConfig {
+    /// 段文件目录
+    pub dir: PathBuf,
+    /// 单个段文件的滚动阈值（字节）
+    pub segment_bytes: u64,
+    /// 每批写入后是否 fsync（关闭后仅保证进程崩溃不丢，机器掉电可能丢失）
+    pub sync: bool,
+}
+
+impl WalConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_bytes: 64 * 1024 * 1024,
+            sync: true,
+        }
+    }
+
+    pub fn with_segment_bytes(mut self, bytes: u64) -> Self {
+        self.segment_bytes = bytes;
+        self
+    }
+
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+}
+
+const FLAG_ARCHIVED: u8 = 0b01;
+const FLAG_BLOCK: u8 = 0b10;
+
+/// 一次提交的日志记录（`None` 表示删除），或区块水位线标记
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalRecord {
+    pub commit_ts: u64,
+    pub writes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    /// 归档模式下的提交：重放时同时写回版本化键（见 [`crate::archive`]）
+    pub archived: bool,
+    /// 区块水位线标记：`commit_ts` 为该高度的水位线，`writes` 为空
+    pub block_height: Option<u64>,
+}
+
+impl WalRecord {
+    /// 区块 `height` 的水位线标记
+    pub fn block(height: u64, ts: u64) -> Self {
+        Self {
+            commit_ts: ts,
+            block_height: Some(height),
+            ..Self::default()
+        }
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        let mut payload = Vec::with_capacity(21 + self.writes.len() * 16);
+        payload.extend_from_slice(&self.commit_ts.to_le_bytes());
+        let mut flags = 0;
+        if self.archived {
+            flags |= FLAG_ARCHIVED;
+        }
+        if self.block_height.is_some() {
+            flags |= FLAG_BLOCK;
+        }
+        payload.push(flags);
+        if let Some(height) = self.block_height {
+            payload.extend_from_slice(&height.to_le_bytes());
+        }
+        payload.extend_from_slice(&(self.writes.len() as u32).to_le_bytes());
+        for (key, value) in &self.writes {
+            payload.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            payload.extend_from_slice(key);
+            match value {
+                Some(v) => {
+                    payload.push(1);
+                    payload.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                    payload.extend_from_slice(v);
+                }
+                None => payload.push(0),
+            }
+        }
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        out.extend_from_slice(&payload);
+    }
+
+    fn decode(payload: &[u8]) -> Result<Self> {
+        let mut cursor = payload;
+        let mut take = |n: usize| -> Result<&[u8]> {
+            if cursor.len() < n {
+                bail!("truncated wal record");
+            }
+            let (head, rest) = cursor.split_at(n);
+            cursor = rest;
+            Ok(head)
+        };
+        let commit_ts = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let flags = take(1)?[0];
+        if flags & !(FLAG_ARCHIVED | FLAG_BLOCK) != 0 {
+            bail!("invalid wal record flags {flags:#04x}");
+        }
+        let block_height = if flags & FLAG_BLOCK != 0 {
+            Some(u64::from_le_bytes(take(8)?.try_into().unwrap()))
+        } else {
+            None
+        };
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let mut writes = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let key_len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let key = take(key_len)?.to_vec();
+            let value = match take(1)?[0] {
+                0 => None,
+                1 => {
+                    let len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+                    Some(take(len)?.to_vec())
+                }
+                tag => bail!("invalid wal write tag {tag}"),
+            };
+            writes.push((key, value));
+        }
+        if !cursor.is_empty() {
+            bail!("trailing bytes in wal record");
+        }
+        Ok(Self {
+            commit_ts,
+            writes,
+            archived: flags & FLAG_ARCHIVED != 0,
+            block_height,
+        })
+    }
+}
+
+/// 组提交共享状态
+#[derive(Default)]
+struct GroupState {
+    buffer: Vec<u8>,
+    buffer_max_ts: u64,
+    next_lsn: u64,
+    durable_lsn: u64,
+    flushing: bool,
+    failed: Option<String>,
+}
+
+struct Segment {
+    seq: u64,
+    path: PathBuf,
+    /// 段内记录的最大 commit_ts（空段为 0）
+    max_ts: u64,
+}
+
+struct Segments {
+    sealed: Vec<Segment>,
+    active: Segment,
+    active_file: File,
+    active_bytes: u64,
+}
+
+/// 追加式提交日志
+pub struct CommitLog {
+    config: WalConfig,
+    group: Mutex<GroupState>,
+    group_done: Condvar,
+    segments: Mutex<Segments>,
+}
+
+impl CommitLog {
+    /// 打开日志目录：已有段保留为只读段（待重放 / 截断），新记录写入新建的段
+    ///
+    /// 最后一段的撕裂尾部在此截掉并落盘，之后它与更早的段一样按完整段校验
+    pub fn open(config: WalConfig) -> Result<Self> {
+        fs::create_dir_all(&config.dir)
+            .with_context(|| format!("Failed to create wal dir {:?}", config.dir))?;
+        let mut sealed = Vec::new();
+        let existing = list_segments(&config.dir)?;
+        let last = existing.len().checked_sub(1);
+        for (i, (seq, path)) in existing.into_iter().enumerate() {
+            let is_last = Some(i) == last;
+            let (records, valid_len) = read_segment(&path, is_last)?;
+            if is_last && valid_len < fs::metadata(&path)?.len() {
+                let file = OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .with_context(|| format!("Failed to open wal segment {path:?}"))?;
+                file.set_len(valid_len)
+                    .and_then(|_| file.sync_all())
+                    .with_context(|| format!("Failed to trim torn wal tail {path:?}"))?;
+            }
+            let max_ts = records.iter().map(|r| r.commit_ts).max().unwrap_or(0);
+            sealed.push(Segment { seq, path, max_ts });
+        }
+        let next_seq = sealed.last().map_or(0, |s| s.seq + 1);
+        let (active, active_file) = create_segment(&config.dir, next_seq)?;
+        Ok(Self {
+            config,
+            group: Mutex::new(GroupState::default()),
+            group_done: Condvar::new(),
+            segments: Mutex::new(Segments {
+                sealed,
+                active,
+                active_file,
+                active_bytes: 0,
+            }),
+        })
+    }
+
+    /// 读取目录下全部记录（按段序、段内按写入序）；目录不存在时为空
+    pub fn replay(dir: impl AsRef<Path>) -> Result<Vec<WalRecord>> {
+        let dir = dir.as_ref();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let segments = list_segments(dir)?;
+        let last = segments.len().checked_sub(1);
+        let mut records = Vec::new();
+        for (i, (_, path)) in segments.iter().enumerate() {
+            records.extend(read_segment(path, Some(i) == last)?.0);
+        }
+        Ok(records)
+    }
+
+    /// 追加一条记录，返回时记录已写入（`sync` 开启时已 fsync）
+    ///
+    /// 并发调用自动组成一批：先到者成为 leader 写入当前缓冲的全部记录，其余调用等待该批完成。
+    pub fn append(&self, record: &WalRecord) -> Result<()> {
+        let mut state = self
+            .group
+            .lock()
+            .map_err(|_| anyhow!("wal lock poisoned"))?;
+        if let Some(err) = &state.failed {
+            bail!("wal unavailable after write failure: {err}");
+        }
+        record.encode_into(&mut state.buffer);
+        state.buffer_max_ts = state.buffer_max_ts.max(record.commit_ts);
+        state.next_lsn += 1;
+        let lsn = state.next_lsn;
+
+        loop {
+            if state.durable_lsn >= lsn {
+                return Ok(());
+            }
+            if let Some(err) = &state.failed {
+                bail!("wal write failed: {err}");
+            }
+            if !state.flushing {
+                state.flushing = true;
+                let buffer = std::mem::take(&mut state.buffer);
+                let max_ts = std::mem::take(&mut state.buffer_max_ts);
+                let batch_lsn = state.next_lsn;
+                drop(state);
+
+                let result = self.write_batch(&buffer, max_ts);
+
+                state = self
+                    .group
+                    .lock()
+                    .map_err(|_| anyhow!("wal lock poisoned"))?;
+                state.flushing = false;
+                match result {
+                    Ok(()) => state.durable_lsn = batch_lsn,
+                    Err(e) => state.failed = Some(e.to_string()),
+                }
+                self.group_done.notify_all();
+                continue;
+            }
+            state = self
+                .group_done
+                .wait(state)
+                .map_err(|_| anyhow!("wal lock poisoned"))?;
+        }
+    }
+
+    fn write_batch(&self, buffer: &[u8], max_ts: u64) -> Result<()> {
+        let mut segments = self
+            .segments
+            .lock()
+            .map_err(|_| anyhow!("wal lock poisoned"))?;
+        if segments.active_bytes > 0
+            && segments.active_bytes + buffer.len() as u64 > self.config.segment_bytes
+        {
+            self.rotate(&mut segments)?;
+        }
+        segments
+            .active_file
+            .write_all(buffer)
+            .context("Failed to append wal")?;
+        if self.config.sync {
+            segments
+                .active_file
+                .sync_data()
+                .context("Failed to sync wal")?;
+        }
+        segments.active_bytes += buffer.len() as u64;
+        segments.active.max_ts = segments.active.max_ts.max(max_ts);
+        Ok(())
+    }
+
+    fn rotate(&self, segments: &mut Segments) -> Result<()> {
+        let (next, file) = create_segment(&self.config.dir, segments.active.seq + 1)?;
+        let old = std::mem::replace(&mut segments.active, next);
+        segments.active_file = file;
+        segments.active_bytes = 0;
+        segments.sealed.push(old);
+        Ok(())
+    }
+
+    /// 删除所有记录 ts 都不超过 `upto_ts` 的段（当前段满足条件时先滚动），返回删除的段数
+    ///
+    /// 调用方须保证 ts ≤ `upto_ts` 的提交已反映在持久化存储中（即刷盘水位线）
+    pub fn truncate(&self, upto_ts: u64) -> Result<usize> {
+        let mut segments = self
+            .segments
+            .lock()
+            .map_err(|_| anyhow!("wal lock poisoned"))?;
+        if segments.active_bytes > 0 && segments.active.max_ts <= upto_ts {
+            self.rotate(&mut segments)?;
+        }
+        let mut removed = 0;
+        let mut kept = Vec::with_capacity(segments.sealed.len());
+        for segment in std::mem::take(&mut segments.sealed) {
+            if segment.max_ts <= upto_ts {
+                fs::remove_file(&segment.path)
+                    .with_context(|| format!("Failed to remove wal segment {:?}", segment.path))?;
+                removed += 1;
+            } else {
+                kept.push(segment);
+            }
+        }
+        segments.sealed = kept;
+        Ok(removed)
+    }
+
+    /// 当前段文件数量（含活跃段）
+    pub fn segment_count(&self) -> usize {
+        self.segments
+            .lock()
+            .map(|s| s.sealed.len() + 1)
+            .unwrap_or(0)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+}
+
+fn segment_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{seq:016}.{SEGMENT_EXT}"))
+}
+
+fn create_segment(dir: &Path, seq: u64) -> Result<(Segment, File)> {
+    let path = segment_path(dir, seq);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create wal segment {path:?}"))?;
+    Ok((
+        Segment {
+            seq,
+            path,
+            max_ts: 0,
+        },
+        file,
+    ))
+}
+
+/// 按序号升序列出段文件
+fn list_segments(dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read wal dir {dir:?}"))? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXT) {
+            continue;
+        }
+        if let Some(seq) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+        {
+            out.push((seq, path));
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// 读取单个段，返回记录与有效数据长度
+///
+/// 撕裂写入只可能出现在段末尾：不足一个头部、记录越过文件末尾、或最后一条记录校验和不符。
+/// `allow_torn_tail` 时（最后一段）丢弃这样的尾部；否则以及任何位于中间的坏记录均视为损坏并返回错误。
+fn read_segment(path: &Path, allow_torn_tail: bool) -> Result<(Vec<WalRecord>, u64)> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read wal segment {path:?}"))?;
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let torn = if bytes.len() - pos < HEADER_BYTES {
+            true
+        } else {
+            let len = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+            let crc = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().unwrap());
+            let start = pos + HEADER_BYTES;
+            if len > MAX_RECORD_BYTES || bytes.len() - start < len {
+                true
+            } else {
+                let payload = &bytes[start..start + len];
+                if crc32fast::hash(payload) != crc {
+                    if start + len != bytes.len() {
+                        bail!("corrupted wal segment {path:?}: checksum mismatch at offset {pos}");
+                    }
+                    true
+                } else {
+                    records.push(WalRecord::decode(payload).with_context(|| {
+                        format!("corrupted wal segment {path:?} at offset {pos}")
+                    })?);
+                    pos = start + len;
+                    false
+                }
+            }
+        };
+        if torn {
+            if !allow_torn_tail {
+                bail!("corrupted wal segment {path:?}: incomplete record at offset {pos}");
+            }
+            break;
+        }
+    }
+    Ok((records, pos as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn record(ts: u64, key: &str, value: Option<&str>) -> WalRecord {
+        WalRecord {
+            commit_ts: ts,
+            writes: vec![(
+                key.as_bytes().to_vec(),
+                value.map(|v| v.as_bytes().to_vec()),
+            )],
+            ..WalRecord::default()
+        }
+    }
+
+    #[test]
+    fn test_append_replay_and_torn_tail() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = CommitLog::open(WalConfig::new(dir.path()).with_segment_bytes(64))?;
+        let records: Vec<_> = (1..=10u64)
+            .map(|ts| record(ts, &format!("k{ts}"), (ts % 3 != 0).then_some("v")))
+            .collect();
+        for r in &records {
+            log.append(r)?;
+        }
+        assert!(log.segment_count() > 1, "small segments should rotate");
+        assert_eq!(CommitLog::replay(dir.path())?, records);
+
+        // 末段尾部的撕裂写入被忽略
+        let (_, last) = list_segments(dir.path())?.pop().unwrap();
+        let mut file = OpenOptions::new().append(true).open(&last)?;
+        file.write_all(&[42, 0, 0, 0, 1, 2, 3])?;
+        drop(file);
+        assert_eq!(CommitLog::replay(dir.path())?, records);
+
+        // 重新打开时截掉撕裂尾部，新记录写入新段后旧段仍可完整读取
+        drop(log);
+        let log = CommitLog::open(WalConfig::new(dir.path()))?;
+        let marker = WalRecord::block(7, 11);
+        log.append(&marker)?;
+        let mut expected = records.clone();
+        expected.push(marker);
+        assert_eq!(CommitLog::replay(dir.path())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_corruption_in_sealed_segment_is_an_error() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = CommitLog::open(WalConfig::new(dir.path()).with_segment_bytes(1))?;
+        for ts in 1..=3u64 {
+            log.append(&record(ts, "k", Some("v")))?;
+        }
+        drop(log);
+        let segments = list_segments(dir.path())?;
+        assert!(segments.len() >= 3);
+
+        // 非最后一段的尾部残缺 / 校验和不符都不是撕裂写入
+        let (_, first) = &segments[0];
+        let original = fs::read(first)?;
+        fs::write(first, &original[..original.len() - 1])?;
+        assert!(CommitLog::replay(dir.path()).is_err());
+        assert!(CommitLog::open(WalConfig::new(dir.path())).is_err());
+        let mut flipped = original.clone();
+        *flipped.last_mut().unwrap() ^= 0xff;
+        fs::write(first, &flipped)?;
+        assert!(CommitLog::replay(dir.path()).is_err());
+
+        // 最后一段中间的坏记录同样报错
+        fs::write(first, &original)?;
+        let (_, last) = segments.last().unwrap();
+        let mut bytes = fs::read(last)?;
+        if bytes.is_empty() {
+            bytes = original.clone();
+        }
+        let mut doubled = bytes.clone();
+        doubled.extend_from_slice(&bytes);
+        doubled[HEADER_BYTES] ^= 0xff;
+        fs::write(last, &doubled)?;
+        assert!(CommitLog::replay(dir.path()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_keeps_segments_after_watermark() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = CommitLog::open(WalConfig::new(dir.path()).with_segment_bytes(1))?;
+        for ts in 1..=6u64 {
+            log.append(&record(ts, "k", Some("v")))?;
+        }
+        assert_eq!(log.truncate(4)?, 4);
+        let remaining: Vec<u64> = CommitLog::replay(dir.path())?
+            .iter()
+            .map(|r| r.commit_ts)
+            .collect();
+        assert_eq!(remaining, vec![5, 6]);
+
+        // 重新打开后旧段按内容计算最大 ts，同样可截断
+        drop(log);
+        let log = CommitLog::open(WalConfig::new(dir.path()))?;
+        assert_eq!(log.truncate(6)?, 2);
+        assert!(CommitLog::replay(dir.path())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_commit_concurrent_appends() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = Arc::new(CommitLog::open(WalConfig::new(dir.path()))?);
+        let handles: Vec<_> = (0..8u64)
+            .map(|t| {
+                let log = Arc::clone(&log);
+                std::thread::spawn(move || {
+                    for i in 0..50u64 {
+                        log.append(&record(t * 1000 + i, &format!("t{t}"), Some("v")))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let mut ts: Vec<u64> = CommitLog::replay(dir.path())?
+            .iter()
+            .map(|r| r.commit_ts)
+            .collect();
+        ts.sort();
+        let expected: Vec<u64> = (0..8u64)
+            .flat_map(|t| (0..50u64).map(move |i| t * 1000 + i))
+            .collect();
+        assert_eq!(ts, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,844 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2025 XujueKing <leadbrand@me.com>
+
+// 开发者：king
+// Developer: king
+
+//! WASM 执行引擎：[`ExecutionEngine`] 的 WASM 实现
+//!
+//! 调用约定:
+//! - 模块导出线性内存 `memory` 与入口函数 `call() -> i32`（0 表示成功，非 0 表示回滚）
+//! - 输入通过 `contract_api::input_len` / `contract_api::read_input` 复制到 guest 内存
+//! - 返回数据通过 `contract_api::set_return` 设置
+//! - `chain_api::emit_event` 产生的事件映射为 [`Log`]（address = 当前合约，topics 为空）
+//!
+//! - 不支持原生价值转移：`ExecutionContext::value` 非 0 的调用直接返回错误
+//!
+//! 编译后的模块按代码哈希缓存在执行器自有的 [`ModuleCache`] 中，同一代码只编译、校验一次。
+//!
+//! 执行期间的写入缓冲在 [`OverlayStorage`] 中，不直接修改底层存储；
+//! 成功时按 ReadWriteSet 写集合导出 [`StateChange`]，由调用方通过
+//! [`WasmExecutor::apply_state_changes`] 决定是否落盘。
+
+use crate::contract_registry::{self, ModuleCache};
+use crate::execution_trait::{
+    ContractResult, EngineType, ExecutionContext, ExecutionEngine, Log, StateChange,
+};
+use crate::gas::{self, GasSchedule, StorageDeposit};
+use crate::host::{self, HostState};
+use crate::storage::{MemoryStorage, Storage};
+use anyhow::{bail, Result};
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use wasmtime::{Engine, Extern, ExternType, Linker, Module, Store, StoreLimitsBuilder, ValType};
+
+/// 默认入口函数名
+pub const DEFAULT_ENTRY_POINT: &str = "call";
+
+/// WASM 页大小（64 KiB）
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+/// 模块资源限制
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// 线性内存最大页数（64 KiB / 页）
+    pub max_memory_pages: u32,
+    /// 表元素数量上限
+    pub max_table_elements: u32,
+    /// 代码大小上限（字节）
+    pub max_code_size: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_pages: 256, // 16 MiB
+            max_table_elements: 10_000,
+            max_code_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// 模块校验错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("code size {size} exceeds limit {limit}")]
+    CodeTooLarge { size: usize, limit: usize },
+    #[error("invalid wasm module: {0}")]
+    InvalidModule(String),
+    #[error("import {module}::{name} is not a registered host function")]
+    ImportNotAllowed { module: String, name: String },
+    #[error("import {module}::{name} signature does not match host function")]
+    ImportSignatureMismatch { module: String, name: String },
+    #[error("memory requires {pages} pages, limit is {limit}")]
+    MemoryTooLarge { pages: u64, limit: u32 },
+    #[error("table requires {elements} elements, limit is {limit}")]
+    TableTooLarge { elements: u32, limit: u32 },
+    #[error("64-bit memories are not supported")]
+    Memory64NotSupported,
+    #[error("missing export `{0}`")]
+    MissingExport(String),
+    #[error("entry point `{0}` must have signature () -> i32")]
+    InvalidEntryPoint(String),
+}
+
+/// 写缓冲存储：读穿透到共享底层存储，写入仅保存在本地
+pub struct OverlayStorage<S: Storage> {
+    base: Arc<RwLock<S>>,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<S: Storage> OverlayStorage<S> {
+    pub fn new(base: Arc<RwLock<S>>) -> Self {
+        Self {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// 本地缓冲的写入（None 表示删除）
+    pub fn pending(&self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        self.writes.get(key).cloned()
+    }
+
+    /// 全部缓冲写入（按键有序）
+    pub fn writes(&self) -> &BTreeMap<Vec<u8>, Option<Vec<u8>>> {
+        &self.writes
+    }
+}
+
+impl<S: Storage> Storage for OverlayStorage<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.writes.get(key) {
+            Some(v) => Ok(v.clone()),
+            None => self.base.read().get(key),
+        }
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.writes.insert(key.to_vec(), None);
+        Ok(())
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> =
+            self.base.read().scan(prefix)?.into_iter().collect();
+        for (k, v) in self
+            .writes
+            .range(prefix.to_vec()..)
+            .take_while(|(k, _)| k.starts_with(prefix))
+        {
+            match v {
+                Some(val) => {
+                    merged.insert(k.clone(), val.clone());
+                }
+                None => {
+                    merged.remove(k);
+                }
+            }
+        }
+        Ok(merged.into_iter().collect())
+    }
+}
+
+/// WASM 执行引擎
+///
+/// 持有 fuel 计量引擎与共享状态（`Arc<RwLock<S>>`），满足 `ExecutionEngine: Send + Sync`
+pub struct WasmExecutor<S: Storage + Send + Sync + 'static = MemoryStorage> {
+    engine: Engine,
+    state: Arc<RwLock<S>>,
+    gas_schedule: GasSchedule,
+    limits: WasmLimits,
+    entry_point: String,
+    /// 本执行器引擎编译的模块（模块不能跨引擎使用，故不与其它执行器共享）
+    modules: ModuleCache,
+    /// 已按当前限制与入口函数校验通过的代码哈希
+    validated: Mutex<HashSet<[u8; 32]>>,
+}
+
+impl<S: Storage + Send + Sync + 'static> WasmExecutor<S> {
+    /// 以独占的存储创建执行器
+    pub fn new(storage: S) -> Self {
+        Self::with_shared_state(Arc::new(RwLock::new(storage)))
+    }
+
+    /// 以共享存储创建执行器
+    pub fn with_shared_state(state: Arc<RwLock<S>>) -> Self {
+        Self {
+            engine: gas::metered_engine().expect("fuel-metered engine config is valid"),
+            state,
+            gas_schedule: GasSchedule::default(),
+            limits: WasmLimits::default(),
+            entry_point: DEFAULT_ENTRY_POINT.to_string(),
+            modules: ModuleCache::new(),
+            validated: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 设置 host 调用计费表
+    pub fn with_gas_schedule(mut self, schedule: GasSchedule) -> Self {
+        self.gas_schedule = schedule;
+        self
+    }
+
+    /// 设置模块资源限制
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.limits = limits;
+        self.validated.get_mut().clear();
+        self
+    }
+
+    /// 设置入口函数名
+    pub fn with_entry_point(mut self, entry_point: impl Into<String>) -> Self {
+        self.entry_point = entry_point.into();
+        self.validated.get_mut().clear();
+        self
+    }
+
+    /// 编译模块缓存（命中率等统计见 [`ModuleCache::stats`]）
+    pub fn module_cache(&self) -> &ModuleCache {
+        &self.modules
+    }
+
+    /// 获取共享状态
+    pub fn state(&self) -> Arc<RwLock<S>> {
+        self.state.clone()
+    }
+
+    /// 获取模块资源限制
+    pub fn limits(&self) -> &WasmLimits {
+        &self.limits
+    }
+
+    /// 将执行结果中的状态变更写入底层存储
+    pub fn apply_state_changes(&self, changes: &[StateChange]) -> Result<()> {
+        let mut state = self.state.write();
+        for change in changes {
+            match &change.value {
+                Some(v) => state.set(&change.key, v)?,
+                None => state.delete(&change.key)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn new_store(
+        &self,
+        state: HostState<OverlayStorage<S>>,
+    ) -> Store<HostState<OverlayStorage<S>>> {
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|s| &mut s.limits);
+        store
+    }
+
+    /// 编译并校验模块（导入白名单、内存限制、入口函数签名）
+    ///
+    /// 模块取自 [`ModuleCache`]；同一代码哈希校验通过一次后不再重复校验
+    fn compile_validated(&self, code: &[u8]) -> Result<Module> {
+        if code.len() > self.limits.max_code_size {
+            return Err(ValidationError::CodeTooLarge {
+                size: code.len(),
+                limit: self.limits.max_code_size,
+            }
+            .into());
+        }
+        let hash = contract_registry::code_hash(code);
+        let module = self
+            .modules
+            .get_or_compile(&self.engine, code)
+            .map_err(|e| ValidationError::InvalidModule(e.to_string()))?;
+        if self.validated.lock().contains(&hash) {
+            return Ok(module);
+        }
+        self.validate_declarations(code)?;
+
+        // 导入白名单：仅允许已注册的 host functions，且签名一致
+        let mut linker = Linker::new(&self.engine);
+        host::register_host_functions(&mut linker)?;
+        let mut store = self.new_store(HostState::new(
+            Arc::new(Mutex::new(OverlayStorage::new(self.state.clone()))),
+            0,
+            0,
+        ));
+        for import in module.imports() {
+            let (module_name, name) = (import.module(), import.name());
+            let not_allowed = || ValidationError::ImportNotAllowed {
+                module: module_name.to_string(),
+                name: name.to_string(),
+            };
+            let ExternType::Func(expected) = import.ty() else {
+                return Err(not_allowed().into());
+            };
+            if !host::HOST_FUNCTIONS.contains(&(module_name, name)) {
+                return Err(not_allowed().into());
+            }
+            let Some(Extern::Func(func)) = linker.get(&mut store, module_name, name) else {
+                return Err(not_allowed().into());
+            };
+            let actual = func.ty(&store);
+            if !expected.params().eq(actual.params()) || !expected.results().eq(actual.results()) {
+                return Err(ValidationError::ImportSignatureMismatch {
+                    module: module_name.to_string(),
+                    name: name.to_string(),
+                }
+                .into());
+            }
+        }
+
+        // 导出校验：memory + 入口函数（内存上限已在 validate_declarations 中检查）
+        let mut has_memory = false;
+        let mut has_entry = false;
+        for export in module.exports() {
+            match export.ty() {
+                ExternType::Memory(_) if export.name() == "memory" => has_memory = true,
+                ExternType::Func(f) if export.name() == self.entry_point => {
+                    let results: Vec<ValType> = f.results().collect();
+                    if f.params().len() != 0 || !matches!(results.as_slice(), [ValType::I32]) {
+                        return Err(
+                            ValidationError::InvalidEntryPoint(self.entry_point.clone()).into()
+                        );
+                    }
+                    has_entry = true;
+                }
+                _ => {}
+            }
+        }
+        if !has_memory {
+            return Err(ValidationError::MissingExport("memory".to_string()).into());
+        }
+        if !has_entry {
+            return Err(ValidationError::MissingExport(self.entry_point.clone()).into());
+        }
+
+        self.validated.lock().insert(hash);
+        Ok(module)
+    }
+
+    /// 校验模块内定义的全部 memory / table 声明（含未导出的），初始大小不得超过限制
+    ///
+    /// 导入的 memory / table 已被导入白名单拒绝，这里只需检查定义段
+    fn validate_declarations(&self, code: &[u8]) -> Result<()> {
+        for payload in wasmparser::Parser::new(0).parse_all(code) {
+            let payload = payload.map_err(|e| ValidationError::InvalidModule(e.to_string()))?;
+            match payload {
+                wasmparser::Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        let memory =
+                            memory.map_err(|e| ValidationError::InvalidModule(e.to_string()))?;
+                        if memory.memory64 {
+                            return Err(ValidationError::Memory64NotSupported.into());
+                        }
+                        if memory.initial > self.limits.max_memory_pages as u64 {
+                            return Err(ValidationError::MemoryTooLarge {
+                                pages: memory.initial,
+                                limit: self.limits.max_memory_pages,
+                            }
+                            .into());
+                        }
+                    }
+                }
+                wasmparser::Payload::TableSection(reader) => {
+                    for table in reader {
+                        let table =
+                            table.map_err(|e| ValidationError::InvalidModule(e.to_string()))?;
+                        if table.ty.initial > self.limits.max_table_elements {
+                            return Err(ValidationError::TableTooLarge {
+                                elements: table.ty.initial,
+                                limit: self.limits.max_table_elements,
+                            }
+                            .into());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Storage + Send + Sync + 'static> ExecutionEngine for WasmExecutor<S> {
+    fn execute(
+        &self,
+        code: &[u8],
+        input: &[u8],
+        context: &ExecutionContext,
+    ) -> Result<ContractResult> {
+        if context.value != 0 {
+            bail!(
+                "wasm executor does not support value transfers (value = {})",
+                context.value
+            );
+        }
+        let module = self.compile_validated(code)?;
+
+        let overlay = Arc::new(Mutex::new(OverlayStorage::new(self.state.clone())));
+        let mut state = HostState::new(overlay.clone(), context.block_number, context.timestamp);
+        state.gas_schedule = self.gas_schedule.clone();
+        state.gas_limit = context.gas_limit;
+        state.caller_address = context.caller;
+        state.contract_address = context.contract;
+        state.storage_prefix = contract_registry::storage_prefix(&context.contract);
+        state.input = input.to_vec();
+        state.limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.max_memory_pages as usize * WASM_PAGE_SIZE)
+            .table_elements(self.limits.max_table_elements)
+            .instances(1)
+            .build();
+
+        let mut store = self.new_store(state);
+        store.set_fuel(context.gas_limit)?;
+
+        let mut linker = Linker::new(&self.engine);
+        host::register_host_functions(&mut linker)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+        if let Some(memory) = instance.get_memory(&mut store, "memory") {
+            store.data_mut().memory = Some(memory);
+        }
+
+        let func = instance.get_typed_func::<(), i32>(&mut store, &self.entry_point)?;
+        let outcome = func.call(&mut store, ());
+        let gas_used = context
+            .gas_limit
+            .saturating_sub(store.get_fuel().unwrap_or(0));
+
+        let exit_code = match outcome {
+            Ok(code) => code,
+            Err(e) => {
+                // Trap / gas 耗尽：执行失败，丢弃全部副作用
+                log::debug!(
+                    "wasm execution failed: {}",
+                    gas::map_out_of_gas(e, context.gas_limit)
+                );
+                return Ok(ContractResult {
+                    success: false,
+                    return_data: Vec::new(),
+                    gas_used,
+                    logs: Vec::new(),
+                    state_changes: Vec::new(),
+                    storage_deposit: StorageDeposit::default(),
+                });
+            }
+        };
+
+        let host_state = store.data();
+        if exit_code != 0 {
+            // 回滚：保留返回数据（错误信息），丢弃日志与状态变更
+            return Ok(ContractResult {
+                success: false,
+                return_data: host_state.return_data.clone(),
+                gas_used,
+                logs: Vec::new(),
+                state_changes: Vec::new(),
+                storage_deposit: StorageDeposit::default(),
+            });
+        }
+
+        let logs = host_state
+            .events
+            .iter()
+            .map(|data| Log {
+                address: context.contract,
+                topics: Vec::new(),
+                data: data.clone(),
+            })
+            .collect();
+
+        let mut written: Vec<&Vec<u8>> = host_state.read_write_set.write_set.iter().collect();
+        written.sort();
+        let overlay_ref = overlay.lock();
+        let state_changes = written
+            .into_iter()
+            .filter_map(|key| {
+                overlay_ref.pending(key).map(|value| StateChange {
+                    key: key.clone(),
+                    value,
+                })
+            })
+            .collect();
+
+        Ok(ContractResult {
+            success: true,
+            return_data: host_state.return_data.clone(),
+            gas_used: host_state.storage_deposit.settle(gas_used),
+            logs,
+            state_changes,
+            storage_deposit: host_state.storage_deposit,
+        })
+    }
+
+    fn engine_type(&self) -> EngineType {
+        EngineType::Wasm
+    }
+
+    fn validate_code(&self, code: &[u8]) -> Result<()> {
+        self.compile_validated(code).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(gas_limit: u64) -> ExecutionContext {
+        ExecutionContext {
+            caller: [1u8; 20],
+            contract: [2u8; 20],
+            value: 0,
+            gas_limit,
+            block_number: 7,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    /// 读取输入，写入 storage[key] = input，发射事件并原样返回输入
+    const ECHO_WAT: &str = r#"
+    (module
+        (import "contract_api" "input_len" (func $input_len (result i32)))
+        (import "contract_api" "read_input" (func $read_input (param i32 i32) (result i32)))
+        (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+        (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+        (import "storage_api" "storage_delete" (func $storage_delete (param i32 i32) (result i32)))
+        (import "chain_api" "emit_event" (func $emit_event (param i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "key")
+        (data (i32.const 8) "old")
+        (data (i32.const 16) "echoed")
+        (func (export "call") (result i32)
+            (local $len i32)
+            (local.set $len (call $input_len))
+            (call $read_input (i32.const 1024) (local.get $len))
+            drop
+            (call $storage_set (i32.const 0) (i32.const 3) (i32.const 1024) (local.get $len))
+            drop
+            (call $storage_delete (i32.const 8) (i32.const 3))
+            drop
+            (call $emit_event (i32.const 16) (i32.const 6))
+            drop
+            (call $set_return (i32.const 1024) (local.get $len))
+            drop
+            (i32.const 0)
+        )
+    )
+    "#;
+
+    #[test]
+    fn test_wasm_executor_input_return_logs_state_changes() -> Result<()> {
+        let scoped =
+            |key: &[u8]| [contract_registry::storage_prefix(&[2u8; 20]), key.to_vec()].concat();
+        let mut storage = MemoryStorage::new();
+        storage.set(&scoped(b"old"), b"x")?;
+        let executor = WasmExecutor::new(storage);
+        let wasm = wat::parse_str(ECHO_WAT)?;
+
+        assert_eq!(executor.engine_type(), EngineType::Wasm);
+        let result = executor.execute(&wasm, b"hello", &ctx(1_000_000))?;
+
+        assert!(result.success);
+        assert_eq!(result.return_data, b"hello");
+        assert!(result.gas_used > 0);
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(result.logs[0].address, [2u8; 20]);
+        assert_eq!(result.logs[0].data, b"echoed");
+        assert_eq!(result.state_changes.len(), 2);
+        assert_eq!(result.state_changes[0].key, scoped(b"key"));
+        assert_eq!(
+            result.state_changes[0].value.as_deref(),
+            Some(&b"hello"[..])
+        );
+        assert_eq!(result.state_changes[1].key, scoped(b"old"));
+        assert_eq!(result.state_changes[1].value, None);
+
+        // 写入按 key + value 收押金，删除退还旧条目押金
+        let schedule = GasSchedule::default();
+        assert_eq!(
+            result.storage_deposit,
+            StorageDeposit {
+                charged: schedule.storage_deposit(scoped(b"key").len() + 5),
+                refunded: schedule.storage_deposit(scoped(b"old").len() + 1),
+            }
+        );
+
+        // 执行本身不修改底层存储，apply 后才生效
+        assert_eq!(executor.state().read().get(&scoped(b"key"))?, None);
+        executor.apply_state_changes(&result.state_changes)?;
+        assert_eq!(
+            executor.state().read().get(&scoped(b"key"))?,
+            Some(b"hello".to_vec())
+        );
+        assert_eq!(executor.state().read().get(&scoped(b"old"))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_delete_refund_lowers_gas_used() -> Result<()> {
+        let scoped =
+            |key: &[u8]| [contract_registry::storage_prefix(&[2u8; 20]), key.to_vec()].concat();
+        let wasm = wat::parse_str(ECHO_WAT)?;
+        let fresh =
+            WasmExecutor::new(MemoryStorage::new()).execute(&wasm, b"hi", &ctx(1_000_000))?;
+
+        // 同样的指令与 host 调用，删除已有条目时退还的押金直接抵扣净收费
+        let mut storage = MemoryStorage::new();
+        storage.set(&scoped(b"old"), b"xyz")?;
+        let deleting = WasmExecutor::new(storage).execute(&wasm, b"hi", &ctx(1_000_000))?;
+        let refund = GasSchedule::default().storage_deposit(scoped(b"old").len() + 3);
+        assert_eq!(deleting.storage_deposit.refunded, refund);
+        assert_eq!(deleting.gas_used, fresh.gas_used - refund);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_reuses_cached_module_and_rejects_value() -> Result<()> {
+        let executor = WasmExecutor::new(MemoryStorage::new());
+        let wasm = wat::parse_str(ECHO_WAT)?;
+        executor.validate_code(&wasm)?;
+        for _ in 0..3 {
+            assert!(executor.execute(&wasm, b"hi", &ctx(1_000_000))?.success);
+        }
+        let stats = executor.module_cache().stats();
+        assert_eq!((stats.compiles, stats.hits), (1, 3));
+
+        let mut paying = ctx(1_000_000);
+        paying.value = 1;
+        assert!(executor.execute(&wasm, b"hi", &paying).is_err());
+        assert_eq!(executor.state().read().scan(b"")?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_storage_isolated_per_contract() -> Result<()> {
+        let executor = WasmExecutor::new(MemoryStorage::new());
+        let wasm = wat::parse_str(ECHO_WAT)?;
+
+        let first = executor.execute(&wasm, b"aaaa", &ctx(1_000_000))?;
+        executor.apply_state_changes(&first.state_changes)?;
+
+        // 另一合约写同名 key 不会覆盖前者
+        let mut other = ctx(1_000_000);
+        other.contract = [3u8; 20];
+        let second = executor.execute(&wasm, b"bb", &other)?;
+        executor.apply_state_changes(&second.state_changes)?;
+
+        let key_of =
+            |addr: &[u8; 20]| [contract_registry::storage_prefix(addr), b"key".to_vec()].concat();
+        assert_eq!(
+            executor.state().read().get(&key_of(&[2u8; 20]))?,
+            Some(b"aaaa".to_vec())
+        );
+        assert_eq!(
+            executor.state().read().get(&key_of(&[3u8; 20]))?,
+            Some(b"bb".to_vec())
+        );
+        assert_eq!(executor.state().read().get(b"key")?, None);
+
+        // 覆盖写为更短的值：不再收费，退还差额
+        let shrink = executor.execute(&wasm, b"a", &ctx(1_000_000))?;
+        let per_byte = GasSchedule::default().storage_deposit_per_byte;
+        assert_eq!(
+            shrink.storage_deposit,
+            StorageDeposit {
+                charged: 0,
+                refunded: 3 * per_byte,
+            }
+        );
+        assert!(shrink.gas_used < first.gas_used);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_revert_and_out_of_gas() -> Result<()> {
+        let executor = WasmExecutor::new(MemoryStorage::new());
+
+        let revert = wat::parse_str(
+            r#"
+            (module
+                (import "storage_api" "storage_set" (func $storage_set (param i32 i32 i32 i32) (result i32)))
+                (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "denied")
+                (func (export "call") (result i32)
+                    (call $storage_set (i32.const 0) (i32.const 3) (i32.const 0) (i32.const 6))
+                    drop
+                    (call $set_return (i32.const 0) (i32.const 6))
+                    drop
+                    (i32.const 1)
+                )
+            )
+            "#,
+        )?;
+        let result = executor.execute(&revert, &[], &ctx(1_000_000))?;
+        assert!(!result.success);
+        assert_eq!(result.return_data, b"denied");
+        assert!(result.state_changes.is_empty());
+
+        let spin = wat::parse_str(
+            r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "call") (result i32)
+                    (loop $l br $l)
+                    (i32.const 0)
+                )
+            )
+            "#,
+        )?;
+        let result = executor.execute(&spin, &[], &ctx(20_000))?;
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 20_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_validate_code() -> Result<()> {
+        let executor = WasmExecutor::new(MemoryStorage::new());
+        executor.validate_code(&wat::parse_str(ECHO_WAT)?)?;
+
+        let check = |wat: &str| -> ValidationError {
+            let wasm = wat::parse_str(wat).unwrap();
+            executor
+                .validate_code(&wasm)
+                .unwrap_err()
+                .downcast::<ValidationError>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            check(
+                r#"(module
+                    (import "env" "abort" (func))
+                    (memory (export "memory") 1)
+                    (func (export "call") (result i32) (i32.const 0)))"#
+            ),
+            ValidationError::ImportNotAllowed {
+                module: "env".into(),
+                name: "abort".into()
+            }
+        );
+        assert_eq!(
+            check(
+                r#"(module
+                    (import "storage_api" "storage_get" (func (param i32) (result i32)))
+                    (memory (export "memory") 1)
+                    (func (export "call") (result i32) (i32.const 0)))"#
+            ),
+            ValidationError::ImportSignatureMismatch {
+                module: "storage_api".into(),
+                name: "storage_get".into()
+            }
+        );
+        assert_eq!(
+            check(
+                r#"(module
+                    (memory (export "memory") 1000)
+                    (func (export "call") (result i32) (i32.const 0)))"#
+            ),
+            ValidationError::MemoryTooLarge {
+                pages: 1000,
+                limit: 256
+            }
+        );
+        assert_eq!(
+            check(r#"(module (func (export "call") (result i32) (i32.const 0)))"#),
+            ValidationError::MissingExport("memory".into())
+        );
+        assert_eq!(
+            check(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (func (export "call") (param i32) (result i32) (i32.const 0)))"#
+            ),
+            ValidationError::InvalidEntryPoint("call".into())
+        );
+        assert!(matches!(
+            executor
+                .validate_code(b"not wasm")
+                .unwrap_err()
+                .downcast::<ValidationError>(),
+            Ok(ValidationError::InvalidModule(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_memory_growth_limited() -> Result<()> {
+        // 返回数据首字节 = (memory.grow 4 == -1)，即 grow 是否被拒绝
+        let wasm = wat::parse_str(
+            r#"
+            (module
+                (import "contract_api" "set_return" (func $set_return (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (func (export "call") (result i32)
+                    (i32.store8 (i32.const 0)
+                        (i32.eq (memory.grow (i32.const 4)) (i32.const -1)))
+                    (drop (call $set_return (i32.const 0) (i32.const 1)))
+                    (i32.const 0)
+                )
+            )
+            "#,
+        )?;
+        let grow_refused = |max_memory_pages| -> Result<u8> {
+            let executor = WasmExecutor::new(MemoryStorage::new()).with_limits(WasmLimits {
+                max_memory_pages,
+                ..WasmLimits::default()
+            });
+            let result = executor.execute(&wasm, &[], &ctx(1_000_000))?;
+            assert!(result.success);
+            Ok(result.return_data[0])
+        };
+        // 上限 2 页：grow 到 5 页被拒绝；上限 8 页：同一模块 grow 成功
+        assert_eq!(grow_refused(2)?, 1);
+        assert_eq!(grow_refused(8)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_validates_unexported_declarations() -> Result<()> {
+        let executor = WasmExecutor::new(MemoryStorage::new());
+        let check = |wat: &str| -> ValidationError {
+            let wasm = wat::parse_str(wat).unwrap();
+            executor
+                .validate_code(&wasm)
+                .unwrap_err()
+                .downcast()
+                .unwrap()
+        };
+        // 未导出的内存同样受页数限制（先于导出校验）
+        assert_eq!(
+            check(
+                r#"(module
+                    (memory 1000)
+                    (func (export "call") (result i32) (i32.const 0)))"#
+            ),
+            ValidationError::MemoryTooLarge {
+                pages: 1000,
+                limit: 256
+            }
+        );
+        assert_eq!(
+            check(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (table 100000 funcref)
+                    (func (export "call") (result i32) (i32.const 0)))"#
+            ),
+            ValidationError::TableTooLarge {
+                elements: 100_000,
+                limit: 10_000
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wasm_executor_is_dyn_engine() {
+        let engine: Box<dyn ExecutionEngine> = Box::new(WasmExecutor::new(MemoryStorage::new()));
+        assert_eq!(engine.engine_type(), EngineType::Wasm);
+    }
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Block-STM 并行执行等价性属性测试 (集成测试层)
+// 要点:
+// 1. 随机生成的区块（计数器 / 复制 / 删除 / 写后 trap，集中在 4 个键上以制造冲突）
+// 2. 乐观并行执行与按序逐笔执行的最终状态、逐笔结果（返回值 / 成功与否 / gas）完全一致
+// 3. 基础状态随机预置，覆盖“首次写入”与“覆盖写”两种押金路径
+
+use proptest::prelude::*;
+use std::sync::{Arc, OnceLock};
+use vm_runtime::{BlockExecutor, MemoryStorage, Storage, WasmTransaction};
+
+const KEYS: &[u8] = b"abcd";
+
+/// 每个键 X 导出 `incr_X` / `del_X` / `boom_X`，每对键导出 `copy_X_Y`（Y = X，X 不存在时写 0）
+fn block_module() -> &'static [u8] {
+    static WASM: OnceLock<Vec<u8>> = OnceLock::new();
+    WASM.get_or_init(|| {
+        let mut funcs = String::new();
+        for (i, x) in KEYS.iter().map(|k| *k as char).enumerate() {
+            funcs += &format!(
+                r#"
+        (func (export "incr_{x}") (result i32)
+            (i32.store8 (i32.const 16) (i32.add (call $load (i32.const {i})) (i32.const 1)))
+            (drop (call $set (i32.const {i}) (i32.const 1) (i32.const 16) (i32.const 1)))
+            (i32.load8_u (i32.const 16)))
+        (func (export "del_{x}") (result i32)
+            (call $del (i32.const {i}) (i32.const 1)))
+        (func (export "boom_{x}") (result i32)
+            (drop (call $set (i32.const {i}) (i32.const 1) (i32.const {i}) (i32.const 1)))
+            unreachable)"#
+            );
+            for (j, y) in KEYS.iter().map(|k| *k as char).enumerate() {
+                funcs += &format!(
+                    r#"
+        (func (export "copy_{x}_{y}") (result i32)
+            (drop (call $load (i32.const {i})))
+            (drop (call $set (i32.const {j}) (i32.const 1) (i32.const 16) (i32.const 1)))
+            (i32.load8_u (i32.const 16)))"#
+                );
+            }
+        }
+        let wat = format!(
+            r#"
+    (module
+        (import "storage_api" "storage_get" (func $get (param i32 i32) (result i64)))
+        (import "storage_api" "storage_read_value" (func $read (param i32 i32) (result i32)))
+        (import "storage_api" "storage_set" (func $set (param i32 i32 i32 i32) (result i32)))
+        (import "storage_api" "storage_delete" (func $del (param i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "abcd")
+        (func $load (param $key i32) (result i32)
+            (i32.store8 (i32.const 16) (i32.const 0))
+            (if (i64.ne (call $get (local.get $key) (i32.const 1)) (i64.const 0))
+                (then (drop (call $read (i32.const 16) (i32.const 1)))))
+            (i32.load8_u (i32.const 16)))
+        {funcs}
+    )"#
+        );
+        wat::parse_str(wat).expect("generated module is valid")
+    })
+}
+
+fn shared_executor() -> &'static BlockExecutor {
+    static EXECUTOR: OnceLock<BlockExecutor> = OnceLock::new();
+    EXECUTOR.get_or_init(|| BlockExecutor::default().with_block(42, 1_700_000_000))
+}
+
+fn op_name(kind: u8, x: usize, y: usize) -> String {
+    let (x, y) = (KEYS[x] as char, KEYS[y] as char);
+    match kind {
+        0 | 1 => format!("incr_{x}"),
+        2 => format!("copy_{x}_{y}"),
+        3 => format!("del_{x}"),
+        _ => format!("boom_{x}"),
+    }
+}
+
+fn base_storage(initial: &[Option<u8>]) -> Arc<MemoryStorage> {
+    let mut storage = MemoryStorage::new();
+    for (key, value) in KEYS.iter().zip(initial) {
+        if let Some(v) = value {
+            storage.set(&[*key], &[*v]).unwrap();
+        }
+    }
+    Arc::new(storage)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn parallel_block_equals_sequential(
+        initial in prop::collection::vec(prop::option::of(any::<u8>()), KEYS.len()),
+        ops in prop::collection::vec((0u8..5, 0usize..KEYS.len(), 0usize..KEYS.len()), 1..32),
+    ) {
+        let module = block_module();
+        let txs: Vec<WasmTransaction> = ops
+            .iter()
+            .map(|&(kind, x, y)| WasmTransaction::new(module, op_name(kind, x, y)))
+            .collect();
+        let executor = shared_executor();
+
+        let parallel = executor.execute(base_storage(&initial), &txs).unwrap();
+        let sequential = executor.execute_sequential(base_storage(&initial), &txs).unwrap();
+
+        prop_assert_eq!(&parallel.state_changes, &sequential.state_changes);
+        prop_assert_eq!(parallel.results.len(), txs.len());
+        for (p, s) in parallel.results.iter().zip(&sequential.results) {
+            prop_assert_eq!(p.tx_id, s.tx_id);
+            prop_assert_eq!(p.success, s.success);
+            prop_assert_eq!(p.return_value, s.return_value);
+            prop_assert_eq!(p.gas_used, s.gas_used);
+            prop_assert_eq!(p.storage_deposit, s.storage_deposit);
+        }
+        prop_assert_eq!(
+            parallel.stats.executions,
+            txs.len() as u64 + parallel.stats.reexecutions
+        );
+
+        let mut applied = MemoryStorage::new();
+        parallel.apply_to(&mut applied).unwrap();
+        let mut expected = MemoryStorage::new();
+        sequential.apply_to(&mut expected).unwrap();
+        prop_assert_eq!(applied.scan(b"").unwrap(), expected.scan(b"").unwrap());
+    }
+}
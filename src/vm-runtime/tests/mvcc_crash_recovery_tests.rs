@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// MVCC 刷盘崩溃恢复测试 (MemoryStorage 始终运行；RocksDB 变体需启用 rocksdb-storage 特性)
+// 要点:
+// 1. 删除以墓碑形式刷盘，重启后已删除的键不会从存储复活
+// 2. 最后一次刷盘之后的提交（覆盖写 / 删除 / 新键）在未正常关闭时全部丢失
+// 3. 重启后存储内容恰为刷盘时刻的状态，水位线与刷盘时的 flush_watermark 一致
+// MemoryStorage 变体以"丢弃 MvccStore、保留存储对象"模拟重启
+
+use std::collections::BTreeMap;
+use vm_runtime::{MemoryStorage, MvccStore, Storage, FLUSH_WATERMARK_KEY};
+
+fn commit(store: &std::sync::Arc<MvccStore>, ops: &[(&str, Option<&str>)]) {
+    let mut txn = store.begin();
+    for (key, value) in ops {
+        match value {
+            Some(v) => txn.write(key.as_bytes().to_vec(), v.as_bytes().to_vec()),
+            None => txn.delete(key.as_bytes().to_vec()),
+        }
+    }
+    txn.commit().expect("commit");
+}
+
+/// 两次刷盘（含删除与覆盖写）后再提交一批不刷盘的修改并丢弃内存状态；
+/// 返回重启后应看到的用户状态与水位线
+fn flush_then_crash(storage: &mut dyn Storage) -> (BTreeMap<Vec<u8>, Vec<u8>>, u64) {
+    let store = MvccStore::new();
+    for i in 0..10 {
+        let (key, value) = (format!("k{i}"), format!("v{i}"));
+        commit(&store, &[(key.as_str(), Some(value.as_str()))]);
+    }
+    store.flush_to_storage(storage, 3).expect("first flush");
+
+    // 第二次刷盘包含删除与覆盖写
+    commit(&store, &[("k3", None), ("k7", Some("v7'"))]);
+    commit(&store, &[("k8", None)]);
+    commit(&store, &[("k8", Some("v8'")), ("k9", None)]);
+    store.flush_to_storage(storage, 3).expect("second flush");
+    let watermark = store.flush_watermark();
+
+    let mut expected = BTreeMap::new();
+    for i in [0, 1, 2, 4, 5, 6] {
+        expected.insert(format!("k{i}").into_bytes(), format!("v{i}").into_bytes());
+    }
+    expected.insert(b"k7".to_vec(), b"v7'".to_vec());
+    expected.insert(b"k8".to_vec(), b"v8'".to_vec());
+
+    // 刷盘后的提交不落盘，随后直接丢弃内存状态（模拟崩溃）
+    commit(
+        &store,
+        &[("k0", None), ("k1", Some("lost")), ("k10", Some("lost"))],
+    );
+    drop(store);
+    (expected, watermark)
+}
+
+fn assert_post_flush_state(
+    storage: &dyn Storage,
+    expected: &BTreeMap<Vec<u8>, Vec<u8>>,
+    watermark: u64,
+) {
+    assert_eq!(
+        MvccStore::stored_flush_watermark(storage).unwrap(),
+        Some(watermark)
+    );
+    let state: BTreeMap<Vec<u8>, Vec<u8>> = storage
+        .scan(b"")
+        .unwrap()
+        .into_iter()
+        .filter(|(k, _)| k.as_slice() != FLUSH_WATERMARK_KEY)
+        .collect();
+    assert_eq!(&state, expected);
+    assert!(
+        storage.get(b"k3").unwrap().is_none(),
+        "deleted key must not resurrect"
+    );
+
+    // 从存储恢复的新进程看到同样的状态，新提交排在水位线之后
+    let store = MvccStore::new();
+    store.load_from_storage(storage, None).unwrap();
+    let mut txn = store.begin();
+    assert!(txn.start_ts > watermark);
+    for (key, value) in expected {
+        assert_eq!(txn.read(key).as_ref(), Some(value));
+    }
+    assert_eq!(txn.read(b"k10"), None);
+}
+
+/// 第一个进程写入并刷盘
+fn flush_initial(storage: &mut dyn Storage) {
+    let store = MvccStore::new();
+    commit(&store, &[("a", Some("1")), ("b", Some("2"))]);
+    store.flush_to_storage(storage, 1).expect("flush");
+}
+
+/// 新进程只看到删除：墓碑同样需要刷成后端删除
+fn flush_delete_only(storage: &mut dyn Storage) {
+    let store = MvccStore::new();
+    commit(&store, &[("a", None)]);
+    store.flush_to_storage(storage, 1).expect("flush delete");
+}
+
+fn assert_delete_applied(storage: &dyn Storage) {
+    assert!(storage.get(b"a").unwrap().is_none());
+    assert_eq!(storage.get(b"b").unwrap(), Some(b"2".to_vec()));
+}
+
+#[test]
+fn test_restart_sees_exact_post_flush_state_memory() {
+    let mut storage = MemoryStorage::new();
+    let (expected, watermark) = flush_then_crash(&mut storage);
+    assert_post_flush_state(&storage, &expected, watermark);
+}
+
+#[test]
+fn test_delete_only_flush_after_restart_memory() {
+    let mut storage = MemoryStorage::new();
+    flush_initial(&mut storage);
+    flush_delete_only(&mut storage);
+    assert_delete_applied(&storage);
+}
+
+#[cfg(feature = "rocksdb-storage")]
+mod rocksdb {
+    use super::*;
+    use vm_runtime::RocksDBStorage;
+
+    #[test]
+    fn test_restart_sees_exact_post_flush_state() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (expected, watermark) = {
+            let mut rocks = RocksDBStorage::new_with_path(dir.path()).expect("rocksdb init");
+            flush_then_crash(&mut rocks)
+        };
+
+        let rocks = RocksDBStorage::new_with_path(dir.path()).expect("reopen rocksdb");
+        assert_post_flush_state(&rocks, &expected, watermark);
+    }
+
+    #[test]
+    fn test_delete_only_flush_after_restart() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        {
+            let mut rocks = RocksDBStorage::new_with_path(dir.path()).expect("rocksdb init");
+            flush_initial(&mut rocks);
+        }
+        {
+            let mut rocks = RocksDBStorage::new_with_path(dir.path()).expect("reopen rocksdb");
+            flush_delete_only(&mut rocks);
+        }
+
+        let rocks = RocksDBStorage::new_with_path(dir.path()).expect("reopen rocksdb");
+        assert_delete_applied(&rocks);
+    }
+}